@@ -1,24 +1,56 @@
+use std::collections::HashSet;
 use std::fs;
 use std::net::Ipv4Addr;
 use std::path::Path;
-use std::process;
 use std::str::FromStr;
 use std::sync::Arc;
 
 use clap::builder::PossibleValue;
+use clap::parser::ValueSource;
 use clap::{Arg, ArgAction, ArgMatches, Command};
-use ipnetwork::IpNetwork;
-use pnet::packet::arp::{ArpHardwareType, ArpOperation};
+use ipnetwork::{IpNetwork, Ipv4Network};
+use pnet::packet::arp::{ArpHardwareType, ArpOperation, ArpOperations};
 use pnet::packet::ethernet::EtherType;
 use pnet_datalink::MacAddr;
+use regex::Regex;
 
+use crate::bandwidth::parse_to_bits_per_second;
+use crate::error::ScanError;
+use crate::network::DATALINK_RCV_TIMEOUT;
 use crate::time::parse_to_milliseconds;
+use crate::utils;
+use crate::vendor;
+use crate::webhook;
 
 const TIMEOUT_MS_FAST: u64 = 800;
 const TIMEOUT_MS_DEFAULT: u64 = 2000;
+const TIMEOUT_MS_THOROUGH: u64 = 5000;
+const JITTER_PERCENT_PROFILE: u8 = 30;
 
 const HOST_RETRY_DEFAULT: usize = 1;
 const REQUEST_MS_INTERVAL: u64 = 10;
+const TX_THREADS_DEFAULT: usize = 1;
+const DNS_TIMEOUT_MS_DEFAULT: u64 = 2000;
+const DNS_THREADS_DEFAULT: usize = 4;
+const PROBE_TIMEOUT_MS_DEFAULT: u64 = 500;
+const SNMP_COMMUNITY_DEFAULT: &str = "public";
+const MAX_HOSTS_DEFAULT: u128 = 65536;
+
+const VLAN_ID_MIN: u16 = 1;
+const VLAN_ID_MAX: u16 = 4094;
+
+// Explicit padding targets a complete Ethernet frame (without FCS), from the
+// 60-byte minimum up to the standard MTU.
+const PAD_TO_MIN: usize = 60;
+const PAD_TO_MAX: usize = 1514;
+
+// IPv6 networks are far too large to be scanned as a whole, neighbor scans are
+// limited to 65536 addresses per target network.
+pub const IPV6_PREFIX_MIN: u8 = 112;
+
+// Each decoy multiplies the requests sent to every target, the decoy count is
+// kept low to avoid flooding the network.
+const DECOY_COUNT_MAX: usize = 8;
 
 const CLI_VERSION: &str = env!("CARGO_PKG_VERSION");
 
@@ -30,15 +62,144 @@ const EXAMPLES_HELP: &str = "EXAMPLES:
     # List network interfaces
     arp-scan -l
 
+    # List the interfaces that can be scanned, without loopback & down ones
+    arp-scan -l --ready-only
+
+    # Load default options from a TOML file, overridden by CLI options
+    arp-scan --config ./daily-scan.toml -o json
+
+    # Launch a scan on the interface with index 3 (see interface list)
+    arp-scan --interface-index 3
+
+    # Launch a scan on the first Ethernet interface, whatever its name
+    arp-scan --interface-regex '^(eth|en)'
+
+    # Scan the networks of all interfaces on a multi-homed host
+    arp-scan --all-interfaces -o json
+
+    # Launch a scan on positional targets (network, range & single host)
+    arp-scan -i eth0 192.168.1.0/28 10.0.0.1-10.0.0.50 10.0.1.8
+
+    # Launch a scan on a comma-separated list of hosts
+    arp-scan -i eth0 10.0.0.1,10.0.0.5,10.0.0.40-10.0.0.45
+
     # Launch a scan on a specific range
     arp-scan -i eth0 -n 10.37.3.1,10.37.4.55/24
 
+    # Launch a scan on a range, without probing the gateway
+    arp-scan -n 192.168.1.0/24 -x 192.168.1.1
+
+    # Spread the requests over a large range, without buffering the targets
+    arp-scan -i eth0 -n 10.0.0.0/16 --order stride --seed 42
+
+    # Hide each request among 3 decoys with spoofed source IPv4 (lab research)
+    arp-scan -i eth0 --decoys 3
+
+    # Only report the devices that are not in the inventory of known MACs
+    arp-scan -i eth0 --known-macs ./inventory.txt --only-new-macs
+
+    # Rotate the source MAC of requests through a pool, in a random order
+    arp-scan -i eth0 --mac-pool ./macs.txt --random --seed 42
+
     # Launch a scan on a specific interface with a custom IP and stealth profile
     arp-scan -i eth0 --source-ip 192.168.0.42 --profile stealth
 
+    # Listen to ARP traffic for 1 minute, without sending requests
+    arp-scan -i eth0 --passive -t 1m
+
+    # Repeat a scan every 30 seconds to monitor the network
+    arp-scan -i eth0 --watch 30
+
+    # Never let an automated scan run for more than 2 minutes
+    arp-scan -i eth0 --retry 5 --max-duration 120
+
+    # Only report the hosts that appeared or disappeared after the first round
+    arp-scan -i eth0 --watch 30 --changes-only -o json
+
+    # Monitor the network with a single row per host, updated each round
+    arp-scan -i eth0 --watch 30 --live
+
     # Launch a scan on VLAN 45 with JSON output
     arp-scan -Q 45 -o json
 
+    # Write an HTML report of the scan results
+    arp-scan -o html --output-file report.html
+
+    # Fail with exit code 2 in scripts when no host responds
+    arp-scan -q --fail-if-empty || echo no host found
+
+    # Check the crafted ARP request (VLAN, padding, ...) without root access
+    arp-scan --self-test -Q 45 --pad-to 64
+
+    # Print the addresses that would be scanned, without sending requests
+    arp-scan 192.168.1.0/24 -x 192.168.1.0/28 --dry-run
+
+    # Print a custom line for each host, for a downstream tool
+    arp-scan --format '{ipv4}\\t{mac}\\t{vendor}'
+
+    # Keep a time series of the network, one JSON line per round
+    arp-scan -i eth0 --watch 60 --append-jsonl ./history.jsonl
+
+    # Display the results table and export them in JSON
+    arp-scan -o plain -o json:results.json
+
+    # Keep the results table, and extract the host count in a script
+    arp-scan --plain-summary 2>&1 >/dev/null | grep ^SUMMARY
+
+    # Find the hosts with SSH or HTTP services
+    arp-scan -i eth0 --probe-ports 22,80,443
+
+    # Feed a SIEM with each host as soon as it is discovered
+    arp-scan -i eth0 --watch 60 --webhook https://siem.local/hooks/arp
+
+    # Zero-traffic inventory from the ARP cache and the observed traffic
+    arp-scan -i eth0 --passive --include-cache -t 30s
+
+    # Discover IPv6 neighbors with Neighbor Solicitations
+    arp-scan -i eth0 --ipv6 fd00::/120
+
+    # Only report Raspberry Pi devices
+    arp-scan --mac-prefix B8:27:EB --mac-prefix DC:A6:32
+
+    # Wake a host up before scanning the network
+    arp-scan --wake 00:11:22:33:44:55
+
+    # Retry twice, doubling the request interval on each retry pass
+    arp-scan -r 3 --retry-backoff 2
+
+    # Send at most 50 ARP requests
+    arp-scan -c 50
+
+    # Scan a /12 network, above the default limit of 65536 hosts
+    arp-scan 172.16.0.0/12 --force
+
+    # Check if any host is alive on the network
+    arp-scan --first
+
+    # Log every received frame on stderr, with the reason it was ignored
+    arp-scan -vv -o json > results.json
+
+    # Only display the result table, without banner & progress
+    arp-scan -q > scan.log
+
+    # Display the fastest hosts first in the results table
+    arp-scan --sort latency --show-latency
+
+    # Save the sent & received frames for Wireshark
+    arp-scan -i eth0 --pcap scan.pcap
+
+    # Report hosts added, removed or changed since the last nightly scan
+    arp-scan --compare nightly.json
+
+    # Export readable JSON results
+    arp-scan -o json --pretty
+
+    # Write a Markdown table to paste in a ticket
+    arp-scan -o md:hosts.md
+
+    # Write scan metrics for the node_exporter textfile collector
+    arp-scan -o prometheus --output-file /var/lib/node_exporter/arp_scan.prom
+
 ";
 
 /**
@@ -49,6 +210,12 @@ pub fn build_args() -> Command {
     Command::new("arp-scan")
         .version(CLI_VERSION)
         .about("A minimalistic ARP scan tool written in Rust")
+        .arg(
+            Arg::new("config")
+                .long("config")
+                .value_name("FILE_PATH")
+                .help("TOML file with default options (default to ~/.config/arp-scan-rs/config.toml)"),
+        )
         .arg(
             Arg::new("profile")
                 .short('p')
@@ -57,6 +224,8 @@ pub fn build_args() -> Command {
                 .value_parser([
                     PossibleValue::new("default").help("Default scan profile"),
                     PossibleValue::new("fast").help("Fast ARP scans (less accurate)"),
+                    PossibleValue::new("thorough")
+                        .help("Longer timeout, more retries (most accurate)"),
                     PossibleValue::new("stealth").help("Slower scans (minimize impact)"),
                     PossibleValue::new("chaos").help("Randomly-selected values"),
                 ])
@@ -64,9 +233,11 @@ pub fn build_args() -> Command {
         )
         .arg(
             Arg::new("index")
-                .long("index")
+                .long("interface-index")
+                .alias("index")
                 .value_name("INTERFACE_INDEX")
-                .help("Network interface index (defaults to first 'up' interface with IPv4)"),
+                .conflicts_with("interface")
+                .help("Network interface index, as shown in the interface list"),
         )
         .arg(
             Arg::new("interface")
@@ -75,12 +246,49 @@ pub fn build_args() -> Command {
                 .value_name("INTERFACE_NAME")
                 .help("Network interface name (defaults to first 'up' interface with IPv4)"),
         )
+        .arg(
+            Arg::new("interface_regex")
+                .long("interface-regex")
+                .value_name("PATTERN")
+                .conflicts_with_all(["interface", "index"])
+                .help("Select the first eligible interface whose name matches a regex"),
+        )
+        .arg(
+            Arg::new("all_interfaces")
+                .long("all-interfaces")
+                .alias("repeat-interface")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all([
+                    "interface",
+                    "index",
+                    "interface_regex",
+                    "network",
+                    "file",
+                    "targets",
+                ])
+                .help("Scan all eligible interfaces (up, not loopback, with IPv4) one after another"),
+        )
         .arg(
             Arg::new("network")
                 .short('n')
                 .long("network")
                 .value_name("NETWORK_RANGE")
-                .help("Network range to scan (defaults to first IPv4 network on the interface)"),
+                .help("Network range to scan (defaults to all IPv4 networks on the interface)"),
+        )
+        .arg(
+            Arg::new("primary_only")
+                .long("primary-only")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["network", "file", "targets"])
+                .help("Only scan the first network of the interface (skip secondary subnets)"),
+        )
+        .arg(
+            Arg::new("exclude")
+                .short('x')
+                .long("exclude")
+                .value_name("NETWORK_RANGE")
+                .action(ArgAction::Append)
+                .help("Exclude an IPv4 address or range from the scan (repeatable)"),
         )
         .arg(
             Arg::new("file")
@@ -97,6 +305,12 @@ pub fn build_args() -> Command {
                 .value_name("TIMEOUT_DURATION")
                 .help("ARP response timeout (2000ms)"),
         )
+        .arg(
+            Arg::new("adaptive_timeout")
+                .long("adaptive-timeout")
+                .action(ArgAction::SetTrue)
+                .help("Adapt the response timeout to the observed round-trip times"),
+        )
         .arg(
             Arg::new("source_ip")
                 .short('S')
@@ -115,7 +329,55 @@ pub fn build_args() -> Command {
             Arg::new("source_mac")
                 .long("source-mac")
                 .value_name("SOURCE_MAC")
-                .help("Source MAC address for requests (default to 00:00:00:00:00:00)"),
+                .help("Source MAC address for requests (default to interface MAC)"),
+        )
+        .arg(
+            Arg::new("mac_pool")
+                .long("mac-pool")
+                .value_name("FILE_PATH")
+                .conflicts_with_all(["source_mac", "passive"])
+                .help("Rotate the source MAC of requests through a file of MAC addresses"),
+        )
+        .arg(
+            Arg::new("decoys")
+                .long("decoys")
+                .value_name("COUNT")
+                .conflicts_with_all(["passive", "ipv6"])
+                .help("Mix COUNT decoy requests with spoofed source IPv4 among each request (detection research only)"),
+        )
+        .arg(
+            Arg::new("known_macs")
+                .long("known-macs")
+                .value_name("FILE_PATH")
+                .help("Highlight the hosts whose MAC address is not in a file of known MACs"),
+        )
+        .arg(
+            Arg::new("only_new_macs")
+                .long("only-new-macs")
+                .action(ArgAction::SetTrue)
+                .requires("known_macs")
+                .help("Only report the hosts whose MAC address is not known"),
+        )
+        .arg(
+            Arg::new("wake")
+                .long("wake")
+                .value_name("MAC")
+                .action(ArgAction::Append)
+                .help("Send a Wake-on-LAN magic packet before scanning (repeatable)"),
+        )
+        .arg(
+            Arg::new("wake_only")
+                .long("wake-only")
+                .action(ArgAction::SetTrue)
+                .requires("wake")
+                .help("Only send Wake-on-LAN packets, without scanning"),
+        )
+        .arg(
+            Arg::new("mac_prefix")
+                .long("mac-prefix")
+                .value_name("OUI")
+                .action(ArgAction::Append)
+                .help("Only report hosts matching a MAC prefix, e.g. B8:27:EB (repeatable)"),
         )
         .arg(
             Arg::new("numeric")
@@ -128,7 +390,7 @@ pub fn build_args() -> Command {
                 .short('Q')
                 .long("vlan")
                 .value_name("VLAN_ID")
-                .help("Send using 802.1Q with VLAN ID"),
+                .help("Send using 802.1Q with VLAN ID (1-4094)"),
         )
         .arg(
             Arg::new("retry_count")
@@ -137,6 +399,32 @@ pub fn build_args() -> Command {
                 .value_name("RETRY_COUNT")
                 .help("Host retry attempt count (default to 1)"),
         )
+        .arg(
+            Arg::new("count")
+                .short('c')
+                .long("count")
+                .value_name("REQUEST_COUNT")
+                .help("Maximum number of ARP requests sent, including retries"),
+        )
+        .arg(
+            Arg::new("retry_all")
+                .long("retry-all")
+                .action(ArgAction::SetTrue)
+                .help("Retry all targets, including hosts that already responded"),
+        )
+        .arg(
+            Arg::new("retry_on_silence")
+                .long("retry-on-silence")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("retry_all")
+                .help("Only iterate over the unanswered addresses on retry passes"),
+        )
+        .arg(
+            Arg::new("retry_backoff")
+                .long("retry-backoff")
+                .value_name("FACTOR")
+                .help("Multiply the request interval by FACTOR on each retry pass (default to 1)"),
+        )
         .arg(
             Arg::new("random")
                 .short('R')
@@ -144,20 +432,205 @@ pub fn build_args() -> Command {
                 .action(ArgAction::SetTrue)
                 .help("Randomize the target list"),
         )
+        .arg(
+            Arg::new("order")
+                .long("order")
+                .value_name("TARGET_ORDER")
+                .value_parser([
+                    PossibleValue::new("sequential").help("Ascending address order (default)"),
+                    PossibleValue::new("shuffle").help("Shuffle the targets (same as '--random')"),
+                    PossibleValue::new("stride").help("Spread the targets with a constant stride"),
+                ])
+                .conflicts_with("random")
+                .help("Order in which the targets are requested"),
+        )
         .arg(
             Arg::new("interval")
                 .short('I')
                 .long("interval")
                 .value_name("INTERVAL_DURATION")
-                .help("Milliseconds between ARP requests (defaults to 10ms)"),
+                .help("Duration between ARP requests (defaults to 10ms)"),
+        )
+        .arg(
+            Arg::new("jitter")
+                .long("jitter")
+                .value_name("PERCENT")
+                .help("Randomize each interval between ARP requests within ±PERCENT"),
+        )
+        .arg(
+            Arg::new("seed")
+                .long("seed")
+                .value_name("SEED")
+                .help("Seed for random values, making scans reproducible"),
         )
         .arg(
             Arg::new("bandwidth")
                 .short('B')
                 .long("bandwidth")
-                .value_name("BITS")
+                .value_name("BANDWIDTH")
                 .conflicts_with("interval")
-                .help("Limit scan bandwidth (bits/second)"),
+                .help("Limit scan bandwidth (bits/second, 500k, 1M, 2MB, ...)"),
+        )
+        .arg(
+            Arg::new("max_hosts")
+                .long("max-hosts")
+                .value_name("HOST_COUNT")
+                .help("Refuse to scan more target addresses than this (default to 65536)"),
+        )
+        .arg(
+            Arg::new("force")
+                .long("force")
+                .action(ArgAction::SetTrue)
+                .help("Scan the targets even if they exceed the maximum host count"),
+        )
+        .arg(
+            Arg::new("passive")
+                .long("passive")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all([
+                    "retry_count",
+                    "retry_backoff",
+                    "count",
+                    "interval",
+                    "bandwidth",
+                    "tx_threads",
+                    "jitter",
+                ])
+                .help("Listen to ARP traffic without sending any request"),
+        )
+        .arg(
+            Arg::new("include_cache")
+                .long("include-cache")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("ipv6")
+                .help("Add the system ARP cache entries to the results (Linux only)"),
+        )
+        .arg(
+            Arg::new("ipv6")
+                .short('6')
+                .long("ipv6")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all([
+                    "passive",
+                    "watch",
+                    "vlan",
+                    "source_ip",
+                    "hw_type",
+                    "hw_addr",
+                    "proto_type",
+                    "proto_addr",
+                    "arp_operation",
+                    "pad_to",
+                ])
+                .help("Scan IPv6 neighbors with ICMPv6 Neighbor Solicitations"),
+        )
+        .arg(
+            Arg::new("sample")
+                .long("sample")
+                .value_name("PERCENT")
+                .conflicts_with("passive")
+                .help("Only scan a random sample of the target addresses"),
+        )
+        .arg(
+            Arg::new("dry_run")
+                .long("dry-run")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["passive", "watch"])
+                .help("Print the target IPv4 addresses and exit, without scanning"),
+        )
+        .arg(
+            Arg::new("first")
+                .short('1')
+                .long("first")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("watch")
+                .help("Stop the scan as soon as one host replies"),
+        )
+        .arg(
+            Arg::new("watch")
+                .short('w')
+                .long("watch")
+                .value_name("SECONDS")
+                .help("Repeat the scan every N seconds, until interrupted"),
+        )
+        .arg(
+            Arg::new("max_duration")
+                .long("max-duration")
+                .value_name("SECONDS")
+                .help("End the scan with partial results after N seconds"),
+        )
+        .arg(
+            Arg::new("live")
+                .long("live")
+                .action(ArgAction::SetTrue)
+                .requires("watch")
+                .help("Update a single row per host across watch rounds"),
+        )
+        .arg(
+            Arg::new("changes_only")
+                .long("changes-only")
+                .action(ArgAction::SetTrue)
+                .requires("watch")
+                .conflicts_with("live")
+                .help("Only report the hosts that changed since the previous watch round"),
+        )
+        .arg(
+            Arg::new("dns_timeout")
+                .long("dns-timeout")
+                .value_name("MS")
+                .help("Timeout of each reverse DNS lookup (2000ms)"),
+        )
+        .arg(
+            Arg::new("dns_threads")
+                .long("dns-threads")
+                .value_name("THREAD_COUNT")
+                .help("Number of concurrent reverse DNS lookups (default to 4)"),
+        )
+        .arg(
+            Arg::new("probe_ports")
+                .long("probe-ports")
+                .value_name("PORT_LIST")
+                .conflicts_with("passive")
+                .help("Probe TCP ports on discovered hosts (e.g. 22,80,8000-8080)"),
+        )
+        .arg(
+            Arg::new("probe_timeout")
+                .long("probe-timeout")
+                .value_name("MS")
+                .requires("probe_ports")
+                .help("Timeout of each TCP port probe (500ms)"),
+        )
+        .arg(
+            Arg::new("snmp")
+                .long("snmp")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("passive")
+                .help("Query the SNMP system name (sysName) of discovered hosts"),
+        )
+        .arg(
+            Arg::new("snmp_community")
+                .long("snmp-community")
+                .value_name("COMMUNITY")
+                .requires("snmp")
+                .help("SNMP community of the sysName queries (default to public)"),
+        )
+        .arg(
+            Arg::new("webhook")
+                .long("webhook")
+                .value_name("URL")
+                .help("POST each discovered host as a JSON object to an HTTP endpoint"),
+        )
+        .arg(
+            Arg::new("rcv_timeout")
+                .long("rcv-timeout")
+                .value_name("RCV_TIMEOUT_DURATION")
+                .help("Read timeout of the datalink receiver (defaults to 500ms)"),
+        )
+        .arg(
+            Arg::new("tx_threads")
+                .long("tx-threads")
+                .value_name("THREAD_COUNT")
+                .help("Number of threads sending ARP requests (default to 1)"),
         )
         .arg(
             Arg::new("oui-file")
@@ -166,32 +639,188 @@ pub fn build_args() -> Command {
                 .default_value("/usr/share/arp-scan/ieee-oui.csv")
                 .help("Path to custom IEEE OUI CSV file for vendor lookup"),
         )
+        .arg(
+            Arg::new("no_oui_autodetect")
+                .long("no-oui-autodetect")
+                .action(ArgAction::SetTrue)
+                .help("Do not search system vendor databases when no OUI file is given"),
+        )
+        .arg(
+            Arg::new("show_latency")
+                .long("show-latency")
+                .action(ArgAction::SetTrue)
+                .help("Display the response latency of each host"),
+        )
+        .arg(
+            Arg::new("show_reply_count")
+                .long("show-reply-count")
+                .action(ArgAction::SetTrue)
+                .help("Display the number of replies received from each host"),
+        )
+        .arg(
+            Arg::new("pcap")
+                .long("pcap")
+                .value_name("FILE_PATH")
+                .help("Write all sent & received frames in a pcap capture file"),
+        )
+        .arg(
+            Arg::new("compare")
+                .long("compare")
+                .value_name("FILE_PATH")
+                .help("Compare the results with a previous JSON or NDJSON export"),
+        )
+        .arg(
+            Arg::new("sort")
+                .long("sort")
+                .value_name("SORT_FIELD")
+                .value_parser([
+                    PossibleValue::new("ipv4").help("Sort by IP address (default)"),
+                    PossibleValue::new("mac").help("Sort by MAC address"),
+                    PossibleValue::new("vendor").help("Sort by vendor name"),
+                    PossibleValue::new("latency").help("Sort by response latency"),
+                ])
+                .help("Field used to sort the scan results"),
+        )
+        .arg(
+            Arg::new("show_oui")
+                .long("show-oui")
+                .action(ArgAction::SetTrue)
+                .help("Display the OUI prefix next to unknown vendors"),
+        )
+        .arg(
+            Arg::new("vendor_summary")
+                .long("vendor-summary")
+                .action(ArgAction::SetTrue)
+                .help("Display the number of hosts of each vendor after the results"),
+        )
+        .arg(
+            Arg::new("plain_summary")
+                .long("plain-summary")
+                .action(ArgAction::SetTrue)
+                .help("Print a single-line machine-parseable summary on stderr"),
+        )
+        .arg(
+            Arg::new("update_oui")
+                .long("update-oui")
+                .value_name("FILE_PATH")
+                .num_args(0..=1)
+                .help("Download the IEEE OUI database (default to the OUI file or user cache path) and exit"),
+        )
         .arg(
             Arg::new("list")
                 .short('l')
                 .long("list")
                 .action(ArgAction::SetTrue)
-                .exclusive(true)
                 .help("List network interfaces and exit"),
         )
+        .arg(
+            Arg::new("ready_only")
+                .long("ready-only")
+                .action(ArgAction::SetTrue)
+                .requires("list")
+                .help("Only list the interfaces eligible for scans"),
+        )
         .arg(
             Arg::new("output")
                 .short('o')
                 .long("output")
-                .value_name("FORMAT")
-                .value_parser([
-                    PossibleValue::new("plain").help("Verbose output with table"),
-                    PossibleValue::new("json").help("JSON format"),
-                    PossibleValue::new("yaml").help("YAML format"),
-                    PossibleValue::new("csv").help("CSV format"),
-                ])
-                .help("Define output format"),
+                .value_name("FORMAT[:FILE]")
+                .action(ArgAction::Append)
+                .help(format!(
+                    "Define output format ({}), optionally written to a file, can be repeated",
+                    describe_output_formats(false)
+                )),
+        )
+        .arg(
+            Arg::new("output_template")
+                .long("format")
+                .alias("output-template")
+                .value_name("TEMPLATE")
+                .help("Replace the plain table by one line per host, such as '{ipv4}\\t{mac}\\t{vendor}'"),
+        )
+        .arg(
+            Arg::new("output_file")
+                .long("output-file")
+                .value_name("FILE_PATH")
+                .help(format!(
+                    "Write exported results to a file ({} formats)",
+                    describe_output_formats(true)
+                )),
+        )
+        .arg(
+            Arg::new("append_jsonl")
+                .long("append-jsonl")
+                .value_name("FILE_PATH")
+                .help("Append the results of each scan as a JSON line to a file"),
+        )
+        .arg(
+            Arg::new("fail_if_empty")
+                .long("fail-if-empty")
+                .action(ArgAction::SetTrue)
+                .help("Exit with code 2 if no host has been found"),
+        )
+        .arg(
+            Arg::new("quiet")
+                .short('q')
+                .long("quiet")
+                .action(ArgAction::SetTrue)
+                .help("Only display the results, without banner & progress"),
+        )
+        .arg(
+            Arg::new("no_banner")
+                .long("no-banner")
+                .action(ArgAction::SetTrue)
+                .help("Do not display the banner, but keep scan details & progress"),
+        )
+        .arg(
+            Arg::new("verbose")
+                .short('v')
+                .long("verbose")
+                .action(ArgAction::Count)
+                .help("Log each new host on stderr (-v), or every received frame (-vv)"),
+        )
+        .arg(
+            Arg::new("pretty")
+                .long("pretty")
+                .action(ArgAction::SetTrue)
+                .help("Indent the JSON output for readability"),
+        )
+        .arg(
+            Arg::new("sign")
+                .long("sign")
+                .action(ArgAction::SetTrue)
+                .help("Add a SHA-256 hash of the results to JSON & YAML outputs"),
+        )
+        .arg(
+            Arg::new("per_network_summary")
+                .long("per-network-summary")
+                .action(ArgAction::SetTrue)
+                .help("Display the number of hosts found in each scanned network"),
+        )
+        .arg(
+            Arg::new("compact")
+                .long("compact")
+                .action(ArgAction::SetTrue)
+                .help("Truncate the results table to fit the terminal width"),
+        )
+        .arg(
+            Arg::new("width")
+                .long("width")
+                .value_name("COLUMNS")
+                .requires("compact")
+                .help("Width of the compact results table (defaults to the terminal width)"),
+        )
+        .arg(
+            Arg::new("no_color")
+                .long("no-color")
+                .action(ArgAction::SetTrue)
+                .help("Disable colors in the terminal output"),
         )
         .arg(
             Arg::new("hw_type")
                 .long("hw-type")
                 .value_name("HW_TYPE")
-                .help("Custom ARP hardware field"),
+                .help("Custom ARP hardware type, decimal or hexadecimal (default: 1, Ethernet)"),
         )
         .arg(
             Arg::new("hw_addr")
@@ -203,7 +832,7 @@ pub fn build_args() -> Command {
             Arg::new("proto_type")
                 .long("proto-type")
                 .value_name("PROTO_TYPE")
-                .help("Custom ARP proto type"),
+                .help("Custom ARP protocol type, decimal or hexadecimal (default: 0x0800, IPv4)"),
         )
         .arg(
             Arg::new("proto_addr")
@@ -215,7 +844,27 @@ pub fn build_args() -> Command {
             Arg::new("arp_operation")
                 .long("arp-op")
                 .value_name("OPERATION_ID")
-                .help("Custom ARP operation ID"),
+                .help("Custom ARP operation ID (1 or 'request', 2 or 'reply')"),
+        )
+        .arg(
+            Arg::new("pad_to")
+                .long("pad-to")
+                .value_name("BYTES")
+                .help("Pad ARP request frames to the given length (default: driver padding)"),
+        )
+        .arg(
+            Arg::new("pad_byte")
+                .long("pad-byte")
+                .value_name("BYTE")
+                .requires("pad_to")
+                .help("Fill byte of the ARP request padding, decimal or hexadecimal (default: 0)"),
+        )
+        .arg(
+            Arg::new("self_test")
+                .long("self-test")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["ipv6", "passive"])
+                .help("Build & check a sample ARP request without any interface, then exit"),
         )
         .arg(
             Arg::new("packet_help")
@@ -224,51 +873,278 @@ pub fn build_args() -> Command {
                 .exclusive(true)
                 .help("Print details about an ARP packet and exit"),
         )
+        .arg(
+            Arg::new("targets")
+                .value_name("TARGETS")
+                .num_args(1..)
+                .conflicts_with_all(["network", "file"])
+                .help("Targets to scan (192.168.1.0/24, 10.0.0.1-10.0.0.50, 10.0.0.8, ...)"),
+        )
         .after_help(EXAMPLES_HELP)
 }
 
+/**
+ * Find the output format of the interface list ('--list'), which is the first
+ * requested output format. The list is only exported in JSON, other formats
+ * fall back to the plain table.
+ */
+pub fn compute_list_format(matches: &ArgMatches) -> OutputFormat {
+    let first_format = matches
+        .get_many::<String>("output")
+        .into_iter()
+        .flatten()
+        .next()
+        .and_then(|output_text| {
+            let format_text = output_text.split(':').next().unwrap_or_default();
+            ScanOptions::parse_output_format(format_text)
+        });
+
+    match first_format {
+        Some(OutputFormat::Json) => OutputFormat::Json,
+        _ => OutputFormat::Plain,
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum OutputFormat {
     Plain,
     Json,
     Yaml,
     Csv,
+    Ndjson,
+    Html,
+    Markdown,
+    Prometheus,
+    Xml,
+    SummaryJson,
 }
 
-pub enum ProfileType {
-    Default,
-    Fast,
-    Stealth,
-    Chaos,
+/**
+ * Names of the output formats given to '--output', in the order of the help
+ * messages ('text' and 'md' are also accepted as aliases).
+ */
+const OUTPUT_FORMATS: [(&str, OutputFormat); 10] = [
+    ("plain", OutputFormat::Plain),
+    ("json", OutputFormat::Json),
+    ("yaml", OutputFormat::Yaml),
+    ("csv", OutputFormat::Csv),
+    ("ndjson", OutputFormat::Ndjson),
+    ("html", OutputFormat::Html),
+    ("markdown", OutputFormat::Markdown),
+    ("prometheus", OutputFormat::Prometheus),
+    ("xml", OutputFormat::Xml),
+    ("summary-json", OutputFormat::SummaryJson),
+];
+
+impl OutputFormat {
+    /**
+     * Plain & streamed outputs are printed during the whole scan process, so
+     * they can't be written in an output file.
+     */
+    fn is_streamed(&self) -> bool {
+        matches!(self, OutputFormat::Plain | OutputFormat::Ndjson)
+    }
 }
 
-pub enum ScanTiming {
-    Interval(u64),
-    Bandwidth(u64),
+/**
+ * List the output format names for help & error messages (e.g.
+ * 'json/yaml/csv'), optionally restricted to the exported formats.
+ */
+fn describe_output_formats(is_export_only: bool) -> String {
+    OUTPUT_FORMATS
+        .iter()
+        .filter(|(_, format)| !is_export_only || !format.is_streamed())
+        .map(|(name, _)| *name)
+        .collect::<Vec<&str>>()
+        .join("/")
+}
+
+/**
+ * A requested output, printed on stdout when no file is given. Several
+ * outputs can be requested at once (e.g. a plain table and a JSON file).
+ */
+#[derive(Debug, PartialEq)]
+pub struct OutputTarget {
+    pub format: OutputFormat,
+    pub file: Option<String>,
+}
+
+/**
+ * Field used to order the scan results, shared by the table and all exported
+ * formats so that every output lists the hosts in the same order.
+ */
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SortField {
+    Ipv4,
+    Mac,
+    Vendor,
+    Latency,
+}
+
+/**
+ * The order in which the target addresses are requested. Neither the shuffle
+ * nor the stride order stores the target addresses, so that huge ranges can
+ * be randomized (see the NetworkIterator).
+ */
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum TargetOrder {
+    Sequential,
+    Shuffle,
+    Stride,
+}
+
+pub enum ProfileType {
+    Default,
+    Fast,
+    Thorough,
+    Stealth,
+    Chaos,
+}
+
+/**
+ * A coherent bundle of scan options set by a profile. These values are only
+ * defaults: each one is overridden by the matching CLI option, so that a
+ * profile can still be fine-tuned (e.g. '--profile stealth -I 50ms').
+ */
+struct ProfileDefaults {
+    timeout_ms: u64,
+    interval_ms: u64,
+    retry_count: usize,
+    jitter_percent: Option<u8>,
+    randomize_targets: bool,
+    resolve_hostname: bool,
+}
+
+impl ProfileType {
+    fn defaults(&self) -> ProfileDefaults {
+        let default_options = ProfileDefaults {
+            timeout_ms: TIMEOUT_MS_DEFAULT,
+            interval_ms: REQUEST_MS_INTERVAL,
+            retry_count: HOST_RETRY_DEFAULT,
+            jitter_percent: None,
+            randomize_targets: false,
+            resolve_hostname: true,
+        };
+
+        match self {
+            ProfileType::Default => default_options,
+            ProfileType::Fast => ProfileDefaults {
+                timeout_ms: TIMEOUT_MS_FAST,
+                interval_ms: 0,
+                ..default_options
+            },
+            ProfileType::Thorough => ProfileDefaults {
+                timeout_ms: TIMEOUT_MS_THOROUGH,
+                retry_count: HOST_RETRY_DEFAULT * 3,
+                jitter_percent: Some(JITTER_PERCENT_PROFILE),
+                ..default_options
+            },
+            // Hostnames are not resolved, to avoid any DNS query towards
+            // the scanned hosts.
+            ProfileType::Stealth => ProfileDefaults {
+                interval_ms: REQUEST_MS_INTERVAL * 10,
+                jitter_percent: Some(JITTER_PERCENT_PROFILE),
+                randomize_targets: true,
+                resolve_hostname: false,
+                ..default_options
+            },
+            ProfileType::Chaos => ProfileDefaults {
+                retry_count: HOST_RETRY_DEFAULT * 2,
+                randomize_targets: true,
+                ..default_options
+            },
+        }
+    }
+}
+
+pub enum ScanTiming {
+    Interval(u64),
+    Bandwidth(u64),
 }
 
 pub struct ScanOptions {
     #[allow(dead_code)]
     pub profile: ProfileType,
     pub interface_name: Option<String>,
+    pub interface_pattern: Option<Regex>,
     pub interface_index: Option<u32>,
+    pub all_interfaces: bool,
     pub network_range: Option<Vec<ipnetwork::IpNetwork>>,
+    pub excluded_networks: Vec<ipnetwork::IpNetwork>,
     pub timeout_ms: u64,
+    pub adaptive_timeout: bool,
     pub resolve_hostname: bool,
+    pub dns_timeout_ms: u64,
+    pub dns_threads: usize,
+    pub probe_ports: Vec<u16>,
+    pub probe_timeout_ms: u64,
+    pub snmp_community: Option<String>,
+    pub webhook_url: Option<String>,
     pub source_ipv4: Option<Ipv4Addr>,
     pub source_mac: Option<MacAddr>,
+    pub mac_pool: Vec<MacAddr>,
+    pub decoy_count: usize,
+    pub known_macs: Option<HashSet<MacAddr>>,
+    pub only_new_macs: bool,
+    pub wake_targets: Vec<MacAddr>,
+    pub wake_only: bool,
+    pub mac_prefixes: Vec<[u8; 3]>,
     pub destination_mac: Option<MacAddr>,
     pub vlan_id: Option<u16>,
     pub retry_count: usize,
+    pub retry_all: bool,
+    pub retry_on_silence: bool,
+    pub retry_backoff: f64,
+    pub request_limit: Option<usize>,
+    pub max_hosts: Option<u128>,
+    pub tx_threads: usize,
+    pub rcv_timeout_ms: u64,
     pub scan_timing: ScanTiming,
-    pub randomize_targets: bool,
-    pub output: OutputFormat,
+    pub passive: bool,
+    pub include_cache: bool,
+    pub primary_only: bool,
+    pub ipv6: bool,
+    pub dry_run: bool,
+    pub stop_on_first: bool,
+    pub target_order: TargetOrder,
+    pub jitter_percent: Option<u8>,
+    pub seed: Option<u64>,
+    pub sample_percent: Option<f64>,
+    pub watch_interval_ms: Option<u64>,
+    pub changes_only: bool,
+    pub max_duration_ms: Option<u64>,
+    pub live_display: bool,
+    pub outputs: Vec<OutputTarget>,
+    pub output_template: Option<Vec<utils::TemplateToken>>,
+    pub pretty_json: bool,
+    pub append_file: Option<String>,
+    pub sign_output: bool,
+    pub quiet: bool,
+    pub show_banner: bool,
+    pub verbose_level: u8,
+    pub fail_if_empty: bool,
+    pub color_enabled: bool,
     pub oui_file: String,
+    pub oui_update_file: Option<String>,
+    pub show_oui: bool,
+    pub vendor_summary: bool,
+    pub per_network_summary: bool,
+    pub plain_summary: bool,
+    pub compact_width: Option<usize>,
+    pub show_latency: bool,
+    pub show_reply_count: bool,
+    pub sort_field: SortField,
+    pub compare_file: Option<String>,
+    pub pcap_file: Option<String>,
     pub hw_type: Option<ArpHardwareType>,
     pub hw_addr: Option<u8>,
     pub proto_type: Option<EtherType>,
     pub proto_addr: Option<u8>,
     pub arp_operation: Option<ArpOperation>,
+    pub pad_to: Option<usize>,
+    pub pad_byte: u8,
     pub packet_help: bool,
+    pub self_test: bool,
 }
 
 impl ScanOptions {
@@ -319,92 +1195,581 @@ impl ScanOptions {
         Ok(Some(networks))
     }
 
+    /**
+     * Computes the networks given as positional targets. Each target may be a
+     * network (CIDR notation), a range of IPv4 addresses (with a dash) or a
+     * single IPv4 address. Ranges are converted to the minimal list of networks
+     * covering all addresses of the range. A single argument may also hold a
+     * comma-separated list of targets, each one being parsed on its own.
+     */
+    fn compute_target_networks(target_values: Vec<&String>) -> Result<Vec<IpNetwork>, String> {
+        let mut networks: Vec<IpNetwork> = vec![];
+        let target_tokens = target_values
+            .into_iter()
+            .flat_map(|target_value| target_value.split(','))
+            .map(str::trim)
+            .filter(|target_text| !target_text.is_empty());
+        for target_text in target_tokens {
+            let target_networks = ScanOptions::parse_target(target_text)
+                .map_err(|err| format!("Invalid target '{}' ({})", target_text, err))?;
+            networks.extend(target_networks);
+        }
+        Ok(ScanOptions::dedup_networks(networks))
+    }
+
+    /**
+     * Remove the networks already covered by another target, so that each
+     * address is only scanned once (e.g. a host given next to its network).
+     * CIDR networks either contain each other or do not overlap at all.
+     */
+    fn dedup_networks(networks: Vec<IpNetwork>) -> Vec<IpNetwork> {
+        let contains_network = |outer: &IpNetwork, inner: &IpNetwork| {
+            outer.prefix() <= inner.prefix() && outer.contains(inner.network())
+        };
+
+        let mut unique_networks: Vec<IpNetwork> = vec![];
+        for network in networks {
+            if unique_networks
+                .iter()
+                .any(|unique_network| contains_network(unique_network, &network))
+            {
+                continue;
+            }
+            unique_networks.retain(|unique_network| !contains_network(&network, unique_network));
+            unique_networks.push(network);
+        }
+        unique_networks
+    }
+
+    /**
+     * Computes the requested outputs, each one given as 'FORMAT' for stdout or
+     * 'FORMAT:FILE' for a file. The legacy '--output-file' option applies to
+     * a single output without destination. Plain & streamed outputs are
+     * printed during the whole scan process, only the exported formats can be
+     * written in an output file.
+     */
+    fn compute_output_targets(
+        output_values: Vec<&String>,
+        output_file: Option<&String>,
+    ) -> Result<Vec<OutputTarget>, String> {
+        let mut outputs: Vec<OutputTarget> = vec![];
+        for output_text in output_values {
+            let (format_text, file) = match output_text.split_once(':') {
+                Some((_, "")) => {
+                    return Err(format!("Expected a file path for output '{}'", output_text));
+                }
+                Some((format_text, file)) => (format_text, Some(file.to_string())),
+                None => (output_text.as_str(), None),
+            };
+            let format = ScanOptions::parse_output_format(format_text).ok_or_else(|| {
+                format!(
+                    "Expected correct output format ({})",
+                    describe_output_formats(false)
+                )
+            })?;
+            outputs.push(OutputTarget { format, file });
+        }
+
+        if outputs.is_empty() {
+            outputs.push(OutputTarget {
+                format: OutputFormat::Plain,
+                file: None,
+            });
+        }
+
+        if let Some(output_file) = output_file {
+            let mut stdout_outputs = outputs.iter_mut().filter(|output| output.file.is_none());
+            match (stdout_outputs.next(), stdout_outputs.next()) {
+                (Some(output), None) => output.file = Some(output_file.clone()),
+                _ => return Err("Expected a single output format with output file".to_string()),
+            }
+        }
+
+        let has_streamed_file = outputs
+            .iter()
+            .any(|output| output.file.is_some() && output.format.is_streamed());
+        if has_streamed_file {
+            return Err(format!(
+                "Expected an export format with output file ({})",
+                describe_output_formats(true)
+            ));
+        }
+
+        let stdout_count = outputs
+            .iter()
+            .filter(|output| output.file.is_none())
+            .count();
+        if stdout_count > 1 {
+            return Err("Expected a single output format on stdout".to_string());
+        }
+
+        Ok(outputs)
+    }
+
+    fn parse_output_format(format_text: &str) -> Option<OutputFormat> {
+        let format_text = match format_text {
+            "text" => "plain",
+            "md" => "markdown",
+            _ => format_text,
+        };
+        OUTPUT_FORMATS
+            .iter()
+            .find(|(name, _)| *name == format_text)
+            .map(|(_, format)| *format)
+    }
+
+    fn parse_target(target_text: &str) -> Result<Vec<IpNetwork>, String> {
+        match target_text.split_once('-') {
+            Some((start_text, end_text)) => {
+                let start_ipv4 = start_text
+                    .trim()
+                    .parse::<Ipv4Addr>()
+                    .map_err(|_| format!("invalid range start {}", start_text))?;
+                let end_ipv4 = end_text
+                    .trim()
+                    .parse::<Ipv4Addr>()
+                    .map_err(|_| format!("invalid range end {}", end_text))?;
+
+                if start_ipv4 > end_ipv4 {
+                    return Err("range start is after range end".to_string());
+                }
+                Ok(ScanOptions::convert_range_to_networks(start_ipv4, end_ipv4))
+            }
+            None => match IpNetwork::from_str(target_text) {
+                Ok(network) => Ok(vec![network]),
+                Err(err) => Err(err.to_string()),
+            },
+        }
+    }
+
+    /**
+     * Converts an inclusive range of IPv4 addresses to the minimal list of
+     * networks. At each step, the largest network aligned on the current
+     * address (and not going beyond the range end) is selected.
+     */
+    fn convert_range_to_networks(start_ipv4: Ipv4Addr, end_ipv4: Ipv4Addr) -> Vec<IpNetwork> {
+        let mut networks: Vec<IpNetwork> = vec![];
+        let mut current: u64 = u32::from(start_ipv4).into();
+        let end: u64 = u32::from(end_ipv4).into();
+
+        while current <= end {
+            let mut block_size: u64 = match current {
+                0 => 1 << 32,
+                _ => 1 << current.trailing_zeros().min(32),
+            };
+            while current + block_size - 1 > end {
+                block_size >>= 1;
+            }
+
+            let prefix = 32 - block_size.trailing_zeros() as u8;
+            let network_ipv4 = Ipv4Addr::from(current as u32);
+            let network = Ipv4Network::new(network_ipv4, prefix)
+                .expect("Internal prefix computation failed for IPv4 range");
+            networks.push(IpNetwork::V4(network));
+
+            current += block_size;
+        }
+
+        networks
+    }
+
+    /**
+     * Check that all requested networks match the scan mode: IPv4 networks
+     * for ARP scans, and IPv6 networks for neighbor scans. IPv6 networks are
+     * limited in size, since each address requires a single solicitation.
+     */
+    fn check_network_family(networks: &[IpNetwork], is_ipv6: bool) -> Result<(), String> {
+        for network in networks {
+            match (network, is_ipv6) {
+                (IpNetwork::V4(_), false) => {}
+                (IpNetwork::V6(_), false) => {
+                    return Err(format!("{} is an IPv6 network, use --ipv6", network));
+                }
+                (IpNetwork::V4(_), true) => {
+                    return Err(format!("{} is an IPv4 network, remove --ipv6", network));
+                }
+                (IpNetwork::V6(ipv6_network), true) => {
+                    if ipv6_network.prefix() < IPV6_PREFIX_MIN {
+                        return Err(format!(
+                            "{} is too large, IPv6 networks should be /{} or smaller",
+                            network, IPV6_PREFIX_MIN
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /**
+     * Computes the list of networks excluded from the scan. Each excluded
+     * value may either be a single IPv4 address or a network range, an empty
+     * list means that no target will be skipped.
+     */
+    fn compute_excluded_networks(exclude_values: Vec<&String>) -> Result<Vec<IpNetwork>, String> {
+        exclude_values
+            .iter()
+            .map(|exclude_text| {
+                IpNetwork::from_str(exclude_text)
+                    .map_err(|err| format!("Expected valid IPv4 excluded range ({})", err))
+            })
+            .collect()
+    }
+
+    /**
+     * Parse a 802.1Q VLAN identifier. The 0 and 4095 values are reserved by
+     * the standard, only identifiers between 1 and 4094 can be used.
+     */
+    fn parse_vlan_id(vlan_text: &str) -> Result<u16, String> {
+        match vlan_text.parse::<u16>() {
+            Ok(vlan_number) if (VLAN_ID_MIN..=VLAN_ID_MAX).contains(&vlan_number) => {
+                Ok(vlan_number)
+            }
+            Ok(vlan_number) => Err(format!(
+                "{} is out of range ({}-{})",
+                vlan_number, VLAN_ID_MIN, VLAN_ID_MAX
+            )),
+            Err(err) => Err(format!("{} is not a number ({})", vlan_text, err)),
+        }
+    }
+
+    /**
+     * Parse a 2-byte ARP type field (hardware or protocol type), given as a
+     * decimal number or as an hexadecimal number prefixed with '0x' (as found
+     * in the EtherType registry, e.g. '0x0800').
+     */
+    fn parse_type_number(type_text: &str) -> Result<u16, String> {
+        let parsed_number = match type_text
+            .strip_prefix("0x")
+            .or_else(|| type_text.strip_prefix("0X"))
+        {
+            Some(hex_text) => u16::from_str_radix(hex_text, 16),
+            None => type_text.parse::<u16>(),
+        };
+        parsed_number.map_err(|_| format!("{} does not fit in 16 bits", type_text))
+    }
+
+    /**
+     * Parse an ARP operation, given as a number or by name for the common
+     * operations ('request' or 'reply'). Other operation numbers are kept as
+     * is, to test how hosts handle unusual ARP packets.
+     */
+    fn parse_arp_operation(operation_text: &str) -> Result<ArpOperation, String> {
+        match operation_text.to_lowercase().as_str() {
+            "request" => Ok(ArpOperations::Request),
+            "reply" => Ok(ArpOperations::Reply),
+            _ => operation_text
+                .parse::<u16>()
+                .map(ArpOperation::new)
+                .map_err(|_| format!("{} is not a 16-bit number", operation_text)),
+        }
+    }
+
+    /**
+     * Parse a number of seconds into milliseconds. Values that would overflow
+     * once converted are rejected, instead of wrapping to a shorter duration.
+     */
+    fn parse_seconds_to_milliseconds(seconds_text: &str) -> Result<u64, String> {
+        let seconds = seconds_text
+            .parse::<u64>()
+            .map_err(|err| format!("{} is not a number ({})", seconds_text, err))?;
+        seconds
+            .checked_mul(1000)
+            .ok_or_else(|| format!("{} seconds is too large", seconds_text))
+    }
+
+    /**
+     * Parse the padded length of ARP request frames, which should be a valid
+     * Ethernet frame length (FCS excluded).
+     */
+    fn parse_pad_length(length_text: &str) -> Result<usize, String> {
+        match length_text.parse::<usize>() {
+            Ok(length) if (PAD_TO_MIN..=PAD_TO_MAX).contains(&length) => Ok(length),
+            _ => Err(format!(
+                "{} is not between {} and {} bytes",
+                length_text, PAD_TO_MIN, PAD_TO_MAX
+            )),
+        }
+    }
+
+    /**
+     * Parse a list of MAC addresses (source MAC pool, known MACs), one address
+     * per line. Empty lines and comments (starting with '#') are skipped, an
+     * invalid line is reported with its line number.
+     */
+    fn parse_mac_list(list_text: &str) -> Result<Vec<MacAddr>, String> {
+        let mut mac_list: Vec<MacAddr> = vec![];
+        for (line_index, line) in list_text.lines().enumerate() {
+            let mac_text = line.trim();
+            if mac_text.is_empty() || mac_text.starts_with('#') {
+                continue;
+            }
+            let mac_address = mac_text.parse::<MacAddr>().map_err(|_| {
+                format!(
+                    "{} is not a MAC address (line {})",
+                    mac_text,
+                    line_index + 1
+                )
+            })?;
+            mac_list.push(mac_address);
+        }
+
+        match mac_list.is_empty() {
+            true => Err("the file contains no MAC address".to_string()),
+            false => Ok(mac_list),
+        }
+    }
+
+    /**
+     * Parse a 3-byte OUI prefix, given with or without separators (e.g.
+     * 'B8:27:EB', 'b8-27-eb' or 'B827EB').
+     */
+    fn parse_mac_prefix(prefix_text: &str) -> Result<[u8; 3], String> {
+        let hex_text: String = prefix_text
+            .chars()
+            .filter(|character| !matches!(character, ':' | '-' | '.'))
+            .collect();
+
+        if hex_text.len() != 6
+            || !hex_text
+                .chars()
+                .all(|character| character.is_ascii_hexdigit())
+        {
+            return Err(format!("{} is not a 3-byte OUI prefix", prefix_text));
+        }
+
+        let mut prefix = [0u8; 3];
+        for (index, byte) in prefix.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex_text[index * 2..index * 2 + 2], 16)
+                .map_err(|err| format!("{} is not hexadecimal ({})", prefix_text, err))?;
+        }
+        Ok(prefix)
+    }
+
+    /**
+     * Parse a list of TCP ports, separated by commas. Each item is either a
+     * single port or an inclusive range (e.g. '22,80,8000-8010'). The ports
+     * are returned sorted and without duplicates.
+     */
+    fn parse_port_list(port_list: &str) -> Result<Vec<u16>, String> {
+        let parse_port = |port_text: &str| match port_text.trim().parse::<u16>() {
+            Ok(port) if port > 0 => Ok(port),
+            _ => Err(format!("{} is not a valid port", port_text.trim())),
+        };
+
+        let mut ports: Vec<u16> = vec![];
+        for port_item in port_list.split(',') {
+            match port_item.split_once('-') {
+                Some((start_text, end_text)) => {
+                    let (start_port, end_port) = (parse_port(start_text)?, parse_port(end_text)?);
+                    if start_port > end_port {
+                        return Err(format!("{} is not an increasing range", port_item));
+                    }
+                    ports.extend(start_port..=end_port);
+                }
+                None => ports.push(parse_port(port_item)?),
+            }
+        }
+
+        ports.sort_unstable();
+        ports.dedup();
+        Ok(ports)
+    }
+
     /**
      * Computes scan timing constraints, as requested by the user through CLI
      * arguments. The scan timing constraints will be either expressed in bandwidth
      * (bits per second) or interval between ARP requests (in milliseconds).
      */
-    fn compute_scan_timing(matches: &ArgMatches, profile: &ProfileType) -> ScanTiming {
+    fn compute_scan_timing(
+        matches: &ArgMatches,
+        profile_interval_ms: u64,
+    ) -> Result<ScanTiming, ScanError> {
         match (
             matches.get_one::<String>("bandwidth"),
             matches.get_one::<String>("interval"),
         ) {
-            (Some(bandwidth_text), None) => {
-                let bits_second: u64 = bandwidth_text.parse().unwrap_or_else(|err| {
-                    eprintln!("Expected positive number, {}", err);
-                    process::exit(1);
-                });
-                ScanTiming::Bandwidth(bits_second)
-            }
+            (Some(bandwidth_text), None) => parse_to_bits_per_second(bandwidth_text)
+                .map(ScanTiming::Bandwidth)
+                .map_err(|err| {
+                    ScanError::InvalidOption(format!("Expected correct bandwidth, {}", err))
+                }),
             (None, Some(interval_text)) => parse_to_milliseconds(interval_text)
                 .map(ScanTiming::Interval)
-                .unwrap_or_else(|err| {
-                    eprintln!("Expected correct interval, {}", err);
-                    process::exit(1);
+                .map_err(|err| {
+                    ScanError::InvalidOption(format!("Expected correct interval, {}", err))
                 }),
-            _ => match profile {
-                ProfileType::Stealth => ScanTiming::Interval(REQUEST_MS_INTERVAL * 2),
-                ProfileType::Fast => ScanTiming::Interval(0),
-                _ => ScanTiming::Interval(REQUEST_MS_INTERVAL),
-            },
+            _ => Ok(ScanTiming::Interval(profile_interval_ms)),
         }
     }
 
     /**
      * Build a new 'ScanOptions' struct that will be used in the whole CLI such
      * as the network level, the display details and more. The scan options reflect
-     * user requests for the CLI and should not be mutated.
+     * user requests for the CLI and should not be mutated. Invalid arguments
+     * are returned as errors, so that embedders never exit the process.
      */
-    pub fn new(matches: &ArgMatches) -> Arc<Self> {
+    pub fn new(matches: &ArgMatches) -> Result<Arc<Self>, ScanError> {
         let profile = match matches.get_one::<String>("profile") {
             Some(output_request) => match output_request.as_ref() {
                 "default" | "d" => ProfileType::Default,
                 "fast" | "f" => ProfileType::Fast,
+                "thorough" | "t" => ProfileType::Thorough,
                 "stealth" | "s" => ProfileType::Stealth,
                 "chaos" | "c" => ProfileType::Chaos,
                 _ => {
-                    eprintln!("Expected correct profile name (default/fast/stealth/chaos)");
-                    process::exit(1);
+                    return Err(ScanError::InvalidOption(
+                        "Expected correct profile name (default/fast/thorough/stealth/chaos)"
+                            .to_string(),
+                    ))
                 }
             },
             None => ProfileType::Default,
         };
+        let profile_defaults = profile.defaults();
 
         let interface_name = matches.get_one::<String>("interface").cloned();
-        let interface_index_str = matches.get_one::<String>("index").cloned();
-        let interface_index = interface_index_str.unwrap_or_default().parse::<u32>().ok();
+        let interface_pattern = match matches.get_one::<String>("interface_regex") {
+            Some(pattern_text) => match Regex::new(pattern_text) {
+                Ok(pattern) => Some(pattern),
+                Err(err) => {
+                    return Err(ScanError::InvalidOption(format!(
+                        "Expected valid interface name pattern ({})",
+                        err
+                    )))
+                }
+            },
+            None => None,
+        };
+        let all_interfaces = matches.get_flag("all_interfaces");
+        let interface_index: Option<u32> = match matches.get_one::<String>("index") {
+            Some(index_text) => match index_text.parse::<u32>() {
+                Ok(index_number) => Some(index_number),
+                Err(_) => {
+                    return Err(ScanError::InvalidOption(
+                        "Expected positive number as interface index".to_string(),
+                    ))
+                }
+            },
+            None => None,
+        };
 
         let file_option = matches.get_one::<String>("file");
         let network_option = matches.get_one::<String>("network");
 
-        let network_range = ScanOptions::compute_networks(file_option, network_option)
-            .unwrap_or_else(|err| {
-                eprintln!("Could not compute requested network range to scan");
-                eprintln!("{}", err);
-                process::exit(1);
-            });
+        let target_values: Option<Vec<&String>> = matches
+            .get_many::<String>("targets")
+            .map(|values| values.collect());
+
+        let ipv6 = matches.get_flag("ipv6");
+        let network_range = match target_values {
+            Some(target_values) => ScanOptions::compute_target_networks(target_values).map(Some),
+            None => ScanOptions::compute_networks(file_option, network_option),
+        }
+        .and_then(|network_range| {
+            if let Some(networks) = &network_range {
+                ScanOptions::check_network_family(networks, ipv6)?;
+            }
+            Ok(network_range)
+        })
+        .map_err(|err| {
+            ScanError::InvalidOption(format!(
+                "Could not compute requested network range to scan\n{}",
+                err
+            ))
+        })?;
+
+        let exclude_values: Vec<&String> = matches
+            .get_many::<String>("exclude")
+            .map(|values| values.collect())
+            .unwrap_or_default();
+        let excluded_networks =
+            ScanOptions::compute_excluded_networks(exclude_values).map_err(|err| {
+                ScanError::InvalidOption(format!(
+                    "Could not compute excluded network range\n{}",
+                    err
+                ))
+            })?;
 
         let timeout_ms: u64 = match matches.get_one::<String>("timeout") {
-            Some(timeout_text) => parse_to_milliseconds(timeout_text).unwrap_or_else(|err| {
-                eprintln!("Expected correct timeout, {}", err);
-                process::exit(1);
-            }),
-            None => match profile {
-                ProfileType::Fast => TIMEOUT_MS_FAST,
-                _ => TIMEOUT_MS_DEFAULT,
-            },
+            Some(timeout_text) => parse_to_milliseconds(timeout_text).map_err(|err| {
+                ScanError::InvalidOption(format!("Expected correct timeout, {}", err))
+            })?,
+            None => profile_defaults.timeout_ms,
         };
 
+        let adaptive_timeout = matches.get_flag("adaptive_timeout");
+
         // Hostnames will not be resolved in numeric mode or stealth profile
-        let resolve_hostname =
-            !matches.get_flag("numeric") && !matches!(profile, ProfileType::Stealth);
+        let resolve_hostname = !matches.get_flag("numeric") && profile_defaults.resolve_hostname;
+
+        let dns_timeout_ms = match matches.get_one::<String>("dns_timeout") {
+            Some(dns_timeout) => match dns_timeout.parse::<u64>() {
+                Ok(timeout_ms) if timeout_ms > 0 => timeout_ms,
+                _ => {
+                    return Err(ScanError::InvalidOption(
+                        "Expected strictly positive number of milliseconds for DNS timeout"
+                            .to_string(),
+                    ))
+                }
+            },
+            None => DNS_TIMEOUT_MS_DEFAULT,
+        };
+
+        let dns_threads = match matches.get_one::<String>("dns_threads") {
+            Some(dns_threads) => match dns_threads.parse::<usize>() {
+                Ok(thread_count) if thread_count > 0 => thread_count,
+                _ => {
+                    return Err(ScanError::InvalidOption(
+                        "Expected strictly positive number for DNS thread count".to_string(),
+                    ))
+                }
+            },
+            None => DNS_THREADS_DEFAULT,
+        };
+
+        let probe_ports = match matches.get_one::<String>("probe_ports") {
+            Some(port_list) => ScanOptions::parse_port_list(port_list).map_err(|err| {
+                ScanError::InvalidOption(format!("Expected valid TCP port list, {}", err))
+            })?,
+            None => vec![],
+        };
+
+        let probe_timeout_ms = match matches.get_one::<String>("probe_timeout") {
+            Some(probe_timeout) => match probe_timeout.parse::<u64>() {
+                Ok(timeout_ms) if timeout_ms > 0 => timeout_ms,
+                _ => {
+                    return Err(ScanError::InvalidOption(
+                        "Expected strictly positive number of milliseconds for probe timeout"
+                            .to_string(),
+                    ))
+                }
+            },
+            None => PROBE_TIMEOUT_MS_DEFAULT,
+        };
+
+        let snmp_community = match matches.get_flag("snmp") {
+            true => Some(
+                matches
+                    .get_one::<String>("snmp_community")
+                    .cloned()
+                    .unwrap_or_else(|| SNMP_COMMUNITY_DEFAULT.to_string()),
+            ),
+            false => None,
+        };
+
+        let webhook_url = matches.get_one::<String>("webhook").cloned();
+        if let Some(url) = &webhook_url {
+            webhook::validate_webhook_url(url).map_err(ScanError::InvalidOption)?;
+        }
 
         let source_ipv4: Option<Ipv4Addr> = match matches.get_one::<String>("source_ip") {
             Some(source_ip) => match source_ip.parse::<Ipv4Addr>() {
                 Ok(parsed_ipv4) => Some(parsed_ipv4),
                 Err(_) => {
-                    eprintln!("Expected valid IPv4 as source IP");
-                    process::exit(1);
+                    return Err(ScanError::InvalidOption(
+                        "Expected valid IPv4 as source IP".to_string(),
+                    ))
                 }
             },
             None => None,
@@ -414,8 +1779,9 @@ impl ScanOptions {
             Some(mac_address) => match mac_address.parse::<MacAddr>() {
                 Ok(parsed_mac) => Some(parsed_mac),
                 Err(_) => {
-                    eprintln!("Expected valid MAC address as destination");
-                    process::exit(1);
+                    return Err(ScanError::InvalidOption(
+                        "Expected valid MAC address as destination".to_string(),
+                    ))
                 }
             },
             None => None,
@@ -425,19 +1791,86 @@ impl ScanOptions {
             Some(mac_address) => match mac_address.parse::<MacAddr>() {
                 Ok(parsed_mac) => Some(parsed_mac),
                 Err(_) => {
-                    eprintln!("Expected valid MAC address as source");
-                    process::exit(1);
+                    return Err(ScanError::InvalidOption(format!(
+                        "Expected valid MAC address as source ({})",
+                        mac_address
+                    )))
                 }
             },
             None => None,
         };
 
+        let mac_pool: Vec<MacAddr> = match matches.get_one::<String>("mac_pool") {
+            Some(file_path) => fs::read_to_string(file_path)
+                .map_err(|err| format!("could not open file {} - {}", file_path, err))
+                .and_then(|content| ScanOptions::parse_mac_list(&content))
+                .map_err(|err| {
+                    ScanError::InvalidOption(format!("Expected valid MAC pool, {}", err))
+                })?,
+            None => vec![],
+        };
+
+        let decoy_count = match matches.get_one::<String>("decoys") {
+            Some(decoy_text) => match decoy_text.parse::<usize>() {
+                Ok(decoy_count) if (1..=DECOY_COUNT_MAX).contains(&decoy_count) => decoy_count,
+                _ => {
+                    return Err(ScanError::InvalidOption(format!(
+                        "Expected decoy count between 1 and {}",
+                        DECOY_COUNT_MAX
+                    )))
+                }
+            },
+            None => 0,
+        };
+
+        let known_macs: Option<HashSet<MacAddr>> = matches
+            .get_one::<String>("known_macs")
+            .map(|file_path| {
+                fs::read_to_string(file_path)
+                    .map_err(|err| format!("could not open file {} - {}", file_path, err))
+                    .and_then(|content| ScanOptions::parse_mac_list(&content))
+                    .map(|mac_list| mac_list.into_iter().collect())
+                    .map_err(|err| {
+                        ScanError::InvalidOption(format!("Expected valid known MAC list, {}", err))
+                    })
+            })
+            .transpose()?;
+        let only_new_macs = matches.get_flag("only_new_macs");
+
+        let wake_targets: Vec<MacAddr> = matches
+            .get_many::<String>("wake")
+            .into_iter()
+            .flatten()
+            .map(|mac_address| {
+                mac_address.parse::<MacAddr>().map_err(|_| {
+                    ScanError::InvalidOption(format!(
+                        "Expected valid MAC address to wake ({})",
+                        mac_address
+                    ))
+                })
+            })
+            .collect::<Result<Vec<MacAddr>, ScanError>>()?;
+        let wake_only = matches.get_flag("wake_only");
+
+        let mac_prefixes: Vec<[u8; 3]> = matches
+            .get_many::<String>("mac_prefix")
+            .into_iter()
+            .flatten()
+            .map(|prefix_text| {
+                ScanOptions::parse_mac_prefix(prefix_text).map_err(|err| {
+                    ScanError::InvalidOption(format!("Expected valid MAC prefix, {}", err))
+                })
+            })
+            .collect::<Result<Vec<[u8; 3]>, ScanError>>()?;
+
         let vlan_id: Option<u16> = match matches.get_one::<String>("vlan") {
-            Some(vlan) => match vlan.parse::<u16>() {
+            Some(vlan) => match ScanOptions::parse_vlan_id(vlan) {
                 Ok(vlan_number) => Some(vlan_number),
-                Err(_) => {
-                    eprintln!("Expected valid VLAN identifier");
-                    process::exit(1);
+                Err(err) => {
+                    return Err(ScanError::InvalidOption(format!(
+                        "Expected valid VLAN identifier, {}",
+                        err
+                    )))
                 }
             },
             None => None,
@@ -447,124 +1880,554 @@ impl ScanOptions {
             Some(retry_count) => match retry_count.parse::<usize>() {
                 Ok(retry_number) => retry_number,
                 Err(_) => {
-                    eprintln!("Expected positive number for host retry count");
-                    process::exit(1);
+                    return Err(ScanError::InvalidOption(
+                        "Expected positive number for host retry count".to_string(),
+                    ))
                 }
             },
-            None => match profile {
-                ProfileType::Chaos => HOST_RETRY_DEFAULT * 2,
-                _ => HOST_RETRY_DEFAULT,
-            },
+            None => profile_defaults.retry_count,
         };
 
-        let scan_timing: ScanTiming = ScanOptions::compute_scan_timing(matches, &profile);
+        let retry_all = matches.get_flag("retry_all");
+        let retry_on_silence = matches.get_flag("retry_on_silence");
 
-        let output = match matches.get_one::<String>("output") {
-            Some(output_request) => match output_request.as_ref() {
-                "json" => OutputFormat::Json,
-                "yaml" => OutputFormat::Yaml,
-                "plain" | "text" => OutputFormat::Plain,
-                "csv" => OutputFormat::Csv,
+        let retry_backoff = match matches.get_one::<String>("retry_backoff") {
+            Some(backoff_text) => match backoff_text.parse::<f64>() {
+                Ok(backoff_factor) if backoff_factor.is_finite() && backoff_factor >= 1.0 => {
+                    backoff_factor
+                }
+                _ => {
+                    return Err(ScanError::InvalidOption(
+                        "Expected number greater than or equal to 1 for retry backoff".to_string(),
+                    ))
+                }
+            },
+            None => 1.0,
+        };
+
+        let request_limit = match matches.get_one::<String>("count") {
+            Some(count_text) => match count_text.parse::<usize>() {
+                Ok(request_count) if request_count > 0 => Some(request_count),
                 _ => {
-                    eprintln!("Expected correct output format (json/yaml/plain)");
-                    process::exit(1);
+                    return Err(ScanError::InvalidOption(
+                        "Expected strictly positive number for request count".to_string(),
+                    ))
                 }
             },
-            None => OutputFormat::Plain,
+            None => None,
         };
 
-        let randomize_targets = matches.get_flag("random")
-            || matches!(profile, ProfileType::Stealth | ProfileType::Chaos);
+        // The host limit is lifted with '--force', for scans of large ranges
+        // that are really expected.
+        let max_hosts = match matches.get_one::<String>("max_hosts") {
+            Some(max_hosts_text) => match max_hosts_text.parse::<u128>() {
+                Ok(max_hosts) if max_hosts > 0 => Some(max_hosts),
+                _ => {
+                    return Err(ScanError::InvalidOption(
+                        "Expected strictly positive number for maximum host count".to_string(),
+                    ))
+                }
+            },
+            None => Some(MAX_HOSTS_DEFAULT),
+        }
+        .filter(|_| !matches.get_flag("force"));
 
-        let oui_file: String = match matches.get_one::<String>("oui-file") {
-            Some(file) => file.to_string(),
-            None => "/usr/share/arp-scan/ieee-oui.csv".to_string(),
+        let tx_threads = match matches.get_one::<String>("tx_threads") {
+            Some(tx_threads) => match tx_threads.parse::<usize>() {
+                Ok(thread_count) if thread_count > 0 => thread_count,
+                _ => {
+                    return Err(ScanError::InvalidOption(
+                        "Expected strictly positive number for sender thread count".to_string(),
+                    ))
+                }
+            },
+            None => TX_THREADS_DEFAULT,
         };
 
-        let hw_type = match matches.get_one::<String>("hw_type") {
-            Some(hw_type_text) => match hw_type_text.parse::<u16>() {
-                Ok(type_number) => Some(ArpHardwareType::new(type_number)),
-                Err(_) => {
-                    eprintln!("Expected valid ARP hardware type number");
-                    process::exit(1);
+        let rcv_timeout_ms = match matches.get_one::<String>("rcv_timeout") {
+            Some(rcv_timeout_text) => match parse_to_milliseconds(rcv_timeout_text) {
+                Ok(rcv_timeout_ms) if rcv_timeout_ms > 0 => rcv_timeout_ms,
+                _ => {
+                    return Err(ScanError::InvalidOption(
+                        "Expected strictly positive duration for receiver timeout".to_string(),
+                    ))
                 }
             },
-            None => None,
+            None => DATALINK_RCV_TIMEOUT,
         };
 
-        let hw_addr = match matches.get_one::<String>("hw_addr") {
-            Some(hw_addr_text) => match hw_addr_text.parse::<u8>() {
-                Ok(addr_length) => Some(addr_length),
-                Err(_) => {
-                    eprintln!("Expected valid ARP hardware address length");
-                    process::exit(1);
+        let scan_timing: ScanTiming =
+            ScanOptions::compute_scan_timing(matches, profile_defaults.interval_ms)?;
+
+        let output_values: Vec<&String> = matches
+            .get_many::<String>("output")
+            .into_iter()
+            .flatten()
+            .collect();
+        let output_file = matches.get_one::<String>("output_file");
+        let append_file = matches.get_one::<String>("append_jsonl").cloned();
+        let outputs = ScanOptions::compute_output_targets(output_values, output_file)
+            .map_err(ScanError::InvalidOption)?;
+
+        let output_template = match matches.get_one::<String>("output_template") {
+            Some(template_text) => match utils::parse_output_template(template_text) {
+                Ok(template) => Some(template),
+                Err(err) => {
+                    return Err(ScanError::InvalidOption(format!(
+                        "Expected valid output template, {}",
+                        err
+                    )))
                 }
             },
             None => None,
         };
 
-        let proto_type = match matches.get_one::<String>("proto_type") {
-            Some(proto_type_text) => match proto_type_text.parse::<u16>() {
-                Ok(type_number) => Some(EtherType::new(type_number)),
+        let pretty_json = matches.get_flag("pretty");
+        let sign_output = matches.get_flag("sign");
+        let quiet = matches.get_flag("quiet");
+        let show_banner = !matches.get_flag("no_banner");
+        let verbose_level = matches.get_count("verbose");
+        let fail_if_empty = matches.get_flag("fail_if_empty");
+
+        let color_enabled = utils::is_color_enabled(matches.get_flag("no_color"));
+
+        let passive = matches.get_flag("passive");
+        let include_cache = matches.get_flag("include_cache");
+        let primary_only = matches.get_flag("primary_only");
+        let dry_run = matches.get_flag("dry_run");
+        let stop_on_first = matches.get_flag("first");
+
+        let target_order = match matches.get_one::<String>("order") {
+            Some(order_text) => match order_text.as_ref() {
+                "sequential" => TargetOrder::Sequential,
+                "shuffle" => TargetOrder::Shuffle,
+                "stride" => TargetOrder::Stride,
+                _ => {
+                    return Err(ScanError::InvalidOption(
+                        "Expected correct target order (sequential/shuffle/stride)".to_string(),
+                    ))
+                }
+            },
+            None if matches.get_flag("random") || profile_defaults.randomize_targets => {
+                TargetOrder::Shuffle
+            }
+            None => TargetOrder::Sequential,
+        };
+
+        let jitter_percent = match matches.get_one::<String>("jitter") {
+            Some(jitter_text) => match jitter_text.trim_end_matches('%').parse::<u8>() {
+                Ok(jitter_percent) if jitter_percent <= 100 => Some(jitter_percent),
+                _ => {
+                    return Err(ScanError::InvalidOption(
+                        "Expected jitter percentage between 0 and 100".to_string(),
+                    ))
+                }
+            },
+            None => profile_defaults.jitter_percent,
+        };
+
+        let seed = match matches.get_one::<String>("seed") {
+            Some(seed_text) => match seed_text.parse::<u64>() {
+                Ok(seed) => Some(seed),
                 Err(_) => {
-                    eprintln!("Expected valid ARP proto type number");
-                    process::exit(1);
+                    return Err(ScanError::InvalidOption(
+                        "Expected positive number for random seed".to_string(),
+                    ))
                 }
             },
             None => None,
         };
 
-        let proto_addr = match matches.get_one::<String>("proto_addr") {
-            Some(proto_addr_text) => match proto_addr_text.parse::<u8>() {
-                Ok(addr_length) => Some(addr_length),
-                Err(_) => {
-                    eprintln!("Expected valid ARP hardware address length");
-                    process::exit(1);
+        let sample_percent = match matches.get_one::<String>("sample") {
+            Some(sample_text) => match sample_text.trim_end_matches('%').parse::<f64>() {
+                Ok(sample_percent) if sample_percent > 0.0 && sample_percent <= 100.0 => {
+                    Some(sample_percent)
+                }
+                _ => {
+                    return Err(ScanError::InvalidOption(
+                        "Expected sample percentage between 0 and 100".to_string(),
+                    ))
                 }
             },
             None => None,
         };
 
-        let arp_operation = match matches.get_one::<String>("arp_operation") {
-            Some(arp_op_text) => match arp_op_text.parse::<u16>() {
-                Ok(op_number) => Some(ArpOperation::new(op_number)),
+        let watch_interval_ms: Option<u64> = match matches.get_one::<String>("watch") {
+            Some(watch_text) => match ScanOptions::parse_seconds_to_milliseconds(watch_text) {
+                Ok(watch_interval_ms) => Some(watch_interval_ms),
                 Err(_) => {
-                    eprintln!("Expected valid ARP operation number");
-                    process::exit(1);
+                    return Err(ScanError::InvalidOption(
+                        "Expected positive number of seconds for watch mode".to_string(),
+                    ))
                 }
             },
             None => None,
         };
+        let live_display = matches.get_flag("live");
+        let changes_only = matches.get_flag("changes_only");
+
+        let max_duration_ms: Option<u64> = match matches.get_one::<String>("max_duration") {
+            Some(duration_text) => {
+                match ScanOptions::parse_seconds_to_milliseconds(duration_text) {
+                    Ok(duration_ms) if duration_ms > 0 => Some(duration_ms),
+                    _ => {
+                        return Err(ScanError::InvalidOption(
+                            "Expected strictly positive number of seconds for max duration"
+                                .to_string(),
+                        ))
+                    }
+                }
+            }
+            None => None,
+        };
 
-        let packet_help = matches.get_flag("packet_help");
-
-        Arc::new(ScanOptions {
-            profile,
+        let requested_oui_file: String = match matches.get_one::<String>("oui-file") {
+            Some(file) => file.to_string(),
+            None => "/usr/share/arp-scan/ieee-oui.csv".to_string(),
+        };
+
+        // Without an explicit OUI file, the databases installed by the system
+        // packages (ieee-data, Wireshark) are used if the default one is absent.
+        let is_default_oui_file =
+            matches.value_source("oui-file") == Some(ValueSource::DefaultValue);
+        let oui_file = match is_default_oui_file && !matches.get_flag("no_oui_autodetect") {
+            true => vendor::find_system_database(&requested_oui_file)
+                .unwrap_or_else(|| requested_oui_file.clone()),
+            false => requested_oui_file.clone(),
+        };
+
+        let show_oui = matches.get_flag("show_oui");
+        let vendor_summary = matches.get_flag("vendor_summary");
+        let per_network_summary = matches.get_flag("per_network_summary");
+        let plain_summary = matches.get_flag("plain_summary");
+
+        let compact_width = match matches.get_one::<String>("width") {
+            Some(width_text) => match width_text.parse::<usize>() {
+                Ok(width) if width > 0 => Some(width),
+                _ => {
+                    return Err(ScanError::InvalidOption(
+                        "Expected strictly positive number for table width".to_string(),
+                    ))
+                }
+            },
+            None => None,
+        }
+        .or_else(|| matches.get_flag("compact").then(utils::find_terminal_width));
+        let show_latency = matches.get_flag("show_latency");
+        let show_reply_count = matches.get_flag("show_reply_count");
+
+        let sort_field = match matches.get_one::<String>("sort") {
+            Some(sort_text) => match sort_text.as_ref() {
+                "ipv4" => SortField::Ipv4,
+                "mac" => SortField::Mac,
+                "vendor" => SortField::Vendor,
+                "latency" => SortField::Latency,
+                _ => {
+                    return Err(ScanError::InvalidOption(
+                        "Expected correct sort field (ipv4/mac/vendor/latency)".to_string(),
+                    ))
+                }
+            },
+            None => SortField::Ipv4,
+        };
+
+        let compare_file = matches.get_one::<String>("compare").cloned();
+        let pcap_file = matches.get_one::<String>("pcap").cloned();
+
+        // Without a path, the registry is downloaded to the given OUI file, or
+        // to the user cache directory (instead of the system default path).
+        let oui_update_file: Option<String> = match matches.contains_id("update_oui") {
+            true => match matches.get_one::<String>("update_oui") {
+                Some(update_file) => Some(update_file.clone()),
+                None if !is_default_oui_file => Some(requested_oui_file),
+                None => match vendor::find_cache_database_path() {
+                    Some(cache_path) => Some(cache_path),
+                    None => {
+                        return Err(ScanError::InvalidOption(
+                            "Expected a path for the OUI update (no cache directory)".to_string(),
+                        ))
+                    }
+                },
+            },
+            false => None,
+        };
+
+        let hw_type = match matches.get_one::<String>("hw_type") {
+            Some(hw_type_text) => match ScanOptions::parse_type_number(hw_type_text) {
+                Ok(type_number) => Some(ArpHardwareType::new(type_number)),
+                Err(err) => {
+                    return Err(ScanError::InvalidOption(format!(
+                        "Expected valid ARP hardware type number, {}",
+                        err
+                    )))
+                }
+            },
+            None => None,
+        };
+
+        let hw_addr = match matches.get_one::<String>("hw_addr") {
+            Some(hw_addr_text) => match hw_addr_text.parse::<u8>() {
+                Ok(addr_length) => Some(addr_length),
+                Err(_) => {
+                    return Err(ScanError::InvalidOption(
+                        "Expected valid ARP hardware address length".to_string(),
+                    ))
+                }
+            },
+            None => None,
+        };
+
+        let proto_type = match matches.get_one::<String>("proto_type") {
+            Some(proto_type_text) => match ScanOptions::parse_type_number(proto_type_text) {
+                Ok(type_number) => Some(EtherType::new(type_number)),
+                Err(err) => {
+                    return Err(ScanError::InvalidOption(format!(
+                        "Expected valid ARP proto type number, {}",
+                        err
+                    )))
+                }
+            },
+            None => None,
+        };
+
+        let proto_addr = match matches.get_one::<String>("proto_addr") {
+            Some(proto_addr_text) => match proto_addr_text.parse::<u8>() {
+                Ok(addr_length) => Some(addr_length),
+                Err(_) => {
+                    return Err(ScanError::InvalidOption(
+                        "Expected valid ARP hardware address length".to_string(),
+                    ))
+                }
+            },
+            None => None,
+        };
+
+        let arp_operation = match matches.get_one::<String>("arp_operation") {
+            Some(arp_op_text) => match ScanOptions::parse_arp_operation(arp_op_text) {
+                Ok(arp_operation) => Some(arp_operation),
+                Err(err) => {
+                    return Err(ScanError::InvalidOption(format!(
+                        "Expected valid ARP operation, {}",
+                        err
+                    )))
+                }
+            },
+            None => None,
+        };
+
+        let pad_to = match matches.get_one::<String>("pad_to") {
+            Some(pad_to_text) => match ScanOptions::parse_pad_length(pad_to_text) {
+                Ok(pad_length) => Some(pad_length),
+                Err(err) => {
+                    return Err(ScanError::InvalidOption(format!(
+                        "Expected valid padding length, {}",
+                        err
+                    )))
+                }
+            },
+            None => None,
+        };
+
+        let pad_byte = match matches.get_one::<String>("pad_byte") {
+            Some(pad_byte_text) => {
+                match ScanOptions::parse_type_number(pad_byte_text).and_then(|byte_number| {
+                    u8::try_from(byte_number)
+                        .map_err(|_| format!("{} does not fit in 8 bits", pad_byte_text))
+                }) {
+                    Ok(byte_number) => byte_number,
+                    Err(err) => {
+                        return Err(ScanError::InvalidOption(format!(
+                            "Expected valid padding byte, {}",
+                            err
+                        )))
+                    }
+                }
+            }
+            None => 0,
+        };
+
+        let packet_help = matches.get_flag("packet_help");
+        let self_test = matches.get_flag("self_test");
+
+        Ok(Arc::new(ScanOptions {
+            profile,
             interface_name,
+            interface_pattern,
             interface_index,
+            all_interfaces,
             network_range,
+            excluded_networks,
             timeout_ms,
+            adaptive_timeout,
             resolve_hostname,
+            dns_timeout_ms,
+            dns_threads,
+            probe_ports,
+            probe_timeout_ms,
+            snmp_community,
+            webhook_url,
             source_ipv4,
             destination_mac,
             source_mac,
+            mac_pool,
+            decoy_count,
+            known_macs,
+            only_new_macs,
+            wake_targets,
+            wake_only,
+            mac_prefixes,
             vlan_id,
             retry_count,
+            retry_all,
+            retry_on_silence,
+            retry_backoff,
+            request_limit,
+            max_hosts,
+            tx_threads,
+            rcv_timeout_ms,
             scan_timing,
-            randomize_targets,
-            output,
+            passive,
+            include_cache,
+            primary_only,
+            ipv6,
+            dry_run,
+            stop_on_first,
+            target_order,
+            jitter_percent,
+            seed,
+            sample_percent,
+            watch_interval_ms,
+            changes_only,
+            max_duration_ms,
+            live_display,
+            outputs,
+            output_template,
+            pretty_json,
+            append_file,
+            sign_output,
+            quiet,
+            show_banner,
+            verbose_level,
+            fail_if_empty,
+            color_enabled,
             oui_file,
+            oui_update_file,
+            show_oui,
+            vendor_summary,
+            per_network_summary,
+            plain_summary,
+            compact_width,
+            show_latency,
+            show_reply_count,
+            sort_field,
+            compare_file,
+            pcap_file,
             hw_type,
             hw_addr,
             proto_type,
             proto_addr,
             arp_operation,
+            pad_to,
+            pad_byte,
             packet_help,
-        })
+            self_test,
+        }))
+    }
+
+    pub fn has_random_order(&self) -> bool {
+        self.target_order != TargetOrder::Sequential
     }
 
     pub fn is_plain_output(&self) -> bool {
-        matches!(&self.output, OutputFormat::Plain)
+        self.has_stdout_output(OutputFormat::Plain)
+    }
+
+    /**
+     * Compute the number of ARP requests that will be sent for a given amount
+     * of targets (including retries), capped by the requested count if any.
+     */
+    pub fn compute_request_count(&self, network_size: u128) -> u128 {
+        if self.passive {
+            return 0;
+        }
+
+        let request_count = network_size * self.retry_count as u128;
+        match self.request_limit {
+            Some(request_limit) => request_count.min(request_limit as u128),
+            None => request_count,
+        }
+    }
+
+    /**
+     * Compute the request interval of a retry pass (the first pass having the
+     * index 0). With a retry backoff, the interval of each retry pass is
+     * multiplied by the backoff factor, giving slow responders more time.
+     */
+    pub fn compute_pass_interval(&self, interval_ms: u64, pass_index: usize) -> u64 {
+        let pass_exponent = i32::try_from(pass_index).unwrap_or(i32::MAX);
+        let pass_interval_ms = interval_ms as f64 * self.retry_backoff.powi(pass_exponent);
+        pass_interval_ms.round().min(u64::MAX as f64) as u64
+    }
+
+    /**
+     * Progress details (banner, scan details, sent packets, ...) are only
+     * displayed for plain outputs, unless the quiet mode is requested or the
+     * table is replaced by a host template.
+     */
+    pub fn is_progress_output(&self) -> bool {
+        self.is_plain_output() && !self.quiet && self.output_template.is_none()
+    }
+
+    /**
+     * Newly discovered hosts are logged on stderr as soon as they respond in
+     * verbose mode (-v), which keeps stdout clean for machine outputs.
+     */
+    pub fn is_host_logged(&self) -> bool {
+        self.verbose_level >= 1
+    }
+
+    /**
+     * Every received frame is logged on stderr in the very verbose mode
+     * (-vv), with the reason why it was ignored or accepted.
+     */
+    pub fn is_frame_logged(&self) -> bool {
+        self.verbose_level >= 2
+    }
+
+    /**
+     * Check if a MAC address should be reported, based on the requested OUI
+     * prefixes. All MAC addresses are reported when no prefix is given.
+     */
+    /**
+     * Check a MAC address against the known MAC list, nothing being known
+     * (or unexpected) without '--known-macs'.
+     */
+    pub fn is_known_mac(&self, mac: MacAddr) -> Option<bool> {
+        self.known_macs
+            .as_ref()
+            .map(|known_macs| known_macs.contains(&mac))
+    }
+
+    pub fn matches_mac_prefix(&self, mac: MacAddr) -> bool {
+        self.mac_prefixes.is_empty()
+            || self
+                .mac_prefixes
+                .iter()
+                .any(|prefix| prefix == &[mac.0, mac.1, mac.2])
+    }
+
+    pub fn is_stream_output(&self) -> bool {
+        self.has_stdout_output(OutputFormat::Ndjson)
+    }
+
+    fn has_stdout_output(&self, format: OutputFormat) -> bool {
+        self.outputs
+            .iter()
+            .any(|output| output.file.is_none() && output.format == format)
+    }
+
+    pub fn is_watch_mode(&self) -> bool {
+        self.watch_interval_ms.is_some()
     }
 
     pub fn has_vlan(&self) -> bool {
@@ -580,7 +2443,6 @@ impl ScanOptions {
 mod tests {
 
     use super::*;
-    use ipnetwork::Ipv4Network;
 
     #[test]
     fn should_have_no_network_default() {
@@ -671,4 +2533,638 @@ mod tests {
             Err("Expected valid IPv4 network range (invalid address: no-network)".to_string())
         );
     }
+
+    #[test]
+    fn should_handle_excluded_networks() {
+        let single_ip = "192.168.1.1".to_string();
+        let sub_network = "192.168.1.128/25".to_string();
+        let excluded = ScanOptions::compute_excluded_networks(vec![&single_ip, &sub_network]);
+
+        let target_network: Vec<IpNetwork> = vec![
+            IpNetwork::V4(Ipv4Network::new(Ipv4Addr::new(192, 168, 1, 1), 32).unwrap()),
+            IpNetwork::V4(Ipv4Network::new(Ipv4Addr::new(192, 168, 1, 128), 25).unwrap()),
+        ];
+
+        assert_eq!(excluded, Ok(target_network));
+    }
+
+    #[test]
+    fn should_fail_incorrect_excluded_network() {
+        let invalid_ip = "300.1.1.1".to_string();
+        let excluded = ScanOptions::compute_excluded_networks(vec![&invalid_ip]);
+
+        assert_eq!(
+            excluded,
+            Err("Expected valid IPv4 excluded range (invalid address: 300.1.1.1)".to_string())
+        );
+    }
+
+    #[test]
+    fn should_parse_valid_vlan_id() {
+        assert_eq!(ScanOptions::parse_vlan_id("1"), Ok(1));
+        assert_eq!(ScanOptions::parse_vlan_id("4094"), Ok(4094));
+    }
+
+    #[test]
+    fn should_deny_reserved_vlan_id() {
+        assert_eq!(
+            ScanOptions::parse_vlan_id("0"),
+            Err("0 is out of range (1-4094)".to_string())
+        );
+        assert_eq!(
+            ScanOptions::parse_vlan_id("4095"),
+            Err("4095 is out of range (1-4094)".to_string())
+        );
+    }
+
+    #[test]
+    fn should_deny_invalid_vlan_id() {
+        assert!(ScanOptions::parse_vlan_id("vlan").is_err());
+    }
+
+    #[test]
+    fn should_parse_type_numbers() {
+        assert_eq!(ScanOptions::parse_type_number("1"), Ok(1));
+        assert_eq!(ScanOptions::parse_type_number("0x0800"), Ok(0x0800));
+        assert_eq!(ScanOptions::parse_type_number("0X86DD"), Ok(0x86dd));
+        assert_eq!(
+            ScanOptions::parse_type_number("65536"),
+            Err("65536 does not fit in 16 bits".to_string())
+        );
+        assert!(ScanOptions::parse_type_number("0xZZ").is_err());
+    }
+
+    #[test]
+    fn should_parse_source_mac() {
+        let matches =
+            build_args().get_matches_from(vec!["arp-scan", "--source-mac", "02:42:ac:11:00:02"]);
+        let options = ScanOptions::new(&matches).unwrap();
+
+        assert_eq!(
+            options.source_mac,
+            Some(MacAddr::new(0x02, 0x42, 0xac, 0x11, 0x00, 0x02))
+        );
+    }
+
+    #[test]
+    fn should_parse_decoy_count() {
+        let default_options =
+            ScanOptions::new(&build_args().get_matches_from(vec!["arp-scan"])).unwrap();
+        let decoy_options =
+            ScanOptions::new(&build_args().get_matches_from(vec!["arp-scan", "--decoys", "3"]))
+                .unwrap();
+
+        assert_eq!(default_options.decoy_count, 0);
+        assert_eq!(decoy_options.decoy_count, 3);
+        assert!(build_args()
+            .try_get_matches_from(vec!["arp-scan", "--decoys", "3", "--passive"])
+            .is_err());
+    }
+
+    #[test]
+    fn should_return_invalid_options() {
+        let decoy_matches = build_args().get_matches_from(vec!["arp-scan", "--decoys", "99"]);
+        let mac_matches = build_args().get_matches_from(vec!["arp-scan", "--wake", "02:42"]);
+
+        assert_eq!(
+            ScanOptions::new(&decoy_matches).err(),
+            Some(ScanError::InvalidOption(
+                "Expected decoy count between 1 and 8".to_string()
+            ))
+        );
+        assert_eq!(
+            ScanOptions::new(&mac_matches).err(),
+            Some(ScanError::InvalidOption(
+                "Expected valid MAC address to wake (02:42)".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn should_check_known_macs() {
+        let path = std::env::temp_dir().join("arp-scan-known-macs-test");
+        fs::write(&path, "# inventory\n02:42:ac:11:00:02\n").unwrap();
+        let path_text = path.to_string_lossy().to_string();
+
+        let default_options =
+            ScanOptions::new(&build_args().get_matches_from(vec!["arp-scan"])).unwrap();
+        let known_options = ScanOptions::new(&build_args().get_matches_from(vec![
+            "arp-scan",
+            "--known-macs",
+            &path_text,
+            "--only-new-macs",
+        ]))
+        .unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let known_mac = MacAddr::new(0x02, 0x42, 0xac, 0x11, 0x00, 0x02);
+        let unexpected_mac = MacAddr::new(0x02, 0x42, 0xac, 0x11, 0x00, 0x03);
+        assert_eq!(default_options.is_known_mac(known_mac), None);
+        assert_eq!(known_options.is_known_mac(known_mac), Some(true));
+        assert_eq!(known_options.is_known_mac(unexpected_mac), Some(false));
+        assert!(known_options.only_new_macs);
+        assert!(build_args()
+            .try_get_matches_from(vec!["arp-scan", "--only-new-macs"])
+            .is_err());
+    }
+
+    #[test]
+    fn should_parse_mac_list() {
+        let mac_pool = ScanOptions::parse_mac_list(
+            "# lab devices\n02:42:ac:11:00:02\n\n  02:42:ac:11:00:03  \n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            mac_pool,
+            vec![
+                MacAddr::new(0x02, 0x42, 0xac, 0x11, 0x00, 0x02),
+                MacAddr::new(0x02, 0x42, 0xac, 0x11, 0x00, 0x03),
+            ]
+        );
+        assert_eq!(
+            ScanOptions::parse_mac_list("02:42:ac:11:00:02\nnot-a-mac\n"),
+            Err("not-a-mac is not a MAC address (line 2)".to_string())
+        );
+        assert!(ScanOptions::parse_mac_list("# empty pool\n").is_err());
+    }
+
+    #[test]
+    fn should_default_to_interface_source_mac() {
+        let matches = build_args().get_matches_from(vec!["arp-scan"]);
+        let options = ScanOptions::new(&matches).unwrap();
+
+        assert_eq!(options.source_mac, None);
+    }
+
+    #[test]
+    fn should_cap_request_count() {
+        let default_options =
+            ScanOptions::new(&build_args().get_matches_from(vec!["arp-scan", "-r", "3"])).unwrap();
+        let capped_options = ScanOptions::new(
+            &build_args().get_matches_from(vec!["arp-scan", "-r", "3", "-c", "50"]),
+        )
+        .unwrap();
+
+        assert_eq!(default_options.compute_request_count(256), 768);
+        assert_eq!(capped_options.compute_request_count(256), 50);
+        assert_eq!(capped_options.compute_request_count(10), 30);
+    }
+
+    #[test]
+    fn should_compute_pass_interval_with_backoff() {
+        let default_options =
+            ScanOptions::new(&build_args().get_matches_from(vec!["arp-scan"])).unwrap();
+        let backoff_options = ScanOptions::new(&build_args().get_matches_from(vec![
+            "arp-scan",
+            "-r",
+            "3",
+            "--retry-backoff",
+            "1.5",
+        ]))
+        .unwrap();
+
+        assert_eq!(default_options.compute_pass_interval(10, 2), 10);
+        assert_eq!(backoff_options.compute_pass_interval(10, 0), 10);
+        assert_eq!(backoff_options.compute_pass_interval(10, 1), 15);
+        assert_eq!(backoff_options.compute_pass_interval(10, 2), 23);
+    }
+
+    #[test]
+    fn should_hide_progress_in_quiet_mode() {
+        let default_options =
+            ScanOptions::new(&build_args().get_matches_from(vec!["arp-scan"])).unwrap();
+        let quiet_options =
+            ScanOptions::new(&build_args().get_matches_from(vec!["arp-scan", "-q"])).unwrap();
+
+        assert!(default_options.is_progress_output());
+        assert!(quiet_options.is_plain_output());
+        assert!(!quiet_options.is_progress_output());
+    }
+
+    #[test]
+    fn should_hide_banner_without_quiet() {
+        let default_options =
+            ScanOptions::new(&build_args().get_matches_from(vec!["arp-scan"])).unwrap();
+        let no_banner_options =
+            ScanOptions::new(&build_args().get_matches_from(vec!["arp-scan", "--no-banner"]))
+                .unwrap();
+
+        assert!(default_options.show_banner);
+        assert!(!no_banner_options.show_banner);
+        assert!(no_banner_options.is_progress_output());
+    }
+
+    #[test]
+    fn should_count_verbose_levels() {
+        let default_options =
+            ScanOptions::new(&build_args().get_matches_from(vec!["arp-scan"])).unwrap();
+        let verbose_options =
+            ScanOptions::new(&build_args().get_matches_from(vec!["arp-scan", "-v"])).unwrap();
+        let frame_options =
+            ScanOptions::new(&build_args().get_matches_from(vec!["arp-scan", "-vv"])).unwrap();
+
+        assert!(!default_options.is_host_logged());
+        assert!(verbose_options.is_host_logged() && !verbose_options.is_frame_logged());
+        assert!(frame_options.is_host_logged() && frame_options.is_frame_logged());
+    }
+
+    #[test]
+    fn should_parse_seconds_to_milliseconds() {
+        assert_eq!(ScanOptions::parse_seconds_to_milliseconds("0"), Ok(0));
+        assert_eq!(ScanOptions::parse_seconds_to_milliseconds("30"), Ok(30_000));
+        assert!(ScanOptions::parse_seconds_to_milliseconds("-1").is_err());
+        assert!(ScanOptions::parse_seconds_to_milliseconds("18446744073709552").is_err());
+    }
+
+    #[test]
+    fn should_parse_pad_length() {
+        assert_eq!(ScanOptions::parse_pad_length("60"), Ok(60));
+        assert_eq!(ScanOptions::parse_pad_length("1514"), Ok(1514));
+        assert!(ScanOptions::parse_pad_length("42").is_err());
+        assert!(ScanOptions::parse_pad_length("1515").is_err());
+        assert!(ScanOptions::parse_pad_length("big").is_err());
+    }
+
+    #[test]
+    fn should_parse_arp_operation() {
+        assert_eq!(
+            ScanOptions::parse_arp_operation("reply"),
+            Ok(ArpOperations::Reply)
+        );
+        assert_eq!(
+            ScanOptions::parse_arp_operation("Request"),
+            Ok(ArpOperations::Request)
+        );
+        assert_eq!(
+            ScanOptions::parse_arp_operation("2"),
+            Ok(ArpOperations::Reply)
+        );
+        assert!(ScanOptions::parse_arp_operation("65536").is_err());
+    }
+
+    #[test]
+    fn should_parse_port_list() {
+        assert_eq!(
+            ScanOptions::parse_port_list("443,22, 80,8000-8002,22"),
+            Ok(vec![22, 80, 443, 8000, 8001, 8002])
+        );
+        assert!(ScanOptions::parse_port_list("0").is_err());
+        assert!(ScanOptions::parse_port_list("80-22").is_err());
+        assert!(ScanOptions::parse_port_list("ssh").is_err());
+    }
+
+    #[test]
+    fn should_override_profile_defaults() {
+        let thorough_options = ScanOptions::new(&build_args().get_matches_from(vec![
+            "arp-scan",
+            "--profile",
+            "thorough",
+        ]))
+        .unwrap();
+        let stealth_options = ScanOptions::new(&build_args().get_matches_from(vec![
+            "arp-scan",
+            "--profile",
+            "stealth",
+            "--interval",
+            "50ms",
+            "--jitter",
+            "0",
+        ]))
+        .unwrap();
+
+        assert_eq!(thorough_options.timeout_ms, TIMEOUT_MS_THOROUGH);
+        assert_eq!(thorough_options.retry_count, 3);
+        assert_eq!(
+            thorough_options.jitter_percent,
+            Some(JITTER_PERCENT_PROFILE)
+        );
+        assert!(matches!(
+            stealth_options.scan_timing,
+            ScanTiming::Interval(50)
+        ));
+        assert_eq!(stealth_options.jitter_percent, Some(0));
+        assert_eq!(stealth_options.target_order, TargetOrder::Shuffle);
+        assert!(!stealth_options.resolve_hostname);
+    }
+
+    #[test]
+    fn should_handle_positional_targets() {
+        let network_target = "192.168.1.0/24".to_string();
+        let host_target = "10.0.0.8".to_string();
+        let networks = ScanOptions::compute_target_networks(vec![&network_target, &host_target]);
+
+        let target_network: Vec<IpNetwork> = vec![
+            IpNetwork::V4(Ipv4Network::new(Ipv4Addr::new(192, 168, 1, 0), 24).unwrap()),
+            IpNetwork::V4(Ipv4Network::new(Ipv4Addr::new(10, 0, 0, 8), 32).unwrap()),
+        ];
+
+        assert_eq!(networks, Ok(target_network));
+    }
+
+    #[test]
+    fn should_split_comma_separated_targets() {
+        let list_target = "10.0.0.1, 10.0.0.5,10.0.0.0/28".to_string();
+        let host_target = "10.0.1.1".to_string();
+        let networks = ScanOptions::compute_target_networks(vec![&list_target, &host_target]);
+
+        let target_network: Vec<IpNetwork> = vec![
+            IpNetwork::V4(Ipv4Network::new(Ipv4Addr::new(10, 0, 0, 0), 28).unwrap()),
+            IpNetwork::V4(Ipv4Network::new(Ipv4Addr::new(10, 0, 1, 1), 32).unwrap()),
+        ];
+
+        assert_eq!(networks, Ok(target_network));
+
+        let invalid_target = "10.0.0.1,10.0.0.300,10.0.0.0/28".to_string();
+        let invalid_networks = ScanOptions::compute_target_networks(vec![&invalid_target]);
+        assert!(invalid_networks
+            .unwrap_err()
+            .starts_with("Invalid target '10.0.0.300'"));
+    }
+
+    #[test]
+    fn should_expand_range_target() {
+        let range_target = "10.0.0.1-10.0.0.50".to_string();
+        let networks = ScanOptions::compute_target_networks(vec![&range_target]).unwrap();
+
+        let target_network: Vec<IpNetwork> = vec![
+            IpNetwork::V4(Ipv4Network::new(Ipv4Addr::new(10, 0, 0, 1), 32).unwrap()),
+            IpNetwork::V4(Ipv4Network::new(Ipv4Addr::new(10, 0, 0, 2), 31).unwrap()),
+            IpNetwork::V4(Ipv4Network::new(Ipv4Addr::new(10, 0, 0, 4), 30).unwrap()),
+            IpNetwork::V4(Ipv4Network::new(Ipv4Addr::new(10, 0, 0, 8), 29).unwrap()),
+            IpNetwork::V4(Ipv4Network::new(Ipv4Addr::new(10, 0, 0, 16), 28).unwrap()),
+            IpNetwork::V4(Ipv4Network::new(Ipv4Addr::new(10, 0, 0, 32), 28).unwrap()),
+            IpNetwork::V4(Ipv4Network::new(Ipv4Addr::new(10, 0, 0, 48), 31).unwrap()),
+            IpNetwork::V4(Ipv4Network::new(Ipv4Addr::new(10, 0, 0, 50), 32).unwrap()),
+        ];
+        let address_count: u32 = networks
+            .iter()
+            .map(|network| match network {
+                IpNetwork::V4(ipv4_network) => ipv4_network.size(),
+                IpNetwork::V6(_) => 0,
+            })
+            .sum();
+
+        assert_eq!(networks, target_network);
+        assert_eq!(address_count, 50);
+    }
+
+    #[test]
+    fn should_expand_whole_range_target() {
+        let range_target = "0.0.0.0-255.255.255.255".to_string();
+        let networks = ScanOptions::compute_target_networks(vec![&range_target]);
+
+        let target_network: Vec<IpNetwork> = vec![IpNetwork::V4(
+            Ipv4Network::new(Ipv4Addr::new(0, 0, 0, 0), 0).unwrap(),
+        )];
+
+        assert_eq!(networks, Ok(target_network));
+    }
+
+    #[test]
+    fn should_fail_reversed_range_target() {
+        let range_target = "10.0.0.9-10.0.0.1".to_string();
+        let networks = ScanOptions::compute_target_networks(vec![&range_target]);
+
+        assert_eq!(
+            networks,
+            Err("Invalid target '10.0.0.9-10.0.0.1' (range start is after range end)".to_string())
+        );
+    }
+
+    #[test]
+    fn should_fail_malformed_target() {
+        let valid_target = "10.0.0.1".to_string();
+        let invalid_target = "10.0.0.1-10.0.0.x".to_string();
+        let networks = ScanOptions::compute_target_networks(vec![&valid_target, &invalid_target]);
+
+        assert_eq!(
+            networks,
+            Err("Invalid target '10.0.0.1-10.0.0.x' (invalid range end 10.0.0.x)".to_string())
+        );
+    }
+
+    #[test]
+    fn should_check_ipv6_network_family() {
+        let ipv4_target = "10.0.0.0/24".to_string();
+        let ipv6_target = "fd00::/120".to_string();
+        let large_target = "fd00::/64".to_string();
+        let ipv4_networks = ScanOptions::compute_target_networks(vec![&ipv4_target]).unwrap();
+        let ipv6_networks = ScanOptions::compute_target_networks(vec![&ipv6_target]).unwrap();
+        let large_networks = ScanOptions::compute_target_networks(vec![&large_target]).unwrap();
+
+        assert_eq!(
+            ScanOptions::check_network_family(&ipv4_networks, false),
+            Ok(())
+        );
+        assert_eq!(
+            ScanOptions::check_network_family(&ipv6_networks, true),
+            Ok(())
+        );
+        assert_eq!(
+            ScanOptions::check_network_family(&ipv6_networks, false),
+            Err("fd00::/120 is an IPv6 network, use --ipv6".to_string())
+        );
+        assert_eq!(
+            ScanOptions::check_network_family(&ipv4_networks, true),
+            Err("10.0.0.0/24 is an IPv4 network, remove --ipv6".to_string())
+        );
+        assert_eq!(
+            ScanOptions::check_network_family(&large_networks, true),
+            Err("fd00::/64 is too large, IPv6 networks should be /112 or smaller".to_string())
+        );
+    }
+
+    #[test]
+    fn should_compute_list_format() {
+        let plain_matches = build_args().get_matches_from(vec!["arp-scan", "-l"]);
+        let json_matches = build_args().get_matches_from(vec!["arp-scan", "-l", "-o", "json"]);
+        let yaml_matches = build_args().get_matches_from(vec!["arp-scan", "-l", "-o", "yaml"]);
+
+        assert_eq!(compute_list_format(&plain_matches), OutputFormat::Plain);
+        assert_eq!(compute_list_format(&json_matches), OutputFormat::Json);
+        assert_eq!(compute_list_format(&yaml_matches), OutputFormat::Plain);
+    }
+
+    #[test]
+    fn should_default_to_plain_output() {
+        let outputs = ScanOptions::compute_output_targets(vec![], None);
+
+        assert_eq!(
+            outputs,
+            Ok(vec![OutputTarget {
+                format: OutputFormat::Plain,
+                file: None,
+            }])
+        );
+    }
+
+    #[test]
+    fn should_parse_multiple_outputs() {
+        let plain_output = "plain".to_string();
+        let json_output = "json:results.json".to_string();
+        let csv_output = "csv:hosts.csv".to_string();
+        let outputs = ScanOptions::compute_output_targets(
+            vec![&plain_output, &json_output, &csv_output],
+            None,
+        );
+
+        assert_eq!(
+            outputs,
+            Ok(vec![
+                OutputTarget {
+                    format: OutputFormat::Plain,
+                    file: None,
+                },
+                OutputTarget {
+                    format: OutputFormat::Json,
+                    file: Some("results.json".to_string()),
+                },
+                OutputTarget {
+                    format: OutputFormat::Csv,
+                    file: Some("hosts.csv".to_string()),
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn should_apply_legacy_output_file() {
+        let html_output = "html".to_string();
+        let output_file = "report.html".to_string();
+        let outputs = ScanOptions::compute_output_targets(vec![&html_output], Some(&output_file));
+
+        assert_eq!(
+            outputs,
+            Ok(vec![OutputTarget {
+                format: OutputFormat::Html,
+                file: Some("report.html".to_string()),
+            }])
+        );
+    }
+
+    #[test]
+    fn should_fail_invalid_outputs() {
+        let plain_output = "plain".to_string();
+        let json_output = "json".to_string();
+        let ndjson_file_output = "ndjson:stream.json".to_string();
+        let unknown_output = "toml:results.toml".to_string();
+        let empty_file_output = "json:".to_string();
+
+        assert_eq!(
+            ScanOptions::compute_output_targets(vec![&plain_output, &json_output], None),
+            Err("Expected a single output format on stdout".to_string())
+        );
+        assert_eq!(
+            ScanOptions::compute_output_targets(vec![&ndjson_file_output], None),
+            Err(
+                "Expected an export format with output file (json/yaml/csv/html/markdown/prometheus/xml/summary-json)"
+                    .to_string()
+            )
+        );
+        assert!(ScanOptions::compute_output_targets(vec![&unknown_output], None).is_err());
+        assert!(ScanOptions::compute_output_targets(vec![&empty_file_output], None).is_err());
+    }
+
+    #[test]
+    fn should_parse_mac_prefixes() {
+        assert_eq!(
+            ScanOptions::parse_mac_prefix("B8:27:EB"),
+            Ok([0xb8, 0x27, 0xeb])
+        );
+        assert_eq!(
+            ScanOptions::parse_mac_prefix("b8-27-eb"),
+            Ok([0xb8, 0x27, 0xeb])
+        );
+        assert_eq!(
+            ScanOptions::parse_mac_prefix("B827EB"),
+            Ok([0xb8, 0x27, 0xeb])
+        );
+        assert!(ScanOptions::parse_mac_prefix("B8:27").is_err());
+        assert!(ScanOptions::parse_mac_prefix("B8:27:EB:01").is_err());
+        assert!(ScanOptions::parse_mac_prefix("ZZ:27:EB").is_err());
+    }
+
+    #[test]
+    fn should_match_requested_mac_prefixes() {
+        let default_options =
+            ScanOptions::new(&build_args().get_matches_from(vec!["arp-scan"])).unwrap();
+        let prefix_options = ScanOptions::new(&build_args().get_matches_from(vec![
+            "arp-scan",
+            "--mac-prefix",
+            "B8:27:EB",
+            "--mac-prefix",
+            "dca632",
+        ]))
+        .unwrap();
+
+        let raspberry_mac = MacAddr::new(0xdc, 0xa6, 0x32, 0x01, 0x02, 0x03);
+        let other_mac = MacAddr::new(0x00, 0x11, 0x22, 0x33, 0x44, 0x55);
+
+        assert!(default_options.matches_mac_prefix(other_mac));
+        assert!(prefix_options.matches_mac_prefix(raspberry_mac));
+        assert!(!prefix_options.matches_mac_prefix(other_mac));
+    }
+
+    #[test]
+    fn should_resolve_oui_update_file() {
+        let default_options =
+            ScanOptions::new(&build_args().get_matches_from(vec!["arp-scan"])).unwrap();
+        let update_options = ScanOptions::new(&build_args().get_matches_from(vec![
+            "arp-scan",
+            "--oui-file",
+            "./oui.csv",
+            "--update-oui",
+        ]))
+        .unwrap();
+        let custom_options = ScanOptions::new(&build_args().get_matches_from(vec![
+            "arp-scan",
+            "--update-oui",
+            "/tmp/oui.csv",
+        ]))
+        .unwrap();
+        let cache_options =
+            ScanOptions::new(&build_args().get_matches_from(vec!["arp-scan", "--update-oui"]))
+                .unwrap();
+
+        assert_eq!(default_options.oui_update_file, None);
+        assert_eq!(
+            default_options.oui_file,
+            vendor::find_system_database("/usr/share/arp-scan/ieee-oui.csv")
+                .unwrap_or_else(|| "/usr/share/arp-scan/ieee-oui.csv".to_string())
+        );
+        assert_eq!(
+            update_options.oui_update_file,
+            Some("./oui.csv".to_string())
+        );
+        assert_eq!(
+            custom_options.oui_update_file,
+            Some("/tmp/oui.csv".to_string())
+        );
+        assert_eq!(
+            cache_options.oui_update_file,
+            vendor::find_cache_database_path()
+        );
+    }
+
+    #[test]
+    fn should_deny_passive_dry_run() {
+        let dry_run_options =
+            ScanOptions::new(&build_args().get_matches_from(vec!["arp-scan", "--dry-run"]))
+                .unwrap();
+        let passive_matches =
+            build_args().try_get_matches_from(vec!["arp-scan", "--dry-run", "--passive"]);
+
+        assert!(dry_run_options.dry_run);
+        assert!(passive_matches.is_err());
+    }
+
+    #[test]
+    fn should_require_list_for_ready_only() {
+        let list_matches = build_args().get_matches_from(vec!["arp-scan", "-l", "--ready-only"]);
+        let scan_matches = build_args().try_get_matches_from(vec!["arp-scan", "--ready-only"]);
+
+        assert!(list_matches.get_flag("ready_only"));
+        assert!(scan_matches.is_err());
+    }
 }
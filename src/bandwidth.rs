@@ -0,0 +1,83 @@
+/**
+ * Parse a given bandwidth string into bits per second. This can be used to
+ * convert a string such as '500k', '1M' or '2MB' into adequate bits/second.
+ * Multipliers are decimal (k, M & G), a trailing 'B' expresses the bandwidth
+ * in bytes per second. Without suffix, the value is parsed as bits/second.
+ */
+pub fn parse_to_bits_per_second(bandwidth_arg: &str) -> Result<u64, &str> {
+    let (value_text, unit_factor) = match bandwidth_arg.strip_suffix("Bps") {
+        Some(value_text) => (value_text, 8),
+        None => match bandwidth_arg.strip_suffix('B') {
+            Some(value_text) => (value_text, 8),
+            None => {
+                let value_text = bandwidth_arg
+                    .strip_suffix("bps")
+                    .or_else(|| bandwidth_arg.strip_suffix("bit"))
+                    .or_else(|| bandwidth_arg.strip_suffix('b'))
+                    .unwrap_or(bandwidth_arg);
+                (value_text, 1)
+            }
+        },
+    };
+
+    let (number_text, multiplier): (&str, u64) = match value_text.chars().last() {
+        Some('k') | Some('K') => (&value_text[..value_text.len() - 1], 1_000),
+        Some('M') => (&value_text[..value_text.len() - 1], 1_000_000),
+        Some('G') => (&value_text[..value_text.len() - 1], 1_000_000_000),
+        _ => (value_text, 1),
+    };
+
+    let number = number_text
+        .parse::<u64>()
+        .map_err(|_| "invalid bandwidth value")?;
+    if number == 0 {
+        return Err("bandwidth should be strictly positive");
+    }
+
+    number
+        .checked_mul(multiplier)
+        .and_then(|value| value.checked_mul(unit_factor))
+        .ok_or("bandwidth value is too large")
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn should_parse_bits() {
+        assert_eq!(parse_to_bits_per_second("1000"), Ok(1000));
+        assert_eq!(parse_to_bits_per_second("1000bps"), Ok(1000));
+    }
+
+    #[test]
+    fn should_parse_multipliers() {
+        assert_eq!(parse_to_bits_per_second("500k"), Ok(500_000));
+        assert_eq!(parse_to_bits_per_second("1M"), Ok(1_000_000));
+        assert_eq!(parse_to_bits_per_second("2Gbit"), Ok(2_000_000_000));
+    }
+
+    #[test]
+    fn should_parse_bytes() {
+        assert_eq!(parse_to_bits_per_second("100B"), Ok(800));
+        assert_eq!(parse_to_bits_per_second("1MB"), Ok(8_000_000));
+        assert_eq!(parse_to_bits_per_second("5kBps"), Ok(40_000));
+    }
+
+    #[test]
+    fn should_deny_invalid_bandwidth() {
+        assert_eq!(
+            parse_to_bits_per_second("1.5M"),
+            Err("invalid bandwidth value")
+        );
+        assert_eq!(
+            parse_to_bits_per_second("3z"),
+            Err("invalid bandwidth value")
+        );
+        assert_eq!(
+            parse_to_bits_per_second("0k"),
+            Err("bandwidth should be strictly positive")
+        );
+    }
+}
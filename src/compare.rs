@@ -0,0 +1,302 @@
+use std::fs;
+use std::net::IpAddr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::ScanError;
+use crate::network::TargetDetails;
+use crate::vendor::UNKNOWN_VENDOR;
+
+/**
+ * A host of a previous scan, as exported in the JSON document ('results'
+ * array) or in a single NDJSON line. Only the fields needed for a comparison
+ * are read, so that exports of older versions can still be loaded.
+ */
+#[derive(Deserialize)]
+struct PreviousResultItem {
+    ipv4: Option<String>,
+    ipv6: Option<String>,
+    mac: String,
+    #[serde(default)]
+    vendor: String,
+}
+
+#[derive(Deserialize)]
+struct PreviousGlobalResult {
+    results: Vec<PreviousResultItem>,
+}
+
+/**
+ * A host found in only one of the compared scans.
+ */
+#[derive(Serialize, Debug, PartialEq, Clone)]
+pub struct ComparedHost {
+    pub ip_address: String,
+    pub mac: String,
+    pub vendor: String,
+}
+
+/**
+ * A host found with the same IP address in both scans, but with another MAC
+ * address or vendor (e.g. a replaced device or a spoofed address).
+ */
+#[derive(Serialize, Debug, PartialEq)]
+pub struct ChangedHost {
+    pub ip_address: String,
+    pub previous_mac: String,
+    pub mac: String,
+    pub previous_vendor: String,
+    pub vendor: String,
+}
+
+/**
+ * Differences between a previous scan (loaded from an export file) and the
+ * current scan results. Each list is sorted by IP address.
+ */
+#[derive(Serialize, Debug, PartialEq)]
+pub struct ScanComparison {
+    pub added: Vec<ComparedHost>,
+    pub removed: Vec<ComparedHost>,
+    pub changed: Vec<ChangedHost>,
+}
+
+impl ScanComparison {
+    pub fn new(previous_hosts: &[ComparedHost], target_details: &[TargetDetails]) -> Self {
+        let current_hosts = build_compared_hosts(target_details);
+
+        let find_host = |hosts: &[ComparedHost], ip_address: &str| -> Option<ComparedHost> {
+            hosts
+                .iter()
+                .find(|host| host.ip_address == ip_address)
+                .cloned()
+        };
+
+        let mut added: Vec<ComparedHost> = vec![];
+        let mut changed: Vec<ChangedHost> = vec![];
+        for current_host in current_hosts.iter() {
+            let previous_host = match find_host(previous_hosts, &current_host.ip_address) {
+                Some(previous_host) => previous_host,
+                None => {
+                    added.push(current_host.clone());
+                    continue;
+                }
+            };
+
+            // Vendors are missing from minimal exports, which should not be
+            // reported as a vendor change.
+            let has_mac_changed = !previous_host.mac.eq_ignore_ascii_case(&current_host.mac);
+            let has_vendor_changed =
+                !previous_host.vendor.is_empty() && previous_host.vendor != current_host.vendor;
+            if has_mac_changed || has_vendor_changed {
+                changed.push(ChangedHost {
+                    ip_address: current_host.ip_address.clone(),
+                    previous_mac: previous_host.mac,
+                    mac: current_host.mac.clone(),
+                    previous_vendor: previous_host.vendor,
+                    vendor: current_host.vendor.clone(),
+                });
+            }
+        }
+
+        let mut removed: Vec<ComparedHost> = previous_hosts
+            .iter()
+            .filter(|previous_host| find_host(&current_hosts, &previous_host.ip_address).is_none())
+            .cloned()
+            .collect();
+
+        added.sort_by_key(|host| parse_ip_address(&host.ip_address));
+        removed.sort_by_key(|host| parse_ip_address(&host.ip_address));
+        changed.sort_by_key(|host| parse_ip_address(&host.ip_address));
+
+        ScanComparison {
+            added,
+            removed,
+            changed,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/**
+ * Keep the compared fields of the current scan results, so that they can be
+ * compared with a following scan (e.g. the next round in watch mode).
+ */
+pub fn build_compared_hosts(target_details: &[TargetDetails]) -> Vec<ComparedHost> {
+    target_details
+        .iter()
+        .map(|detail| ComparedHost {
+            ip_address: detail.ip_address().to_string(),
+            mac: detail.mac.to_string(),
+            vendor: detail
+                .vendor
+                .clone()
+                .unwrap_or_else(|| UNKNOWN_VENDOR.to_string()),
+        })
+        .collect()
+}
+
+fn parse_ip_address(ip_text: &str) -> Option<IpAddr> {
+    ip_text.parse::<IpAddr>().ok()
+}
+
+/**
+ * Parse the hosts of a previous scan, exported either as a JSON document or as
+ * an NDJSON stream (one host per line, the summary line being ignored).
+ */
+fn parse_previous_hosts(content: &str) -> Result<Vec<ComparedHost>, String> {
+    let previous_items: Vec<PreviousResultItem> =
+        match serde_json::from_str::<PreviousGlobalResult>(content) {
+            Ok(global_result) => global_result.results,
+            Err(_) => {
+                let mut previous_items = vec![];
+                for line in content.lines().filter(|line| !line.trim().is_empty()) {
+                    let line_value: serde_json::Value =
+                        serde_json::from_str(line).map_err(|err| err.to_string())?;
+                    if line_value.get("mac").is_none() {
+                        continue;
+                    }
+                    let previous_item: PreviousResultItem =
+                        serde_json::from_value(line_value).map_err(|err| err.to_string())?;
+                    previous_items.push(previous_item);
+                }
+                previous_items
+            }
+        };
+
+    Ok(previous_items
+        .into_iter()
+        .filter_map(|item| {
+            let ip_address = item.ipv6.or(item.ipv4)?;
+            Some(ComparedHost {
+                ip_address,
+                mac: item.mac,
+                vendor: item.vendor,
+            })
+        })
+        .collect())
+}
+
+/**
+ * Load the hosts of a previous scan from a JSON or NDJSON export file, before
+ * starting the scan so that an invalid file is reported early.
+ */
+pub fn load_previous_hosts(file_path: &str) -> Result<Vec<ComparedHost>, ScanError> {
+    let content = fs::read_to_string(file_path).map_err(|err| {
+        ScanError::Io(format!(
+            "Could not read previous scan {} ({})",
+            file_path, err
+        ))
+    })?;
+
+    parse_previous_hosts(&content).map_err(|err| {
+        ScanError::Io(format!(
+            "Could not parse previous scan {} ({})",
+            file_path, err
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use pnet_datalink::MacAddr;
+    use std::net::Ipv4Addr;
+
+    fn build_detail(last_octet: u8, mac: MacAddr, vendor: Option<&str>) -> TargetDetails {
+        TargetDetails {
+            vendor: vendor.map(|vendor| vendor.to_string()),
+            ..TargetDetails::test_default(Ipv4Addr::new(192, 168, 1, last_octet), mac)
+        }
+    }
+
+    #[test]
+    fn should_parse_json_and_ndjson_exports() {
+        let json_content = r#"{"packet_count":2,"results":[
+            {"ipv4":"192.168.1.10","mac":"40:55:82:c3:e5:5b","hostname":"","vendor":"Nokia"},
+            {"ipv6":"fd00::1","mac":"40:55:82:c3:e5:5c"}
+        ]}"#;
+        let ndjson_content =
+            "{\"ipv4\":\"192.168.1.10\",\"mac\":\"40:55:82:c3:e5:5b\",\"vendor\":\"Nokia\"}\n\
+            {\"packet_count\":2,\"arp_count\":1}\n";
+
+        let json_hosts = parse_previous_hosts(json_content).unwrap();
+        let ndjson_hosts = parse_previous_hosts(ndjson_content).unwrap();
+
+        assert_eq!(json_hosts.len(), 2);
+        assert_eq!(json_hosts[1].ip_address, "fd00::1");
+        assert_eq!(json_hosts[1].vendor, "");
+        assert_eq!(ndjson_hosts, json_hosts[..1].to_vec());
+        assert!(parse_previous_hosts("not a scan").is_err());
+    }
+
+    #[test]
+    fn should_compare_with_previous_scan() {
+        let previous_host = |ip_address: &str, mac: &str, vendor: &str| ComparedHost {
+            ip_address: ip_address.to_string(),
+            mac: mac.to_string(),
+            vendor: vendor.to_string(),
+        };
+        let previous_hosts = vec![
+            previous_host("192.168.1.30", "40:55:82:c3:e5:5b", "Nokia"),
+            previous_host("192.168.1.10", "40:55:82:C3:E5:01", "Apple"),
+            previous_host("192.168.1.20", "40:55:82:c3:e5:02", "Apple"),
+        ];
+        let target_details = vec![
+            build_detail(40, MacAddr::new(0x40, 0x55, 0x82, 0xc3, 0xe5, 0x04), None),
+            build_detail(
+                20,
+                MacAddr::new(0x40, 0x55, 0x82, 0xc3, 0xe5, 0x03),
+                Some("Apple"),
+            ),
+            build_detail(
+                10,
+                MacAddr::new(0x40, 0x55, 0x82, 0xc3, 0xe5, 0x01),
+                Some("Apple"),
+            ),
+        ];
+
+        let comparison = ScanComparison::new(&previous_hosts, &target_details);
+
+        assert_eq!(
+            comparison.added,
+            vec![previous_host(
+                "192.168.1.40",
+                "40:55:82:c3:e5:04",
+                UNKNOWN_VENDOR
+            )]
+        );
+        assert_eq!(
+            comparison.removed,
+            vec![previous_host("192.168.1.30", "40:55:82:c3:e5:5b", "Nokia")]
+        );
+        assert_eq!(comparison.changed.len(), 1);
+        assert_eq!(comparison.changed[0].ip_address, "192.168.1.20");
+        assert_eq!(comparison.changed[0].previous_mac, "40:55:82:c3:e5:02");
+        assert_eq!(comparison.changed[0].mac, "40:55:82:c3:e5:03");
+        assert!(!comparison.is_empty());
+    }
+
+    #[test]
+    fn should_compare_consecutive_rounds() {
+        let mac = MacAddr::new(0x40, 0x55, 0x82, 0xc3, 0xe5, 0x01);
+        let first_round = vec![build_detail(10, mac, Some("Apple"))];
+        let second_round = vec![
+            build_detail(10, mac, Some("Apple")),
+            build_detail(20, mac, None),
+        ];
+
+        let previous_round_hosts = build_compared_hosts(&first_round);
+        let stable_comparison = ScanComparison::new(&previous_round_hosts, &first_round);
+        let round_comparison = ScanComparison::new(&previous_round_hosts, &second_round);
+
+        assert!(stable_comparison.is_empty());
+        assert_eq!(round_comparison.added.len(), 1);
+        assert_eq!(round_comparison.added[0].ip_address, "192.168.1.20");
+        assert!(round_comparison.removed.is_empty());
+        assert!(round_comparison.changed.is_empty());
+    }
+}
@@ -0,0 +1,426 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use clap::parser::ValueSource;
+use clap::{ArgMatches, Command};
+
+use crate::args::build_args;
+
+const DEFAULT_CONFIG_DIRECTORY: &str = "arp-scan-rs";
+const DEFAULT_CONFIG_FILE: &str = "config.toml";
+
+/**
+ * A value of the configuration file. Numbers are kept as text, since they are
+ * parsed by the CLI options themselves (e.g. '10ms' and '10' intervals).
+ */
+#[derive(Debug, PartialEq)]
+pub enum ConfigValue {
+    Text(String),
+    Flag(bool),
+    List(Vec<String>),
+}
+
+/**
+ * Merge the options of the configuration file into the CLI arguments. Each
+ * key of the file mirrors a long CLI option (e.g. 'interface', 'oui-file'),
+ * and is only used if the option was not given on the command line (and
+ * does not conflict with an option given on the command line). The file is
+ * given with '--config', or found in the user configuration directory.
+ */
+pub fn merge_config_args(cli_args: Vec<String>) -> Result<Vec<String>, String> {
+    // Invalid command lines (and help requests) are left for clap to report,
+    // once parsed with the final arguments.
+    let command = build_args();
+    let cli_matches = match command.clone().try_get_matches_from(&cli_args) {
+        Ok(cli_matches) => cli_matches,
+        Err(_) => return Ok(cli_args),
+    };
+
+    let config_path = match cli_matches.get_one::<String>("config") {
+        Some(config_path) => PathBuf::from(config_path),
+        None => match find_default_config_path() {
+            Some(default_path) if default_path.is_file() => default_path,
+            _ => return Ok(cli_args),
+        },
+    };
+
+    let config_text = fs::read_to_string(&config_path)
+        .map_err(|err| format!("Could not read {} ({})", config_path.display(), err))?;
+    let config_entries = parse_config(&config_text)
+        .map_err(|err| format!("Invalid config file {} ({})", config_path.display(), err))?;
+
+    let config_args = compute_config_args(&command, &cli_matches, config_entries)?;
+    Ok(cli_args.into_iter().chain(config_args).collect())
+}
+
+/**
+ * Find the default configuration file, in the XDG configuration directory
+ * (or '~/.config' when the XDG variable is not set).
+ */
+fn find_default_config_path() -> Option<PathBuf> {
+    let config_directory = match env::var("XDG_CONFIG_HOME") {
+        Ok(xdg_directory) if !xdg_directory.is_empty() => PathBuf::from(xdg_directory),
+        _ => PathBuf::from(env::var("HOME").ok()?).join(".config"),
+    };
+
+    Some(
+        config_directory
+            .join(DEFAULT_CONFIG_DIRECTORY)
+            .join(DEFAULT_CONFIG_FILE),
+    )
+}
+
+/**
+ * Convert the configuration entries to CLI arguments, skipping the options
+ * already given on the command line. Positional targets (the 'targets' key)
+ * are appended after all options.
+ */
+fn compute_config_args(
+    command: &Command,
+    cli_matches: &ArgMatches,
+    config_entries: Vec<(String, ConfigValue)>,
+) -> Result<Vec<String>, String> {
+    let is_given = |arg_id: &str| {
+        cli_matches
+            .ids()
+            .any(|given_id| given_id.as_str() == arg_id)
+            && cli_matches.value_source(arg_id) == Some(ValueSource::CommandLine)
+    };
+
+    let mut option_args: Vec<String> = vec![];
+    let mut target_args: Vec<String> = vec![];
+    for (key, value) in config_entries {
+        let arg = command
+            .get_arguments()
+            .find(|arg| {
+                arg.get_long() == Some(key.as_str())
+                    || (arg.is_positional() && arg.get_id().as_str() == key)
+            })
+            .filter(|arg| arg.get_id().as_str() != "config")
+            .ok_or_else(|| format!("Unknown config key '{}'", key))?;
+
+        // Conflicts may be declared on either side (e.g. '--passive' declares
+        // its conflict with '--retry', but not the other way around).
+        let has_cli_conflict = command
+            .get_arg_conflicts_with(arg)
+            .iter()
+            .any(|conflict| is_given(conflict.get_id().as_str()))
+            || command.get_arguments().any(|given_arg| {
+                is_given(given_arg.get_id().as_str())
+                    && command
+                        .get_arg_conflicts_with(given_arg)
+                        .iter()
+                        .any(|conflict| conflict.get_id() == arg.get_id())
+            });
+        if is_given(arg.get_id().as_str()) || has_cli_conflict {
+            continue;
+        }
+
+        let values = match (value, arg.get_action().takes_values()) {
+            (ConfigValue::Flag(is_enabled), false) => {
+                if is_enabled {
+                    option_args.push(format!("--{}", key));
+                }
+                continue;
+            }
+            (ConfigValue::Flag(_), true) => {
+                return Err(format!("Expected a value for config key '{}'", key));
+            }
+            (_, false) => {
+                return Err(format!("Expected true or false for config key '{}'", key));
+            }
+            (ConfigValue::Text(text), true) => vec![text],
+            (ConfigValue::List(list), true) => list,
+        };
+
+        match arg.is_positional() {
+            true => target_args.extend(values),
+            false => {
+                for value in values {
+                    option_args.push(format!("--{}={}", key, value));
+                }
+            }
+        }
+    }
+
+    option_args.extend(target_args);
+    Ok(option_args)
+}
+
+/**
+ * Parse the subset of TOML used by configuration files: top-level keys with
+ * strings, numbers, booleans or arrays of those (arrays may span multiple
+ * lines). Tables are not supported, since all options are top-level.
+ */
+pub fn parse_config(config_text: &str) -> Result<Vec<(String, ConfigValue)>, String> {
+    let mut entries: Vec<(String, ConfigValue)> = vec![];
+    let mut lines = config_text.lines().enumerate();
+
+    while let Some((line_index, line)) = lines.next() {
+        let entry_text = strip_comment(line).trim();
+        if entry_text.is_empty() {
+            continue;
+        }
+        if entry_text.starts_with('[') {
+            return Err(format!("line {}, tables are not supported", line_index + 1));
+        }
+
+        let (key_text, value_text) = entry_text
+            .split_once('=')
+            .ok_or_else(|| format!("line {}, expected 'key = value'", line_index + 1))?;
+        let key = key_text.trim().trim_matches('"').to_string();
+
+        // Arrays are accumulated until their closing bracket, which may be on
+        // one of the following lines.
+        let mut value_text = value_text.trim().to_string();
+        while value_text.starts_with('[') && !strip_comment(&value_text).trim_end().ends_with(']') {
+            let (_, next_line) = lines
+                .next()
+                .ok_or_else(|| format!("line {}, unclosed array", line_index + 1))?;
+            value_text.push(' ');
+            value_text.push_str(strip_comment(next_line).trim());
+        }
+
+        let value = parse_config_value(value_text.trim())
+            .map_err(|err| format!("line {}, {}", line_index + 1, err))?;
+        if entries.iter().any(|(entry_key, _)| *entry_key == key) {
+            return Err(format!("line {}, duplicate key '{}'", line_index + 1, key));
+        }
+        entries.push((key, value));
+    }
+
+    Ok(entries)
+}
+
+fn parse_config_value(value_text: &str) -> Result<ConfigValue, String> {
+    match value_text {
+        "true" => return Ok(ConfigValue::Flag(true)),
+        "false" => return Ok(ConfigValue::Flag(false)),
+        _ => {}
+    }
+
+    if let Some(array_text) = value_text
+        .strip_prefix('[')
+        .and_then(|array_text| array_text.strip_suffix(']'))
+    {
+        let list = split_array_items(array_text)?
+            .iter()
+            .map(|item_text| parse_scalar(item_text))
+            .collect::<Result<Vec<String>, String>>()?;
+        return Ok(ConfigValue::List(list));
+    }
+
+    parse_scalar(value_text).map(ConfigValue::Text)
+}
+
+/**
+ * Parse a single string or number. Basic strings support the usual escape
+ * sequences, literal strings (single quotes) are kept as they are.
+ */
+fn parse_scalar(scalar_text: &str) -> Result<String, String> {
+    if let Some(literal_text) = scalar_text
+        .strip_prefix('\'')
+        .and_then(|literal_text| literal_text.strip_suffix('\''))
+    {
+        return Ok(literal_text.to_string());
+    }
+
+    if let Some(basic_text) = scalar_text
+        .strip_prefix('"')
+        .and_then(|basic_text| basic_text.strip_suffix('"'))
+    {
+        let mut unescaped = String::new();
+        let mut characters = basic_text.chars();
+        while let Some(character) = characters.next() {
+            if character != '\\' {
+                unescaped.push(character);
+                continue;
+            }
+            match characters.next() {
+                Some('"') => unescaped.push('"'),
+                Some('\\') => unescaped.push('\\'),
+                Some('n') => unescaped.push('\n'),
+                Some('t') => unescaped.push('\t'),
+                _ => return Err(format!("invalid escape sequence in {}", scalar_text)),
+            }
+        }
+        return Ok(unescaped);
+    }
+
+    let is_number = !scalar_text.is_empty()
+        && scalar_text
+            .chars()
+            .all(|character| character.is_ascii_alphanumeric() || "+-._".contains(character));
+    match is_number {
+        true => Ok(scalar_text.replace('_', "")),
+        false => Err(format!("invalid value {}", scalar_text)),
+    }
+}
+
+/**
+ * Split the items of an array on commas, ignoring the commas found inside
+ * strings. A trailing comma is allowed, as in TOML.
+ */
+fn split_array_items(array_text: &str) -> Result<Vec<String>, String> {
+    let mut items: Vec<String> = vec![];
+    let mut current_item = String::new();
+    let mut quote: Option<char> = None;
+
+    for character in array_text.chars() {
+        match (quote, character) {
+            (None, ',') => {
+                items.push(current_item.trim().to_string());
+                current_item.clear();
+                continue;
+            }
+            (None, '"' | '\'') => quote = Some(character),
+            (Some(open_quote), _) if open_quote == character => quote = None,
+            _ => {}
+        }
+        current_item.push(character);
+    }
+
+    if quote.is_some() {
+        return Err("unclosed string in array".to_string());
+    }
+    if !current_item.trim().is_empty() {
+        items.push(current_item.trim().to_string());
+    }
+    match items.iter().any(|item| item.is_empty()) {
+        true => Err("empty array item".to_string()),
+        false => Ok(items),
+    }
+}
+
+/**
+ * Remove the comment of a line, '#' characters inside strings being kept.
+ */
+fn strip_comment(line: &str) -> &str {
+    let mut quote: Option<char> = None;
+    for (index, character) in line.char_indices() {
+        match (quote, character) {
+            (None, '#') => return &line[..index],
+            (None, '"' | '\'') => quote = Some(character),
+            (Some(open_quote), _) if open_quote == character => quote = None,
+            _ => {}
+        }
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn should_parse_config_values() {
+        let config_text = "# Daily scan
+interface = \"eth0\"
+interval = 10   # milliseconds
+numeric = true
+random = false
+oui-file = '/opt/oui.csv'
+exclude = [
+  \"192.168.1.1\",
+  \"192.168.1.254\", # router
+]
+";
+
+        assert_eq!(
+            parse_config(config_text),
+            Ok(vec![
+                (
+                    "interface".to_string(),
+                    ConfigValue::Text("eth0".to_string())
+                ),
+                ("interval".to_string(), ConfigValue::Text("10".to_string())),
+                ("numeric".to_string(), ConfigValue::Flag(true)),
+                ("random".to_string(), ConfigValue::Flag(false)),
+                (
+                    "oui-file".to_string(),
+                    ConfigValue::Text("/opt/oui.csv".to_string())
+                ),
+                (
+                    "exclude".to_string(),
+                    ConfigValue::List(vec!["192.168.1.1".to_string(), "192.168.1.254".to_string()])
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn should_deny_invalid_config() {
+        assert_eq!(
+            parse_config("[scan]\ninterface = \"eth0\""),
+            Err("line 1, tables are not supported".to_string())
+        );
+        assert_eq!(
+            parse_config("interface \"eth0\""),
+            Err("line 1, expected 'key = value'".to_string())
+        );
+        assert_eq!(
+            parse_config("exclude = [\"10.0.0.1\""),
+            Err("line 1, unclosed array".to_string())
+        );
+        assert_eq!(
+            parse_config("numeric = true\nnumeric = false"),
+            Err("line 2, duplicate key 'numeric'".to_string())
+        );
+    }
+
+    fn compute_args(cli_args: Vec<&str>, config_text: &str) -> Result<Vec<String>, String> {
+        let command = build_args();
+        let cli_matches = command.clone().get_matches_from(cli_args);
+        compute_config_args(&command, &cli_matches, parse_config(config_text).unwrap())
+    }
+
+    #[test]
+    fn should_convert_config_to_args() {
+        let config_text = "targets = [\"10.0.0.0/29\"]
+interface = \"eth0\"
+numeric = true
+exclude = [\"10.0.0.1\", \"10.0.0.2\"]
+";
+
+        assert_eq!(
+            compute_args(vec!["arp-scan"], config_text),
+            Ok(vec![
+                "--interface=eth0".to_string(),
+                "--numeric".to_string(),
+                "--exclude=10.0.0.1".to_string(),
+                "--exclude=10.0.0.2".to_string(),
+                "10.0.0.0/29".to_string(),
+            ])
+        );
+    }
+
+    #[test]
+    fn should_give_precedence_to_cli_args() {
+        let config_text = "interface = \"eth0\"
+retry = 3
+targets = [\"10.0.0.0/29\"]
+";
+
+        assert_eq!(
+            compute_args(
+                vec!["arp-scan", "-i", "eth1", "--passive", "-n", "10.0.1.0/24"],
+                config_text
+            ),
+            Ok(vec![])
+        );
+    }
+
+    #[test]
+    fn should_deny_unknown_config_key() {
+        assert_eq!(
+            compute_args(vec!["arp-scan"], "colour = false"),
+            Err("Unknown config key 'colour'".to_string())
+        );
+        assert_eq!(
+            compute_args(vec!["arp-scan"], "numeric = \"yes\""),
+            Err("Expected true or false for config key 'numeric'".to_string())
+        );
+    }
+}
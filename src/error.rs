@@ -0,0 +1,112 @@
+use std::error::Error;
+use std::fmt;
+
+pub const EXIT_CODE_ERROR: i32 = 1;
+pub const EXIT_CODE_NO_HOST: i32 = 2;
+
+/**
+ * Errors that may stop an ARP scan. These errors are returned by the library
+ * functions, so that embedders can handle them without exiting the process.
+ * The CLI prints them in a single place before exiting with an error code.
+ */
+#[derive(Debug, PartialEq)]
+pub enum ScanError {
+    Channel(String),
+    InterfaceNotFound(String),
+    PermissionDenied,
+    Serialization(String),
+    Io(String),
+    InvalidOption(String),
+    Packet(String),
+    SelfTest(String),
+    TooManyHosts(u128, u128),
+    NoHostFound,
+}
+
+impl fmt::Display for ScanError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ScanError::Channel(details) => {
+                write!(formatter, "Datalink channel creation failed ({})", details)
+            }
+            ScanError::InterfaceNotFound(details) => write!(formatter, "{}", details),
+            ScanError::PermissionDenied => write!(
+                formatter,
+                "Should run this binary as root or use --help for options"
+            ),
+            ScanError::Serialization(details) => {
+                write!(formatter, "Could not export results ({})", details)
+            }
+            ScanError::Io(details) => write!(formatter, "{}", details),
+            ScanError::InvalidOption(details) => write!(formatter, "{}", details),
+            ScanError::Packet(packet_name) => {
+                write!(formatter, "Could not build {} packet", packet_name)
+            }
+            ScanError::SelfTest(details) => write!(formatter, "Self-test failed, {}", details),
+            ScanError::TooManyHosts(host_count, max_hosts) => write!(
+                formatter,
+                "Refusing to scan {} hosts, above the limit of {} hosts\n\
+                Use --force to scan anyway, or --max-hosts to raise the limit",
+                host_count, max_hosts
+            ),
+            ScanError::NoHostFound => write!(formatter, "No host found on the network"),
+        }
+    }
+}
+
+impl ScanError {
+    /**
+     * Exit code of the CLI for this error. Scripts can distinguish a scan
+     * without any discovered host (when requested) from other failures.
+     */
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ScanError::NoHostFound => EXIT_CODE_NO_HOST,
+            _ => EXIT_CODE_ERROR,
+        }
+    }
+}
+
+impl Error for ScanError {}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn should_display_scan_errors() {
+        assert_eq!(
+            ScanError::Channel("operation not permitted".to_string()).to_string(),
+            "Datalink channel creation failed (operation not permitted)"
+        );
+        assert_eq!(
+            ScanError::PermissionDenied.to_string(),
+            "Should run this binary as root or use --help for options"
+        );
+        assert_eq!(
+            ScanError::Serialization("invalid UTF-8".to_string()).to_string(),
+            "Could not export results (invalid UTF-8)"
+        );
+        assert_eq!(
+            ScanError::Packet("Ethernet".to_string()).to_string(),
+            "Could not build Ethernet packet"
+        );
+        assert_eq!(
+            ScanError::SelfTest("truncated ARP packet".to_string()).to_string(),
+            "Self-test failed, truncated ARP packet"
+        );
+        assert_eq!(
+            ScanError::TooManyHosts(16_777_216, 65536).to_string(),
+            "Refusing to scan 16777216 hosts, above the limit of 65536 hosts\n\
+            Use --force to scan anyway, or --max-hosts to raise the limit"
+        );
+    }
+
+    #[test]
+    fn should_give_error_exit_codes() {
+        assert_eq!(ScanError::PermissionDenied.exit_code(), 1);
+        assert_eq!(ScanError::Io("broken pipe".to_string()).exit_code(), 1);
+        assert_eq!(ScanError::NoHostFound.exit_code(), 2);
+    }
+}
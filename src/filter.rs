@@ -0,0 +1,274 @@
+use std::io;
+use std::mem;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+use pnet_datalink::{DataLinkReceiver, NetworkInterface};
+
+use crate::error::ScanError;
+
+// Classic BPF opcodes (see 'linux/filter.h'), only the ones used by the
+// EtherType filter: load a half-word, compare with a constant and return.
+const BPF_LD_H_ABS: u16 = 0x28;
+const BPF_JMP_JEQ_K: u16 = 0x15;
+const BPF_RET_K: u16 = 0x06;
+
+const ETHERTYPE_OFFSET: u32 = 12;
+const VLAN_ETHERTYPE_OFFSET: u32 = 16;
+const ETHERTYPE_VLAN: u32 = 0x8100;
+
+// Frames accepted by the filter are kept whole, the receive buffer is large
+// enough for jumbo frames.
+const FILTER_ACCEPT_LENGTH: u32 = 0x0004_0000;
+const RECEIVE_BUFFER_SIZE: usize = 65536;
+
+/**
+ * A datalink receiver reading from a packet socket with a kernel (BPF) filter
+ * attached, so that the frames of other protocols are dropped by the kernel
+ * before being copied to userspace. The datalink backend does not expose its
+ * socket, this receiver therefore owns a dedicated one.
+ */
+pub struct FilteredReceiver {
+    socket: OwnedFd,
+    buffer: Vec<u8>,
+}
+
+impl DataLinkReceiver for FilteredReceiver {
+    fn next(&mut self) -> io::Result<&[u8]> {
+        let frame_length = unsafe {
+            libc::recv(
+                self.socket.as_raw_fd(),
+                self.buffer.as_mut_ptr() as *mut libc::c_void,
+                self.buffer.len(),
+                0,
+            )
+        };
+
+        if frame_length < 0 {
+            let error = io::Error::last_os_error();
+            // Expired reads (and reads interrupted by a signal) are reported
+            // as timeouts, like the datalink backend does.
+            return match error.kind() {
+                io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted => {
+                    Err(io::Error::new(io::ErrorKind::TimedOut, "Timed out"))
+                }
+                _ => Err(error),
+            };
+        }
+
+        Ok(&self.buffer[..frame_length as usize])
+    }
+}
+
+/**
+ * Open a packet socket on the given interface, only receiving the frames with
+ * the given EtherType (directly or behind a single 802.1Q tag). The socket is
+ * created without any protocol and bound once the filter is attached, so that
+ * no unfiltered frame is ever queued. As with the datalink channels, the
+ * interface is kept in promiscuous mode and reads expire after the timeout.
+ */
+pub fn open_filtered_receiver(
+    interface: &NetworkInterface,
+    ethertype: u16,
+    rcv_timeout_ms: u64,
+) -> Result<Box<dyn DataLinkReceiver>, ScanError> {
+    let channel_error =
+        |context: &str, error: io::Error| ScanError::Channel(format!("{} ({})", context, error));
+
+    let raw_socket =
+        unsafe { libc::socket(libc::AF_PACKET, libc::SOCK_RAW | libc::SOCK_CLOEXEC, 0) };
+    if raw_socket < 0 {
+        return Err(channel_error(
+            "could not open packet socket",
+            io::Error::last_os_error(),
+        ));
+    }
+    let socket = unsafe { OwnedFd::from_raw_fd(raw_socket) };
+
+    let mut filter = build_ethertype_filter(ethertype);
+    let filter_program = libc::sock_fprog {
+        len: filter.len() as libc::c_ushort,
+        filter: filter.as_mut_ptr(),
+    };
+    set_socket_option(
+        &socket,
+        libc::SOL_SOCKET,
+        libc::SO_ATTACH_FILTER,
+        &filter_program,
+    )
+    .map_err(|error| channel_error("could not attach EtherType filter", error))?;
+
+    let read_timeout = libc::timeval {
+        tv_sec: (rcv_timeout_ms / 1000) as libc::time_t,
+        tv_usec: ((rcv_timeout_ms % 1000) * 1000) as libc::suseconds_t,
+    };
+    set_socket_option(&socket, libc::SOL_SOCKET, libc::SO_RCVTIMEO, &read_timeout)
+        .map_err(|error| channel_error("could not set read timeout", error))?;
+
+    let mut socket_address: libc::sockaddr_ll = unsafe { mem::zeroed() };
+    socket_address.sll_family = libc::AF_PACKET as libc::c_ushort;
+    socket_address.sll_protocol = (libc::ETH_P_ALL as u16).to_be();
+    socket_address.sll_ifindex = interface.index as libc::c_int;
+    let bind_result = unsafe {
+        libc::bind(
+            socket.as_raw_fd(),
+            &socket_address as *const libc::sockaddr_ll as *const libc::sockaddr,
+            mem::size_of::<libc::sockaddr_ll>() as libc::socklen_t,
+        )
+    };
+    if bind_result < 0 {
+        return Err(channel_error(
+            "could not bind packet socket",
+            io::Error::last_os_error(),
+        ));
+    }
+
+    let mut promiscuous_request: libc::packet_mreq = unsafe { mem::zeroed() };
+    promiscuous_request.mr_ifindex = interface.index as libc::c_int;
+    promiscuous_request.mr_type = libc::PACKET_MR_PROMISC as libc::c_ushort;
+    set_socket_option(
+        &socket,
+        libc::SOL_PACKET,
+        libc::PACKET_ADD_MEMBERSHIP,
+        &promiscuous_request,
+    )
+    .map_err(|error| channel_error("could not enable promiscuous mode", error))?;
+
+    // The frames sent by the scan are not received back on this socket. This
+    // option is only known by recent kernels (4.20+), otherwise these frames
+    // are still dropped by the response thread.
+    let _ = set_socket_option(
+        &socket,
+        libc::SOL_PACKET,
+        libc::PACKET_IGNORE_OUTGOING,
+        &(1 as libc::c_int),
+    );
+
+    Ok(Box::new(FilteredReceiver {
+        socket,
+        buffer: vec![0; RECEIVE_BUFFER_SIZE],
+    }))
+}
+
+/**
+ * Build a classic BPF program accepting the frames with the given EtherType.
+ * Tagged frames are accepted if their inner EtherType matches, for drivers
+ * that do not strip the 802.1Q tag before the filter runs.
+ */
+fn build_ethertype_filter(ethertype: u16) -> Vec<libc::sock_filter> {
+    let instruction = |code: u16, jt: u8, jf: u8, k: u32| libc::sock_filter { code, jt, jf, k };
+    let ethertype = u32::from(ethertype);
+
+    vec![
+        instruction(BPF_LD_H_ABS, 0, 0, ETHERTYPE_OFFSET),
+        instruction(BPF_JMP_JEQ_K, 3, 0, ethertype),
+        instruction(BPF_JMP_JEQ_K, 0, 3, ETHERTYPE_VLAN),
+        instruction(BPF_LD_H_ABS, 0, 0, VLAN_ETHERTYPE_OFFSET),
+        instruction(BPF_JMP_JEQ_K, 0, 1, ethertype),
+        instruction(BPF_RET_K, 0, 0, FILTER_ACCEPT_LENGTH),
+        instruction(BPF_RET_K, 0, 0, 0),
+    ]
+}
+
+fn set_socket_option<T>(
+    socket: &OwnedFd,
+    level: libc::c_int,
+    name: libc::c_int,
+    value: &T,
+) -> io::Result<()> {
+    let option_result = unsafe {
+        libc::setsockopt(
+            socket.as_raw_fd(),
+            level,
+            name,
+            value as *const T as *const libc::c_void,
+            mem::size_of::<T>() as libc::socklen_t,
+        )
+    };
+
+    match option_result {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /**
+     * Run the filter program on a frame, as the kernel would (only the
+     * instructions of the EtherType filter are supported).
+     */
+    fn run_filter(filter: &[libc::sock_filter], frame: &[u8]) -> u32 {
+        let mut accumulator: u32 = 0;
+        let mut index = 0;
+        loop {
+            let instruction = &filter[index];
+            index += 1;
+            match instruction.code {
+                BPF_LD_H_ABS => {
+                    let offset = instruction.k as usize;
+                    match frame.get(offset..offset + 2) {
+                        Some(bytes) => {
+                            accumulator = u16::from_be_bytes([bytes[0], bytes[1]]).into()
+                        }
+                        None => return 0,
+                    }
+                }
+                BPF_JMP_JEQ_K => match accumulator == instruction.k {
+                    true => index += instruction.jt as usize,
+                    false => index += instruction.jf as usize,
+                },
+                BPF_RET_K => return instruction.k,
+                code => panic!("unexpected BPF instruction 0x{:02x}", code),
+            }
+        }
+    }
+
+    fn build_frame(ethertype: u16, payload: &[u8]) -> Vec<u8> {
+        let mut frame = vec![0xff; 12];
+        frame.extend_from_slice(&ethertype.to_be_bytes());
+        frame.extend_from_slice(payload);
+        frame
+    }
+
+    #[test]
+    fn should_accept_arp_frames() {
+        let filter = build_ethertype_filter(0x0806);
+
+        assert_eq!(
+            run_filter(&filter, &build_frame(0x0806, &[0; 28])),
+            FILTER_ACCEPT_LENGTH
+        );
+        assert_eq!(
+            run_filter(&filter, &build_frame(0x8100, &[0x00, 0x2d, 0x08, 0x06])),
+            FILTER_ACCEPT_LENGTH
+        );
+    }
+
+    #[test]
+    fn should_drop_other_frames() {
+        let filter = build_ethertype_filter(0x0806);
+
+        assert_eq!(run_filter(&filter, &build_frame(0x0800, &[0; 20])), 0);
+        assert_eq!(run_filter(&filter, &build_frame(0x86dd, &[0; 40])), 0);
+        assert_eq!(
+            run_filter(&filter, &build_frame(0x8100, &[0x00, 0x2d, 0x08, 0x00])),
+            0
+        );
+        assert_eq!(run_filter(&filter, &build_frame(0x8100, &[0x00])), 0);
+        assert_eq!(run_filter(&filter, &[0xff; 10]), 0);
+    }
+
+    #[test]
+    fn should_filter_neighbor_frames() {
+        let filter = build_ethertype_filter(0x86dd);
+
+        assert_eq!(
+            run_filter(&filter, &build_frame(0x86dd, &[0; 40])),
+            FILTER_ACCEPT_LENGTH
+        );
+        assert_eq!(run_filter(&filter, &build_frame(0x0806, &[0; 28])), 0);
+    }
+}
@@ -0,0 +1,30 @@
+/*!
+ * ARP scan library, used by the `arp-scan` binary. The scan orchestration
+ * (datalink channels, ARP requests & responses) is available through the
+ * `run_scan` function, which returns the scan summary and all discovered
+ * targets without writing anything on the terminal.
+ */
+
+pub mod args;
+pub mod bandwidth;
+pub mod compare;
+pub mod config;
+pub mod error;
+#[cfg(target_os = "linux")]
+pub mod filter;
+pub mod live;
+pub mod ndp;
+pub mod network;
+pub mod pcap;
+pub mod progress;
+pub mod scan;
+pub mod snmp;
+pub mod time;
+pub mod utils;
+pub mod vendor;
+pub mod webhook;
+
+pub use crate::args::ScanOptions;
+pub use crate::error::ScanError;
+pub use crate::network::{ResponseSummary, TargetDetails};
+pub use crate::scan::run_scan;
@@ -0,0 +1,216 @@
+use std::collections::BTreeMap;
+use std::io::{self, IsTerminal};
+use std::net::IpAddr;
+use std::time::SystemTime;
+
+use ansi_term::Color::{Blue, Green, Yellow};
+use ansi_term::Style;
+use pnet_datalink::MacAddr;
+
+use crate::network::TargetDetails;
+use crate::time;
+use crate::utils::color_style;
+use crate::vendor::UNKNOWN_VENDOR;
+
+/**
+ * A host of the live display, with its details merged over all rounds.
+ */
+#[derive(Debug, PartialEq)]
+pub struct LiveHost {
+    pub mac: MacAddr,
+    pub vendor: Option<String>,
+    pub reply_count: usize,
+    pub round_count: usize,
+    pub last_seen: SystemTime,
+    pub is_present: bool,
+}
+
+/**
+ * A de-duplicated display of all hosts discovered during a watch scan. Each
+ * host keeps a single row (ordered by IP address), which is updated by the
+ * following rounds instead of being printed again. Hosts that did not answer
+ * the last round are kept, but displayed as absent.
+ */
+pub struct LiveDisplay {
+    hosts: BTreeMap<IpAddr, LiveHost>,
+    round_count: usize,
+}
+
+impl Default for LiveDisplay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LiveDisplay {
+    pub fn new() -> Self {
+        LiveDisplay {
+            hosts: BTreeMap::new(),
+            round_count: 0,
+        }
+    }
+
+    /**
+     * Merge the results of a new round: known hosts are updated (MAC address,
+     * reply count, last seen time, ...) and new hosts get their own row.
+     */
+    pub fn update(&mut self, target_details: &[TargetDetails]) {
+        self.round_count += 1;
+        for live_host in self.hosts.values_mut() {
+            live_host.is_present = false;
+        }
+
+        for detail in target_details.iter() {
+            let live_host = self
+                .hosts
+                .entry(detail.ip_address())
+                .or_insert_with(|| LiveHost {
+                    mac: detail.mac,
+                    vendor: None,
+                    reply_count: 0,
+                    round_count: 0,
+                    last_seen: detail.last_seen,
+                    is_present: false,
+                });
+            live_host.mac = detail.mac;
+            live_host.vendor = detail.vendor.clone().or(live_host.vendor.take());
+            live_host.reply_count += detail.reply_count;
+            live_host.round_count += 1;
+            live_host.last_seen = detail.last_seen;
+            live_host.is_present = true;
+        }
+    }
+
+    pub fn hosts(&self) -> &BTreeMap<IpAddr, LiveHost> {
+        &self.hosts
+    }
+
+    /**
+     * Redraw the whole display in place. The terminal is cleared before each
+     * redraw, unless the output is redirected.
+     */
+    pub fn draw(&self, color_enabled: bool) {
+        let bold = color_style(Style::new().bold(), color_enabled);
+        let dimmed = color_style(Style::new().dimmed(), color_enabled);
+        let green = color_style(Green.normal(), color_enabled);
+        let blue = color_style(Blue.normal(), color_enabled);
+        let yellow = color_style(Yellow.dimmed(), color_enabled);
+
+        if io::stdout().is_terminal() {
+            print!("\x1B[2J\x1B[1;1H");
+        }
+
+        let ip_len = self
+            .hosts
+            .keys()
+            .map(|ip_address| ip_address.to_string().len() + 2)
+            .fold(17, usize::max);
+        let vendor_len = self
+            .hosts
+            .values()
+            .filter_map(|live_host| live_host.vendor.as_ref())
+            .map(|vendor| vendor.chars().count())
+            .fold(15, usize::max);
+        let table_len = ip_len + 19 + vendor_len + 8 + 9 + 20 + 6;
+
+        println!(
+            "{} {}",
+            bold.paint("LIVE HOSTS"),
+            dimmed.paint(format!(
+                "(round {}, {} hosts seen)",
+                self.round_count,
+                self.hosts.len()
+            ))
+        );
+        println!();
+        println!(
+            "{: <i_max$} {: <19} {: <v_max$} {: <8} {: <9} {}",
+            dimmed.paint("IP Address"),
+            dimmed.paint("MAC Address"),
+            dimmed.paint("Vendor"),
+            dimmed.paint("Replies"),
+            dimmed.paint("Rounds"),
+            dimmed.paint("Last seen"),
+            i_max = ip_len,
+            v_max = vendor_len
+        );
+        println!("{}", dimmed.paint("─".repeat(table_len)));
+
+        for (ip_address, live_host) in self.hosts.iter() {
+            let (ip_style, mac_style) = match live_host.is_present {
+                true => (blue, yellow),
+                false => (dimmed, dimmed),
+            };
+            let presence = match live_host.is_present {
+                true => green.paint("●"),
+                false => dimmed.paint("○"),
+            };
+            let rounds = format!("{}/{}", live_host.round_count, self.round_count);
+            println!(
+                "{: <i_max$} {: <19} {: <v_max$} {: <8} {: <9} {} {}",
+                ip_style.paint(ip_address.to_string()),
+                mac_style.paint(live_host.mac.to_string()),
+                live_host.vendor.as_deref().unwrap_or(UNKNOWN_VENDOR),
+                live_host.reply_count,
+                rounds,
+                time::format_rfc3339(live_host.last_seen),
+                presence,
+                i_max = ip_len,
+                v_max = vendor_len
+            );
+        }
+
+        println!("{}", dimmed.paint("─".repeat(table_len)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::net::Ipv4Addr;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn build_detail(last_octet: u8, last_seen_s: u64) -> TargetDetails {
+        TargetDetails {
+            last_seen: UNIX_EPOCH + Duration::from_secs(last_seen_s),
+            ..TargetDetails::test_default(
+                Ipv4Addr::new(192, 168, 1, last_octet),
+                MacAddr::new(0x40, 0x55, 0x82, 0xc3, 0xe5, last_octet),
+            )
+        }
+    }
+
+    #[test]
+    fn should_update_live_hosts() {
+        let mut live_display = LiveDisplay::new();
+
+        live_display.update(&[build_detail(20, 10), build_detail(10, 10)]);
+        live_display.update(&[build_detail(20, 40), build_detail(30, 40)]);
+
+        let hosts: Vec<(String, usize, usize, bool)> = live_display
+            .hosts()
+            .iter()
+            .map(|(ip_address, live_host)| {
+                (
+                    ip_address.to_string(),
+                    live_host.reply_count,
+                    live_host.round_count,
+                    live_host.is_present,
+                )
+            })
+            .collect();
+        assert_eq!(
+            hosts,
+            vec![
+                ("192.168.1.10".to_string(), 1, 1, false),
+                ("192.168.1.20".to_string(), 2, 2, true),
+                ("192.168.1.30".to_string(), 1, 1, true),
+            ]
+        );
+        assert_eq!(
+            live_display.hosts()[&IpAddr::V4(Ipv4Addr::new(192, 168, 1, 20))].last_seen,
+            UNIX_EPOCH + Duration::from_secs(40)
+        );
+    }
+}
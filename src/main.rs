@@ -1,10 +1,6 @@
-mod args;
-mod network;
-mod time;
-mod utils;
-mod vendor;
-
-use std::net::IpAddr;
+use std::env;
+use std::fs;
+use std::net::Ipv4Addr;
 use std::process;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
@@ -13,20 +9,56 @@ use std::time::Duration;
 
 use ansi_term::Color::Cyan;
 use ansi_term::Style;
-
-use crate::args::{OutputFormat, ScanOptions};
-use crate::network::NetworkIterator;
-use crate::vendor::Vendor;
-
-fn print_banner() {
+use ipnetwork::IpNetwork;
+use pnet_datalink::NetworkInterface;
+#[cfg(unix)]
+use signal_hook::consts::SIGUSR1;
+#[cfg(unix)]
+use signal_hook::iterator::Signals;
+
+use arp_scan::args::{self, OutputFormat, ScanOptions};
+use arp_scan::compare::{self, ComparedHost, ScanComparison};
+use arp_scan::config;
+use arp_scan::error::ScanError;
+use arp_scan::live::LiveDisplay;
+use arp_scan::network::{
+    self, NetworkIterator, ResponseSummary, ScanTracker, TargetDetails, WatchRound,
+};
+use arp_scan::scan::{self, ScanSession};
+use arp_scan::time;
+use arp_scan::utils;
+use arp_scan::vendor;
+
+fn print_banner(color_enabled: bool) {
     println!();
-    println!("{}", Cyan.bold().paint("ARP-SCAN-RS"));
-    println!("{}", Style::new().dimmed().paint("Version 0.14.0"));
+    println!(
+        "{}",
+        utils::color_style(Cyan.bold(), color_enabled).paint("ARP-SCAN-RS")
+    );
+    println!(
+        "{}",
+        utils::color_style(Style::new().dimmed(), color_enabled).paint("Version 0.14.0")
+    );
     println!();
 }
 
 fn main() {
-    let matches = args::build_args().get_matches();
+    // All errors are bubbled up to this single place, where they are printed
+    // before exiting the process with an error code.
+    if let Err(err) = run() {
+        eprintln!("{}", err);
+        process::exit(err.exit_code());
+    }
+}
+
+fn run() -> Result<(), ScanError> {
+    // Options of the configuration file are appended to the command line
+    // arguments, unless they were already given on the command line.
+    let cli_args: Vec<String> = env::args().collect();
+    let all_args = config::merge_config_args(cli_args).map_err(|err| {
+        ScanError::InvalidOption(format!("Could not load the configuration file\n{}", err))
+    })?;
+    let matches = args::build_args().get_matches_from(all_args);
 
     // Find interfaces & list them if requested
     // ----------------------------------------
@@ -37,9 +69,30 @@ fn main() {
     let interfaces = pnet_datalink::interfaces();
 
     if matches.get_flag("list") {
-        print_banner();
-        utils::show_interfaces(&interfaces);
-        process::exit(0);
+        let ready_only = matches.get_flag("ready_only");
+        if args::compute_list_format(&matches) == OutputFormat::Json {
+            let listed_interfaces: Vec<NetworkInterface> = match ready_only {
+                true => utils::select_eligible_interfaces(&interfaces)
+                    .into_iter()
+                    .cloned()
+                    .collect(),
+                false => interfaces.clone(),
+            };
+            let interface_json = utils::export_interfaces_to_json(
+                &listed_interfaces,
+                matches.get_flag("pretty"),
+                network::read_interface_link,
+            )?;
+            println!("{}", interface_json);
+            return Ok(());
+        }
+
+        let color_enabled = utils::is_color_enabled(matches.get_flag("no_color"));
+        if !matches.get_flag("no_banner") {
+            print_banner(color_enabled);
+        }
+        utils::show_interfaces(&interfaces, ready_only, color_enabled);
+        return Ok(());
     }
 
     // Assert requirements for a local network scan
@@ -48,192 +101,430 @@ fn main() {
     // network for the given interface. ARP scans require an active interface
     // with an IPv4 address and root permissions (for crafting ARP packets).
 
-    let scan_options = ScanOptions::new(&matches);
+    let scan_options = ScanOptions::new(&matches)?;
 
     if scan_options.request_protocol_print() {
         utils::print_ascii_packet();
-        process::exit(0);
+        return Ok(());
     }
 
-    if !cfg!(windows) && !utils::is_root_user() {
-        eprintln!("Should run this binary as root or use --help for options");
-        process::exit(1);
+    // The self-test only builds a sample frame, which does not require any
+    // datalink channel nor root permissions.
+    if scan_options.self_test {
+        let frame = network::self_test_arp_request(&scan_options)?;
+        print!("{}", utils::format_hex_dump(&frame));
+        println!("Self-test passed, ARP request of {} bytes", frame.len());
+        return Ok(());
     }
 
-    let (selected_interface, ip_networks) =
-        network::compute_network_configuration(&interfaces, &scan_options);
-
-    if scan_options.is_plain_output() {
-        print_banner();
-        utils::display_prescan_details(&ip_networks, selected_interface, scan_options.clone());
+    if let Some(oui_update_file) = &scan_options.oui_update_file {
+        let prefix_count = vendor::update_database(oui_update_file)?;
+        println!(
+            "Loaded {} OUI prefixes in {}",
+            prefix_count, oui_update_file
+        );
+        return Ok(());
     }
 
-    // Start ARP scan operation
-    // ------------------------
-    // ARP responses on the interface will be collected in a separate thread,
-    // while the main thread sends a batch of ARP requests for each IP in the
-    // local network.
+    // All eligible interfaces may be scanned one after another, otherwise a
+    // single interface is selected (given by the user or the default one).
+    let scan_targets = scan::compute_scan_targets(&interfaces, &scan_options)?;
 
-    let channel_config = pnet_datalink::Config {
-        read_timeout: Some(Duration::from_millis(network::DATALINK_RCV_TIMEOUT)),
-        ..pnet_datalink::Config::default()
-    };
+    // Scan warnings are always reported, even in quiet mode.
+    for scan_warning in scan::describe_scan_warnings(&scan_targets, &scan_options) {
+        eprintln!("[warn] {}", scan_warning);
+    }
 
-    let (mut tx, mut rx) = match pnet_datalink::channel(selected_interface, channel_config) {
-        Ok(pnet_datalink::Channel::Ethernet(tx, rx)) => (tx, rx),
-        Ok(_) => {
-            eprintln!("Expected an Ethernet datalink channel");
-            process::exit(1);
+    // Tiny IPv4 targets are requested as they are, without skipping network
+    // or broadcast addresses, which is explained before the scan starts.
+    if scan_options.is_progress_output() && !scan_options.passive && !scan_options.ipv6 {
+        for (_, ip_networks) in scan_targets.iter() {
+            for network_note in network::describe_small_networks(ip_networks) {
+                eprintln!("[info] {}", network_note);
+            }
         }
-        Err(error) => {
-            eprintln!("Datalink channel creation failed ({})", error);
-            process::exit(1);
+    }
+
+    // Huge target ranges are refused before opening any datalink channel,
+    // unless the scan is forced.
+    let network_size = scan::compute_scan_size(&scan_targets, &scan_options);
+    network::check_host_count(network_size, &scan_options)?;
+
+    // The dry-run mode only lists the targets that would be probed (after
+    // exclusions, randomization and request cap), which does not require any
+    // datalink channel nor root permissions.
+    if scan_options.dry_run {
+        for (_, ip_networks) in scan_targets.iter() {
+            let target_ips = NetworkIterator::new(
+                ip_networks,
+                &scan_options.excluded_networks,
+                scan_options.target_order,
+                scan_options.seed,
+                scan_options.sample_percent,
+            );
+            let target_limit = scan_options.request_limit.unwrap_or(usize::MAX);
+            for target_ip in target_ips.take(target_limit) {
+                println!("{}", target_ip);
+            }
         }
-    };
+        return Ok(());
+    }
 
-    // The 'timed_out' mutex is shared accross the main thread (which performs
-    // ARP packet sending) and the response thread (which receives and stores
-    // all ARP responses).
-    let timed_out = Arc::new(AtomicBool::new(false));
-    let cloned_timed_out = Arc::clone(&timed_out);
+    // The previous scan is loaded before any packet is sent, so that an
+    // invalid comparison file does not waste a whole scan.
+    let compared_hosts: Option<Vec<ComparedHost>> = scan_options
+        .compare_file
+        .as_deref()
+        .map(compare::load_previous_hosts)
+        .transpose()?;
 
-    let mut vendor_list = Vendor::new(&scan_options.oui_file);
+    if !cfg!(windows) && !utils::is_root_user() {
+        return Err(ScanError::PermissionDenied);
+    }
 
-    let cloned_options = Arc::clone(&scan_options);
-    let arp_responses = thread::spawn(move || {
-        network::receive_arp_responses(&mut rx, cloned_options, cloned_timed_out, &mut vendor_list)
-    });
+    if scan_options.is_progress_output() && scan_options.show_banner {
+        print_banner(scan_options.color_enabled);
+    }
+
+    // Start ARP scan operation
+    // ------------------------
+    // ARP responses on the interface will be collected in a separate thread,
+    // while the main thread sends a batch of ARP requests for each IP in the
+    // local network.
 
-    let network_size = utils::compute_network_size(&ip_networks);
+    let has_reached_timeout = Arc::new(AtomicBool::new(false));
+    let mut scan_session = ScanSession::open(
+        scan_targets,
+        Arc::clone(&scan_options),
+        Arc::clone(&has_reached_timeout),
+        true,
+    )?;
+    if scan_options.wake_only {
+        return Ok(());
+    }
 
     let estimations = network::compute_scan_estimation(network_size, &scan_options);
-    let interval_ms = estimations.interval_ms;
 
-    if scan_options.is_plain_output() {
+    if scan_options.is_progress_output() {
+        let dimmed = utils::color_style(Style::new().dimmed(), scan_options.color_enabled);
         let formatted_ms = time::format_milliseconds(estimations.duration_ms);
-        println!(
-            "{: <16} {}",
-            Style::new().dimmed().paint("Estimated time"),
-            formatted_ms
-        );
-        println!(
-            "{: <16} {}",
-            Style::new().dimmed().paint("ARP requests"),
-            network_size
-        );
+        println!("{: <16} {}", dimmed.paint("Estimated time"), formatted_ms);
+        let request_count = match (scan_options.passive, scan_options.request_limit) {
+            (true, _) => 0,
+            (false, Some(request_limit)) => network_size.min(request_limit as u128),
+            (false, None) => network_size,
+        };
+        println!("{: <16} {}", dimmed.paint("ARP requests"), request_count);
         println!(
             "{: <16} {}ms",
-            Style::new().dimmed().paint("Timeout"),
+            dimmed.paint("Timeout"),
             scan_options.timeout_ms
         );
         println!(
             "{: <16} {}ms",
-            Style::new().dimmed().paint("Interval"),
-            interval_ms
+            dimmed.paint("Interval"),
+            estimations.interval_ms
         );
+        if scan_options.tx_threads > 1 {
+            println!(
+                "{: <16} {}",
+                dimmed.paint("Sender threads"),
+                scan_options.tx_threads
+            );
+        }
         println!(
             "{: <16} {} bytes/s",
-            Style::new().dimmed().paint("Bandwidth"),
+            dimmed.paint("Bandwidth"),
             estimations.bandwidth
         );
         println!();
-        println!("{}", Style::new().dimmed().paint("─".repeat(78)));
+        println!("{}", dimmed.paint("─".repeat(78)));
         println!();
     }
 
-    let has_reached_timeout = Arc::new(AtomicBool::new(false));
     let cloned_reached_timeout = Arc::clone(&has_reached_timeout);
 
     ctrlc::set_handler(move || {
         eprintln!("\n[!] Interrupt received, ending scan with partial results...");
         cloned_reached_timeout.store(true, Ordering::Relaxed);
     })
-    .unwrap_or_else(|err| {
-        eprintln!("Could not set CTRL+C handler ({})", err);
-        process::exit(1);
-    });
+    .map_err(|err| ScanError::Io(format!("Could not set CTRL+C handler ({})", err)))?;
 
-    let source_ip = network::find_source_ip(selected_interface, scan_options.source_ipv4);
+    for tracker in scan_session.trackers() {
+        listen_status_signal(tracker)?;
+    }
 
-    // The retry count does right now use a 'brute-force' strategy without
-    // synchronization process with the already known hosts.
-    let mut total_sent = 0u128;
-    for _ in 0..scan_options.retry_count {
-        if has_reached_timeout.load(Ordering::Relaxed) {
-            break;
+    let interface_label = scan_session.interface_label();
+    let gateway_ipv4 = scan_session.gateway_ipv4();
+    let scanned_networks = scan_session.scanned_networks();
+
+    // In watch mode, the whole send & receive cycle is started again after
+    // each round, until an interruption is received. Hosts are compared with
+    // the previous round to find which ones appeared or disappeared.
+    let mut round_index: usize = 0;
+    let mut previous_hosts: Vec<Ipv4Addr> = vec![];
+    let mut previous_round_hosts: Option<Vec<ComparedHost>> = None;
+    let mut live_display = scan_options.live_display.then(LiveDisplay::new);
+    loop {
+        let (response_summary, target_details) = scan_session.scan_round()?;
+
+        let watch_round = scan_options
+            .is_watch_mode()
+            .then(|| WatchRound::new(round_index, &previous_hosts, &target_details));
+        previous_hosts = target_details.iter().map(|detail| detail.ipv4).collect();
+        let comparison = compared_hosts
+            .as_ref()
+            .map(|compared_hosts| ScanComparison::new(compared_hosts, &target_details));
+
+        // With '--changes-only', the rounds following the baseline round only
+        // report the hosts that appeared, disappeared or changed since the
+        // previous round.
+        let round_changes = previous_round_hosts
+            .as_ref()
+            .map(|previous_round_hosts| ScanComparison::new(previous_round_hosts, &target_details));
+        if scan_options.changes_only {
+            previous_round_hosts = Some(compare::build_compared_hosts(&target_details));
         }
 
-        let ip_addresses = NetworkIterator::new(&ip_networks, scan_options.randomize_targets);
+        // The live display replaces the results table, each host keeping a
+        // single row updated by the following rounds.
+        if let Some(live_display) = live_display.as_mut() {
+            live_display.update(&target_details);
+            live_display.draw(scan_options.color_enabled);
+        }
 
-        for ip_address in ip_addresses {
-            if has_reached_timeout.load(Ordering::Relaxed) {
-                break;
-            }
+        // The plain summary is printed on stderr whatever the output format, so
+        // that it can be extracted next to a decorated or exported output.
+        if scan_options.plain_summary {
+            eprintln!(
+                "{}",
+                utils::format_plain_summary(&response_summary, &target_details)
+            );
+        }
 
-            if let IpAddr::V4(ipv4_address) = ip_address {
-                network::send_arp_request(
-                    &mut tx,
-                    selected_interface,
-                    source_ip,
-                    ipv4_address,
-                    Arc::clone(&scan_options),
-                );
-                total_sent += 1;
-
-                // Show progress every 100 packets in plain output mode
-                if scan_options.is_plain_output() && total_sent % 100 == 0 {
-                    let progress_pct = (total_sent as f32
-                        / (network_size * scan_options.retry_count as u128) as f32)
-                        * 100.0;
-                    print!(
-                        "\rSending: {}/{} ({:.1}%)    ",
-                        total_sent,
-                        network_size * scan_options.retry_count as u128,
-                        progress_pct
-                    );
-                    use std::io::Write;
-                    std::io::stdout().flush().unwrap();
-                }
+        // The time series file is never truncated, each round is appended as
+        // a single JSON line whatever the requested outputs.
+        if let Some(append_file) = &scan_options.append_file {
+            let result_line = utils::export_to_json(
+                response_summary.clone(),
+                target_details.clone(),
+                scan_options.sort_field,
+                watch_round.as_ref(),
+                false,
+                scan_options.sign_output,
+            )?;
+            utils::append_result_line(append_file, &result_line)?;
+        }
 
-                thread::sleep(Duration::from_millis(interval_ms));
+        match &round_changes {
+            Some(round_changes) => {
+                display_round_changes(round_changes, &scan_options)?;
             }
+            None => display_scan_output(
+                response_summary,
+                target_details,
+                watch_round.as_ref(),
+                &interface_label,
+                gateway_ipv4,
+                &scanned_networks,
+                &scan_options,
+            )?,
         }
-    }
 
-    if scan_options.is_plain_output() && total_sent > 0 {
-        println!(
-            "\rSent: {} packets · Waiting for responses (timeout: {}ms)            ",
-            total_sent, scan_options.timeout_ms
-        );
+        if let (Some(comparison), Some(compare_file)) = (&comparison, &scan_options.compare_file) {
+            display_scan_comparison(comparison, compare_file, &scan_options)?;
+        }
+
+        let watch_interval_ms = match scan_options.watch_interval_ms {
+            Some(watch_interval_ms) => watch_interval_ms,
+            None => break,
+        };
+
+        let mut sleep_ms_mount: u64 = 0;
+        while !has_reached_timeout.load(Ordering::Relaxed) && sleep_ms_mount < watch_interval_ms {
+            thread::sleep(Duration::from_millis(100));
+            sleep_ms_mount += 100;
+        }
+
+        if has_reached_timeout.load(Ordering::Relaxed) {
+            break;
+        }
+        round_index += 1;
     }
 
-    // Once the ARP packets are sent, the main thread will sleep for T seconds
-    // (where T is the timeout option). After the sleep phase, the response
-    // thread will receive a stop request through the 'timed_out' mutex.
-    let mut sleep_ms_mount: u64 = 0;
-    while !has_reached_timeout.load(Ordering::Relaxed) && sleep_ms_mount < scan_options.timeout_ms {
-        thread::sleep(Duration::from_millis(100));
-        sleep_ms_mount += 100;
+    // Results are always displayed before failing, the exit code only tells
+    // scripts that the last scan round did not discover any host.
+    if scan_options.fail_if_empty && previous_hosts.is_empty() {
+        return Err(ScanError::NoHostFound);
     }
-    timed_out.store(true, Ordering::Relaxed);
 
-    let (response_summary, target_details) = arp_responses.join().unwrap_or_else(|error| {
-        eprintln!("Failed to close receive thread ({:?})", error);
-        process::exit(1);
-    });
+    Ok(())
+}
 
-    match &scan_options.output {
-        OutputFormat::Plain => {
-            utils::display_scan_results(response_summary, target_details, &scan_options)
+/**
+ * Print a live status of the current scan round on stderr each time a SIGUSR1
+ * signal is received (e.g. 'kill -USR1 <pid>'), without stopping the scan.
+ * This is only available on Unix platforms.
+ */
+#[cfg(unix)]
+fn listen_status_signal(tracker: Arc<ScanTracker>) -> Result<(), ScanError> {
+    let mut signals = Signals::new([SIGUSR1])
+        .map_err(|err| ScanError::Io(format!("Could not set SIGUSR1 handler ({})", err)))?;
+
+    thread::spawn(move || {
+        for _ in signals.forever() {
+            eprintln!("[i] {}", tracker.format_status());
         }
-        OutputFormat::Json => println!(
-            "{}",
-            utils::export_to_json(response_summary, target_details)
-        ),
-        OutputFormat::Yaml => println!(
+    });
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn listen_status_signal(_tracker: Arc<ScanTracker>) -> Result<(), ScanError> {
+    Ok(())
+}
+
+/**
+ * Display the differences with a previous scan on stdout, as a JSON object if
+ * the first requested output is JSON or as a marked list of hosts otherwise.
+ */
+fn display_scan_comparison(
+    comparison: &ScanComparison,
+    compare_file: &str,
+    scan_options: &ScanOptions,
+) -> Result<(), ScanError> {
+    let first_format = scan_options.outputs.first().map(|output| &output.format);
+    match first_format {
+        Some(OutputFormat::Json) => println!(
             "{}",
-            utils::export_to_yaml(response_summary, target_details)
+            utils::export_comparison_to_json(comparison, scan_options.pretty_json)?
         ),
-        OutputFormat::Csv => print!("{}", utils::export_to_csv(response_summary, target_details)),
+        _ => utils::display_scan_comparison(comparison, compare_file, scan_options.color_enabled),
     }
+    Ok(())
+}
+
+/**
+ * Display the changes of a watch round (see '--changes-only'). A JSON object
+ * is printed for each round if the first requested output is JSON, while the
+ * plain output stays silent until a host appears, disappears or changes.
+ */
+fn display_round_changes(
+    round_changes: &ScanComparison,
+    scan_options: &ScanOptions,
+) -> Result<(), ScanError> {
+    let first_format = scan_options.outputs.first().map(|output| &output.format);
+    if first_format != Some(&OutputFormat::Json) && round_changes.is_empty() {
+        return Ok(());
+    }
+
+    display_scan_comparison(round_changes, "previous round", scan_options)
+}
+
+/**
+ * Display or export the results of a scan round, based on the requested
+ * outputs. Watch rounds are only given in watch mode. Each output is either
+ * written on stdout, or in its own output file if one has been requested.
+ */
+fn display_scan_output(
+    response_summary: ResponseSummary,
+    target_details: Vec<TargetDetails>,
+    watch_round: Option<&WatchRound>,
+    interface_name: &str,
+    gateway_ipv4: Option<Ipv4Addr>,
+    scanned_networks: &[IpNetwork],
+    scan_options: &ScanOptions,
+) -> Result<(), ScanError> {
+    for output in &scan_options.outputs {
+        let export_text = match &output.format {
+            OutputFormat::Plain if scan_options.live_display => continue,
+            OutputFormat::Plain if scan_options.output_template.is_some() => {
+                let mut sorted_details = target_details.clone();
+                utils::sort_target_details(&mut sorted_details, scan_options.sort_field);
+                let template = scan_options.output_template.as_deref().unwrap_or_default();
+                for detail in sorted_details.iter() {
+                    println!("{}", utils::format_template_line(template, detail));
+                }
+                continue;
+            }
+            OutputFormat::Plain => {
+                utils::display_scan_results(
+                    response_summary.clone(),
+                    target_details.clone(),
+                    watch_round,
+                    gateway_ipv4,
+                    scanned_networks,
+                    scan_options,
+                );
+                continue;
+            }
+            OutputFormat::Ndjson => {
+                println!("{}", utils::export_summary_to_ndjson(&response_summary)?);
+                continue;
+            }
+            OutputFormat::Json => format!(
+                "{}\n",
+                utils::export_to_json(
+                    response_summary.clone(),
+                    target_details.clone(),
+                    scan_options.sort_field,
+                    watch_round,
+                    scan_options.pretty_json,
+                    scan_options.sign_output
+                )?
+            ),
+            OutputFormat::Yaml => format!(
+                "{}\n",
+                utils::export_to_yaml(
+                    response_summary.clone(),
+                    target_details.clone(),
+                    scan_options.sort_field,
+                    watch_round,
+                    scan_options.sign_output
+                )?
+            ),
+            OutputFormat::Csv => utils::export_to_csv(
+                response_summary.clone(),
+                target_details.clone(),
+                scan_options.sort_field,
+            )?,
+            OutputFormat::Html => utils::export_to_html(
+                response_summary.clone(),
+                target_details.clone(),
+                scan_options.sort_field,
+                watch_round,
+            ),
+            OutputFormat::Markdown => utils::export_to_markdown(
+                response_summary.clone(),
+                target_details.clone(),
+                scan_options.sort_field,
+                watch_round,
+            ),
+            OutputFormat::Prometheus => {
+                utils::export_to_prometheus(&response_summary, &target_details, interface_name)
+            }
+            OutputFormat::Xml => utils::export_to_xml(
+                response_summary.clone(),
+                target_details.clone(),
+                scan_options.sort_field,
+                watch_round,
+            ),
+            OutputFormat::SummaryJson => format!(
+                "{}\n",
+                utils::export_summary_to_json(&response_summary, &target_details)?
+            ),
+        };
+
+        match &output.file {
+            Some(output_file) => fs::write(output_file, export_text).map_err(|err| {
+                ScanError::Io(format!(
+                    "Could not write results to {} ({})",
+                    output_file, err
+                ))
+            })?,
+            None => print!("{}", export_text),
+        }
+    }
+
+    Ok(())
 }
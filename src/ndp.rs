@@ -0,0 +1,306 @@
+use std::net::Ipv6Addr;
+
+use pnet::packet::ethernet::{EtherTypes, EthernetPacket, MutableEthernetPacket};
+use pnet::packet::icmpv6::ndp::{
+    MutableNeighborSolicitPacket, NdpOptionTypes, NeighborAdvertPacket,
+};
+use pnet::packet::icmpv6::{self, Icmpv6Packet, Icmpv6Types};
+use pnet::packet::ip::IpNextHeaderProtocols;
+use pnet::packet::ipv6::{Ipv6Packet, MutableIpv6Packet};
+use pnet::packet::Packet;
+use pnet_datalink::{DataLinkSender, MacAddr, NetworkInterface};
+
+use crate::error::ScanError;
+
+// Neighbor Discovery messages are only valid on the local link, receivers must
+// drop any message that does not carry the maximum hop limit (RFC 4861).
+const NDP_HOP_LIMIT: u8 = 255;
+
+const IPV6_PACKET_SIZE: usize = 40;
+const NS_PACKET_SIZE: usize = 24;
+const LINK_LAYER_OPTION_SIZE: usize = 8;
+const NS_FRAME_SIZE: usize = 14 + IPV6_PACKET_SIZE + NS_PACKET_SIZE + LINK_LAYER_OPTION_SIZE;
+
+// NDP option lengths are given in 8-byte units, the packet parser computes
+// the option size on a single byte and would overflow from 32 units.
+const NDP_OPTION_UNITS_MAX: usize = 32;
+
+/**
+ * Send a single Neighbor Solicitation (the IPv6 equivalent of an ARP request)
+ * for a target IPv6 address. The solicitation is sent to the solicited-node
+ * multicast group of the target, so that only the target host should answer.
+ * Returns false if the solicitation could not be sent.
+ */
+pub fn send_neighbor_solicitation(
+    tx: &mut Box<dyn DataLinkSender>,
+    interface: &NetworkInterface,
+    source_mac: MacAddr,
+    source_ipv6: Ipv6Addr,
+    target_ipv6: Ipv6Addr,
+) -> Result<bool, ScanError> {
+    let ethernet_frame = build_neighbor_solicitation(source_mac, source_ipv6, target_ipv6)?;
+    let send_result = tx.send_to(&ethernet_frame, Some(interface.clone()));
+    Ok(matches!(send_result, Some(Ok(()))))
+}
+
+/**
+ * Build the Ethernet frame of a Neighbor Solicitation, with the IPv6 header
+ * and a source link-layer address option (so that the target can answer
+ * without soliciting the scanner in return).
+ */
+fn build_neighbor_solicitation(
+    source_mac: MacAddr,
+    source_ipv6: Ipv6Addr,
+    target_ipv6: Ipv6Addr,
+) -> Result<Vec<u8>, ScanError> {
+    let destination_ipv6 = compute_solicited_node_address(target_ipv6);
+
+    let mut ns_buffer = [0u8; NS_PACKET_SIZE + LINK_LAYER_OPTION_SIZE];
+    let mut ns_packet = MutableNeighborSolicitPacket::new(&mut ns_buffer)
+        .ok_or_else(|| ScanError::Packet("Neighbor Solicitation".to_string()))?;
+    ns_packet.set_icmpv6_type(Icmpv6Types::NeighborSolicit);
+    ns_packet.set_target_addr(target_ipv6);
+
+    let mac_octets = source_mac.octets();
+    let option_offset = NS_PACKET_SIZE;
+    ns_buffer[option_offset] = NdpOptionTypes::SourceLLAddr.0;
+    ns_buffer[option_offset + 1] = 1;
+    ns_buffer[option_offset + 2..option_offset + LINK_LAYER_OPTION_SIZE]
+        .copy_from_slice(&mac_octets);
+
+    let checksum = Icmpv6Packet::new(&ns_buffer)
+        .map(|icmpv6_packet| icmpv6::checksum(&icmpv6_packet, &source_ipv6, &destination_ipv6))
+        .unwrap_or(0);
+    ns_buffer[2..4].copy_from_slice(&checksum.to_be_bytes());
+
+    let mut ipv6_buffer = [0u8; IPV6_PACKET_SIZE + NS_PACKET_SIZE + LINK_LAYER_OPTION_SIZE];
+    let mut ipv6_packet = MutableIpv6Packet::new(&mut ipv6_buffer)
+        .ok_or_else(|| ScanError::Packet("IPv6".to_string()))?;
+    ipv6_packet.set_version(6);
+    ipv6_packet.set_payload_length(ns_buffer.len() as u16);
+    ipv6_packet.set_next_header(IpNextHeaderProtocols::Icmpv6);
+    ipv6_packet.set_hop_limit(NDP_HOP_LIMIT);
+    ipv6_packet.set_source(source_ipv6);
+    ipv6_packet.set_destination(destination_ipv6);
+    ipv6_packet.set_payload(&ns_buffer);
+
+    let mut ethernet_buffer = vec![0u8; NS_FRAME_SIZE];
+    let mut ethernet_packet = MutableEthernetPacket::new(&mut ethernet_buffer)
+        .ok_or_else(|| ScanError::Packet("Ethernet".to_string()))?;
+    ethernet_packet.set_destination(compute_multicast_mac(destination_ipv6));
+    ethernet_packet.set_source(source_mac);
+    ethernet_packet.set_ethertype(EtherTypes::Ipv6);
+    ethernet_packet.set_payload(&ipv6_buffer);
+
+    Ok(ethernet_buffer)
+}
+
+/**
+ * Parse a received Ethernet frame as a Neighbor Advertisement (the IPv6
+ * equivalent of an ARP reply). The advertised IPv6 address is returned with
+ * the MAC address of the sender, other frames are ignored.
+ */
+pub fn parse_neighbor_advertisement(
+    ethernet_packet: &EthernetPacket,
+) -> Option<(Ipv6Addr, MacAddr)> {
+    if ethernet_packet.get_ethertype() != EtherTypes::Ipv6 {
+        return None;
+    }
+
+    let ipv6_packet = Ipv6Packet::new(ethernet_packet.payload())?;
+    if ipv6_packet.get_next_header() != IpNextHeaderProtocols::Icmpv6
+        || ipv6_packet.get_hop_limit() != NDP_HOP_LIMIT
+    {
+        return None;
+    }
+
+    let na_packet = NeighborAdvertPacket::new(ipv6_packet.payload())?;
+    if na_packet.get_icmpv6_type() != Icmpv6Types::NeighborAdvert {
+        return None;
+    }
+    if !has_valid_options(ipv6_packet.payload().get(NS_PACKET_SIZE..)?) {
+        return None;
+    }
+
+    // The target link-layer option is preferred, the Ethernet source may be
+    // rewritten by a bridge (or a proxy answering for the target).
+    let advertised_mac = na_packet
+        .get_options()
+        .iter()
+        .find(|option| option.option_type == NdpOptionTypes::TargetLLAddr && option.data.len() >= 6)
+        .map(|option| {
+            MacAddr::new(
+                option.data[0],
+                option.data[1],
+                option.data[2],
+                option.data[3],
+                option.data[4],
+                option.data[5],
+            )
+        })
+        .unwrap_or_else(|| ethernet_packet.get_source());
+
+    Some((na_packet.get_target_addr(), advertised_mac))
+}
+
+/**
+ * Check the options of a received advertisement before parsing them. Each
+ * option should have a positive length, supported by the packet parser, and
+ * the options should exactly fill the message.
+ */
+fn has_valid_options(options_data: &[u8]) -> bool {
+    let mut offset = 0;
+    while offset < options_data.len() {
+        let option_units = match options_data.get(offset + 1) {
+            Some(option_units) => *option_units as usize,
+            None => return false,
+        };
+        if option_units == 0 || option_units >= NDP_OPTION_UNITS_MAX {
+            return false;
+        }
+        offset += option_units * 8;
+    }
+    offset == options_data.len()
+}
+
+/**
+ * Compute the solicited-node multicast address of an IPv6 address, made of
+ * the 'ff02::1:ff00:0/104' prefix and the 24 low-order bits of the address.
+ */
+fn compute_solicited_node_address(target_ipv6: Ipv6Addr) -> Ipv6Addr {
+    let octets = target_ipv6.octets();
+    Ipv6Addr::from([
+        0xff, 0x02, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x01, 0xff, octets[13], octets[14], octets[15],
+    ])
+}
+
+/**
+ * Compute the Ethernet multicast address of an IPv6 multicast address, made
+ * of the '33:33' prefix and the 32 low-order bits of the address.
+ */
+fn compute_multicast_mac(multicast_ipv6: Ipv6Addr) -> MacAddr {
+    let octets = multicast_ipv6.octets();
+    MacAddr::new(0x33, 0x33, octets[12], octets[13], octets[14], octets[15])
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use pnet::packet::icmpv6::ndp::NeighborSolicitPacket;
+
+    #[test]
+    fn should_compute_solicited_node_address() {
+        let target_ipv6: Ipv6Addr = "fd00::2:abcd:1234".parse().unwrap();
+
+        assert_eq!(
+            compute_solicited_node_address(target_ipv6),
+            "ff02::1:ffcd:1234".parse::<Ipv6Addr>().unwrap()
+        );
+        assert_eq!(
+            compute_multicast_mac(compute_solicited_node_address(target_ipv6)),
+            MacAddr::new(0x33, 0x33, 0xff, 0xcd, 0x12, 0x34)
+        );
+    }
+
+    #[test]
+    fn should_build_neighbor_solicitation() {
+        let source_mac = MacAddr::new(0x02, 0, 0, 0, 0, 0x01);
+        let source_ipv6: Ipv6Addr = "fe80::1".parse().unwrap();
+        let target_ipv6: Ipv6Addr = "fd00::2".parse().unwrap();
+
+        let frame = build_neighbor_solicitation(source_mac, source_ipv6, target_ipv6).unwrap();
+        assert_eq!(frame.len(), NS_FRAME_SIZE);
+
+        let ethernet_packet = EthernetPacket::new(&frame).unwrap();
+        assert_eq!(ethernet_packet.get_ethertype(), EtherTypes::Ipv6);
+        assert_eq!(
+            ethernet_packet.get_destination(),
+            MacAddr::new(0x33, 0x33, 0xff, 0, 0, 0x02)
+        );
+
+        let ipv6_packet = Ipv6Packet::new(ethernet_packet.payload()).unwrap();
+        assert_eq!(ipv6_packet.get_hop_limit(), NDP_HOP_LIMIT);
+        assert_eq!(ipv6_packet.get_source(), source_ipv6);
+
+        let ns_packet = NeighborSolicitPacket::new(ipv6_packet.payload()).unwrap();
+        assert_eq!(ns_packet.get_icmpv6_type(), Icmpv6Types::NeighborSolicit);
+        assert_eq!(ns_packet.get_target_addr(), target_ipv6);
+        assert_eq!(
+            ns_packet.get_options()[0].option_type,
+            NdpOptionTypes::SourceLLAddr
+        );
+
+        let icmpv6_packet = Icmpv6Packet::new(ipv6_packet.payload()).unwrap();
+        assert_eq!(
+            icmpv6::checksum(&icmpv6_packet, &source_ipv6, &ipv6_packet.get_destination()),
+            icmpv6_packet.get_checksum()
+        );
+    }
+
+    fn build_advertisement_frame(target_ipv6: Ipv6Addr, option_mac: Option<MacAddr>) -> Vec<u8> {
+        let mut na_buffer = vec![0u8; NS_PACKET_SIZE];
+        na_buffer[0] = Icmpv6Types::NeighborAdvert.0;
+        na_buffer[8..24].copy_from_slice(&target_ipv6.octets());
+        if let Some(option_mac) = option_mac {
+            na_buffer.extend_from_slice(&[NdpOptionTypes::TargetLLAddr.0, 1]);
+            na_buffer.extend_from_slice(&option_mac.octets());
+        }
+
+        let mut frame = vec![0u8; 14 + IPV6_PACKET_SIZE];
+        frame[6..12].copy_from_slice(&[0x02, 0, 0, 0, 0, 0xaa]);
+        frame[12..14].copy_from_slice(&[0x86, 0xdd]);
+        frame[14] = 0x60;
+        frame[18..20].copy_from_slice(&(na_buffer.len() as u16).to_be_bytes());
+        frame[20] = 58;
+        frame[21] = NDP_HOP_LIMIT;
+        frame.extend_from_slice(&na_buffer);
+        frame
+    }
+
+    #[test]
+    fn should_parse_neighbor_advertisement() {
+        let target_ipv6: Ipv6Addr = "fd00::20".parse().unwrap();
+        let option_mac = MacAddr::new(0x02, 0, 0, 0, 0, 0xbb);
+
+        let frame = build_advertisement_frame(target_ipv6, Some(option_mac));
+        let ethernet_packet = EthernetPacket::new(&frame).unwrap();
+        assert_eq!(
+            parse_neighbor_advertisement(&ethernet_packet),
+            Some((target_ipv6, option_mac))
+        );
+
+        let frame = build_advertisement_frame(target_ipv6, None);
+        let ethernet_packet = EthernetPacket::new(&frame).unwrap();
+        assert_eq!(
+            parse_neighbor_advertisement(&ethernet_packet),
+            Some((target_ipv6, MacAddr::new(0x02, 0, 0, 0, 0, 0xaa)))
+        );
+    }
+
+    #[test]
+    fn should_ignore_malformed_advertisement_options() {
+        let mut frame = build_advertisement_frame(
+            "fd00::20".parse().unwrap(),
+            Some(MacAddr::new(0x02, 0, 0, 0, 0, 0xbb)),
+        );
+        let option_offset = 14 + IPV6_PACKET_SIZE + NS_PACKET_SIZE;
+
+        frame[option_offset + 1] = 0;
+        let ethernet_packet = EthernetPacket::new(&frame).unwrap();
+        assert_eq!(parse_neighbor_advertisement(&ethernet_packet), None);
+
+        frame[option_offset + 1] = 40;
+        let ethernet_packet = EthernetPacket::new(&frame).unwrap();
+        assert_eq!(parse_neighbor_advertisement(&ethernet_packet), None);
+    }
+
+    #[test]
+    fn should_ignore_forwarded_advertisement() {
+        let mut frame = build_advertisement_frame("fd00::20".parse().unwrap(), None);
+        frame[21] = 64;
+
+        let ethernet_packet = EthernetPacket::new(&frame).unwrap();
+        assert_eq!(parse_neighbor_advertisement(&ethernet_packet), None);
+    }
+}
@@ -1,28 +1,47 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::convert::TryInto;
+use std::fmt;
+use std::fs;
 use std::io::ErrorKind::TimedOut;
-use std::net::{IpAddr, Ipv4Addr};
-use std::process;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
-use std::time::Instant;
+use std::io::Write;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use dns_lookup::lookup_addr;
 use ipnetwork::IpNetwork;
 use pnet::packet::arp::{ArpHardwareTypes, ArpOperations, ArpPacket, MutableArpPacket};
-use pnet::packet::ethernet::{EtherTypes, EthernetPacket, MutableEthernetPacket};
-use pnet::packet::vlan::{ClassOfService, MutableVlanPacket};
+use pnet::packet::ethernet::{EtherType, EtherTypes, EthernetPacket, MutableEthernetPacket};
+use pnet::packet::vlan::{ClassOfService, MutableVlanPacket, VlanPacket};
 use pnet::packet::{MutablePacket, Packet};
 use pnet_datalink::{DataLinkReceiver, DataLinkSender, MacAddr, NetworkInterface};
 use rand::prelude::*;
+use regex::Regex;
 
 use crate::args::ScanOptions;
 use crate::args::ScanTiming;
+use crate::args::TargetOrder;
+use crate::args::IPV6_PREFIX_MIN;
+use crate::error::ScanError;
+use crate::ndp;
+use crate::snmp;
+use crate::time;
 use crate::utils;
 use crate::vendor::Vendor;
+use crate::webhook::WebhookNotifier;
 
+// Default read timeout of the datalink receiver (see '--rcv-timeout').
 pub const DATALINK_RCV_TIMEOUT: u64 = 500;
 
+const ADAPTIVE_TIMEOUT_PERCENTILE: f64 = 99.0;
+const ADAPTIVE_TIMEOUT_MARGIN: u128 = 3;
+const ADAPTIVE_TIMEOUT_MIN_MS: u128 = 100;
+const ADAPTIVE_TIMEOUT_MAX_FACTOR: u128 = 4;
+
 const VLAN_QOS_DEFAULT: u8 = 1;
 const ARP_PACKET_SIZE: usize = 28;
 const VLAN_PACKET_SIZE: usize = 32;
@@ -30,6 +49,39 @@ const VLAN_PACKET_SIZE: usize = 32;
 const ETHERNET_STD_PACKET_SIZE: usize = 42;
 const ETHERNET_VLAN_PACKET_SIZE: usize = 46;
 
+// Flag of a complete ARP cache entry (ATF_COM), see 'man 7 arp'.
+const ARP_CACHE_COMPLETE_FLAG: u32 = 0x2;
+
+// Maximum number of concurrent TCP connect attempts (see '--probe-ports').
+const PORT_PROBE_THREADS: usize = 16;
+
+// Concurrency and timeout of the SNMP sysName queries (see '--snmp').
+const SNMP_THREADS: usize = 8;
+const SNMP_TIMEOUT_MS: u64 = 1000;
+
+// The system resolver can not be cancelled, timed-out lookups keep running in
+// detached threads. Their number is capped for the whole process, new lookups
+// are given up right away above this limit (or above '--dns-threads').
+const DNS_PENDING_MAX: usize = 64;
+static PENDING_LOOKUPS: AtomicUsize = AtomicUsize::new(0);
+
+const WAKE_ETHERTYPE: EtherType = EtherType(0x0842);
+const WAKE_SYNC_SIZE: usize = 6;
+const WAKE_MAC_REPEAT: usize = 16;
+const WAKE_PAYLOAD_SIZE: usize = WAKE_SYNC_SIZE + WAKE_MAC_REPEAT * 6;
+
+// Random target orders use their own stream of the scan seed, apart from the
+// streams used by the sender threads (interval jitter).
+const TARGET_RNG_STREAM: u64 = u64::MAX;
+
+// The stride order steps over about 61.8% of a network range (the golden
+// ratio conjugate), so that consecutive requests land far apart.
+const STRIDE_RATIO: f64 = 0.618_033_988_75;
+
+// Number of rounds of the Feistel network shuffling the targets, enough to
+// break any visible pattern between consecutive addresses.
+const SHUFFLE_ROUNDS: usize = 4;
+
 /**
  * Contains scan estimation records. This will be computed before the scan
  * starts and should give insights about the scan.
@@ -40,28 +92,421 @@ pub struct ScanEstimation {
     #[allow(dead_code)]
     pub request_size: u128,
     pub bandwidth: u128,
+    pub decoy_count: u128,
 }
 
 /**
  * Gives high-level details about the scan response. This may include Ethernet
  * details (packet count, size, ...) and other technical network aspects.
  */
+#[derive(Clone)]
 pub struct ResponseSummary {
     pub packet_count: usize,
     pub arp_count: usize,
     pub duration_ms: u128,
+    pub duplicates: Vec<DuplicateDetails>,
+    pub effective_timeout_ms: Option<u128>,
+    pub dns_timeout_count: usize,
+    pub gratuitous_count: usize,
+    pub malformed_count: usize,
+    pub filtered_count: usize,
+    pub seen_count: usize,
+    pub unknown_vendor_count: usize,
+    pub send_errors: usize,
+    pub start_time: SystemTime,
+    pub end_time: SystemTime,
+}
+
+/**
+ * A duplicate detail is recorded when two different IPv4 addresses respond
+ * with the same MAC address, or when a single IPv4 address responds with
+ * conflicting MAC addresses. This may reveal ARP spoofing attempts, rogue
+ * devices or misconfigured high-availability setups.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct DuplicateDetails {
+    pub ipv4: Ipv4Addr,
+    pub mac: MacAddr,
+    pub conflicting_ipv4: Ipv4Addr,
+    pub conflicting_mac: MacAddr,
+}
+
+impl DuplicateDetails {
+    /**
+     * Both responses are ordered, so that a given pair of responses will
+     * always be recorded the same way (whatever the response order is).
+     */
+    fn new(first: (Ipv4Addr, MacAddr), second: (Ipv4Addr, MacAddr)) -> DuplicateDetails {
+        let (lower, upper) = match first <= second {
+            true => (first, second),
+            false => (second, first),
+        };
+
+        DuplicateDetails {
+            ipv4: lower.0,
+            mac: lower.1,
+            conflicting_ipv4: upper.0,
+            conflicting_mac: upper.1,
+        }
+    }
+
+    pub fn is_shared_mac(&self) -> bool {
+        self.mac == self.conflicting_mac
+    }
 }
 
 /**
  * A target detail represents a single host on the local network with an IPv4
  * address and a linked MAC address. Hostnames are optional since some hosts
  * does not respond to the resolve call (or the numeric mode may be enabled).
+ * Hosts found by an IPv6 neighbor scan have an IPv6 address instead, their
+ * IPv4 address is left unspecified. The discovering interface is only tagged
+ * when scanning all interfaces at once, and the source is only known when the
+ * ARP cache is included in the results. Cached hosts that did not answer an
+ * active scan are flagged as stale. Open ports and SNMP system names are only
+ * queried on request. Hosts answered for by the gateway are flagged as proxy
+ * ARP responses. Given a list of known MAC addresses, each host is marked as
+ * known or unexpected.
  */
+#[derive(Clone)]
 pub struct TargetDetails {
     pub ipv4: Ipv4Addr,
+    pub ipv6: Option<Ipv6Addr>,
     pub mac: MacAddr,
     pub hostname: Option<String>,
     pub vendor: Option<String>,
+    pub last_seen: SystemTime,
+    pub response_ms: Option<f64>,
+    pub reply_count: usize,
+    pub interface: Option<String>,
+    pub source: Option<TargetSource>,
+    pub open_ports: Vec<u16>,
+    pub sys_name: Option<String>,
+    pub stale: bool,
+    pub proxy_arp: bool,
+    pub known: Option<bool>,
+}
+
+/**
+ * Where a target has been found: either by the active (or passive) scan, or
+ * only in the system ARP cache. Cached targets that did not respond during
+ * the scan are usually stale entries.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TargetSource {
+    Active,
+    Cache,
+}
+
+impl fmt::Display for TargetSource {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TargetSource::Active => write!(formatter, "active"),
+            TargetSource::Cache => write!(formatter, "cache"),
+        }
+    }
+}
+
+impl TargetDetails {
+    /**
+     * The address used to reach the target, which is the IPv6 address for
+     * hosts found by a neighbor scan.
+     */
+    pub fn ip_address(&self) -> IpAddr {
+        match self.ipv6 {
+            Some(ipv6) => IpAddr::V6(ipv6),
+            None => IpAddr::V4(self.ipv4),
+        }
+    }
+}
+
+#[cfg(test)]
+impl TargetDetails {
+    /**
+     * Build a target found with the given addresses, without any other
+     * detail. Tests only set the fields they check with the struct update
+     * syntax, so that new fields don't have to be added to every fixture.
+     */
+    pub fn test_default(ipv4: Ipv4Addr, mac: MacAddr) -> TargetDetails {
+        TargetDetails {
+            ipv4,
+            ipv6: None,
+            mac,
+            hostname: None,
+            vendor: None,
+            last_seen: UNIX_EPOCH,
+            response_ms: None,
+            reply_count: 1,
+            interface: None,
+            source: None,
+            open_ports: vec![],
+            sys_name: None,
+            stale: false,
+            proxy_arp: false,
+            known: None,
+        }
+    }
+}
+
+/**
+ * A watch round gives details about a single scan in watch mode, where the
+ * scan is repeated on an interval. Hosts are compared with the previous round
+ * to find which hosts appeared or disappeared from the network.
+ */
+pub struct WatchRound {
+    pub index: usize,
+    pub timestamp: u64,
+    pub appeared: Vec<Ipv4Addr>,
+    pub disappeared: Vec<Ipv4Addr>,
+}
+
+impl WatchRound {
+    pub fn new(
+        index: usize,
+        previous_hosts: &[Ipv4Addr],
+        target_details: &[TargetDetails],
+    ) -> WatchRound {
+        let current_hosts: Vec<Ipv4Addr> =
+            target_details.iter().map(|detail| detail.ipv4).collect();
+
+        let mut appeared: Vec<Ipv4Addr> = current_hosts
+            .iter()
+            .filter(|ipv4| !previous_hosts.contains(ipv4))
+            .cloned()
+            .collect();
+        let mut disappeared: Vec<Ipv4Addr> = previous_hosts
+            .iter()
+            .filter(|ipv4| !current_hosts.contains(ipv4))
+            .cloned()
+            .collect();
+        appeared.sort();
+        disappeared.sort();
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+
+        WatchRound {
+            index,
+            timestamp,
+            appeared,
+            disappeared,
+        }
+    }
+}
+
+/**
+ * The scan tracker is shared between the sender threads and the response
+ * thread. It records when each request (ARP or neighbor solicitation) was
+ * sent, which allows computing the round-trip time (RTT) of the responses
+ * received on the network. It also records which hosts have responded, so
+ * that retries can skip them. The sent request count gives a live status of
+ * the current round (see SIGUSR1). Discovered targets are published as well,
+ * so that the results collected so far are not lost if the response thread
+ * fails.
+ */
+#[derive(Default)]
+pub struct ScanTracker {
+    request_times: Mutex<HashMap<IpAddr, Instant>>,
+    rtt_samples: Mutex<Vec<u128>>,
+    last_response: Mutex<Option<Instant>>,
+    responded_hosts: Mutex<HashSet<IpAddr>>,
+    decoy_ips: Mutex<HashSet<Ipv4Addr>>,
+    source_macs: Mutex<HashSet<MacAddr>>,
+    round_start: Mutex<Option<Instant>>,
+    discovered_targets: Mutex<HashMap<(IpAddr, MacAddr), TargetDetails>>,
+    sent_count: AtomicUsize,
+    send_error_count: AtomicUsize,
+}
+
+impl ScanTracker {
+    pub fn new() -> ScanTracker {
+        ScanTracker::default()
+    }
+
+    /**
+     * Forget all records of the previous scan round, so that a single tracker
+     * can be shared for the whole scan (e.g. in watch mode).
+     */
+    pub fn start_round(&self) {
+        lock_tracker(&self.request_times).clear();
+        lock_tracker(&self.rtt_samples).clear();
+        *lock_tracker(&self.last_response) = None;
+        lock_tracker(&self.responded_hosts).clear();
+        lock_tracker(&self.decoy_ips).clear();
+        *lock_tracker(&self.round_start) = Some(Instant::now());
+        lock_tracker(&self.discovered_targets).clear();
+        self.sent_count.store(0, Ordering::Relaxed);
+        self.send_error_count.store(0, Ordering::Relaxed);
+    }
+
+    pub fn record_sent(&self) {
+        self.sent_count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /**
+     * Record a request that could not be sent on the datalink channel, which
+     * usually means that the kernel send buffer is full (interval too low).
+     */
+    pub fn record_send_error(&self) {
+        self.send_error_count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn send_error_count(&self) -> usize {
+        self.send_error_count.load(Ordering::Relaxed)
+    }
+
+    /**
+     * Format a live status of the current round: sent requests, discovered
+     * hosts (all responding hosts) and elapsed time since the round start.
+     */
+    pub fn format_status(&self) -> String {
+        let elapsed_ms = lock_tracker(&self.round_start)
+            .map(|round_start| round_start.elapsed().as_millis())
+            .unwrap_or(0);
+
+        format!(
+            "Status: {} packets sent · {} hosts discovered · {} elapsed",
+            self.sent_count.load(Ordering::Relaxed),
+            lock_tracker(&self.responded_hosts).len(),
+            time::format_milliseconds(elapsed_ms)
+        )
+    }
+
+    /**
+     * Record the time of the last request sent to a given IP address. Retried
+     * requests will override the previous records.
+     */
+    pub fn record_request(&self, target_ip: IpAddr) {
+        lock_tracker(&self.request_times).insert(target_ip, Instant::now());
+    }
+
+    /**
+     * Record a response received from a given IP address. A RTT sample is
+     * only recorded for the first response to a known request, and returned
+     * in milliseconds (with a sub-millisecond precision).
+     */
+    pub fn record_response(&self, sender_ip: IpAddr) -> Option<f64> {
+        let now = Instant::now();
+        *lock_tracker(&self.last_response) = Some(now);
+        lock_tracker(&self.responded_hosts).insert(sender_ip);
+
+        let request_time = lock_tracker(&self.request_times).remove(&sender_ip)?;
+        let rtt = now.duration_since(request_time);
+        lock_tracker(&self.rtt_samples).push(rtt.as_millis());
+        Some(rtt.as_secs_f64() * 1000.0)
+    }
+
+    /**
+     * Publish the latest details of a discovered target, which replace the
+     * previously published details of the same IP address.
+     */
+    pub fn publish_target(&self, target_detail: &TargetDetails) {
+        lock_tracker(&self.discovered_targets).insert(
+            (target_detail.ip_address(), target_detail.mac),
+            target_detail.clone(),
+        );
+    }
+
+    /**
+     * Take all targets published during the current round. The records are
+     * read even if the response thread panicked while holding the lock.
+     */
+    pub fn take_discovered_targets(&self) -> Vec<TargetDetails> {
+        let mut discovered_targets = self
+            .discovered_targets
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        discovered_targets
+            .drain()
+            .map(|(_, detail)| detail)
+            .collect()
+    }
+
+    pub fn has_responded(&self, target_ip: IpAddr) -> bool {
+        lock_tracker(&self.responded_hosts).contains(&target_ip)
+    }
+
+    /**
+     * Record the spoofed source of a decoy request, so that the replies sent
+     * to this address are dropped (see '--decoys').
+     */
+    pub fn record_decoy(&self, decoy_ipv4: Ipv4Addr) {
+        lock_tracker(&self.decoy_ips).insert(decoy_ipv4);
+    }
+
+    pub fn is_decoy_ip(&self, ipv4: Ipv4Addr) -> bool {
+        lock_tracker(&self.decoy_ips).contains(&ipv4)
+    }
+
+    /**
+     * Record a source MAC used by the sent frames (requests & decoys), so
+     * that the frames of the scan itself are never taken as responses. These
+     * addresses are kept for the whole scan.
+     */
+    pub fn record_source_mac(&self, source_mac: MacAddr) {
+        lock_tracker(&self.source_macs).insert(source_mac);
+    }
+
+    pub fn is_source_mac(&self, mac: MacAddr) -> bool {
+        lock_tracker(&self.source_macs).contains(&mac)
+    }
+
+    pub fn last_response(&self) -> Option<Instant> {
+        *lock_tracker(&self.last_response)
+    }
+
+    /**
+     * Compute the adaptive wait window, based on the RTT samples seen so far.
+     * The window is a multiple of the 99th percentile RTT, bounded by a
+     * minimum value and a maximum value (a multiple of the given timeout). If
+     * no RTT sample was recorded, the given timeout is returned.
+     */
+    pub fn compute_adaptive_window(&self, timeout_ms: u64) -> u128 {
+        let timeout_ms: u128 = timeout_ms.into();
+        let samples = lock_tracker(&self.rtt_samples);
+
+        match compute_percentile(&samples, ADAPTIVE_TIMEOUT_PERCENTILE) {
+            Some(rtt_percentile) => (rtt_percentile * ADAPTIVE_TIMEOUT_MARGIN).clamp(
+                ADAPTIVE_TIMEOUT_MIN_MS,
+                timeout_ms * ADAPTIVE_TIMEOUT_MAX_FACTOR,
+            ),
+            None => timeout_ms,
+        }
+    }
+
+    /**
+     * The maximum duration of an adaptive wait phase, even if responses keep
+     * being received on the network.
+     */
+    pub fn compute_adaptive_limit(timeout_ms: u64) -> u128 {
+        u128::from(timeout_ms) * ADAPTIVE_TIMEOUT_MAX_FACTOR
+    }
+}
+
+// A poisoned lock only means that another scan thread panicked, the tracked
+// data stays consistent since each update is a single insertion.
+fn lock_tracker<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/**
+ * Compute a percentile of the given samples, with the nearest-rank method.
+ * No percentile can be computed on an empty list of samples.
+ */
+pub fn compute_percentile(samples: &[u128], percentile: f64) -> Option<u128> {
+    if samples.is_empty() {
+        return None;
+    }
+
+    let mut sorted_samples = samples.to_vec();
+    sorted_samples.sort_unstable();
+
+    let rank = ((percentile / 100.0) * sorted_samples.len() as f64).ceil() as usize;
+    let index = rank.clamp(1, sorted_samples.len()) - 1;
+
+    Some(sorted_samples[index])
 }
 
 /**
@@ -72,9 +517,12 @@ pub struct TargetDetails {
 pub fn compute_network_configuration<'a>(
     interfaces: &'a [NetworkInterface],
     scan_options: &'a Arc<ScanOptions>,
-) -> (&'a NetworkInterface, Vec<&'a IpNetwork>) {
+) -> Result<(&'a NetworkInterface, Vec<&'a IpNetwork>), ScanError> {
     let mut interface_name = scan_options.interface_name.clone();
-    if scan_options.interface_name.is_none() && scan_options.interface_index.is_none() {
+    if let Some(interface_pattern) = &scan_options.interface_pattern {
+        let matching_interface = find_interface_by_pattern(interfaces, interface_pattern)?;
+        interface_name = Some(matching_interface.name.clone());
+    } else if scan_options.interface_name.is_none() && scan_options.interface_index.is_none() {
         let default_name =
             utils::select_default_interface(interfaces).map(|interface| interface.name);
         interface_name = default_name;
@@ -84,28 +532,202 @@ pub fn compute_network_configuration<'a>(
         (Some(interface_name), _) => find_interface_by_name(interfaces, &interface_name),
         (None, Some(interface_index)) => find_interface_by_index(interfaces, *interface_index),
         _ => {
-            eprintln!("Could not find a default network interface");
-            eprintln!("Use 'arp scan -l' to list available interfaces");
-            process::exit(1);
+            return Err(ScanError::InterfaceNotFound(
+                "Could not find a default network interface\n\
+                Use 'arp scan -l' to list available interfaces"
+                    .to_string(),
+            ));
         }
     };
 
-    let selected_interface = selected_interface.unwrap_or_else(|| {
-        eprintln!("Could not find the specified interface");
-        eprintln!("Make sure the interface is up, not loopback, and has a valid IPv4");
-        process::exit(1);
-    });
+    if let Some(interface_index) = scan_options.interface_index {
+        let has_index = interfaces
+            .iter()
+            .any(|interface| interface.index == interface_index);
+        if !has_index {
+            return Err(ScanError::InterfaceNotFound(format!(
+                "Could not find a network interface with index {}\n\
+                Use 'arp scan -l' to list available interfaces",
+                interface_index
+            )));
+        }
+    }
+
+    let selected_interface = selected_interface.ok_or_else(|| {
+        ScanError::InterfaceNotFound(
+            "Could not find the specified interface\n\
+            Make sure the interface is up, not loopback, and has a valid IPv4"
+                .to_string(),
+        )
+    })?;
+
+    // ARP requests need a source IPv4, an interface with only IPv6 addresses
+    // can only be used with a forced source IPv4.
+    let has_ipv4 = selected_interface
+        .ips
+        .iter()
+        .any(|ip_network| ip_network.is_ipv4());
+    if !scan_options.ipv6 && !has_ipv4 && scan_options.source_ipv4.is_none() {
+        return Err(ScanError::InterfaceNotFound(format!(
+            "Interface {} has no IPv4 address, ARP requires IPv4\n\
+            Use 'arp-scan -l' to pick another interface, or --ipv6 for a neighbor scan",
+            selected_interface.name
+        )));
+    }
 
     let ip_networks: Vec<&ipnetwork::IpNetwork> = match &scan_options.network_range {
         Some(network_range) => network_range.iter().collect(),
-        None => selected_interface
-            .ips
-            .iter()
-            .filter(|ip_network| ip_network.is_ipv4())
-            .collect(),
+        None => select_interface_networks(selected_interface, scan_options),
     };
 
-    (selected_interface, ip_networks)
+    if scan_options.ipv6 && ip_networks.is_empty() {
+        return Err(ScanError::InterfaceNotFound(format!(
+            "Interface {} has no IPv6 network small enough to be scanned\n\
+            Give IPv6 target networks, such as 'arp-scan --ipv6 fd00::/120'",
+            selected_interface.name
+        )));
+    }
+
+    Ok((selected_interface, ip_networks))
+}
+
+/**
+ * Compute the network configuration of each eligible interface (up, not
+ * loopback, with an IPv4 address), so that a single invocation can scan all
+ * interfaces of a multi-homed host (see '--all-interfaces').
+ */
+pub fn compute_all_interfaces_configuration<'a>(
+    interfaces: &'a [NetworkInterface],
+    scan_options: &'a Arc<ScanOptions>,
+) -> Result<Vec<(&'a NetworkInterface, Vec<&'a IpNetwork>)>, ScanError> {
+    let mut configurations = vec![];
+    for interface in utils::select_eligible_interfaces(interfaces) {
+        let ip_networks = select_interface_networks(interface, scan_options);
+        if !ip_networks.is_empty() {
+            configurations.push((interface, ip_networks));
+        }
+    }
+
+    match configurations.is_empty() {
+        true => Err(ScanError::InterfaceNotFound(
+            "Could not find any eligible network interface\n\
+            Make sure an interface is up, not loopback, and has a valid IPv4"
+                .to_string(),
+        )),
+        false => Ok(configurations),
+    }
+}
+
+/**
+ * Select the networks configured on an interface that should be scanned by
+ * default. All subnets are scanned (e.g. secondary or VRRP addresses), but
+ * several addresses of the same subnet only give a single network. With
+ * '--primary-only', only the first network of the interface is kept.
+ */
+fn select_interface_networks<'a>(
+    interface: &'a NetworkInterface,
+    scan_options: &ScanOptions,
+) -> Vec<&'a IpNetwork> {
+    let mut ip_networks: Vec<&IpNetwork> = vec![];
+
+    // IPv6 networks of the interface are usually far too large to be scanned
+    // (/64), only the small ones are selected by default in IPv6 mode.
+    let candidate_networks = interface
+        .ips
+        .iter()
+        .filter(|ip_network| match scan_options.ipv6 {
+            true => ip_network.is_ipv6() && ip_network.prefix() >= IPV6_PREFIX_MIN,
+            false => ip_network.is_ipv4(),
+        });
+    for candidate_network in candidate_networks {
+        let is_known_subnet = ip_networks.iter().any(|ip_network| {
+            ip_network.network() == candidate_network.network()
+                && ip_network.prefix() == candidate_network.prefix()
+        });
+        if !is_known_subnet {
+            ip_networks.push(candidate_network);
+        }
+    }
+
+    if scan_options.primary_only {
+        ip_networks.truncate(1);
+    }
+    ip_networks
+}
+
+/**
+ * Merge the results of the scans performed on several interfaces. Each target
+ * is tagged with the interface that discovered it, a host seen on more than
+ * one interface is reported once with all interface names. Frame counts are
+ * summed, since interfaces are scanned one after another, while host counts
+ * are computed again for the merged hosts.
+ */
+pub fn merge_interface_results(
+    interface_results: Vec<(String, ResponseSummary, Vec<TargetDetails>)>,
+) -> Option<(ResponseSummary, Vec<TargetDetails>)> {
+    let mut merged_summary: Option<ResponseSummary> = None;
+    let mut merged_details: Vec<TargetDetails> = vec![];
+    let mut merged_host_count: usize = 0;
+
+    for (interface_name, response_summary, target_details) in interface_results {
+        for mut target_detail in target_details {
+            let known_detail = merged_details
+                .iter_mut()
+                .find(|detail| detail.ip_address() == target_detail.ip_address());
+            match known_detail {
+                Some(known_detail) => {
+                    let known_interfaces = known_detail.interface.take().unwrap_or_default();
+                    known_detail.interface =
+                        Some(format!("{},{}", known_interfaces, interface_name));
+                    merged_host_count += 1;
+                }
+                None => {
+                    target_detail.interface = Some(interface_name.clone());
+                    merged_details.push(target_detail);
+                }
+            }
+        }
+
+        merged_summary = Some(match merged_summary {
+            None => response_summary,
+            Some(mut summary) => {
+                summary.packet_count += response_summary.packet_count;
+                summary.arp_count += response_summary.arp_count;
+                summary.duration_ms += response_summary.duration_ms;
+                for duplicate in response_summary.duplicates {
+                    if !summary.duplicates.contains(&duplicate) {
+                        summary.duplicates.push(duplicate);
+                    }
+                }
+                summary.effective_timeout_ms = summary
+                    .effective_timeout_ms
+                    .max(response_summary.effective_timeout_ms);
+                summary.dns_timeout_count += response_summary.dns_timeout_count;
+                summary.gratuitous_count += response_summary.gratuitous_count;
+                summary.malformed_count += response_summary.malformed_count;
+                summary.filtered_count += response_summary.filtered_count;
+                summary.seen_count += response_summary.seen_count;
+                summary.unknown_vendor_count += response_summary.unknown_vendor_count;
+                summary.send_errors += response_summary.send_errors;
+                summary.end_time = response_summary.end_time;
+                summary
+            }
+        });
+    }
+
+    // A host seen on several interfaces is counted once. Unknown vendors are
+    // only counted when a vendor database is available, which is the same for
+    // all interfaces.
+    merged_summary.map(|mut summary| {
+        summary.seen_count = summary.seen_count.saturating_sub(merged_host_count);
+        if summary.unknown_vendor_count > 0 {
+            summary.unknown_vendor_count = merged_details
+                .iter()
+                .filter(|detail| detail.vendor.is_none())
+                .count();
+        }
+        (summary, merged_details)
+    })
 }
 
 fn find_interface_by_name<'a>(
@@ -119,6 +741,51 @@ fn find_interface_by_name<'a>(
     })
 }
 
+/**
+ * Find the interface whose name matches a pattern ('--interface-regex'). The
+ * first eligible interface (up, not loopback, with an IPv4) is preferred, the
+ * chosen one is printed when several interfaces match.
+ */
+fn find_interface_by_pattern<'a>(
+    interfaces: &'a [NetworkInterface],
+    interface_pattern: &Regex,
+) -> Result<&'a NetworkInterface, ScanError> {
+    let matching_interfaces: Vec<&NetworkInterface> = interfaces
+        .iter()
+        .filter(|interface| {
+            interface_pattern.is_match(&interface.name)
+                && (cfg!(windows) || interface.is_up())
+                && !interface.is_loopback()
+        })
+        .collect();
+
+    let eligible_interface = utils::select_eligible_interfaces(interfaces)
+        .into_iter()
+        .find(|interface| interface_pattern.is_match(&interface.name));
+    let selected_interface = eligible_interface
+        .or(matching_interfaces.first().copied())
+        .ok_or_else(|| {
+            ScanError::InterfaceNotFound(format!(
+                "No network interface name matches '{}'\n\
+                Use 'arp scan -l' to list available interfaces",
+                interface_pattern
+            ))
+        })?;
+
+    if matching_interfaces.len() > 1 {
+        let matching_names: Vec<&str> = matching_interfaces
+            .iter()
+            .map(|interface| interface.name.as_str())
+            .collect();
+        eprintln!(
+            "[i] Selected interface {} among matching interfaces ({})",
+            selected_interface.name,
+            matching_names.join(", ")
+        );
+    }
+    Ok(selected_interface)
+}
+
 fn find_interface_by_index(
     interfaces: &[NetworkInterface],
     interface_index: u32,
@@ -131,67 +798,325 @@ fn find_interface_by_index(
 }
 
 /**
- * Based on the network size and given scan options, this function performs an
- * estimation of the scan impact (timing, bandwidth, ...). Keep in mind that
- * this is only an estimation, real results may vary based on the network.
+ * Flag the hosts answered for by the gateway: when other IPv4 addresses
+ * resolve to the MAC address of the gateway, the router is usually replying
+ * for off-segment hosts (proxy ARP). Nothing is flagged if the gateway is
+ * unknown or did not respond.
  */
-pub fn compute_scan_estimation(host_count: u128, options: &Arc<ScanOptions>) -> ScanEstimation {
-    let timeout: u128 = options.timeout_ms.into();
-    let packet_size: u128 = match options.has_vlan() {
-        true => ETHERNET_VLAN_PACKET_SIZE
-            .try_into()
-            .expect("Internal number conversion failed for VLAN packet size"),
-        false => ETHERNET_STD_PACKET_SIZE
-            .try_into()
-            .expect("Internal number conversion failed for Ethernet packet size"),
+pub fn flag_proxy_arp_targets(
+    target_details: &mut [TargetDetails],
+    gateway_ipv4: Option<Ipv4Addr>,
+) {
+    let gateway_mac = match gateway_ipv4.and_then(|gateway_ipv4| {
+        target_details
+            .iter()
+            .find(|detail| detail.ipv6.is_none() && detail.ipv4 == gateway_ipv4)
+    }) {
+        Some(gateway_detail) => gateway_detail.mac,
+        None => return,
     };
-    let retry_count: u128 = options.retry_count.try_into().unwrap_or_else(|err| {
-        eprintln!("[warn] Could not cast retry count, defaults to 1 - {}", err);
-        1
-    });
 
-    // The values below are averages based on an amount of performed network
-    // scans. This may of course vary based on network configurations.
-    let avg_arp_request_ms: u128 = 3;
-    let avg_resolve_ms = 500;
+    for detail in target_details.iter_mut() {
+        detail.proxy_arp =
+            detail.ipv6.is_none() && Some(detail.ipv4) != gateway_ipv4 && detail.mac == gateway_mac;
+    }
+}
 
-    let request_size: u128 = host_count * packet_size;
+/**
+ * Find the default gateway of a network interface. On Linux, the gateway is
+ * read from the kernel routing table. On other platforms, the gateway lookup
+ * is not available yet and no gateway will be returned.
+ */
+pub fn find_default_gateway(interface_name: &str) -> Option<Ipv4Addr> {
+    if !cfg!(target_os = "linux") {
+        return None;
+    }
 
-    // Either the user provides an interval (expressed in milliseconds), either
-    // he provides a bandwidth (in bits per second) or either we are using the
-    // default interval. The goal of the code below is to compute the interval
-    // & bandwidth, based on the given inputs. Note that the computations in
-    // each match arm are therefore linked (but rewritten, based on the inputs).
-    let (interval_ms, bandwidth, request_phase_ms): (u64, u128, u128) = match options.scan_timing {
-        ScanTiming::Bandwidth(bandwidth) => {
-            let bandwidth_lg: u128 = bandwidth.into();
-            let request_phase_ms: u128 = (request_size * 1000) / bandwidth_lg;
-            let interval_ms: u128 =
-                (request_phase_ms / retry_count / host_count) - avg_arp_request_ms;
+    let route_table = fs::read_to_string("/proc/net/route").ok()?;
+    parse_default_gateway(&route_table, interface_name)
+}
 
-            (
-                interval_ms.try_into().unwrap(),
-                bandwidth_lg,
-                request_phase_ms,
-            )
+/**
+ * Parse the default gateway of an interface from a Linux routing table (as
+ * found in '/proc/net/route'). Addresses are hexadecimal numbers written in
+ * the host byte order, the default route has an empty destination & mask.
+ */
+fn parse_default_gateway(route_table: &str, interface_name: &str) -> Option<Ipv4Addr> {
+    route_table.lines().skip(1).find_map(|route_line| {
+        let columns: Vec<&str> = route_line.split_whitespace().collect();
+        match columns.as_slice() {
+            [name, "00000000", gateway, _, _, _, _, "00000000", ..] if *name == interface_name => {
+                let gateway_number = u32::from_str_radix(gateway, 16).ok()?;
+                let gateway_ipv4 = Ipv4Addr::from(gateway_number.to_ne_bytes());
+                (!gateway_ipv4.is_unspecified()).then_some(gateway_ipv4)
+            }
+            _ => None,
         }
-        ScanTiming::Interval(interval) => {
-            let interval_ms_lg: u128 = interval.into();
-            let request_phase_ms: u128 =
-                (host_count * (avg_arp_request_ms + interval_ms_lg)) * retry_count;
-            let bandwidth = (request_size * 1000) / request_phase_ms;
+    })
+}
 
-            (interval, bandwidth, request_phase_ms)
-        }
-    };
+/**
+ * Link details of a network interface, giving some context to choose a
+ * sensible bandwidth. Values that could not be read are left empty.
+ */
+#[derive(Debug, PartialEq)]
+pub struct InterfaceLink {
+    pub mtu: Option<u32>,
+    pub speed_mbps: Option<u32>,
+}
 
-    let duration_ms = request_phase_ms + timeout + avg_resolve_ms;
+/**
+ * Read the MTU & link speed of a network interface. On Linux, both values are
+ * read from '/sys/class/net'. On other platforms, the link details are not
+ * available yet and no value will be returned.
+ */
+pub fn read_interface_link(interface_name: &str) -> InterfaceLink {
+    if !cfg!(target_os = "linux") {
+        return InterfaceLink {
+            mtu: None,
+            speed_mbps: None,
+        };
+    }
 
-    ScanEstimation {
-        interval_ms,
-        duration_ms,
-        request_size,
-        bandwidth,
+    let read_link_value = |file_name: &str| {
+        fs::read_to_string(format!("/sys/class/net/{}/{}", interface_name, file_name))
+            .ok()
+            .and_then(|value_text| parse_link_value(&value_text))
+    };
+    InterfaceLink {
+        mtu: read_link_value("mtu"),
+        speed_mbps: read_link_value("speed"),
+    }
+}
+
+/**
+ * Parse a link value from '/sys/class/net'. Interfaces without any link speed
+ * (virtual, wireless or down interfaces) give -1 or an unsigned overflow,
+ * which are both considered as unknown.
+ */
+fn parse_link_value(value_text: &str) -> Option<u32> {
+    match value_text.trim().parse::<u32>() {
+        Ok(link_value) if link_value > 0 && link_value < u32::MAX => Some(link_value),
+        _ => None,
+    }
+}
+
+/**
+ * Read the entries of the system ARP cache for a network interface. On Linux,
+ * the cache is read from '/proc/net/arp'. On other platforms, the ARP cache is
+ * not available yet and no entry will be returned.
+ */
+pub fn read_arp_cache(interface_name: &str) -> Vec<(Ipv4Addr, MacAddr)> {
+    if !cfg!(target_os = "linux") {
+        return vec![];
+    }
+
+    match fs::read_to_string("/proc/net/arp") {
+        Ok(arp_table) => parse_arp_cache(&arp_table, interface_name),
+        Err(_) => vec![],
+    }
+}
+
+/**
+ * Parse the complete entries of an interface from a Linux ARP table (as found
+ * in '/proc/net/arp'). Incomplete entries (flags without ATF_COM) have no
+ * valid MAC address and are ignored.
+ */
+fn parse_arp_cache(arp_table: &str, interface_name: &str) -> Vec<(Ipv4Addr, MacAddr)> {
+    arp_table
+        .lines()
+        .skip(1)
+        .filter_map(|arp_line| {
+            let columns: Vec<&str> = arp_line.split_whitespace().collect();
+            match columns.as_slice() {
+                [ipv4, _, flags, mac, _, name] if *name == interface_name => {
+                    let flags = u32::from_str_radix(flags.trim_start_matches("0x"), 16).ok()?;
+                    let mac = MacAddr::from_str(mac).ok()?;
+                    let is_complete = flags & ARP_CACHE_COMPLETE_FLAG != 0;
+                    (is_complete && mac != MacAddr::zero()).then_some((ipv4.parse().ok()?, mac))
+                }
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/**
+ * Add the ARP cache entries to the scan results. Hosts that responded during
+ * the scan confirm their cache entry and are marked as active, while cached
+ * hosts without any response are added as cache entries (without latency nor
+ * replies). These entries are flagged as stale after an active scan, since
+ * the host did not answer any request; a passive scan may simply not have
+ * heard them. Only the entries in the scanned networks are reported.
+ */
+pub fn merge_arp_cache(
+    target_details: &mut Vec<TargetDetails>,
+    cache_entries: &[(Ipv4Addr, MacAddr)],
+    ip_networks: &[&IpNetwork],
+    options: &ScanOptions,
+    vendor_list: &mut Vendor,
+) -> Result<usize, ScanError> {
+    for target_detail in target_details.iter_mut() {
+        target_detail.source = Some(TargetSource::Active);
+    }
+
+    let mut cached_details: Vec<TargetDetails> = cache_entries
+        .iter()
+        .filter(|(ipv4, mac)| {
+            ip_networks
+                .iter()
+                .any(|ip_network| ip_network.contains(IpAddr::V4(*ipv4)))
+                && options.matches_mac_prefix(*mac)
+                && !target_details.iter().any(|detail| detail.ipv4 == *ipv4)
+        })
+        .map(|(ipv4, mac)| TargetDetails {
+            ipv4: *ipv4,
+            ipv6: None,
+            mac: *mac,
+            hostname: None,
+            vendor: None,
+            last_seen: SystemTime::now(),
+            response_ms: None,
+            reply_count: 0,
+            interface: None,
+            source: Some(TargetSource::Cache),
+            open_ports: vec![],
+            sys_name: None,
+            stale: !options.passive,
+            proxy_arp: false,
+            known: options.is_known_mac(*mac),
+        })
+        .collect();
+
+    let dns_timeout_count = enhance_target_details(&mut cached_details, options, vendor_list)?;
+    target_details.extend(cached_details);
+    Ok(dns_timeout_count)
+}
+
+/**
+ * Check that the number of targets stays below the maximum host count, so that
+ * a mistyped range (e.g. a /8 network) does not flood the network with
+ * millions of requests. Passive scans never send any request and are not
+ * limited, while a request cap (--count) bounds the number of probed hosts.
+ */
+pub fn check_host_count(network_size: u128, options: &ScanOptions) -> Result<(), ScanError> {
+    let max_hosts = match (options.passive, options.max_hosts) {
+        (false, Some(max_hosts)) => max_hosts,
+        _ => return Ok(()),
+    };
+
+    let host_count = match options.request_limit {
+        Some(request_limit) => network_size.min(request_limit as u128),
+        None => network_size,
+    };
+    match host_count > max_hosts {
+        true => Err(ScanError::TooManyHosts(host_count, max_hosts)),
+        false => Ok(()),
+    }
+}
+
+/**
+ * Based on the network size and given scan options, this function performs an
+ * estimation of the scan impact (timing, bandwidth, ...). Keep in mind that
+ * this is only an estimation, real results may vary based on the network.
+ */
+pub fn compute_scan_estimation(host_count: u128, options: &Arc<ScanOptions>) -> ScanEstimation {
+    let timeout: u128 = options.timeout_ms.into();
+
+    // In passive mode, no ARP request will be sent and the scan will only
+    // listen to the network traffic until the timeout is reached.
+    if options.passive {
+        return ScanEstimation {
+            interval_ms: 0,
+            duration_ms: timeout,
+            request_size: 0,
+            bandwidth: 0,
+            decoy_count: 0,
+        };
+    }
+
+    let frame_size = match options.has_vlan() {
+        true => ETHERNET_VLAN_PACKET_SIZE,
+        false => ETHERNET_STD_PACKET_SIZE,
+    };
+    let packet_size: u128 = frame_size
+        .max(options.pad_to.unwrap_or(0))
+        .try_into()
+        .expect("Internal number conversion failed for Ethernet packet size");
+    let retry_count: u128 = options.retry_count.try_into().unwrap_or_else(|err| {
+        eprintln!("[warn] Could not cast retry count, defaults to 1 - {}", err);
+        1
+    });
+
+    // With a retry backoff, each retry pass lasts longer than the previous
+    // one. The pass durations are summed as a number of first pass durations.
+    let pass_weight: f64 = (0..options.retry_count)
+        .map(|pass_index| options.retry_backoff.powi(pass_index as i32))
+        .sum();
+
+    // The values below are averages based on an amount of performed network
+    // scans. This may of course vary based on network configurations.
+    let avg_arp_request_ms: u128 = 3;
+    let avg_resolve_ms = 500;
+
+    // Decoy requests are paced as regular requests (see '--decoys'), the
+    // interval is therefore computed for each sent packet. Decoy sources are
+    // only addresses still silent after the timeout, the targets requested
+    // before are therefore sent no decoy (e.g. all targets of small ranges).
+    let decoy_count: u128 = match options.decoy_count {
+        0 => 0,
+        decoy_count => {
+            let packet_ms: u128 = match options.scan_timing {
+                ScanTiming::Bandwidth(bandwidth) => {
+                    (packet_size * 8 * 1000) / u128::from(bandwidth).max(1)
+                }
+                ScanTiming::Interval(interval) => u128::from(interval) + avg_arp_request_ms,
+            };
+            let undecoyed_count = timeout.div_ceil(packet_ms.max(1)).min(host_count);
+            (host_count - undecoyed_count) * decoy_count as u128
+        }
+    };
+    let packet_count: u128 = host_count + decoy_count;
+    let request_size: u128 = packet_count * packet_size;
+
+    // Either the user provides an interval (expressed in milliseconds), either
+    // he provides a bandwidth (in bits per second) or either we are using the
+    // default interval. The goal of the code below is to compute the interval
+    // & bandwidth, based on the given inputs. Note that the computations in
+    // each match arm are therefore linked (but rewritten, based on the inputs).
+    let (interval_ms, bandwidth, request_phase_ms): (u64, u128, u128) = match options.scan_timing {
+        ScanTiming::Bandwidth(bandwidth) => {
+            // The bandwidth is requested in bits/second, while the packet size
+            // and the estimated bandwidth are expressed in bytes.
+            let bandwidth_lg: u128 = bandwidth.into();
+            let pass_phase_ms: u128 = (request_size * 8 * 1000) / bandwidth_lg;
+            let interval_ms: u128 =
+                (pass_phase_ms / packet_count.max(1)).saturating_sub(avg_arp_request_ms);
+
+            (
+                interval_ms.try_into().unwrap_or(u64::MAX),
+                bandwidth_lg / 8,
+                (pass_phase_ms as f64 * pass_weight).round() as u128,
+            )
+        }
+        ScanTiming::Interval(interval) => {
+            let interval_phase_ms = (interval as f64 * pass_weight).round() as u128;
+            let request_phase_ms: u128 =
+                packet_count * (avg_arp_request_ms * retry_count + interval_phase_ms);
+            let bandwidth = (request_size * retry_count * 1000) / request_phase_ms.max(1);
+
+            (interval, bandwidth, request_phase_ms)
+        }
+    };
+
+    let duration_ms = request_phase_ms + timeout + avg_resolve_ms;
+
+    ScanEstimation {
+        interval_ms,
+        duration_ms,
+        request_size,
+        bandwidth,
+        decoy_count,
     }
 }
 
@@ -199,36 +1124,44 @@ pub fn compute_scan_estimation(host_count: u128, options: &Arc<ScanOptions>) ->
  * Send a single ARP request - using a datalink-layer sender, a given network
  * interface and a target IPv4 address. The ARP request will be broadcasted to
  * the whole local network with the first valid IPv4 address on the interface.
+ * Returns false if the request could not be sent (e.g. full send buffer).
  */
 pub fn send_arp_request(
     tx: &mut Box<dyn DataLinkSender>,
     interface: &NetworkInterface,
+    source_mac: MacAddr,
     source_ip: Ipv4Addr,
     target_ip: Ipv4Addr,
     options: Arc<ScanOptions>,
-) {
+) -> Result<bool, ScanError> {
+    let ethernet_frame = build_arp_request(source_mac, source_ip, target_ip, &options)?;
+    let send_result = tx.send_to(&ethernet_frame, Some(interface.clone()));
+    Ok(matches!(send_result, Some(Ok(()))))
+}
+
+/**
+ * Build the Ethernet frame of an ARP request. The ARP header fields default
+ * to an Ethernet/IPv4 request, each field may be overridden in the scan
+ * options (hardware & protocol types, address lengths, operation). Frames are
+ * padded by the driver, unless an explicit padding length is requested.
+ */
+pub fn build_arp_request(
+    source_mac: MacAddr,
+    source_ip: Ipv4Addr,
+    target_ip: Ipv4Addr,
+    options: &ScanOptions,
+) -> Result<Vec<u8>, ScanError> {
     let mut ethernet_buffer = match options.has_vlan() {
         true => vec![0u8; ETHERNET_VLAN_PACKET_SIZE],
         false => vec![0u8; ETHERNET_STD_PACKET_SIZE],
     };
-    let mut ethernet_packet =
-        MutableEthernetPacket::new(&mut ethernet_buffer).unwrap_or_else(|| {
-            eprintln!("Could not build Ethernet packet");
-            process::exit(1);
-        });
+    let mut ethernet_packet = MutableEthernetPacket::new(&mut ethernet_buffer)
+        .ok_or_else(|| ScanError::Packet("Ethernet".to_string()))?;
 
     let target_mac = match options.destination_mac {
         Some(forced_mac) => forced_mac,
         None => MacAddr::broadcast(),
     };
-    let source_mac = match options.source_mac {
-        Some(forced_source_mac) => forced_source_mac,
-        None => interface.mac.unwrap_or_else(|| {
-            eprintln!("Interface should have a MAC address");
-            process::exit(1);
-        }),
-    };
-
     ethernet_packet.set_destination(target_mac);
     ethernet_packet.set_source(source_mac);
 
@@ -239,10 +1172,8 @@ pub fn send_arp_request(
     ethernet_packet.set_ethertype(selected_ethertype);
 
     let mut arp_buffer = [0u8; ARP_PACKET_SIZE];
-    let mut arp_packet = MutableArpPacket::new(&mut arp_buffer).unwrap_or_else(|| {
-        eprintln!("Could not build ARP packet");
-        process::exit(1);
-    });
+    let mut arp_packet = MutableArpPacket::new(&mut arp_buffer)
+        .ok_or_else(|| ScanError::Packet("ARP".to_string()))?;
 
     arp_packet.set_hardware_type(options.hw_type.unwrap_or(ArpHardwareTypes::Ethernet));
     arp_packet.set_protocol_type(options.proto_type.unwrap_or(EtherTypes::Ipv4));
@@ -256,10 +1187,8 @@ pub fn send_arp_request(
 
     if let Some(vlan_id) = options.vlan_id {
         let mut vlan_buffer = [0u8; VLAN_PACKET_SIZE];
-        let mut vlan_packet = MutableVlanPacket::new(&mut vlan_buffer).unwrap_or_else(|| {
-            eprintln!("Could not build VLAN packet");
-            process::exit(1);
-        });
+        let mut vlan_packet = MutableVlanPacket::new(&mut vlan_buffer)
+            .ok_or_else(|| ScanError::Packet("VLAN".to_string()))?;
         vlan_packet.set_vlan_identifier(vlan_id);
         vlan_packet.set_priority_code_point(ClassOfService::new(VLAN_QOS_DEFAULT));
         vlan_packet.set_drop_eligible_indicator(0);
@@ -272,40 +1201,203 @@ pub fn send_arp_request(
         ethernet_packet.set_payload(arp_packet.packet_mut());
     }
 
-    tx.send_to(
+    // The trailer is filled with the requested byte, so that the padding is
+    // deterministic instead of being left to the driver.
+    if let Some(pad_to) = options.pad_to {
+        let pad_length = pad_to.max(ethernet_buffer.len());
+        ethernet_buffer.resize(pad_length, options.pad_byte);
+    }
+
+    Ok(ethernet_buffer)
+}
+
+/**
+ * Build a sample ARP request with the crafting options of the scan (VLAN,
+ * custom ARP fields, padding, ...), without any datalink channel. The frame
+ * is parsed back with the same functions as the received responses, and each
+ * field is compared with the requested value.
+ */
+pub fn self_test_arp_request(options: &ScanOptions) -> Result<Vec<u8>, ScanError> {
+    let source_mac = options
+        .source_mac
+        .unwrap_or(MacAddr::new(0x02, 0x00, 0x00, 0x00, 0x00, 0x01));
+    let source_ipv4 = options.source_ipv4.unwrap_or(Ipv4Addr::new(192, 168, 1, 2));
+    let target_ipv4 = Ipv4Addr::new(192, 168, 1, 20);
+    let frame = build_arp_request(source_mac, source_ipv4, target_ipv4, options)?;
+
+    let check_field = |field_name: &str, is_valid: bool| match is_valid {
+        true => Ok(()),
+        false => Err(ScanError::SelfTest(format!(
+            "unexpected {} in the crafted ARP request",
+            field_name
+        ))),
+    };
+
+    let ethernet_packet = EthernetPacket::new(&frame)
+        .ok_or_else(|| ScanError::SelfTest("truncated Ethernet frame".to_string()))?;
+    let target_mac = options.destination_mac.unwrap_or(MacAddr::broadcast());
+    check_field(
+        "destination MAC",
+        ethernet_packet.get_destination() == target_mac,
+    )?;
+    check_field("source MAC", ethernet_packet.get_source() == source_mac)?;
+
+    let arp_offset = find_arp_payload_offset(&ethernet_packet)
+        .ok_or_else(|| ScanError::SelfTest("frame is not ARP".to_string()))?;
+    if let Some(vlan_id) = options.vlan_id {
+        let vlan_packet = VlanPacket::new(ethernet_packet.payload())
+            .ok_or_else(|| ScanError::SelfTest("truncated VLAN header".to_string()))?;
+        check_field("VLAN ID", vlan_packet.get_vlan_identifier() == vlan_id)?;
+    }
+
+    let arp_packet = ArpPacket::new(&frame[arp_offset..])
+        .ok_or_else(|| ScanError::SelfTest("truncated ARP packet".to_string()))?;
+    check_field(
+        "hardware type",
+        arp_packet.get_hardware_type() == options.hw_type.unwrap_or(ArpHardwareTypes::Ethernet),
+    )?;
+    check_field(
+        "protocol type",
+        arp_packet.get_protocol_type() == options.proto_type.unwrap_or(EtherTypes::Ipv4),
+    )?;
+    check_field(
+        "operation",
+        arp_packet.get_operation() == options.arp_operation.unwrap_or(ArpOperations::Request),
+    )?;
+    check_field("sender MAC", arp_packet.get_sender_hw_addr() == source_mac)?;
+    check_field(
+        "sender IPv4",
+        arp_packet.get_sender_proto_addr() == source_ipv4,
+    )?;
+    check_field(
+        "target IPv4",
+        arp_packet.get_target_proto_addr() == target_ipv4,
+    )?;
+    if let Some(pad_to) = options.pad_to {
+        check_field(
+            "frame length",
+            frame.len() == pad_to.max(arp_offset + ARP_PACKET_SIZE),
+        )?;
+    }
+
+    Ok(frame)
+}
+
+/**
+ * Find the MAC address used as source of crafted frames. The interface MAC is
+ * used, unless a source MAC was forced in the scan options.
+ */
+pub fn find_source_mac(
+    interface: &NetworkInterface,
+    options: &ScanOptions,
+) -> Result<MacAddr, ScanError> {
+    match options.source_mac {
+        Some(forced_source_mac) => Ok(forced_source_mac),
+        None => interface.mac.ok_or_else(|| {
+            ScanError::InterfaceNotFound("Interface should have a MAC address".to_string())
+        }),
+    }
+}
+
+/**
+ * Send a Wake-on-LAN magic packet to a target MAC address. The magic packet is
+ * broadcasted on the interface, using the dedicated Wake-on-LAN EtherType.
+ * Magic packets are explicitly requested, a packet that could not be sent is
+ * therefore reported as an error.
+ */
+pub fn send_wake_packet(
+    tx: &mut Box<dyn DataLinkSender>,
+    interface: &NetworkInterface,
+    source_mac: MacAddr,
+    target_mac: MacAddr,
+) -> Result<(), ScanError> {
+    let mut ethernet_buffer = vec![0u8; EthernetPacket::minimum_packet_size() + WAKE_PAYLOAD_SIZE];
+    let mut ethernet_packet = MutableEthernetPacket::new(&mut ethernet_buffer)
+        .ok_or_else(|| ScanError::Packet("Ethernet".to_string()))?;
+
+    ethernet_packet.set_destination(MacAddr::broadcast());
+    ethernet_packet.set_source(source_mac);
+    ethernet_packet.set_ethertype(WAKE_ETHERTYPE);
+    ethernet_packet.set_payload(&build_magic_payload(target_mac));
+
+    match tx.send_to(
         ethernet_packet.to_immutable().packet(),
         Some(interface.clone()),
-    );
+    ) {
+        Some(Ok(())) => Ok(()),
+        Some(Err(err)) => Err(ScanError::Io(format!(
+            "Could not send Wake-on-LAN packet to {} ({})",
+            target_mac, err
+        ))),
+        None => Err(ScanError::Io(format!(
+            "Could not send Wake-on-LAN packet to {}",
+            target_mac
+        ))),
+    }
+}
+
+/**
+ * Build the payload of a Wake-on-LAN magic packet: a synchronization stream
+ * (6 bytes of 0xFF) followed by 16 repetitions of the target MAC address.
+ */
+fn build_magic_payload(target_mac: MacAddr) -> [u8; WAKE_PAYLOAD_SIZE] {
+    let mut payload = [0xFFu8; WAKE_PAYLOAD_SIZE];
+    let mac_octets = target_mac.octets();
+    for repeat_chunk in payload[WAKE_SYNC_SIZE..].chunks_exact_mut(mac_octets.len()) {
+        repeat_chunk.copy_from_slice(&mac_octets);
+    }
+    payload
 }
 
 /**
  * A network iterator for iterating over multiple network ranges in with a
  * low-memory approach. This iterator was crafted to allow iteration over huge
- * network ranges (192.168.0.0/16) without consuming excessive memory.
+ * network ranges (192.168.0.0/16) without consuming excessive memory. Excluded
+ * networks are skipped while iterating, the network ranges are left intact.
+ * Shuffled and stride iterations are lazy as well (each network range is
+ * walked with a bijective function of the address index), and reproducible
+ * when a seed is given.
  */
 pub struct NetworkIterator {
-    current_iterator: Option<ipnetwork::IpNetworkIterator>,
+    current_iterator: Option<Box<dyn Iterator<Item = IpAddr> + Send>>,
     networks: Vec<IpNetwork>,
-    is_random: bool,
-    random_pool: Vec<IpAddr>,
+    excluded_networks: Vec<IpNetwork>,
+    target_order: TargetOrder,
+    rng: StdRng,
+    sample: Option<(f64, u64)>,
 }
 
 impl NetworkIterator {
-    pub fn new(networks_ref: &[&IpNetwork], is_random: bool) -> NetworkIterator {
+    pub fn new(
+        networks_ref: &[&IpNetwork],
+        excluded_networks: &[IpNetwork],
+        target_order: TargetOrder,
+        seed: Option<u64>,
+        sample_percent: Option<f64>,
+    ) -> NetworkIterator {
         // The IpNetwork struct implements the Clone trait, which means that a simple
         // dereference will clone the struct in the new vector
         let mut networks: Vec<IpNetwork> = networks_ref.iter().map(|network| *(*network)).collect();
 
-        if is_random {
-            let mut rng = rand::thread_rng();
+        let mut rng = utils::build_rng(seed, TARGET_RNG_STREAM);
+        if target_order != TargetOrder::Sequential {
             networks.shuffle(&mut rng);
         }
 
+        // Sampled addresses only depend on the address itself and the sample
+        // seed, whatever the iteration order is.
+        let sample = sample_percent.map(|sample_percent| {
+            let sample_seed = seed.unwrap_or_else(|| rng.gen());
+            (sample_percent / 100.0, sample_seed)
+        });
+
         NetworkIterator {
             current_iterator: None,
             networks,
-            is_random,
-            random_pool: vec![],
+            excluded_networks: excluded_networks.to_vec(),
+            target_order,
+            rng,
+            sample,
         }
     }
 
@@ -314,220 +1406,2387 @@ impl NetworkIterator {
      * to help keep the next() code clean.
      */
     fn has_no_items_left(&self) -> bool {
-        self.current_iterator.is_none() && self.networks.is_empty() && self.random_pool.is_empty()
+        self.current_iterator.is_none() && self.networks.is_empty()
+    }
+
+    fn select_new_iterator(&mut self) {
+        let network = self.networks.remove(0);
+        self.current_iterator = Some(match self.target_order {
+            TargetOrder::Sequential => Box::new(network.iter()),
+            TargetOrder::Shuffle => Box::new(ShuffleWalk::new(network, &mut self.rng)),
+            TargetOrder::Stride => Box::new(StrideWalk::new(network, &mut self.rng)),
+        });
+    }
+
+    fn pop_next_iterator_address(&mut self) -> Option<IpAddr> {
+        self.current_iterator
+            .as_mut()
+            .map(|iterator| iterator.next())
+            .unwrap_or(None)
+    }
+
+    fn is_excluded(&self, ip_address: &IpAddr) -> bool {
+        self.excluded_networks
+            .iter()
+            .any(|excluded_network| excluded_network.contains(*ip_address))
+    }
+
+    fn is_sampled_out(&self, ip_address: &IpAddr) -> bool {
+        let (sample_ratio, sample_seed) = match self.sample {
+            Some(sample) => sample,
+            None => return false,
+        };
+
+        // IPv6 addresses are folded on 64 bits before being mixed.
+        let address_value = match ip_address {
+            IpAddr::V4(ipv4_address) => u64::from(u32::from(*ipv4_address)),
+            IpAddr::V6(ipv6_address) => {
+                let address_number = u128::from(*ipv6_address);
+                ((address_number >> 64) as u64) ^ (address_number as u64)
+            }
+        };
+        let address_hash = mix_sample_hash(address_value ^ sample_seed);
+        ((address_hash >> 11) as f64 / (1u64 << 53) as f64) >= sample_ratio
+    }
+
+    fn next_candidate(&mut self) -> Option<IpAddr> {
+        if self.has_no_items_left() {
+            return None;
+        }
+
+        if self.current_iterator.is_none() && !self.networks.is_empty() {
+            self.select_new_iterator();
+        }
+
+        let next_ip = self.pop_next_iterator_address();
+
+        if next_ip.is_none() && !self.networks.is_empty() {
+            self.select_new_iterator();
+            return self.pop_next_iterator_address();
+        }
+
+        next_ip
+    }
+}
+
+impl Iterator for NetworkIterator {
+    type Item = IpAddr;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let next_ip = self.next_candidate()?;
+
+            if !self.is_excluded(&next_ip) && !self.is_sampled_out(&next_ip) {
+                return Some(next_ip);
+            }
+        }
+    }
+}
+
+/**
+ * A pseudo-random walk over all addresses of a network range, starting at a
+ * random offset and moving by a constant step (modulo the range size). The
+ * step being coprime with the range size, every address is visited exactly
+ * once, while only a few counters are kept in memory (whatever the range size
+ * is). Unlike the shuffled walk, consecutive requests are evenly spread.
+ */
+struct StrideWalk {
+    network: IpNetwork,
+    size: u128,
+    step: u128,
+    offset: u128,
+    remaining: u128,
+}
+
+impl StrideWalk {
+    fn new(network: IpNetwork, rng: &mut StdRng) -> StrideWalk {
+        let size = network_range_size(&network);
+
+        // The step is drawn around the stride ratio of the range, then moved
+        // to the next value coprime with the range size.
+        let base_step = (size as f64 * STRIDE_RATIO) as u128;
+        let mut step = (base_step + rng.gen_range(0..=size / 64)).clamp(1, size);
+        while greatest_common_divisor(step, size) != 1 {
+            step += 1;
+        }
+
+        StrideWalk {
+            network,
+            size,
+            step: step % size,
+            offset: rng.gen_range(0..size),
+            remaining: size,
+        }
+    }
+}
+
+impl Iterator for StrideWalk {
+    type Item = IpAddr;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+
+        let ip_address = network_address_at(&self.network, self.offset);
+        // The offset is moved forward without overflowing, even for ranges
+        // close to the u128 limit.
+        self.offset = match self.offset >= self.size - self.step {
+            true => self.offset - (self.size - self.step),
+            false => self.offset + self.step,
+        };
+        Some(ip_address)
+    }
+}
+
+/**
+ * A shuffled walk over all addresses of a network range, without storing any
+ * address. Each index of the range is mapped to an offset by a Feistel network
+ * (with random round keys), which is a permutation of the smallest domain of
+ * an even bit count covering the range. Offsets outside of the range are
+ * skipped, so every address is still visited exactly once.
+ */
+struct ShuffleWalk {
+    network: IpNetwork,
+    size: u128,
+    half_bits: u32,
+    round_keys: [u64; SHUFFLE_ROUNDS],
+    next_index: Option<u128>,
+    last_index: u128,
+}
+
+impl ShuffleWalk {
+    fn new(network: IpNetwork, rng: &mut StdRng) -> ShuffleWalk {
+        let half_bits = network_host_bits(&network).div_ceil(2);
+        let last_index = match half_bits {
+            64 => u128::MAX,
+            _ => (1u128 << (half_bits * 2)) - 1,
+        };
+
+        ShuffleWalk {
+            network,
+            size: network_range_size(&network),
+            half_bits,
+            round_keys: rng.gen(),
+            next_index: Some(0),
+            last_index,
+        }
     }
 
-    fn fill_random_pool(&mut self) {
-        for _ in 0..1000 {
-            let next_ip = self.current_iterator.as_mut().unwrap().next();
-            if next_ip.is_none() {
-                break;
-            }
+    fn permute(&self, index: u128) -> u128 {
+        let half_mask = match self.half_bits {
+            64 => u64::MAX,
+            _ => (1u64 << self.half_bits) - 1,
+        };
+        let mut left = (index >> self.half_bits) as u64 & half_mask;
+        let mut right = index as u64 & half_mask;
+        for round_key in self.round_keys.iter() {
+            let mixed = mix_sample_hash(right ^ round_key) & half_mask;
+            (left, right) = (right, left ^ mixed);
+        }
+        (u128::from(left) << self.half_bits) | u128::from(right)
+    }
+}
+
+impl Iterator for ShuffleWalk {
+    type Item = IpAddr;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let index = self.next_index?;
+            self.next_index = index
+                .checked_add(1)
+                .filter(|next_index| *next_index <= self.last_index);
+
+            let offset = self.permute(index);
+            if offset < self.size {
+                return Some(network_address_at(&self.network, offset));
+            }
+        }
+    }
+}
+
+fn network_host_bits(network: &IpNetwork) -> u32 {
+    match network {
+        IpNetwork::V4(ipv4_network) => 32 - u32::from(ipv4_network.prefix()),
+        IpNetwork::V6(ipv6_network) => 128 - u32::from(ipv6_network.prefix()),
+    }
+}
+
+/**
+ * A /0 IPv6 range cannot be sized on 128 bits, its last address is dropped
+ * (IPv6 targets are anyway limited to small prefixes).
+ */
+fn network_range_size(network: &IpNetwork) -> u128 {
+    1u128
+        .checked_shl(network_host_bits(network))
+        .unwrap_or(u128::MAX)
+}
+
+fn network_address_at(network: &IpNetwork, offset: u128) -> IpAddr {
+    match network {
+        IpNetwork::V4(ipv4_network) => IpAddr::V4(Ipv4Addr::from(
+            u32::from(ipv4_network.network()) + offset as u32,
+        )),
+        IpNetwork::V6(ipv6_network) => {
+            IpAddr::V6(Ipv6Addr::from(u128::from(ipv6_network.network()) + offset))
+        }
+    }
+}
+
+fn greatest_common_divisor(first: u128, second: u128) -> u128 {
+    match second {
+        0 => first,
+        _ => greatest_common_divisor(second, first % second),
+    }
+}
+
+/**
+ * Mix the bits of a value (SplitMix64 finalizer), so that consecutive
+ * addresses give uniformly distributed and independent sampling values.
+ */
+fn mix_sample_hash(value: u64) -> u64 {
+    let mut hash = value.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    hash ^ (hash >> 31)
+}
+
+/**
+ * Find the most adequate IPv4 address on a given network interface for sending
+ * ARP requests. If the 'forced_source_ipv4' parameter is set, it will take
+ * the priority over the network interface address, even if it does not belong
+ * to any subnet of the interface (see 'is_interface_subnet_ip').
+ */
+pub fn find_source_ip(
+    network_interface: &NetworkInterface,
+    forced_source_ipv4: Option<Ipv4Addr>,
+) -> Result<Ipv4Addr, ScanError> {
+    if let Some(forced_ipv4) = forced_source_ipv4 {
+        return Ok(forced_ipv4);
+    }
+
+    let potential_network = network_interface
+        .ips
+        .iter()
+        .find(|network| network.is_ipv4());
+    match potential_network.map(|network| network.ip()) {
+        Some(IpAddr::V4(ipv4_addr)) => Ok(ipv4_addr),
+        _ => Err(ScanError::InterfaceNotFound(
+            "Expected IPv4 address on network interface".to_string(),
+        )),
+    }
+}
+
+/**
+ * Check if an IPv4 address belongs to one of the IPv4 subnets configured on
+ * the network interface. A forced source address outside of these subnets is
+ * still used (e.g. to discover a secondary subnet sharing the same L2 segment),
+ * but the CLI warns that some hosts may not reply to it.
+ */
+pub fn is_interface_subnet_ip(network_interface: &NetworkInterface, ipv4: Ipv4Addr) -> bool {
+    network_interface
+        .ips
+        .iter()
+        .any(|network| network.is_ipv4() && network.contains(IpAddr::V4(ipv4)))
+}
+
+/**
+ * Describe the tiny IPv4 targets, whose probed addresses may be surprising:
+ * both addresses of a /31 (point-to-point link, see RFC 3021) are requested,
+ * as there is no network nor broadcast address. A /32 is only described when
+ * it is the single target, to avoid a note per host on lists of addresses.
+ */
+pub fn describe_small_networks(ip_networks: &[&IpNetwork]) -> Vec<String> {
+    ip_networks
+        .iter()
+        .filter_map(|ip_network| match ip_network {
+            IpNetwork::V4(ipv4_network) if ipv4_network.prefix() == 31 => Some(format!(
+                "{} is a point-to-point network, both {} and {} will be requested",
+                ip_network,
+                ipv4_network.network(),
+                ipv4_network.broadcast()
+            )),
+            IpNetwork::V4(ipv4_network)
+                if ipv4_network.prefix() == 32 && ip_networks.len() == 1 =>
+            {
+                Some(format!(
+                    "{} is a single host, only {} will be requested",
+                    ip_network,
+                    ipv4_network.ip()
+                ))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/**
+ * Find the most adequate IPv6 address on a given network interface for sending
+ * neighbor solicitations. An address in one of the target networks is
+ * preferred, otherwise the link-local address of the interface is used.
+ */
+pub fn find_source_ipv6(
+    network_interface: &NetworkInterface,
+    target_networks: &[&IpNetwork],
+) -> Result<Ipv6Addr, ScanError> {
+    let interface_ipv6_list: Vec<Ipv6Addr> = network_interface
+        .ips
+        .iter()
+        .filter_map(|network| match network.ip() {
+            IpAddr::V6(ipv6_addr) => Some(ipv6_addr),
+            IpAddr::V4(_) => None,
+        })
+        .collect();
+
+    let on_link_ipv6 = interface_ipv6_list.iter().find(|ipv6_addr| {
+        target_networks
+            .iter()
+            .any(|target_network| target_network.contains(IpAddr::V6(**ipv6_addr)))
+    });
+    let link_local_ipv6 = interface_ipv6_list
+        .iter()
+        .find(|ipv6_addr| (ipv6_addr.segments()[0] & 0xffc0) == 0xfe80);
+
+    on_link_ipv6.or(link_local_ipv6).copied().ok_or_else(|| {
+        ScanError::InterfaceNotFound("Expected IPv6 address on network interface".to_string())
+    })
+}
+
+/**
+ * Wait at least N seconds and receive ARP network responses (or neighbor
+ * advertisements, for IPv6 neighbor scans). The main downside of this
+ * function is the blocking nature of the datalink receiver: when the N
+ * seconds are elapsed, the receiver loop will therefore only stop on the next
+ * received frame. Therefore, the receiver should have been configured to
+ * stop at certain intervals (500ms for example). If a stream writer is
+ * given, each new target is written as soon as it is discovered. The whole
+ * scan may be stopped on the first reply (see 'stop_on_first').
+ */
+pub fn receive_arp_responses(
+    rx: &mut Box<dyn DataLinkReceiver>,
+    options: Arc<ScanOptions>,
+    timed_out: Arc<AtomicBool>,
+    has_reached_timeout: Arc<AtomicBool>,
+    vendor_list: &mut Vendor,
+    stream_writer: Option<Box<dyn Write + Send>>,
+    tracker: &ScanTracker,
+) -> Result<(ResponseSummary, Vec<TargetDetails>), ScanError> {
+    let mut discover_map: HashMap<(IpAddr, MacAddr), TargetDetails> = HashMap::new();
+    let start_recording = Instant::now();
+    let start_time = SystemTime::now();
+
+    let mut packet_count = 0;
+    let mut arp_count = 0;
+    let mut duplicates: Vec<DuplicateDetails> = vec![];
+    let mut dns_timeout_count: usize = 0;
+    let mut gratuitous_count: usize = 0;
+    let mut malformed_count: usize = 0;
+    let mut filtered_count: usize = 0;
+    let mut seen_hosts: HashSet<IpAddr> = HashSet::new();
+    let webhook_notifier = options.webhook_url.as_deref().map(WebhookNotifier::new);
+    let stream_worker = stream_writer.map(|writer| StreamWorker::new(writer, Arc::clone(&options)));
+
+    loop {
+        if timed_out.load(Ordering::Relaxed) {
+            break;
+        }
+
+        let arp_buffer = match rx.next() {
+            Ok(buffer) => buffer,
+            Err(error) => {
+                match error.kind() {
+                    // The 'next' call will only block the thread for a given
+                    // amount of microseconds. The goal is to avoid long blocks
+                    // due to the lack of packets received.
+                    TimedOut => continue,
+                    _ => {
+                        return Err(ScanError::Io(format!(
+                            "Failed to receive ARP requests ({})",
+                            error
+                        )));
+                    }
+                };
+            }
+        };
+        packet_count += 1;
+
+        let (sender_ip, sender_mac, is_reply, is_gratuitous) =
+            match parse_response_frame(arp_buffer, &options) {
+                ParsedFrame::Response(sender_ip, sender_mac, is_reply, is_gratuitous) => {
+                    (sender_ip, sender_mac, is_reply, is_gratuitous)
+                }
+                ParsedFrame::Filtered(ethertype) => {
+                    filtered_count += 1;
+                    if options.is_frame_logged() {
+                        eprintln!("[vv] Filtered frame with EtherType 0x{:04x}", ethertype);
+                    }
+                    continue;
+                }
+                ParsedFrame::Ignored(reason) => {
+                    if options.is_frame_logged() {
+                        eprintln!("[vv] Ignored {}", reason);
+                    }
+                    continue;
+                }
+                ParsedFrame::Malformed(reason) => {
+                    malformed_count += 1;
+                    if options.is_frame_logged() {
+                        eprintln!("[vv] Ignored malformed {}", reason);
+                    }
+                    continue;
+                }
+            };
+        arp_count += 1;
+
+        // The frames sent by the scan itself (requests & decoys) may be seen
+        // by the receiver, whatever their operation they are dropped, as well
+        // as the replies addressed to the spoofed sources of decoys.
+        if tracker.is_source_mac(sender_mac) {
+            if options.is_frame_logged() {
+                eprintln!("[vv] Ignored frame sent by the scan from {}", sender_ip);
+            }
+            continue;
+        }
+        let is_decoy_frame = options.decoy_count > 0
+            && match sender_ip {
+                IpAddr::V4(sender_ipv4) => tracker.is_decoy_ip(sender_ipv4),
+                IpAddr::V6(_) => false,
+            };
+        let is_decoy_reply = options.decoy_count > 0
+            && is_reply
+            && read_arp_target_ipv4(arp_buffer).is_some_and(|ipv4| tracker.is_decoy_ip(ipv4));
+        if is_decoy_frame || is_decoy_reply {
+            if options.is_frame_logged() {
+                eprintln!(
+                    "[vv] Ignored {} from {} (decoy)",
+                    describe_response(is_reply, sender_ip),
+                    sender_ip
+                );
+            }
+            continue;
+        }
+
+        // Extract the details and add the essential fields in the discover
+        // map. Please note that results are grouped by IP & MAC address pair -
+        // which means that a MAC change will appear as two separete records in
+        // the result table, while later responses of a host (e.g. to retried
+        // requests) update its single record and keep the first latency.
+        let discover_key = (sender_ip, sender_mac);
+        let is_new_target = !discover_map.contains_key(&discover_key);
+        let response_ms = tracker.record_response(sender_ip).or_else(|| {
+            discover_map
+                .get(&discover_key)
+                .and_then(|detail| detail.response_ms)
+        });
+
+        // Gratuitous ARPs are counted apart, the announcing host is still
+        // recorded as any other discovered target.
+        if is_gratuitous {
+            gratuitous_count += 1;
+        }
+
+        // All senders are counted as seen hosts, but only the ones matching
+        // the requested MAC prefixes are reported in the results.
+        seen_hosts.insert(sender_ip);
+        if !options.matches_mac_prefix(sender_mac) {
+            if options.is_frame_logged() {
+                eprintln!(
+                    "[vv] Ignored {} from {} at {} (MAC prefix not requested)",
+                    describe_response(is_reply, sender_ip),
+                    sender_ip,
+                    sender_mac
+                );
+            }
+            continue;
+        }
+
+        if options.is_frame_logged() {
+            eprintln!(
+                "[vv] Received {} from {} at {}",
+                describe_response(is_reply, sender_ip),
+                sender_ip,
+                sender_mac
+            );
+        }
+        if options.is_host_logged() && is_new_target {
+            eprintln!("[v] Discovered {} at {}", sender_ip, sender_mac);
+        }
+
+        // When only the first reply is expected, both the response thread
+        // and the main thread (sending requests or waiting for the timeout)
+        // are stopped right away.
+        if options.stop_on_first && is_reply {
+            timed_out.store(true, Ordering::Relaxed);
+            has_reached_timeout.store(true, Ordering::Relaxed);
+        }
+
+        let (sender_ipv4, sender_ipv6) = match sender_ip {
+            IpAddr::V4(sender_ipv4) => (sender_ipv4, None),
+            IpAddr::V6(sender_ipv6) => (Ipv4Addr::UNSPECIFIED, Some(sender_ipv6)),
+        };
+
+        // Duplicates are only tracked for ARP responses.
+        if sender_ipv6.is_none() {
+            for duplicate in find_duplicates(&discover_map, sender_ipv4, sender_mac) {
+                if !duplicates.contains(&duplicate) {
+                    duplicates.push(duplicate);
+                }
+            }
+        }
+
+        let mut target_detail = TargetDetails {
+            ipv4: sender_ipv4,
+            ipv6: sender_ipv6,
+            mac: sender_mac,
+            hostname: None,
+            vendor: None,
+            last_seen: SystemTime::now(),
+            response_ms,
+            reply_count: count_replies(&discover_map, discover_key),
+            interface: None,
+            source: None,
+            open_ports: vec![],
+            sys_name: None,
+            stale: false,
+            proxy_arp: false,
+            known: options.is_known_mac(sender_mac),
+        };
+        // With '--only-new-macs', known hosts are still recorded but neither
+        // streamed nor notified (they are dropped from the final results).
+        let is_reported =
+            is_new_target && !(options.only_new_macs && target_detail.known == Some(true));
+
+        // Streamed targets are enhanced right away, since they will be
+        // written before the end of the scan. Only the first response of
+        // a given IP & MAC address pair will be written on the stream, the
+        // network enhancements are left to the stream worker so that frames
+        // keep being received meanwhile.
+        if let Some(stream_worker) = &stream_worker {
+            if is_reported {
+                target_detail.vendor = vendor_list.lookup(&sender_mac);
+                stream_worker.stream(target_detail.clone());
+            }
+        }
+
+        // Webhook notifications are sent as soon as a host is discovered, the
+        // slower enhancements (DNS, ports, ...) are not waited for.
+        if let Some(webhook_notifier) = &webhook_notifier {
+            if is_reported {
+                let mut notified_detail = target_detail.clone();
+                if notified_detail.vendor.is_none() {
+                    notified_detail.vendor = vendor_list.lookup(&sender_mac);
+                }
+                webhook_notifier.notify(
+                    sender_ip.to_string(),
+                    utils::export_target_to_ndjson(&notified_detail)?,
+                );
+            }
+        }
+
+        tracker.publish_target(&target_detail);
+        discover_map.insert(discover_key, target_detail);
+    }
+    if let Some(webhook_notifier) = webhook_notifier {
+        webhook_notifier.finish();
+    }
+
+    // For each target found, enhance each item with additional results
+    // results such as the hostname & MAC vendor. Streamed targets were
+    // already enhanced by the stream worker.
+    let is_streamed = stream_worker.is_some();
+    if let Some(stream_worker) = stream_worker {
+        let (streamed_targets, stream_dns_timeout_count) = stream_worker.finish()?;
+        dns_timeout_count += stream_dns_timeout_count;
+        for streamed_target in streamed_targets {
+            let discover_key = (streamed_target.ip_address(), streamed_target.mac);
+            if let Some(target_detail) = discover_map.get_mut(&discover_key) {
+                target_detail.vendor = streamed_target.vendor;
+                target_detail.hostname = streamed_target.hostname;
+                target_detail.open_ports = streamed_target.open_ports;
+                target_detail.sys_name = streamed_target.sys_name;
+            }
+        }
+    }
+    let mut target_details: Vec<TargetDetails> = discover_map.into_values().collect();
+    if !is_streamed {
+        dns_timeout_count += enhance_target_details(&mut target_details, &options, vendor_list)?;
+    }
+
+    // Vendors are counted as unknown only when a vendor database is available,
+    // the cached lookups also cover the streamed targets.
+    let unknown_vendor_count = match vendor_list.has_vendor_db() {
+        true => target_details
+            .iter()
+            .filter(|detail| vendor_list.lookup(&detail.mac).is_none())
+            .count(),
+        false => 0,
+    };
+
+    // The response summary can be used to display analytics related to the
+    // performed ARP scans (packet counts, timings, ...)
+    let response_summary = ResponseSummary {
+        packet_count,
+        arp_count,
+        duration_ms: start_recording.elapsed().as_millis(),
+        duplicates,
+        effective_timeout_ms: None,
+        dns_timeout_count,
+        gratuitous_count,
+        malformed_count,
+        filtered_count,
+        seen_count: seen_hosts.len(),
+        unknown_vendor_count,
+        send_errors: 0,
+        start_time,
+        end_time: SystemTime::now(),
+    };
+    Ok((response_summary, target_details))
+}
+
+/**
+ * Describe a received response for the verbose logs. Any ARP packet is
+ * considered as a reply in passive mode, while neighbor advertisements are the
+ * only accepted IPv6 responses.
+ */
+fn describe_response(is_reply: bool, sender_ip: IpAddr) -> &'static str {
+    match (sender_ip, is_reply) {
+        (IpAddr::V6(_), _) => "neighbor advertisement",
+        (IpAddr::V4(_), true) => "ARP reply",
+        (IpAddr::V4(_), false) => "ARP request",
+    }
+}
+
+/**
+ * Count the responses received from a sender (IP & MAC address pair),
+ * including the current one. A host answering on every retry pass is
+ * consistently present, while a lower count reveals an intermittent host.
+ */
+fn count_replies(
+    discover_map: &HashMap<(IpAddr, MacAddr), TargetDetails>,
+    discover_key: (IpAddr, MacAddr),
+) -> usize {
+    discover_map
+        .get(&discover_key)
+        .map_or(0, |detail| detail.reply_count)
+        + 1
+}
+
+/**
+ * Outcome of the parsing of a received frame: a response (sender IP & MAC,
+ * reply & gratuitous flags), a frame dropped by the EtherType pre-filter, a
+ * valid frame that is not a response, or a malformed frame. The reasons are
+ * only used in the frame logs.
+ */
+#[derive(Debug, PartialEq)]
+enum ParsedFrame {
+    Response(IpAddr, MacAddr, bool, bool),
+    Filtered(u16),
+    Ignored(String),
+    Malformed(String),
+}
+
+/**
+ * The EtherType of the expected responses: neighbor advertisements (IPv6)
+ * for neighbor scans, ARP replies otherwise.
+ */
+pub fn find_response_ethertype(options: &ScanOptions) -> EtherType {
+    match options.ipv6 {
+        true => EtherTypes::Ipv6,
+        false => EtherTypes::Arp,
+    }
+}
+
+/**
+ * Parse a raw frame received on the datalink channel. Every header is read
+ * through bounds-checked packet views, truncated frames are reported as
+ * malformed instead of being indexed past their end.
+ */
+fn parse_response_frame(frame: &[u8], options: &ScanOptions) -> ParsedFrame {
+    let expected_ethertype = find_response_ethertype(options);
+    if let Some(ethertype) = read_inner_ethertype(frame) {
+        if ethertype != expected_ethertype.0 {
+            return ParsedFrame::Filtered(ethertype);
+        }
+    }
+
+    let ethernet_packet = match EthernetPacket::new(frame) {
+        Some(packet) => packet,
+        None => return ParsedFrame::Malformed(format!("truncated frame ({} bytes)", frame.len())),
+    };
+
+    // Neighbor advertisements are the IPv6 equivalent of ARP replies, any
+    // advertisement is considered as a reply.
+    if options.ipv6 {
+        return match ndp::parse_neighbor_advertisement(&ethernet_packet) {
+            Some((sender_ipv6, sender_mac)) => {
+                ParsedFrame::Response(IpAddr::V6(sender_ipv6), sender_mac, true, false)
+            }
+            None => ParsedFrame::Ignored(format!(
+                "frame from {} with EtherType 0x{:04x} (not a neighbor advertisement)",
+                ethernet_packet.get_source(),
+                ethernet_packet.get_ethertype().0
+            )),
+        };
+    }
+
+    let arp_offset = match find_arp_payload_offset(&ethernet_packet) {
+        Some(offset) => offset,
+        None if ethernet_packet.get_ethertype() == EtherTypes::Vlan
+            && VlanPacket::new(ethernet_packet.payload()).is_none() =>
+        {
+            return ParsedFrame::Malformed(format!(
+                "VLAN frame from {} (truncated 802.1Q header)",
+                ethernet_packet.get_source()
+            ));
+        }
+        None => {
+            return ParsedFrame::Ignored(format!(
+                "frame from {} with EtherType 0x{:04x} (not ARP)",
+                ethernet_packet.get_source(),
+                ethernet_packet.get_ethertype().0
+            ));
+        }
+    };
+    let arp = match frame.get(arp_offset..).and_then(ArpPacket::new) {
+        Some(arp) => arp,
+        None => {
+            return ParsedFrame::Malformed(format!(
+                "ARP frame from {} (truncated ARP packet)",
+                ethernet_packet.get_source()
+            ));
+        }
+    };
+
+    // In passive mode, any ARP packet is considered as a reply.
+    let is_reply = options.passive || arp.get_operation() == ArpOperations::Reply;
+    ParsedFrame::Response(
+        IpAddr::V4(arp.get_sender_proto_addr()),
+        arp.get_sender_hw_addr(),
+        is_reply,
+        is_gratuitous_arp(&arp),
+    )
+}
+
+/**
+ * Read the EtherType of a received frame directly from its bytes, looking
+ * through a single 802.1Q tag. This pre-filter runs before any packet view is
+ * built, so that the non-ARP traffic of busy segments is dropped as early as
+ * possible. On Linux, the same filter is already run by the kernel (see the
+ * 'filter' module), this check covers the other platforms. Truncated headers
+ * return no EtherType, and are left to the regular parsing.
+ */
+fn read_inner_ethertype(frame: &[u8]) -> Option<u16> {
+    let read_u16 = |offset: usize| -> Option<u16> {
+        let bytes = frame.get(offset..offset + 2)?;
+        Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+    };
+
+    match read_u16(12)? {
+        ethertype if ethertype == EtherTypes::Vlan.0 => read_u16(16),
+        ethertype => Some(ethertype),
+    }
+}
+
+/**
+ * Read the target IPv4 address of an ARP frame, which is not needed by the
+ * regular response parsing (only used to spot the replies to decoys).
+ */
+fn read_arp_target_ipv4(frame: &[u8]) -> Option<Ipv4Addr> {
+    let ethernet_packet = EthernetPacket::new(frame)?;
+    let arp_offset = find_arp_payload_offset(&ethernet_packet)?;
+    let arp = frame.get(arp_offset..).and_then(ArpPacket::new)?;
+    Some(arp.get_target_proto_addr())
+}
+
+/**
+ * Find where the ARP payload starts in a received Ethernet frame. Frames may
+ * be tagged with an 802.1Q header (VLAN), which shifts the ARP payload. Other
+ * frames (non-ARP) will return no offset and should be ignored.
+ */
+fn find_arp_payload_offset(ethernet_packet: &EthernetPacket) -> Option<usize> {
+    match ethernet_packet.get_ethertype() {
+        EtherTypes::Arp => Some(EthernetPacket::minimum_packet_size()),
+        EtherTypes::Vlan => {
+            let vlan_packet = VlanPacket::new(ethernet_packet.payload())?;
+            match vlan_packet.get_ethertype() {
+                EtherTypes::Arp => {
+                    Some(EthernetPacket::minimum_packet_size() + VlanPacket::minimum_packet_size())
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/**
+ * Check if an ARP packet is a gratuitous ARP, where a host announces its own
+ * IPv4 address (the sender & target IPv4 addresses are the same). These can be
+ * requests or replies, and are periodically broadcasted by many devices.
+ */
+fn is_gratuitous_arp(arp_packet: &ArpPacket) -> bool {
+    let sender_ipv4 = arp_packet.get_sender_proto_addr();
+    !sender_ipv4.is_unspecified() && sender_ipv4 == arp_packet.get_target_proto_addr()
+}
+
+/**
+ * Find all duplicates caused by a new ARP response, compared to the targets
+ * already discovered. The same IPv4 address may have been seen with another
+ * MAC address, or the same MAC address may have been seen on another IPv4.
+ */
+fn find_duplicates(
+    discover_map: &HashMap<(IpAddr, MacAddr), TargetDetails>,
+    sender_ipv4: Ipv4Addr,
+    sender_mac: MacAddr,
+) -> Vec<DuplicateDetails> {
+    discover_map
+        .values()
+        .filter(|detail| {
+            let is_conflicting_mac = detail.ipv4 == sender_ipv4 && detail.mac != sender_mac;
+            let is_shared_mac = detail.ipv4 != sender_ipv4 && detail.mac == sender_mac;
+            is_conflicting_mac || is_shared_mac
+        })
+        .map(|detail| DuplicateDetails::new((detail.ipv4, detail.mac), (sender_ipv4, sender_mac)))
+        .collect()
+}
+
+/**
+ * Enhance discovered targets with additional details, such as the hostname
+ * (if enabled in the scan options) and the MAC vendor (if a database exists).
+ * Returns the number of hostname lookups that timed out.
+ */
+pub fn enhance_target_details(
+    target_details: &mut [TargetDetails],
+    options: &ScanOptions,
+    vendor_list: &mut Vendor,
+) -> Result<usize, ScanError> {
+    if vendor_list.has_vendor_db() {
+        for target_detail in target_details.iter_mut() {
+            target_detail.vendor = vendor_list.lookup(&target_detail.mac);
+        }
+    }
+
+    enhance_network_details(target_details, options)
+}
+
+/**
+ * Enhance targets with the details queried on the network (open ports, SNMP
+ * system name & hostname), which may take a while for unresponsive hosts.
+ * Returns the number of hostname lookups that timed out.
+ */
+fn enhance_network_details(
+    target_details: &mut [TargetDetails],
+    options: &ScanOptions,
+) -> Result<usize, ScanError> {
+    if !options.probe_ports.is_empty() {
+        probe_open_ports(
+            target_details,
+            &options.probe_ports,
+            options.probe_timeout_ms,
+        )?;
+    }
+
+    if let Some(snmp_community) = &options.snmp_community {
+        query_sys_names(target_details, snmp_community)?;
+    }
+
+    match options.resolve_hostname {
+        true => resolve_hostnames(target_details, options.dns_timeout_ms, options.dns_threads),
+        false => Ok(0),
+    }
+}
+
+/**
+ * Query the SNMP system name of all targets, with a bounded number of
+ * concurrent queries. Targets without SNMP agent (or with another community)
+ * keep an empty system name.
+ */
+fn query_sys_names(target_details: &mut [TargetDetails], community: &str) -> Result<(), ScanError> {
+    let target_ip_list: Vec<IpAddr> = target_details
+        .iter()
+        .map(|detail| detail.ip_address())
+        .collect();
+
+    let sys_names = utils::parallel_map(&target_ip_list, SNMP_THREADS, |ip| {
+        snmp::query_sys_name(*ip, community, SNMP_TIMEOUT_MS)
+    })?;
+    for (target_detail, sys_name) in target_details.iter_mut().zip(sys_names) {
+        target_detail.sys_name = sys_name;
+    }
+    Ok(())
+}
+
+/**
+ * Probe the given TCP ports on all targets with a connect attempt, with a
+ * bounded number of concurrent probes. A port is reported as open when the
+ * connection is accepted before the timeout, closed and filtered ports are
+ * not reported. Open ports are kept sorted on each target.
+ */
+fn probe_open_ports(
+    target_details: &mut [TargetDetails],
+    ports: &[u16],
+    timeout_ms: u64,
+) -> Result<(), ScanError> {
+    let probe_list: Vec<(usize, SocketAddr)> = target_details
+        .iter()
+        .enumerate()
+        .flat_map(|(target_index, detail)| {
+            ports
+                .iter()
+                .map(move |port| (target_index, SocketAddr::new(detail.ip_address(), *port)))
+        })
+        .collect();
+    let timeout = Duration::from_millis(timeout_ms);
+
+    let probe_results =
+        utils::parallel_map(&probe_list, PORT_PROBE_THREADS, |(_, socket_addr)| {
+            TcpStream::connect_timeout(socket_addr, timeout).is_ok()
+        })?;
+
+    let mut open_ports: Vec<Vec<u16>> = vec![vec![]; target_details.len()];
+    for ((target_index, socket_addr), is_open) in probe_list.iter().zip(probe_results) {
+        if is_open {
+            open_ports[*target_index].push(socket_addr.port());
+        }
+    }
+    for (target_detail, mut target_ports) in target_details.iter_mut().zip(open_ports) {
+        target_ports.sort_unstable();
+        target_detail.open_ports = target_ports;
+    }
+    Ok(())
+}
+
+/**
+ * Resolve the hostnames of all given targets, with a bounded number of
+ * concurrent lookups. Each lookup is limited by a timeout, so that a few
+ * unresponsive PTR requests do not stall the whole result phase. Targets with
+ * a failed or timed-out lookup keep an empty hostname. Returns the number of
+ * lookups that timed out.
+ */
+fn resolve_hostnames(
+    target_details: &mut [TargetDetails],
+    timeout_ms: u64,
+    thread_count: usize,
+) -> Result<usize, ScanError> {
+    let target_ip_list: Vec<IpAddr> = target_details
+        .iter()
+        .map(|detail| detail.ip_address())
+        .collect();
+
+    let pending_max = thread_count.max(DNS_PENDING_MAX);
+    let lookup_results = utils::parallel_map(&target_ip_list, thread_count, |ip| {
+        let ip = *ip;
+        run_lookup_with_timeout(
+            move || find_hostname(ip),
+            timeout_ms,
+            &PENDING_LOOKUPS,
+            pending_max,
+        )
+    })?;
+
+    let mut timed_out_count = 0;
+    for (target_detail, lookup_result) in target_details.iter_mut().zip(lookup_results) {
+        target_detail.hostname = match lookup_result {
+            Ok(hostname) => hostname,
+            Err(RecvTimeoutError::Timeout) => {
+                timed_out_count += 1;
+                None
+            }
+            Err(RecvTimeoutError::Disconnected) => None,
+        };
+    }
+    Ok(timed_out_count)
+}
+
+/**
+ * Run a hostname lookup, giving up after the timeout. The system resolver can
+ * not be cancelled, a timed-out lookup is left running in a detached thread
+ * and its late result is discarded. Detached lookups are counted until they
+ * end, so that an unresponsive resolver can not pile up threads: above the
+ * pending limit, the lookup is not started and is reported as timed out.
+ */
+fn run_lookup_with_timeout<F>(
+    lookup: F,
+    timeout_ms: u64,
+    pending_lookups: &'static AtomicUsize,
+    pending_max: usize,
+) -> Result<Option<String>, RecvTimeoutError>
+where
+    F: FnOnce() -> Option<String> + Send + 'static,
+{
+    let reserved_count =
+        pending_lookups.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |pending_count| {
+            (pending_count < pending_max).then_some(pending_count + 1)
+        });
+    if reserved_count.is_err() {
+        return Err(RecvTimeoutError::Timeout);
+    }
+
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        // The receiver may be gone if the lookup already timed out
+        let _ = sender.send(lookup());
+        pending_lookups.fetch_sub(1, Ordering::Relaxed);
+    });
+
+    receiver.recv_timeout(Duration::from_millis(timeout_ms))
+}
+
+/**
+ * Enhances & writes the streamed targets on a separate thread, so that the
+ * network enhancements (reverse DNS, port probes, SNMP) never stop the
+ * response thread from reading frames. Streamed targets are given back once
+ * the stream is finished, to complete the final results.
+ */
+struct StreamWorker {
+    sender: Sender<TargetDetails>,
+    worker: JoinHandle<Result<(Vec<TargetDetails>, usize), ScanError>>,
+}
+
+impl StreamWorker {
+    fn new(mut writer: Box<dyn Write + Send>, options: Arc<ScanOptions>) -> Self {
+        let (sender, receiver) = mpsc::channel::<TargetDetails>();
+
+        let worker = thread::spawn(move || {
+            let mut streamed_targets = vec![];
+            let mut dns_timeout_count = 0;
+            for mut target_detail in receiver {
+                dns_timeout_count +=
+                    enhance_network_details(std::slice::from_mut(&mut target_detail), &options)?;
+                write_stream_line(
+                    &mut writer,
+                    &utils::export_target_to_ndjson(&target_detail)?,
+                )?;
+                streamed_targets.push(target_detail);
+            }
+            Ok((streamed_targets, dns_timeout_count))
+        });
+
+        StreamWorker { sender, worker }
+    }
+
+    /**
+     * Queue a discovered target, a failed worker (e.g. on a closed pipe) is
+     * only reported when the stream is finished.
+     */
+    fn stream(&self, target_detail: TargetDetails) {
+        let _ = self.sender.send(target_detail);
+    }
+
+    /**
+     * Stop accepting targets, and wait until all queued targets are written.
+     * Returns the streamed targets with the number of timed-out lookups.
+     */
+    fn finish(self) -> Result<(Vec<TargetDetails>, usize), ScanError> {
+        drop(self.sender);
+        self.worker
+            .join()
+            .unwrap_or_else(|_| Err(ScanError::Io("Result stream thread failed".to_string())))
+    }
+}
+
+/**
+ * Write a single line on the result stream and flush it immediately, so that
+ * consumers (pipes, other tools, ...) receive each result in real time.
+ */
+fn write_stream_line(writer: &mut Box<dyn Write + Send>, line: &str) -> Result<(), ScanError> {
+    writeln!(writer, "{}", line)
+        .and_then(|_| writer.flush())
+        .map_err(|err| ScanError::Io(format!("Could not write streamed result ({})", err)))
+}
+
+/**
+ * Find the local hostname linked to an IP address. This will perform a
+ * reverse DNS request in the local network to find the IP hostname.
+ */
+fn find_hostname(ip: IpAddr) -> Option<String> {
+    match lookup_addr(&ip) {
+        Ok(hostname) => {
+            // The 'lookup_addr' function returns an IP address if no hostname
+            // was found. If this is the case, we prefer switching to None.
+            if hostname.parse::<IpAddr>().is_ok() {
+                return None;
+            }
+
+            Some(hostname)
+        }
+        Err(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::args::build_args;
+
+    use ipnetwork::{Ipv4Network, Ipv6Network};
+    use pnet::packet::arp::ArpHardwareType;
+    use pnet::packet::ethernet::EtherType;
+    use std::env;
+    use std::str::FromStr;
+
+    const IFF_UP: u32 = 1;
+
+    fn build_target(ipv4: Ipv4Addr) -> TargetDetails {
+        TargetDetails::test_default(ipv4, MacAddr::zero())
+    }
+
+    #[test]
+    fn should_merge_interface_results() {
+        let build_summary = |packet_count: usize, duration_ms: u128| ResponseSummary {
+            packet_count,
+            arp_count: packet_count,
+            duration_ms,
+            duplicates: vec![],
+            effective_timeout_ms: None,
+            dns_timeout_count: 0,
+            gratuitous_count: 0,
+            malformed_count: 0,
+            filtered_count: 0,
+            seen_count: 0,
+            unknown_vendor_count: 0,
+            send_errors: 0,
+            start_time: UNIX_EPOCH,
+            end_time: UNIX_EPOCH,
+        };
+        let interface_results = vec![
+            (
+                "eth0".to_string(),
+                build_summary(4, 1000),
+                vec![
+                    build_target(Ipv4Addr::new(192, 168, 1, 1)),
+                    build_target(Ipv4Addr::new(10, 0, 0, 1)),
+                ],
+            ),
+            (
+                "eth1".to_string(),
+                build_summary(2, 500),
+                vec![
+                    build_target(Ipv4Addr::new(10, 0, 0, 1)),
+                    build_target(Ipv4Addr::new(10, 0, 0, 2)),
+                ],
+            ),
+        ];
+
+        let (response_summary, target_details) =
+            merge_interface_results(interface_results).unwrap();
+
+        assert_eq!(response_summary.packet_count, 6);
+        assert_eq!(response_summary.duration_ms, 1500);
+        let interfaces: Vec<Option<&str>> = target_details
+            .iter()
+            .map(|detail| detail.interface.as_deref())
+            .collect();
+        assert_eq!(
+            interfaces,
+            vec![Some("eth0"), Some("eth0,eth1"), Some("eth1")]
+        );
+        assert!(merge_interface_results(vec![]).is_none());
+    }
+
+    #[test]
+    fn should_count_merged_hosts_once() {
+        let build_summary = || ResponseSummary {
+            packet_count: 1,
+            arp_count: 1,
+            duration_ms: 500,
+            duplicates: vec![],
+            effective_timeout_ms: None,
+            dns_timeout_count: 0,
+            gratuitous_count: 0,
+            malformed_count: 0,
+            filtered_count: 0,
+            seen_count: 1,
+            unknown_vendor_count: 1,
+            send_errors: 0,
+            start_time: UNIX_EPOCH,
+            end_time: UNIX_EPOCH,
+        };
+        let interface_results = vec![
+            (
+                "eth0".to_string(),
+                build_summary(),
+                vec![build_target(Ipv4Addr::new(10, 0, 0, 1))],
+            ),
+            (
+                "eth1".to_string(),
+                build_summary(),
+                vec![build_target(Ipv4Addr::new(10, 0, 0, 1))],
+            ),
+        ];
+
+        let (response_summary, target_details) =
+            merge_interface_results(interface_results).unwrap();
+
+        assert_eq!(target_details.len(), 1);
+        assert_eq!(response_summary.packet_count, 2);
+        assert_eq!(response_summary.seen_count, 1);
+        assert_eq!(response_summary.unknown_vendor_count, 1);
+    }
+
+    #[test]
+    fn should_find_all_hosts_on_first_round() {
+        let target_details = vec![build_target(Ipv4Addr::new(192, 168, 1, 1))];
+
+        let watch_round = WatchRound::new(0, &[], &target_details);
+
+        assert_eq!(watch_round.index, 0);
+        assert_eq!(watch_round.appeared, vec![Ipv4Addr::new(192, 168, 1, 1)]);
+        assert!(watch_round.disappeared.is_empty());
+    }
+
+    #[test]
+    fn should_find_changed_hosts_between_rounds() {
+        let previous_hosts = vec![Ipv4Addr::new(192, 168, 1, 1), Ipv4Addr::new(192, 168, 1, 2)];
+        let target_details = vec![
+            build_target(Ipv4Addr::new(192, 168, 1, 2)),
+            build_target(Ipv4Addr::new(192, 168, 1, 3)),
+        ];
+
+        let watch_round = WatchRound::new(3, &previous_hosts, &target_details);
+
+        assert_eq!(watch_round.appeared, vec![Ipv4Addr::new(192, 168, 1, 3)]);
+        assert_eq!(watch_round.disappeared, vec![Ipv4Addr::new(192, 168, 1, 1)]);
+    }
+
+    #[test]
+    fn should_not_find_duplicates_for_same_response() {
+        let mut discover_map: HashMap<(IpAddr, MacAddr), TargetDetails> = HashMap::new();
+        let target = build_target(Ipv4Addr::new(192, 168, 1, 1));
+        discover_map.insert((IpAddr::V4(target.ipv4), target.mac), target);
+
+        let duplicates = find_duplicates(
+            &discover_map,
+            Ipv4Addr::new(192, 168, 1, 1),
+            MacAddr::zero(),
+        );
+
+        assert!(duplicates.is_empty());
+    }
+
+    #[test]
+    fn should_find_shared_mac_duplicate() {
+        let mut discover_map: HashMap<(IpAddr, MacAddr), TargetDetails> = HashMap::new();
+        let target = build_target(Ipv4Addr::new(192, 168, 1, 5));
+        discover_map.insert((IpAddr::V4(target.ipv4), target.mac), target);
+
+        let duplicates = find_duplicates(
+            &discover_map,
+            Ipv4Addr::new(192, 168, 1, 2),
+            MacAddr::zero(),
+        );
+
+        assert_eq!(
+            duplicates,
+            vec![DuplicateDetails {
+                ipv4: Ipv4Addr::new(192, 168, 1, 2),
+                mac: MacAddr::zero(),
+                conflicting_ipv4: Ipv4Addr::new(192, 168, 1, 5),
+                conflicting_mac: MacAddr::zero(),
+            }]
+        );
+        assert!(duplicates[0].is_shared_mac());
+    }
+
+    #[test]
+    fn should_find_conflicting_mac_duplicate() {
+        let mut discover_map: HashMap<(IpAddr, MacAddr), TargetDetails> = HashMap::new();
+        let target = build_target(Ipv4Addr::new(192, 168, 1, 1));
+        discover_map.insert((IpAddr::V4(target.ipv4), target.mac), target);
+
+        let spoofed_mac = MacAddr::new(0x40, 0x55, 0x82, 0xc3, 0xe5, 0x5b);
+        let duplicates = find_duplicates(&discover_map, Ipv4Addr::new(192, 168, 1, 1), spoofed_mac);
+
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].conflicting_mac, spoofed_mac);
+        assert!(!duplicates[0].is_shared_mac());
+    }
+
+    fn build_ethernet_frame(ethertype: EtherType, payload: &[u8]) -> Vec<u8> {
+        let mut ethernet_buffer = vec![0u8; EthernetPacket::minimum_packet_size() + payload.len()];
+        let mut ethernet_packet = MutableEthernetPacket::new(&mut ethernet_buffer).unwrap();
+        ethernet_packet.set_ethertype(ethertype);
+        ethernet_packet.set_payload(payload);
+
+        ethernet_buffer
+    }
+
+    fn build_interface(name: &str, index: u32, flags: u32) -> NetworkInterface {
+        NetworkInterface {
+            name: name.to_string(),
+            description: String::new(),
+            index,
+            mac: Some(MacAddr::zero()),
+            ips: vec![],
+            flags,
+        }
+    }
+
+    #[test]
+    fn should_flag_proxy_arp_targets() {
+        let gateway_mac = MacAddr::new(0x40, 0x55, 0x82, 0xc3, 0xe5, 0x01);
+        let mut target_details: Vec<TargetDetails> = [1, 20, 30]
+            .iter()
+            .map(|last_octet| build_target(Ipv4Addr::new(192, 168, 1, *last_octet)))
+            .collect();
+        target_details[0].mac = gateway_mac;
+        target_details[2].mac = gateway_mac;
+
+        flag_proxy_arp_targets(&mut target_details, None);
+        assert!(target_details.iter().all(|detail| !detail.proxy_arp));
+
+        flag_proxy_arp_targets(&mut target_details, Some(Ipv4Addr::new(192, 168, 1, 1)));
+        let flags: Vec<bool> = target_details
+            .iter()
+            .map(|detail| detail.proxy_arp)
+            .collect();
+        assert_eq!(flags, vec![false, false, true]);
+    }
+
+    #[test]
+    fn should_parse_default_gateway() {
+        let route_table =
+            "Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT
+eth0\t00000000\t0101A8C0\t0003\t0\t0\t100\t00000000\t0\t0\t0
+eth0\t0001A8C0\t00000000\t0001\t0\t0\t100\t00FFFFFF\t0\t0\t0
+wlan0\t00000000\t0100000A\t0003\t0\t0\t600\t00000000\t0\t0\t0
+";
+
+        if cfg!(target_endian = "little") {
+            assert_eq!(
+                parse_default_gateway(route_table, "eth0"),
+                Some(Ipv4Addr::new(192, 168, 1, 1))
+            );
+            assert_eq!(
+                parse_default_gateway(route_table, "wlan0"),
+                Some(Ipv4Addr::new(10, 0, 0, 1))
+            );
+        }
+        assert_eq!(parse_default_gateway(route_table, "eth1"), None);
+    }
+
+    #[test]
+    fn should_parse_link_value() {
+        assert_eq!(parse_link_value("1500\n"), Some(1500));
+        assert_eq!(parse_link_value("1000\n"), Some(1000));
+        assert_eq!(parse_link_value("-1\n"), None);
+        assert_eq!(parse_link_value("4294967295\n"), None);
+        assert_eq!(parse_link_value(""), None);
+    }
+
+    #[test]
+    fn should_find_interface_by_index() {
+        let interfaces = vec![
+            build_interface("eth0", 2, IFF_UP),
+            build_interface("eth1", 3, IFF_UP),
+        ];
+
+        let interface = find_interface_by_index(&interfaces, 3);
+
+        assert_eq!(
+            interface.map(|interface| interface.name.clone()),
+            Some("eth1".to_string())
+        );
+    }
+
+    #[test]
+    fn should_not_find_down_interface_by_index() {
+        let interfaces = vec![build_interface("eth0", 2, 0)];
+
+        assert!(find_interface_by_index(&interfaces, 2).is_none());
+        assert!(find_interface_by_index(&interfaces, 7).is_none());
+    }
+
+    #[test]
+    fn should_fail_configuration_on_unknown_index() {
+        let interfaces = vec![build_interface("eth0", 2, IFF_UP)];
+        let matches = build_args().get_matches_from(vec!["arp-scan", "--interface-index", "7"]);
+        let options = ScanOptions::new(&matches).unwrap();
+
+        let configuration = compute_network_configuration(&interfaces, &options);
+
+        assert_eq!(
+            configuration.err(),
+            Some(ScanError::InterfaceNotFound(
+                "Could not find a network interface with index 7\n\
+                Use 'arp scan -l' to list available interfaces"
+                    .to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn should_find_interface_by_pattern() {
+        let mut wired_interface = build_interface("ens5", 3, IFF_UP);
+        wired_interface.ips = vec![IpNetwork::from_str("10.0.0.5/24").unwrap()];
+        let interfaces = vec![
+            build_interface("eth0", 2, IFF_UP),
+            wired_interface,
+            build_interface("wlan0", 4, IFF_UP),
+        ];
+
+        let interface = find_interface_by_pattern(&interfaces, &Regex::new("^e").unwrap());
+        let fallback_interface =
+            find_interface_by_pattern(&interfaces, &Regex::new("^wl").unwrap());
+
+        assert_eq!(interface.map(|interface| interface.index), Ok(3));
+        assert_eq!(fallback_interface.map(|interface| interface.index), Ok(4));
+        assert_eq!(
+            find_interface_by_pattern(&interfaces, &Regex::new("^br").unwrap()).err(),
+            Some(ScanError::InterfaceNotFound(
+                "No network interface name matches '^br'\n\
+                Use 'arp scan -l' to list available interfaces"
+                    .to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn should_fail_configuration_without_ipv4() {
+        let mut interface = build_interface("eth0", 2, IFF_UP);
+        interface.ips = vec![IpNetwork::from_str("fd00::8/64").unwrap()];
+        let interfaces = vec![interface];
+        let matches = build_args().get_matches_from(vec!["arp-scan", "-i", "eth0"]);
+        let forced_matches = build_args().get_matches_from(vec![
+            "arp-scan",
+            "-i",
+            "eth0",
+            "--source-ip",
+            "192.168.1.8",
+        ]);
+        let options = ScanOptions::new(&matches).unwrap();
+        let forced_options = ScanOptions::new(&forced_matches).unwrap();
+
+        assert_eq!(
+            compute_network_configuration(&interfaces, &options).err(),
+            Some(ScanError::InterfaceNotFound(
+                "Interface eth0 has no IPv4 address, ARP requires IPv4\n\
+                Use 'arp-scan -l' to pick another interface, or --ipv6 for a neighbor scan"
+                    .to_string()
+            ))
+        );
+        assert!(compute_network_configuration(&interfaces, &forced_options).is_ok());
+    }
+
+    #[test]
+    fn should_select_all_interface_subnets() {
+        let mut interface = build_interface("eth0", 2, IFF_UP);
+        interface.ips = vec![
+            IpNetwork::from_str("192.168.1.21/24").unwrap(),
+            IpNetwork::from_str("fd00::8/64").unwrap(),
+            IpNetwork::from_str("192.168.1.1/24").unwrap(),
+            IpNetwork::from_str("10.0.0.5/28").unwrap(),
+        ];
+        let options = ScanOptions::new(&build_args().get_matches_from(vec!["arp-scan"])).unwrap();
+        let primary_options =
+            ScanOptions::new(&build_args().get_matches_from(vec!["arp-scan", "--primary-only"]))
+                .unwrap();
+
+        let ip_networks = select_interface_networks(&interface, &options);
+        let primary_networks = select_interface_networks(&interface, &primary_options);
+
+        assert_eq!(ip_networks, vec![&interface.ips[0], &interface.ips[3]]);
+        assert_eq!(primary_networks, vec![&interface.ips[0]]);
+    }
+
+    #[test]
+    fn should_describe_small_networks() {
+        let point_to_point = IpNetwork::from_str("10.0.0.1/31").unwrap();
+        let single_host = IpNetwork::from_str("192.168.1.20/32").unwrap();
+        let other_host = IpNetwork::from_str("192.168.1.30/32").unwrap();
+        let subnet = IpNetwork::from_str("192.168.1.0/24").unwrap();
+
+        assert_eq!(
+            describe_small_networks(&[&point_to_point, &subnet]),
+            vec!["10.0.0.1/31 is a point-to-point network, both 10.0.0.0 and 10.0.0.1 will be requested"]
+        );
+        assert_eq!(
+            describe_small_networks(&[&single_host]),
+            vec!["192.168.1.20/32 is a single host, only 192.168.1.20 will be requested"]
+        );
+        assert!(describe_small_networks(&[&single_host, &other_host]).is_empty());
+    }
+
+    #[test]
+    fn should_fail_source_ip_without_ipv4() {
+        let interface = build_interface("eth0", 2, IFF_UP);
+
+        assert!(matches!(
+            find_source_ip(&interface, None),
+            Err(ScanError::InterfaceNotFound(_))
+        ));
+        assert_eq!(
+            find_source_ip(&interface, Some(Ipv4Addr::new(10, 0, 0, 1))),
+            Ok(Ipv4Addr::new(10, 0, 0, 1))
+        );
+    }
+
+    #[test]
+    fn should_prefer_forced_source_ip() {
+        let mut interface = build_interface("eth0", 2, IFF_UP);
+        interface.ips = vec![
+            IpNetwork::from_str("fe80::1/64").unwrap(),
+            IpNetwork::from_str("192.168.1.10/24").unwrap(),
+            IpNetwork::from_str("10.10.0.10/16").unwrap(),
+        ];
+        let secondary_ipv4 = Ipv4Addr::new(172, 16, 5, 1);
+
+        assert_eq!(
+            find_source_ip(&interface, None),
+            Ok(Ipv4Addr::new(192, 168, 1, 10))
+        );
+        assert_eq!(
+            find_source_ip(&interface, Some(secondary_ipv4)),
+            Ok(secondary_ipv4)
+        );
+        assert!(is_interface_subnet_ip(
+            &interface,
+            Ipv4Addr::new(10, 10, 42, 1)
+        ));
+        assert!(!is_interface_subnet_ip(&interface, secondary_ipv4));
+    }
+
+    #[test]
+    fn should_find_source_ipv6() {
+        let mut interface = build_interface("eth0", 2, IFF_UP);
+        interface.ips = vec![
+            IpNetwork::from_str("192.168.1.2/24").unwrap(),
+            IpNetwork::from_str("fe80::1/64").unwrap(),
+            IpNetwork::from_str("fd00::2/64").unwrap(),
+        ];
+        let on_link_network = IpNetwork::from_str("fd00::/120").unwrap();
+        let remote_network = IpNetwork::from_str("fd01::/120").unwrap();
+
+        assert_eq!(
+            find_source_ipv6(&interface, &[&on_link_network]),
+            Ok("fd00::2".parse().unwrap())
+        );
+        assert_eq!(
+            find_source_ipv6(&interface, &[&remote_network]),
+            Ok("fe80::1".parse().unwrap())
+        );
+
+        interface.ips.truncate(1);
+        assert!(matches!(
+            find_source_ipv6(&interface, &[&on_link_network]),
+            Err(ScanError::InterfaceNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn should_compute_percentiles() {
+        let samples: Vec<u128> = (1..=100).rev().collect();
+
+        assert_eq!(compute_percentile(&samples, 50.0), Some(50));
+        assert_eq!(compute_percentile(&samples, 99.0), Some(99));
+        assert_eq!(compute_percentile(&samples, 100.0), Some(100));
+        assert_eq!(compute_percentile(&samples, 0.0), Some(1));
+    }
+
+    #[test]
+    fn should_compute_percentile_on_few_samples() {
+        let samples: Vec<u128> = vec![12, 3, 250];
+
+        assert_eq!(compute_percentile(&samples, 99.0), Some(250));
+        assert_eq!(compute_percentile(&[], 99.0), None);
+    }
+
+    #[test]
+    fn should_compute_default_adaptive_window() {
+        let tracker = ScanTracker::new();
+
+        assert_eq!(tracker.compute_adaptive_window(2000), 2000);
+    }
+
+    #[test]
+    fn should_bound_adaptive_window() {
+        let tracker = ScanTracker::new();
+        tracker.rtt_samples.lock().unwrap().extend(vec![2, 5, 8]);
+        assert_eq!(tracker.compute_adaptive_window(2000), 100);
+
+        tracker.rtt_samples.lock().unwrap().extend(vec![120, 5000]);
+        assert_eq!(tracker.compute_adaptive_window(2000), 8000);
+    }
+
+    #[test]
+    fn should_record_rtt_once_per_request() {
+        let tracker = ScanTracker::new();
+        let target_ipv4 = Ipv4Addr::new(192, 168, 1, 1);
+
+        tracker.record_request(IpAddr::V4(target_ipv4));
+        thread::sleep(Duration::from_millis(2));
+        let first_latency = tracker.record_response(IpAddr::V4(target_ipv4));
+        let second_latency = tracker.record_response(IpAddr::V4(target_ipv4));
+
+        assert!(first_latency.is_some_and(|latency| latency >= 2.0));
+        assert_eq!(second_latency, None);
+        assert_eq!(tracker.rtt_samples.lock().unwrap().len(), 1);
+        assert!(tracker.last_response().is_some());
+    }
+
+    #[test]
+    fn should_keep_published_targets_after_panic() {
+        let tracker = ScanTracker::new();
+        tracker.start_round();
+
+        let thread_result = thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    tracker.publish_target(&build_target(Ipv4Addr::new(192, 168, 1, 10)));
+                    tracker.publish_target(&build_target(Ipv4Addr::new(192, 168, 1, 20)));
+                    tracker.publish_target(&build_target(Ipv4Addr::new(192, 168, 1, 10)));
+                    panic!("Unexpected frame");
+                })
+                .join()
+        });
+        let mut discovered_ips: Vec<Ipv4Addr> = tracker
+            .take_discovered_targets()
+            .iter()
+            .map(|detail| detail.ipv4)
+            .collect();
+        discovered_ips.sort();
+
+        assert!(thread_result.is_err());
+        assert_eq!(
+            discovered_ips,
+            vec![
+                Ipv4Addr::new(192, 168, 1, 10),
+                Ipv4Addr::new(192, 168, 1, 20)
+            ]
+        );
+        assert!(tracker.take_discovered_targets().is_empty());
+    }
+
+    #[test]
+    fn should_reset_status_on_new_round() {
+        let tracker = ScanTracker::new();
+        tracker.start_round();
+        tracker.record_sent();
+        tracker.record_sent();
+        tracker.record_response(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1)));
+
+        assert!(tracker
+            .format_status()
+            .starts_with("Status: 2 packets sent · 1 hosts discovered · "));
+
+        tracker.start_round();
+        assert!(tracker
+            .format_status()
+            .starts_with("Status: 0 packets sent · 0 hosts discovered · "));
+        assert!(tracker.last_response().is_none());
+    }
+
+    #[test]
+    fn should_record_responded_hosts() {
+        let tracker = ScanTracker::new();
+
+        tracker.record_response(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1)));
+
+        assert!(tracker.has_responded(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1))));
+        assert!(!tracker.has_responded(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 2))));
+    }
+
+    #[test]
+    fn should_derive_interval_from_bandwidth() {
+        let matches = build_args().get_matches_from(vec!["arp-scan", "-B", "10kB", "-r", "2"]);
+        let options = ScanOptions::new(&matches).unwrap();
+
+        let estimation = compute_scan_estimation(256, &options);
+
+        // 256 requests of 42 bytes take ~1075ms per pass at 10kB/s
+        assert_eq!(estimation.bandwidth, 10_000);
+        assert_eq!(estimation.interval_ms, 1);
+        assert_eq!(estimation.duration_ms, 2150 + 2000 + 500);
+    }
+
+    #[test]
+    fn should_estimate_decoy_requests() {
+        let bandwidth_options = ScanOptions::new(
+            &build_args().get_matches_from(vec!["arp-scan", "-B", "10kB", "--decoys", "3"]),
+        )
+        .unwrap();
+        let interval_options = ScanOptions::new(
+            &build_args()
+                .get_matches_from(vec!["arp-scan", "-I", "10", "-t", "260ms", "--decoys", "3"]),
+        )
+        .unwrap();
+
+        // Targets requested once the timeout is reached are sent 4 requests
+        // (3 decoys), all paced at the interval. At 4ms per packet, the first
+        // 500 targets are requested before the timeout.
+        let bandwidth_estimation = compute_scan_estimation(4096, &bandwidth_options);
+        assert_eq!(bandwidth_estimation.decoy_count, 3596 * 3);
+        assert_eq!(bandwidth_estimation.request_size, 14884 * 42);
+        assert_eq!(bandwidth_estimation.bandwidth, 10_000);
+        assert_eq!(bandwidth_estimation.interval_ms, 1);
+        assert_eq!(bandwidth_estimation.duration_ms, 62512 + 2000 + 500);
+
+        // At 13ms per packet, the first 20 targets are requested before the
+        // timeout.
+        let interval_estimation = compute_scan_estimation(100, &interval_options);
+        assert_eq!(interval_estimation.decoy_count, 80 * 3);
+        assert_eq!(interval_estimation.interval_ms, 10);
+        assert_eq!(interval_estimation.duration_ms, 340 * (3 + 10) + 260 + 500);
+    }
+
+    #[test]
+    fn should_estimate_no_decoy_for_small_ranges() {
+        let options = ScanOptions::new(
+            &build_args().get_matches_from(vec!["arp-scan", "-I", "10", "--decoys", "3"]),
+        )
+        .unwrap();
+
+        let estimation = compute_scan_estimation(16, &options);
+
+        assert_eq!(estimation.decoy_count, 0);
+        assert_eq!(estimation.duration_ms, 16 * (3 + 10) + 2000 + 500);
+    }
+
+    #[test]
+    fn should_limit_host_count() {
+        let default_options =
+            ScanOptions::new(&build_args().get_matches_from(vec!["arp-scan"])).unwrap();
+        let forced_options =
+            ScanOptions::new(&build_args().get_matches_from(vec!["arp-scan", "--force"])).unwrap();
+        let capped_options =
+            ScanOptions::new(&build_args().get_matches_from(vec!["arp-scan", "-c", "100"]))
+                .unwrap();
+        let custom_options = ScanOptions::new(&build_args().get_matches_from(vec![
+            "arp-scan",
+            "--max-hosts",
+            "256",
+        ]))
+        .unwrap();
+
+        assert_eq!(check_host_count(65536, &default_options), Ok(()));
+        assert_eq!(
+            check_host_count(1 << 24, &default_options),
+            Err(ScanError::TooManyHosts(1 << 24, 65536))
+        );
+        assert_eq!(check_host_count(1 << 24, &forced_options), Ok(()));
+        assert_eq!(check_host_count(1 << 24, &capped_options), Ok(()));
+        assert_eq!(
+            check_host_count(512, &custom_options),
+            Err(ScanError::TooManyHosts(512, 256))
+        );
+    }
+
+    #[test]
+    fn should_estimate_retry_backoff() {
+        let default_options =
+            ScanOptions::new(&build_args().get_matches_from(vec!["arp-scan", "-r", "3"])).unwrap();
+        let backoff_options = ScanOptions::new(&build_args().get_matches_from(vec![
+            "arp-scan",
+            "-r",
+            "3",
+            "--retry-backoff",
+            "2",
+        ]))
+        .unwrap();
+
+        // Each pass waits 10ms, 20ms and 40ms between requests with backoff
+        let default_estimation = compute_scan_estimation(100, &default_options);
+        let backoff_estimation = compute_scan_estimation(100, &backoff_options);
+
+        assert_eq!(default_estimation.duration_ms, 100 * (9 + 30) + 2000 + 500);
+        assert_eq!(backoff_estimation.duration_ms, 100 * (9 + 70) + 2000 + 500);
+        assert_eq!(backoff_estimation.interval_ms, 10);
+    }
+
+    #[test]
+    fn should_estimate_passive_scan() {
+        let matches = build_args().get_matches_from(vec!["arp-scan", "--passive", "-t", "5s"]);
+        let options = ScanOptions::new(&matches).unwrap();
+
+        let estimation = compute_scan_estimation(256, &options);
+
+        assert_eq!(estimation.duration_ms, 5000);
+        assert_eq!(estimation.request_size, 0);
+        assert_eq!(estimation.bandwidth, 0);
+    }
+
+    #[test]
+    fn should_find_untagged_arp_offset() {
+        let frame = build_ethernet_frame(EtherTypes::Arp, &[0u8; ARP_PACKET_SIZE]);
+        let ethernet_packet = EthernetPacket::new(&frame).unwrap();
+
+        assert_eq!(find_arp_payload_offset(&ethernet_packet), Some(14));
+    }
+
+    #[test]
+    fn should_find_vlan_tagged_arp_offset() {
+        let mut vlan_buffer = [0u8; VLAN_PACKET_SIZE];
+        let mut vlan_packet = MutableVlanPacket::new(&mut vlan_buffer).unwrap();
+        vlan_packet.set_vlan_identifier(42);
+        vlan_packet.set_ethertype(EtherTypes::Arp);
+
+        let frame = build_ethernet_frame(EtherTypes::Vlan, &vlan_buffer);
+        let ethernet_packet = EthernetPacket::new(&frame).unwrap();
+
+        assert_eq!(find_arp_payload_offset(&ethernet_packet), Some(18));
+    }
+
+    fn build_arp_frame(sender_ipv4: Ipv4Addr, target_ipv4: Ipv4Addr) -> Vec<u8> {
+        let mut arp_buffer = [0u8; ARP_PACKET_SIZE];
+        let mut arp_packet = MutableArpPacket::new(&mut arp_buffer).unwrap();
+        arp_packet.set_hardware_type(ArpHardwareTypes::Ethernet);
+        arp_packet.set_protocol_type(EtherTypes::Ipv4);
+        arp_packet.set_hw_addr_len(6);
+        arp_packet.set_proto_addr_len(4);
+        arp_packet.set_operation(ArpOperations::Request);
+        arp_packet.set_sender_hw_addr(MacAddr::new(0x00, 0x11, 0x22, 0x33, 0x44, 0x55));
+        arp_packet.set_sender_proto_addr(sender_ipv4);
+        arp_packet.set_target_hw_addr(MacAddr::zero());
+        arp_packet.set_target_proto_addr(target_ipv4);
+
+        build_ethernet_frame(EtherTypes::Arp, &arp_buffer)
+    }
+
+    #[test]
+    fn should_build_default_arp_request() {
+        let options = ScanOptions::new(&build_args().get_matches_from(vec!["arp-scan"])).unwrap();
+        let frame = build_arp_request(
+            MacAddr::new(0x02, 0, 0, 0, 0, 0x01),
+            Ipv4Addr::new(192, 168, 1, 2),
+            Ipv4Addr::new(192, 168, 1, 20),
+            &options,
+        )
+        .unwrap();
+
+        let arp_packet = ArpPacket::new(&frame[EthernetPacket::minimum_packet_size()..]).unwrap();
+        assert_eq!(arp_packet.get_hardware_type(), ArpHardwareTypes::Ethernet);
+        assert_eq!(arp_packet.get_protocol_type(), EtherTypes::Ipv4);
+        assert_eq!(
+            arp_packet.get_target_proto_addr(),
+            Ipv4Addr::new(192, 168, 1, 20)
+        );
+    }
+
+    #[test]
+    fn should_build_custom_arp_request() {
+        let matches = build_args().get_matches_from(vec![
+            "arp-scan",
+            "--hw-type",
+            "6",
+            "--proto-type",
+            "0x86DD",
+        ]);
+        let options = ScanOptions::new(&matches).unwrap();
+        let frame = build_arp_request(
+            MacAddr::new(0x02, 0, 0, 0, 0, 0x01),
+            Ipv4Addr::new(192, 168, 1, 2),
+            Ipv4Addr::new(192, 168, 1, 20),
+            &options,
+        )
+        .unwrap();
+
+        let arp_packet = ArpPacket::new(&frame[EthernetPacket::minimum_packet_size()..]).unwrap();
+        assert_eq!(arp_packet.get_hardware_type(), ArpHardwareType::new(6));
+        assert_eq!(arp_packet.get_protocol_type(), EtherTypes::Ipv6);
+    }
+
+    #[test]
+    fn should_pad_arp_request() {
+        let matches =
+            build_args().get_matches_from(vec!["arp-scan", "--pad-to", "64", "--pad-byte", "0xAA"]);
+        let options = ScanOptions::new(&matches).unwrap();
+        let frame = build_arp_request(
+            MacAddr::new(0x02, 0, 0, 0, 0, 0x01),
+            Ipv4Addr::new(192, 168, 1, 2),
+            Ipv4Addr::new(192, 168, 1, 20),
+            &options,
+        )
+        .unwrap();
+
+        assert_eq!(frame.len(), 64);
+        assert!(frame[ETHERNET_STD_PACKET_SIZE..]
+            .iter()
+            .all(|byte| *byte == 0xaa));
+        let arp_packet = ArpPacket::new(&frame[EthernetPacket::minimum_packet_size()..]).unwrap();
+        assert_eq!(
+            arp_packet.get_target_proto_addr(),
+            Ipv4Addr::new(192, 168, 1, 20)
+        );
+    }
+
+    #[test]
+    fn should_pass_self_test() {
+        let matches = build_args().get_matches_from(vec![
+            "arp-scan",
+            "--self-test",
+            "-Q",
+            "45",
+            "--arp-op",
+            "reply",
+            "--pad-to",
+            "64",
+        ]);
+        let options = ScanOptions::new(&matches).unwrap();
+
+        let frame = self_test_arp_request(&options).unwrap();
+
+        assert_eq!(frame.len(), 64);
+        assert_eq!(&frame[12..14], &[0x81, 0x00]);
+    }
+
+    #[test]
+    fn should_flag_malformed_frames() {
+        let options = ScanOptions::new(&build_args().get_matches_from(vec!["arp-scan"])).unwrap();
+        let arp_frame = build_arp_frame(
+            Ipv4Addr::new(192, 168, 1, 20),
+            Ipv4Addr::new(192, 168, 1, 1),
+        );
+        let ipv4_frame = build_ethernet_frame(EtherTypes::Ipv4, &[0u8; 20]);
+        let vlan_frame = build_ethernet_frame(EtherTypes::Vlan, &[0u8; 2]);
+
+        assert!(matches!(
+            parse_response_frame(&arp_frame, &options),
+            ParsedFrame::Response(IpAddr::V4(sender_ipv4), _, false, false)
+                if sender_ipv4 == Ipv4Addr::new(192, 168, 1, 20)
+        ));
+        assert!(matches!(
+            parse_response_frame(&arp_frame[..10], &options),
+            ParsedFrame::Malformed(_)
+        ));
+        assert!(matches!(
+            parse_response_frame(&arp_frame[..30], &options),
+            ParsedFrame::Malformed(_)
+        ));
+        assert!(matches!(
+            parse_response_frame(&vlan_frame, &options),
+            ParsedFrame::Malformed(_)
+        ));
+        assert!(matches!(
+            parse_response_frame(&ipv4_frame, &options),
+            ParsedFrame::Filtered(0x0800)
+        ));
+    }
+
+    #[test]
+    fn should_track_decoy_requests() {
+        let reply_frame = build_arp_frame(
+            Ipv4Addr::new(192, 168, 1, 20),
+            Ipv4Addr::new(192, 168, 1, 77),
+        );
+        let tracker = ScanTracker::new();
+        tracker.record_decoy(Ipv4Addr::new(192, 168, 1, 77));
+
+        let decoy_target = read_arp_target_ipv4(&reply_frame).unwrap();
+        assert_eq!(decoy_target, Ipv4Addr::new(192, 168, 1, 77));
+        assert!(tracker.is_decoy_ip(decoy_target));
+        assert_eq!(read_arp_target_ipv4(&reply_frame[..20]), None);
+
+        tracker.start_round();
+        assert!(!tracker.is_decoy_ip(decoy_target));
+    }
+
+    #[test]
+    fn should_read_inner_ethertype() {
+        let arp_frame = build_arp_frame(
+            Ipv4Addr::new(192, 168, 1, 20),
+            Ipv4Addr::new(192, 168, 1, 1),
+        );
+        let mut vlan_payload = vec![0x00, 0x0a, 0x08, 0x06];
+        vlan_payload.extend_from_slice(&arp_frame[14..]);
+        let vlan_frame = build_ethernet_frame(EtherTypes::Vlan, &vlan_payload);
+
+        assert_eq!(read_inner_ethertype(&arp_frame), Some(0x0806));
+        assert_eq!(read_inner_ethertype(&vlan_frame), Some(0x0806));
+        assert_eq!(read_inner_ethertype(&vlan_frame[..17]), None);
+        assert_eq!(read_inner_ethertype(&arp_frame[..13]), None);
+    }
+
+    #[test]
+    fn should_detect_gratuitous_arp_frame() {
+        let announced_ipv4 = Ipv4Addr::new(192, 168, 1, 20);
+        let frame = build_arp_frame(announced_ipv4, announced_ipv4);
+
+        let ethernet_packet = EthernetPacket::new(&frame).unwrap();
+        let arp_offset = find_arp_payload_offset(&ethernet_packet).unwrap();
+        let arp_packet = ArpPacket::new(&frame[arp_offset..]).unwrap();
+
+        assert!(is_gratuitous_arp(&arp_packet));
+        assert_eq!(arp_packet.get_sender_proto_addr(), announced_ipv4);
+    }
+
+    #[test]
+    fn should_not_detect_solicited_arp_frame() {
+        let request_frame = build_arp_frame(
+            Ipv4Addr::new(192, 168, 1, 20),
+            Ipv4Addr::new(192, 168, 1, 1),
+        );
+        let probe_frame = build_arp_frame(Ipv4Addr::UNSPECIFIED, Ipv4Addr::UNSPECIFIED);
+
+        for frame in [request_frame, probe_frame] {
+            let arp_offset =
+                find_arp_payload_offset(&EthernetPacket::new(&frame).unwrap()).unwrap();
+            let arp_packet = ArpPacket::new(&frame[arp_offset..]).unwrap();
 
-            self.random_pool.push(next_ip.unwrap());
+            assert!(!is_gratuitous_arp(&arp_packet));
         }
-
-        let mut rng = rand::thread_rng();
-        self.random_pool.shuffle(&mut rng);
     }
 
-    fn select_new_iterator(&mut self) {
-        self.current_iterator = Some(self.networks.remove(0).iter());
+    struct FakeReceiver {
+        frames: Vec<Vec<u8>>,
+        current_frame: Vec<u8>,
     }
 
-    fn pop_next_iterator_address(&mut self) -> Option<IpAddr> {
-        self.current_iterator
-            .as_mut()
-            .map(|iterator| iterator.next())
-            .unwrap_or(None)
+    impl DataLinkReceiver for FakeReceiver {
+        fn next(&mut self) -> std::io::Result<&[u8]> {
+            match self.frames.is_empty() {
+                true => Err(std::io::Error::from(TimedOut)),
+                false => {
+                    self.current_frame = self.frames.remove(0);
+                    Ok(&self.current_frame)
+                }
+            }
+        }
     }
-}
 
-impl Iterator for NetworkIterator {
-    type Item = IpAddr;
+    #[test]
+    fn should_stop_on_first_reply() {
+        let mut reply_frame = build_arp_frame(
+            Ipv4Addr::new(192, 168, 1, 20),
+            Ipv4Addr::new(192, 168, 1, 1),
+        );
+        reply_frame[EthernetPacket::minimum_packet_size() + 7] = 2;
+        let late_frame = build_arp_frame(
+            Ipv4Addr::new(192, 168, 1, 30),
+            Ipv4Addr::new(192, 168, 1, 1),
+        );
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.has_no_items_left() {
-            return None;
-        }
+        let mut rx: Box<dyn DataLinkReceiver> = Box::new(FakeReceiver {
+            frames: vec![reply_frame, late_frame],
+            current_frame: vec![],
+        });
+        let matches = build_args().get_matches_from(vec!["arp-scan", "--first", "--numeric"]);
+        let options = ScanOptions::new(&matches).unwrap();
+        let timed_out = Arc::new(AtomicBool::new(false));
+        let has_reached_timeout = Arc::new(AtomicBool::new(false));
 
-        if self.current_iterator.is_none() && !self.networks.is_empty() {
-            self.select_new_iterator();
-        }
+        let (response_summary, target_details) = receive_arp_responses(
+            &mut rx,
+            options,
+            Arc::clone(&timed_out),
+            Arc::clone(&has_reached_timeout),
+            &mut Vendor::new("/unknown/oui.csv"),
+            None,
+            &ScanTracker::new(),
+        )
+        .unwrap();
 
-        if self.is_random && self.random_pool.is_empty() {
-            self.fill_random_pool();
-        }
+        assert!(timed_out.load(Ordering::Relaxed));
+        assert!(has_reached_timeout.load(Ordering::Relaxed));
+        assert_eq!(response_summary.arp_count, 1);
+        assert_eq!(target_details.len(), 1);
+        assert_eq!(target_details[0].ipv4, Ipv4Addr::new(192, 168, 1, 20));
+    }
 
-        let next_ip = match self.is_random {
-            true => self.random_pool.pop(),
-            false => self.pop_next_iterator_address(),
-        };
+    #[test]
+    fn should_only_report_matching_mac_prefix() {
+        let arp_offset = EthernetPacket::minimum_packet_size();
+        let mut other_frame = build_arp_frame(
+            Ipv4Addr::new(192, 168, 1, 20),
+            Ipv4Addr::new(192, 168, 1, 1),
+        );
+        other_frame[arp_offset + 7] = 2;
+        let mut matching_frame = build_arp_frame(
+            Ipv4Addr::new(192, 168, 1, 30),
+            Ipv4Addr::new(192, 168, 1, 1),
+        );
+        matching_frame[arp_offset + 7] = 2;
+        matching_frame[arp_offset + 8..arp_offset + 11].copy_from_slice(&[0xb8, 0x27, 0xeb]);
+        let late_frame = build_arp_frame(
+            Ipv4Addr::new(192, 168, 1, 40),
+            Ipv4Addr::new(192, 168, 1, 1),
+        );
 
-        if next_ip.is_none() && !self.networks.is_empty() {
-            self.select_new_iterator();
-            return self.pop_next_iterator_address();
-        }
+        let mut rx: Box<dyn DataLinkReceiver> = Box::new(FakeReceiver {
+            frames: vec![other_frame, matching_frame, late_frame],
+            current_frame: vec![],
+        });
+        let matches = build_args().get_matches_from(vec![
+            "arp-scan",
+            "--first",
+            "--numeric",
+            "--mac-prefix",
+            "b8-27-eb",
+        ]);
+        let options = ScanOptions::new(&matches).unwrap();
 
-        next_ip
+        let (response_summary, target_details) = receive_arp_responses(
+            &mut rx,
+            options,
+            Arc::new(AtomicBool::new(false)),
+            Arc::new(AtomicBool::new(false)),
+            &mut Vendor::new("/unknown/oui.csv"),
+            None,
+            &ScanTracker::new(),
+        )
+        .unwrap();
+
+        assert_eq!(response_summary.arp_count, 2);
+        assert_eq!(response_summary.seen_count, 2);
+        assert_eq!(target_details.len(), 1);
+        assert_eq!(target_details[0].ipv4, Ipv4Addr::new(192, 168, 1, 30));
     }
-}
 
-/**
- * Find the most adequate IPv4 address on a given network interface for sending
- * ARP requests. If the 'forced_source_ipv4' parameter is set, it will take
- * the priority over the network interface address.
- */
-pub fn find_source_ip(
-    network_interface: &NetworkInterface,
-    forced_source_ipv4: Option<Ipv4Addr>,
-) -> Ipv4Addr {
-    if let Some(forced_ipv4) = forced_source_ipv4 {
-        return forced_ipv4;
+    #[test]
+    fn should_count_replies_of_each_host() {
+        let arp_offset = EthernetPacket::minimum_packet_size();
+        let mut reply_frame = build_arp_frame(
+            Ipv4Addr::new(192, 168, 1, 20),
+            Ipv4Addr::new(192, 168, 1, 1),
+        );
+        reply_frame[arp_offset + 7] = 2;
+        let other_frame = build_arp_frame(
+            Ipv4Addr::new(192, 168, 1, 30),
+            Ipv4Addr::new(192, 168, 1, 1),
+        );
+
+        let mut rx: Box<dyn DataLinkReceiver> = Box::new(FakeReceiver {
+            frames: vec![reply_frame.clone(), other_frame, reply_frame],
+            current_frame: vec![],
+        });
+        let matches = build_args().get_matches_from(vec!["arp-scan", "--numeric"]);
+        let options = ScanOptions::new(&matches).unwrap();
+        let timed_out = Arc::new(AtomicBool::new(false));
+
+        let cloned_timed_out = Arc::clone(&timed_out);
+        let stop_thread = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            cloned_timed_out.store(true, Ordering::Relaxed);
+        });
+        let (response_summary, mut target_details) = receive_arp_responses(
+            &mut rx,
+            options,
+            timed_out,
+            Arc::new(AtomicBool::new(false)),
+            &mut Vendor::new("/unknown/oui.csv"),
+            None,
+            &ScanTracker::new(),
+        )
+        .unwrap();
+        stop_thread.join().unwrap();
+        target_details.sort_by_key(|detail| detail.ipv4);
+
+        assert_eq!(response_summary.arp_count, 3);
+        assert_eq!(target_details.len(), 2);
+        assert_eq!(target_details[0].reply_count, 2);
+        assert_eq!(target_details[1].reply_count, 1);
     }
 
-    let potential_network = network_interface
-        .ips
-        .iter()
-        .find(|network| network.is_ipv4());
-    match potential_network.map(|network| network.ip()) {
-        Some(IpAddr::V4(ipv4_addr)) => ipv4_addr,
-        _ => {
-            eprintln!("Expected IPv4 address on network interface");
-            process::exit(1);
-        }
+    #[test]
+    fn should_keep_single_record_per_ip_and_mac() {
+        let arp_offset = EthernetPacket::minimum_packet_size();
+        let mut reply_frame = build_arp_frame(
+            Ipv4Addr::new(192, 168, 1, 20),
+            Ipv4Addr::new(192, 168, 1, 1),
+        );
+        reply_frame[arp_offset + 7] = 2;
+        let mut spoofed_frame = reply_frame.clone();
+        spoofed_frame[arp_offset + 8..arp_offset + 14]
+            .copy_from_slice(&[0x40, 0x55, 0x82, 0xc3, 0xe5, 0x5b]);
+
+        let mut rx: Box<dyn DataLinkReceiver> = Box::new(FakeReceiver {
+            frames: vec![reply_frame.clone(), spoofed_frame, reply_frame],
+            current_frame: vec![],
+        });
+        let matches = build_args().get_matches_from(vec!["arp-scan", "--numeric"]);
+        let options = ScanOptions::new(&matches).unwrap();
+        let timed_out = Arc::new(AtomicBool::new(false));
+
+        let cloned_timed_out = Arc::clone(&timed_out);
+        let stop_thread = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            cloned_timed_out.store(true, Ordering::Relaxed);
+        });
+        let (response_summary, mut target_details) = receive_arp_responses(
+            &mut rx,
+            options,
+            timed_out,
+            Arc::new(AtomicBool::new(false)),
+            &mut Vendor::new("/unknown/oui.csv"),
+            None,
+            &ScanTracker::new(),
+        )
+        .unwrap();
+        stop_thread.join().unwrap();
+        target_details.sort_by_key(|detail| detail.reply_count);
+
+        assert_eq!(target_details.len(), 2);
+        assert_eq!(target_details[0].mac.0, 0x40);
+        assert_eq!(target_details[0].reply_count, 1);
+        assert_eq!(target_details[1].reply_count, 2);
+        assert_eq!(response_summary.duplicates.len(), 1);
     }
-}
 
-/**
- * Wait at least N seconds and receive ARP network responses. The main
- * downside of this function is the blocking nature of the datalink receiver:
- * when the N seconds are elapsed, the receiver loop will therefore only stop
- * on the next received frame. Therefore, the receiver should have been
- * configured to stop at certain intervals (500ms for example).
- */
-pub fn receive_arp_responses(
-    rx: &mut Box<dyn DataLinkReceiver>,
-    options: Arc<ScanOptions>,
-    timed_out: Arc<AtomicBool>,
-    vendor_list: &mut Vendor,
-) -> (ResponseSummary, Vec<TargetDetails>) {
-    let mut discover_map: HashMap<Ipv4Addr, TargetDetails> = HashMap::new();
-    let start_recording = Instant::now();
+    #[test]
+    fn should_drop_frames_sent_by_scan() {
+        let arp_offset = EthernetPacket::minimum_packet_size();
+        let decoy_frame = build_arp_frame(
+            Ipv4Addr::new(192, 168, 1, 77),
+            Ipv4Addr::new(192, 168, 1, 20),
+        );
+        let request_frame = build_arp_frame(
+            Ipv4Addr::new(192, 168, 1, 2),
+            Ipv4Addr::new(192, 168, 1, 20),
+        );
+        let mut reply_frame = build_arp_frame(
+            Ipv4Addr::new(192, 168, 1, 20),
+            Ipv4Addr::new(192, 168, 1, 2),
+        );
+        reply_frame[arp_offset + 7] = 2;
+        reply_frame[arp_offset + 8..arp_offset + 14]
+            .copy_from_slice(&[0x40, 0x55, 0x82, 0xc3, 0xe5, 0x5b]);
+        let mut echoed_decoy_frame = decoy_frame.clone();
+        echoed_decoy_frame[arp_offset + 8..arp_offset + 14]
+            .copy_from_slice(&[0x40, 0x55, 0x82, 0xc3, 0xe5, 0x5c]);
 
-    let mut packet_count = 0;
-    let mut arp_count = 0;
+        let mut rx: Box<dyn DataLinkReceiver> = Box::new(FakeReceiver {
+            frames: vec![decoy_frame, request_frame, echoed_decoy_frame, reply_frame],
+            current_frame: vec![],
+        });
+        let matches = build_args().get_matches_from(vec!["arp-scan", "--numeric", "--decoys", "2"]);
+        let options = ScanOptions::new(&matches).unwrap();
+        let tracker = ScanTracker::new();
+        tracker.record_source_mac(MacAddr::new(0x00, 0x11, 0x22, 0x33, 0x44, 0x55));
+        tracker.record_decoy(Ipv4Addr::new(192, 168, 1, 77));
+        let timed_out = Arc::new(AtomicBool::new(false));
 
-    loop {
-        if timed_out.load(Ordering::Relaxed) {
-            break;
-        }
+        let cloned_timed_out = Arc::clone(&timed_out);
+        let stop_thread = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            cloned_timed_out.store(true, Ordering::Relaxed);
+        });
+        let (response_summary, target_details) = receive_arp_responses(
+            &mut rx,
+            options,
+            timed_out,
+            Arc::new(AtomicBool::new(false)),
+            &mut Vendor::new("/unknown/oui.csv"),
+            None,
+            &tracker,
+        )
+        .unwrap();
+        stop_thread.join().unwrap();
 
-        let arp_buffer = match rx.next() {
-            Ok(buffer) => buffer,
-            Err(error) => {
-                match error.kind() {
-                    // The 'next' call will only block the thread for a given
-                    // amount of microseconds. The goal is to avoid long blocks
-                    // due to the lack of packets received.
-                    TimedOut => continue,
-                    _ => {
-                        eprintln!("Failed to receive ARP requests ({})", error);
-                        process::exit(1);
-                    }
-                };
-            }
-        };
-        packet_count += 1;
+        assert_eq!(response_summary.arp_count, 4);
+        assert_eq!(target_details.len(), 1);
+        assert_eq!(target_details[0].ipv4, Ipv4Addr::new(192, 168, 1, 20));
+    }
 
-        let ethernet_packet = match EthernetPacket::new(arp_buffer) {
-            Some(packet) => packet,
-            None => continue,
-        };
+    #[test]
+    fn should_count_unknown_vendors() {
+        let arp_offset = EthernetPacket::minimum_packet_size();
+        let mut unknown_frame = build_arp_frame(
+            Ipv4Addr::new(192, 168, 1, 20),
+            Ipv4Addr::new(192, 168, 1, 1),
+        );
+        unknown_frame[arp_offset + 8..arp_offset + 11].copy_from_slice(&[0xbb, 0xbb, 0xbb]);
+        let mut nokia_frame = build_arp_frame(
+            Ipv4Addr::new(192, 168, 1, 30),
+            Ipv4Addr::new(192, 168, 1, 1),
+        );
+        nokia_frame[arp_offset + 7] = 2;
+        nokia_frame[arp_offset + 8..arp_offset + 11].copy_from_slice(&[0x40, 0x55, 0x82]);
 
-        let is_arp_type = matches!(ethernet_packet.get_ethertype(), EtherTypes::Arp);
-        if !is_arp_type {
-            continue;
-        }
+        let mut rx: Box<dyn DataLinkReceiver> = Box::new(FakeReceiver {
+            frames: vec![unknown_frame, nokia_frame],
+            current_frame: vec![],
+        });
+        let matches = build_args().get_matches_from(vec!["arp-scan", "--first", "--numeric"]);
+        let options = ScanOptions::new(&matches).unwrap();
 
-        let arp_packet =
-            ArpPacket::new(&arp_buffer[MutableEthernetPacket::minimum_packet_size()..]);
-        arp_count += 1;
+        let (response_summary, target_details) = receive_arp_responses(
+            &mut rx,
+            options,
+            Arc::new(AtomicBool::new(false)),
+            Arc::new(AtomicBool::new(false)),
+            &mut Vendor::new("./data/ieee-oui.csv"),
+            None,
+            &ScanTracker::new(),
+        )
+        .unwrap();
 
-        // If we found an ARP packet, extract the details and add the essential
-        // fields in the discover map. Please note that results are grouped by
-        // IPv4 address - which means that a MAC change will appear as two
-        // separete records in the result table.
-        if let Some(arp) = arp_packet {
-            let sender_ipv4 = arp.get_sender_proto_addr();
-            let sender_mac = arp.get_sender_hw_addr();
-
-            discover_map.insert(
-                sender_ipv4,
-                TargetDetails {
-                    ipv4: sender_ipv4,
-                    mac: sender_mac,
-                    hostname: None,
-                    vendor: None,
-                },
-            );
-        }
+        assert_eq!(target_details.len(), 2);
+        assert_eq!(response_summary.unknown_vendor_count, 1);
     }
 
-    // For each target found, enhance each item with additional results
-    // results such as the hostname & MAC vendor.
-    let target_details = discover_map
-        .into_values()
-        .map(|mut target_detail| {
-            if options.resolve_hostname {
-                target_detail.hostname = find_hostname(target_detail.ipv4);
-            }
+    #[test]
+    fn should_ignore_non_arp_frames() {
+        let mut vlan_buffer = [0u8; VLAN_PACKET_SIZE];
+        let mut vlan_packet = MutableVlanPacket::new(&mut vlan_buffer).unwrap();
+        vlan_packet.set_ethertype(EtherTypes::Ipv4);
 
-            if vendor_list.has_vendor_db() {
-                target_detail.vendor = vendor_list.search_by_mac(&target_detail.mac);
-            }
+        let ipv4_frame = build_ethernet_frame(EtherTypes::Ipv4, &[0u8; 20]);
+        let vlan_ipv4_frame = build_ethernet_frame(EtherTypes::Vlan, &vlan_buffer);
 
-            target_detail
-        })
-        .collect();
+        assert_eq!(
+            find_arp_payload_offset(&EthernetPacket::new(&ipv4_frame).unwrap()),
+            None
+        );
+        assert_eq!(
+            find_arp_payload_offset(&EthernetPacket::new(&vlan_ipv4_frame).unwrap()),
+            None
+        );
+    }
 
-    // The response summary can be used to display analytics related to the
-    // performed ARP scans (packet counts, timings, ...)
-    let response_summary = ResponseSummary {
-        packet_count,
-        arp_count,
-        duration_ms: start_recording.elapsed().as_millis(),
-    };
-    (response_summary, target_details)
-}
+    #[test]
+    fn should_build_magic_payload() {
+        let target_mac = MacAddr::new(0x00, 0x11, 0x22, 0x33, 0x44, 0x55);
 
-/**
- * Find the local hostname linked to an IPv4 address. This will perform a
- * reverse DNS request in the local network to find the IPv4 hostname.
- */
-fn find_hostname(ipv4: Ipv4Addr) -> Option<String> {
-    let ip: IpAddr = ipv4.into();
-    match lookup_addr(&ip) {
-        Ok(hostname) => {
-            // The 'lookup_addr' function returns an IP address if no hostname
-            // was found. If this is the case, we prefer switching to None.
-            if hostname.parse::<IpAddr>().is_ok() {
-                return None;
-            }
+        let payload = build_magic_payload(target_mac);
 
-            Some(hostname)
-        }
-        Err(_) => None,
+        assert_eq!(payload.len(), 102);
+        assert_eq!(payload[0..6], [0xFF; 6]);
+        assert_eq!(payload[6..12], [0x00, 0x11, 0x22, 0x33, 0x44, 0x55]);
+        assert_eq!(payload[96..102], [0x00, 0x11, 0x22, 0x33, 0x44, 0x55]);
     }
-}
 
-#[cfg(test)]
-mod tests {
+    struct FailingSender;
 
-    use super::*;
+    impl DataLinkSender for FailingSender {
+        fn build_and_send(
+            &mut self,
+            _num_packets: usize,
+            _packet_size: usize,
+            _func: &mut dyn FnMut(&mut [u8]),
+        ) -> Option<std::io::Result<()>> {
+            None
+        }
 
-    use ipnetwork::Ipv4Network;
-    use std::env;
+        fn send_to(
+            &mut self,
+            _packet: &[u8],
+            _dst: Option<NetworkInterface>,
+        ) -> Option<std::io::Result<()>> {
+            Some(Err(std::io::Error::other("No buffer space available")))
+        }
+    }
+
+    #[test]
+    fn should_report_wake_packet_errors() {
+        let mut tx: Box<dyn DataLinkSender> = Box::new(FailingSender);
+        let target_mac = MacAddr::new(0x00, 0x11, 0x22, 0x33, 0x44, 0x55);
+
+        assert_eq!(
+            send_wake_packet(
+                &mut tx,
+                &build_interface("eth0", 2, 0),
+                MacAddr::zero(),
+                target_mac
+            ),
+            Err(ScanError::Io(
+                "Could not send Wake-on-LAN packet to 00:11:22:33:44:55 (No buffer space available)"
+                    .to_string()
+            ))
+        );
+    }
 
     #[test]
     fn should_resolve_public_ip() {
@@ -537,7 +3796,10 @@ mod tests {
             assert_eq!(true, true);
         } else {
             let ipv4 = Ipv4Addr::new(1, 1, 1, 1);
-            assert_eq!(find_hostname(ipv4), Some("one.one.one.one".to_string()));
+            assert_eq!(
+                find_hostname(IpAddr::V4(ipv4)),
+                Some("one.one.one.one".to_string())
+            );
         }
     }
 
@@ -545,19 +3807,105 @@ mod tests {
     fn should_resolve_localhost() {
         let ipv4 = Ipv4Addr::new(127, 0, 0, 1);
 
-        assert_eq!(find_hostname(ipv4), Some("localhost".to_string()));
+        assert_eq!(
+            find_hostname(IpAddr::V4(ipv4)),
+            Some("localhost".to_string())
+        );
     }
 
     #[test]
     fn should_not_resolve_unknown_ip() {
         let ipv4 = Ipv4Addr::new(10, 254, 254, 254);
 
-        assert_eq!(find_hostname(ipv4), None);
+        assert_eq!(find_hostname(IpAddr::V4(ipv4)), None);
+    }
+
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn should_stream_targets_from_worker() {
+        let options =
+            ScanOptions::new(&build_args().get_matches_from(vec!["arp-scan", "--numeric"]))
+                .unwrap();
+        let stream_buffer = Arc::new(Mutex::new(vec![]));
+        let stream_worker =
+            StreamWorker::new(Box::new(SharedBuffer(Arc::clone(&stream_buffer))), options);
+
+        stream_worker.stream(TargetDetails::test_default(
+            Ipv4Addr::new(192, 168, 1, 10),
+            MacAddr::zero(),
+        ));
+        stream_worker.stream(TargetDetails::test_default(
+            Ipv4Addr::new(192, 168, 1, 20),
+            MacAddr::zero(),
+        ));
+        let (streamed_targets, dns_timeout_count) = stream_worker.finish().unwrap();
+
+        let stream_output = String::from_utf8(stream_buffer.lock().unwrap().clone()).unwrap();
+        assert_eq!(streamed_targets.len(), 2);
+        assert_eq!(streamed_targets[1].ipv4, Ipv4Addr::new(192, 168, 1, 20));
+        assert_eq!(dns_timeout_count, 0);
+        assert_eq!(stream_output.lines().count(), 2);
+        assert!(stream_output.lines().all(|line| line.starts_with('{')));
+    }
+
+    #[test]
+    fn should_cap_pending_lookups() {
+        static TEST_PENDING_LOOKUPS: AtomicUsize = AtomicUsize::new(0);
+        let slow_lookup = || {
+            thread::sleep(Duration::from_millis(300));
+            Some("slow.local".to_string())
+        };
+
+        let timed_out_lookup = run_lookup_with_timeout(slow_lookup, 10, &TEST_PENDING_LOOKUPS, 1);
+        let refused_lookup = run_lookup_with_timeout(
+            || Some("fast.local".to_string()),
+            1000,
+            &TEST_PENDING_LOOKUPS,
+            1,
+        );
+        thread::sleep(Duration::from_millis(600));
+        let next_lookup = run_lookup_with_timeout(
+            || Some("fast.local".to_string()),
+            1000,
+            &TEST_PENDING_LOOKUPS,
+            1,
+        );
+
+        assert_eq!(timed_out_lookup, Err(RecvTimeoutError::Timeout));
+        assert_eq!(refused_lookup, Err(RecvTimeoutError::Timeout));
+        assert_eq!(next_lookup, Ok(Some("fast.local".to_string())));
+    }
+
+    #[test]
+    fn should_resolve_hostnames_concurrently() {
+        let mut target_details: Vec<TargetDetails> = [1, 2, 1]
+            .iter()
+            .map(|last_byte| {
+                TargetDetails::test_default(Ipv4Addr::new(127, 0, 0, *last_byte), MacAddr::zero())
+            })
+            .collect();
+
+        let timed_out_count = resolve_hostnames(&mut target_details, 5000, 2);
+
+        assert_eq!(timed_out_count, Ok(0));
+        assert_eq!(target_details[0].hostname, Some("localhost".to_string()));
+        assert_eq!(target_details[2].hostname, Some("localhost".to_string()));
     }
 
     #[test]
     fn should_iterate_over_empty_networks() {
-        let mut iterator = NetworkIterator::new(&vec![], false);
+        let mut iterator = NetworkIterator::new(&[], &[], TargetOrder::Sequential, None, None);
 
         assert_eq!(iterator.next(), None);
     }
@@ -567,7 +3915,8 @@ mod tests {
         let network_a = IpNetwork::V4(Ipv4Network::new(Ipv4Addr::new(192, 168, 1, 1), 32).unwrap());
         let target_network: Vec<&IpNetwork> = vec![&network_a];
 
-        let mut iterator = NetworkIterator::new(&target_network, false);
+        let mut iterator =
+            NetworkIterator::new(&target_network, &[], TargetOrder::Sequential, None, None);
 
         assert_eq!(
             iterator.next(),
@@ -581,7 +3930,8 @@ mod tests {
         let network_a = IpNetwork::V4(Ipv4Network::new(Ipv4Addr::new(192, 168, 1, 1), 24).unwrap());
         let target_network: Vec<&IpNetwork> = vec![&network_a];
 
-        let mut iterator = NetworkIterator::new(&target_network, false);
+        let mut iterator =
+            NetworkIterator::new(&target_network, &[], TargetOrder::Sequential, None, None);
 
         assert_eq!(
             iterator.next(),
@@ -603,7 +3953,8 @@ mod tests {
         let network_b = IpNetwork::V4(Ipv4Network::new(Ipv4Addr::new(10, 10, 20, 20), 32).unwrap());
         let target_network: Vec<&IpNetwork> = vec![&network_a, &network_b];
 
-        let mut iterator = NetworkIterator::new(&target_network, false);
+        let mut iterator =
+            NetworkIterator::new(&target_network, &[], TargetOrder::Sequential, None, None);
 
         assert_eq!(
             iterator.next(),
@@ -622,10 +3973,250 @@ mod tests {
         let network_b = IpNetwork::V4(Ipv4Network::new(Ipv4Addr::new(10, 10, 20, 20), 32).unwrap());
         let target_network: Vec<&IpNetwork> = vec![&network_a, &network_b];
 
-        let mut iterator = NetworkIterator::new(&target_network, true);
+        let mut iterator =
+            NetworkIterator::new(&target_network, &[], TargetOrder::Shuffle, None, None);
 
-        assert_eq!(iterator.next().is_some(), true);
-        assert_eq!(iterator.next().is_some(), true);
+        assert!(iterator.next().is_some());
+        assert!(iterator.next().is_some());
         assert_eq!(iterator.next(), None);
     }
+
+    #[test]
+    fn should_skip_excluded_address() {
+        let network_a = IpNetwork::V4(Ipv4Network::new(Ipv4Addr::new(192, 168, 1, 0), 30).unwrap());
+        let target_network: Vec<&IpNetwork> = vec![&network_a];
+        let excluded_network =
+            IpNetwork::V4(Ipv4Network::new(Ipv4Addr::new(192, 168, 1, 1), 32).unwrap());
+
+        let iterator = NetworkIterator::new(
+            &target_network,
+            &[excluded_network],
+            TargetOrder::Sequential,
+            None,
+            None,
+        );
+
+        assert_eq!(
+            iterator.collect::<Vec<IpAddr>>(),
+            vec![
+                IpAddr::V4(Ipv4Addr::new(192, 168, 1, 0)),
+                IpAddr::V4(Ipv4Addr::new(192, 168, 1, 2)),
+                IpAddr::V4(Ipv4Addr::new(192, 168, 1, 3)),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_reproduce_random_iteration_with_seed() {
+        let network_a = IpNetwork::V4(Ipv4Network::new(Ipv4Addr::new(10, 0, 0, 0), 22).unwrap());
+        let network_b = IpNetwork::V4(Ipv4Network::new(Ipv4Addr::new(192, 168, 1, 0), 24).unwrap());
+        let target_network: Vec<&IpNetwork> = vec![&network_a, &network_b];
+
+        let first_addresses: Vec<IpAddr> =
+            NetworkIterator::new(&target_network, &[], TargetOrder::Shuffle, Some(42), None)
+                .collect();
+        let second_addresses: Vec<IpAddr> =
+            NetworkIterator::new(&target_network, &[], TargetOrder::Shuffle, Some(42), None)
+                .collect();
+        let other_addresses: Vec<IpAddr> =
+            NetworkIterator::new(&target_network, &[], TargetOrder::Shuffle, Some(43), None)
+                .collect();
+
+        assert_eq!(first_addresses.len(), 1024 + 256);
+        assert_eq!(first_addresses, second_addresses);
+        assert_ne!(first_addresses, other_addresses);
+    }
+
+    #[test]
+    fn should_cover_all_addresses_with_stride() {
+        let network_a = IpNetwork::V4(Ipv4Network::new(Ipv4Addr::new(10, 0, 0, 0), 20).unwrap());
+        let network_b = IpNetwork::V4(Ipv4Network::new(Ipv4Addr::new(192, 168, 1, 7), 32).unwrap());
+        let network_c = IpNetwork::V6(Ipv6Network::new("fd00::".parse().unwrap(), 120).unwrap());
+        let target_network: Vec<&IpNetwork> = vec![&network_a, &network_b, &network_c];
+
+        let stride_addresses: Vec<IpAddr> =
+            NetworkIterator::new(&target_network, &[], TargetOrder::Stride, Some(42), None)
+                .collect();
+        let mut sorted_addresses = stride_addresses.clone();
+        sorted_addresses.sort();
+        let mut sequential_addresses: Vec<IpAddr> =
+            NetworkIterator::new(&target_network, &[], TargetOrder::Sequential, None, None)
+                .collect();
+        sequential_addresses.sort();
+
+        assert_eq!(stride_addresses.len(), 4096 + 1 + 256);
+        assert_eq!(sorted_addresses, sequential_addresses);
+        assert_ne!(stride_addresses, sequential_addresses);
+        assert_eq!(
+            stride_addresses,
+            NetworkIterator::new(&target_network, &[], TargetOrder::Stride, Some(42), None)
+                .collect::<Vec<IpAddr>>()
+        );
+    }
+
+    #[test]
+    fn should_shuffle_each_address_once() {
+        let network_a = IpNetwork::V4(Ipv4Network::new(Ipv4Addr::new(10, 1, 0, 0), 16).unwrap());
+        let network_b = IpNetwork::V4(Ipv4Network::new(Ipv4Addr::new(10, 0, 0, 0), 8).unwrap());
+        let target_network: Vec<&IpNetwork> = vec![&network_a];
+
+        let shuffled_addresses: Vec<IpAddr> =
+            NetworkIterator::new(&target_network, &[], TargetOrder::Shuffle, Some(42), None)
+                .collect();
+        let unique_addresses: HashSet<IpAddr> = shuffled_addresses.iter().copied().collect();
+        let sequential_head: Vec<IpAddr> =
+            NetworkIterator::new(&target_network, &[], TargetOrder::Sequential, None, None)
+                .take(16)
+                .collect();
+
+        assert_eq!(shuffled_addresses.len(), 65_536);
+        assert_eq!(unique_addresses.len(), 65_536);
+        assert!(unique_addresses
+            .iter()
+            .all(|ip_address| network_a.contains(*ip_address)));
+        assert_ne!(shuffled_addresses[..16], sequential_head[..]);
+
+        // A /8 range is shuffled lazily, the first addresses are yielded
+        // without enumerating the 16M addresses of the range.
+        let large_network: Vec<&IpNetwork> = vec![&network_b];
+        let large_iterator =
+            NetworkIterator::new(&large_network, &[], TargetOrder::Shuffle, Some(42), None);
+        assert!(large_iterator
+            .take(100)
+            .all(|ip_address| network_b.contains(ip_address)));
+    }
+
+    #[test]
+    fn should_sample_requested_percentage() {
+        let network_a = IpNetwork::V4(Ipv4Network::new(Ipv4Addr::new(10, 0, 0, 0), 16).unwrap());
+        let target_network: Vec<&IpNetwork> = vec![&network_a];
+
+        let sampled_addresses: Vec<IpAddr> = NetworkIterator::new(
+            &target_network,
+            &[],
+            TargetOrder::Shuffle,
+            Some(42),
+            Some(10.0),
+        )
+        .collect();
+        let mut reordered_addresses: Vec<IpAddr> = NetworkIterator::new(
+            &target_network,
+            &[],
+            TargetOrder::Sequential,
+            Some(42),
+            Some(10.0),
+        )
+        .collect();
+        let mut sorted_addresses = sampled_addresses.clone();
+        sorted_addresses.sort();
+        reordered_addresses.sort();
+
+        let expected_count = 65_536.0 * 0.1;
+        assert!((sampled_addresses.len() as f64 - expected_count).abs() < expected_count * 0.05);
+        assert_eq!(sorted_addresses, reordered_addresses);
+    }
+
+    #[test]
+    fn should_skip_excluded_network() {
+        let network_a = IpNetwork::V4(Ipv4Network::new(Ipv4Addr::new(10, 0, 0, 0), 24).unwrap());
+        let target_network: Vec<&IpNetwork> = vec![&network_a];
+        let excluded_network =
+            IpNetwork::V4(Ipv4Network::new(Ipv4Addr::new(10, 0, 0, 16), 28).unwrap());
+
+        let iterator = NetworkIterator::new(
+            &target_network,
+            &[excluded_network],
+            TargetOrder::Shuffle,
+            None,
+            None,
+        );
+        let addresses: Vec<IpAddr> = iterator.collect();
+
+        assert_eq!(addresses.len(), 240);
+        assert!(!addresses.contains(&IpAddr::V4(Ipv4Addr::new(10, 0, 0, 16))));
+        assert!(!addresses.contains(&IpAddr::V4(Ipv4Addr::new(10, 0, 0, 31))));
+        assert!(addresses.contains(&IpAddr::V4(Ipv4Addr::new(10, 0, 0, 32))));
+    }
+
+    #[test]
+    fn should_probe_open_ports() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let open_port = listener.local_addr().unwrap().port();
+        let closed_port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let mut target_details = vec![build_target(Ipv4Addr::LOCALHOST)];
+
+        probe_open_ports(&mut target_details, &[closed_port, open_port], 500).unwrap();
+
+        assert_eq!(target_details[0].open_ports, vec![open_port]);
+    }
+
+    #[test]
+    fn should_mark_silent_cache_entries_as_stale() {
+        let options = ScanOptions::new(&build_args().get_matches_from(vec![
+            "arp-scan",
+            "--include-cache",
+            "--numeric",
+        ]))
+        .unwrap();
+        let network = IpNetwork::from_str("192.168.1.0/24").unwrap();
+        let mut target_details = vec![build_target(Ipv4Addr::new(192, 168, 1, 1))];
+        let cache_entries = vec![
+            (Ipv4Addr::new(192, 168, 1, 1), MacAddr::zero()),
+            (
+                Ipv4Addr::new(192, 168, 1, 30),
+                MacAddr::new(0x40, 0x55, 0x82, 0xc3, 0xe5, 0x30),
+            ),
+            (
+                Ipv4Addr::new(10, 0, 0, 1),
+                MacAddr::new(0x40, 0x55, 0x82, 0xc3, 0xe5, 0x01),
+            ),
+        ];
+
+        merge_arp_cache(
+            &mut target_details,
+            &cache_entries,
+            &[&network],
+            &options,
+            &mut Vendor::new("/unknown/oui.csv"),
+        )
+        .unwrap();
+
+        assert_eq!(target_details.len(), 2);
+        assert_eq!(target_details[0].source, Some(TargetSource::Active));
+        assert!(!target_details[0].stale);
+        assert_eq!(target_details[1].ipv4, Ipv4Addr::new(192, 168, 1, 30));
+        assert_eq!(target_details[1].source, Some(TargetSource::Cache));
+        assert!(target_details[1].stale);
+    }
+
+    #[test]
+    fn should_parse_arp_cache() {
+        let arp_table =
+            "IP address       HW type     Flags       HW address            Mask     Device\n\
+            192.168.1.1      0x1         0x2         40:55:82:c3:e5:5b     *        eth0\n\
+            192.168.1.20     0x1         0x0         00:00:00:00:00:00     *        eth0\n\
+            192.168.1.30     0x1         0x6         40:55:82:c3:e5:30     *        eth0\n\
+            10.0.0.1         0x1         0x2         40:55:82:c3:e5:01     *        wlan0\n";
+
+        let cache_entries = parse_arp_cache(arp_table, "eth0");
+
+        assert_eq!(
+            cache_entries,
+            vec![
+                (
+                    Ipv4Addr::new(192, 168, 1, 1),
+                    MacAddr::new(0x40, 0x55, 0x82, 0xc3, 0xe5, 0x5b)
+                ),
+                (
+                    Ipv4Addr::new(192, 168, 1, 30),
+                    MacAddr::new(0x40, 0x55, 0x82, 0xc3, 0xe5, 0x30)
+                ),
+            ]
+        );
+        assert!(parse_arp_cache(arp_table, "eth1").is_empty());
+    }
 }
@@ -0,0 +1,179 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use pnet_datalink::{DataLinkReceiver, DataLinkSender, NetworkInterface};
+
+use crate::error::ScanError;
+
+const PCAP_MAGIC_NUMBER: u32 = 0xa1b2_c3d4;
+const PCAP_VERSION_MAJOR: u16 = 2;
+const PCAP_VERSION_MINOR: u16 = 4;
+const PCAP_SNAPSHOT_LENGTH: u32 = 65535;
+const PCAP_LINKTYPE_ETHERNET: u32 = 1;
+
+/**
+ * A packet capture file in the libpcap format, which can be opened with
+ * Wireshark or tcpdump. Frames are written by the sender threads and the
+ * response thread, the file writes are therefore buffered behind a lock.
+ */
+pub struct PacketCapture {
+    writer: Mutex<BufWriter<File>>,
+}
+
+impl PacketCapture {
+    /**
+     * Create the capture file and write the global pcap header (Ethernet
+     * link type, microsecond timestamps).
+     */
+    pub fn create(file_path: &str) -> Result<PacketCapture, ScanError> {
+        let capture_error = |err: io::Error| {
+            ScanError::Io(format!("Could not create capture {} ({})", file_path, err))
+        };
+
+        let mut writer = BufWriter::new(File::create(file_path).map_err(capture_error)?);
+
+        let mut global_header: Vec<u8> = Vec::with_capacity(24);
+        global_header.extend_from_slice(&PCAP_MAGIC_NUMBER.to_le_bytes());
+        global_header.extend_from_slice(&PCAP_VERSION_MAJOR.to_le_bytes());
+        global_header.extend_from_slice(&PCAP_VERSION_MINOR.to_le_bytes());
+        global_header.extend_from_slice(&0_i32.to_le_bytes());
+        global_header.extend_from_slice(&0_u32.to_le_bytes());
+        global_header.extend_from_slice(&PCAP_SNAPSHOT_LENGTH.to_le_bytes());
+        global_header.extend_from_slice(&PCAP_LINKTYPE_ETHERNET.to_le_bytes());
+        writer.write_all(&global_header).map_err(capture_error)?;
+
+        Ok(PacketCapture {
+            writer: Mutex::new(writer),
+        })
+    }
+
+    /**
+     * Append a single Ethernet frame to the capture, with the current time as
+     * timestamp. Write failures are reported but never stop the scan.
+     */
+    pub fn write_frame(&self, frame: &[u8]) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let frame_length = frame.len() as u32;
+        let captured_length = frame_length.min(PCAP_SNAPSHOT_LENGTH);
+
+        let mut record: Vec<u8> = Vec::with_capacity(16 + captured_length as usize);
+        record.extend_from_slice(&(timestamp.as_secs() as u32).to_le_bytes());
+        record.extend_from_slice(&timestamp.subsec_micros().to_le_bytes());
+        record.extend_from_slice(&captured_length.to_le_bytes());
+        record.extend_from_slice(&frame_length.to_le_bytes());
+        record.extend_from_slice(&frame[..captured_length as usize]);
+
+        match self.writer.lock() {
+            Ok(mut writer) => {
+                if let Err(err) = writer.write_all(&record) {
+                    eprintln!("Could not write packet capture ({})", err);
+                }
+            }
+            Err(err) => eprintln!("Could not lock packet capture ({})", err),
+        }
+    }
+
+    /**
+     * Flush all buffered frames to the capture file. This is done after each
+     * scan round, so that an interrupted scan still gives a complete file.
+     */
+    pub fn flush(&self) -> Result<(), ScanError> {
+        let mut writer = self
+            .writer
+            .lock()
+            .map_err(|err| ScanError::Io(format!("Could not lock packet capture ({})", err)))?;
+        writer
+            .flush()
+            .map_err(|err| ScanError::Io(format!("Could not write packet capture ({})", err)))
+    }
+}
+
+/**
+ * A datalink sender writing each sent frame in the packet capture, before
+ * forwarding it to the wrapped sender.
+ */
+pub struct CaptureSender {
+    sender: Box<dyn DataLinkSender>,
+    capture: Arc<PacketCapture>,
+}
+
+impl CaptureSender {
+    pub fn new(sender: Box<dyn DataLinkSender>, capture: Arc<PacketCapture>) -> Self {
+        CaptureSender { sender, capture }
+    }
+}
+
+impl DataLinkSender for CaptureSender {
+    fn build_and_send(
+        &mut self,
+        num_packets: usize,
+        packet_size: usize,
+        func: &mut dyn FnMut(&mut [u8]),
+    ) -> Option<io::Result<()>> {
+        let capture = &self.capture;
+        self.sender
+            .build_and_send(num_packets, packet_size, &mut |packet: &mut [u8]| {
+                func(packet);
+                capture.write_frame(packet);
+            })
+    }
+
+    fn send_to(&mut self, packet: &[u8], dst: Option<NetworkInterface>) -> Option<io::Result<()>> {
+        self.capture.write_frame(packet);
+        self.sender.send_to(packet, dst)
+    }
+}
+
+/**
+ * A datalink receiver writing each received frame in the packet capture,
+ * including the frames that are not ARP responses.
+ */
+pub struct CaptureReceiver {
+    receiver: Box<dyn DataLinkReceiver>,
+    capture: Arc<PacketCapture>,
+}
+
+impl CaptureReceiver {
+    pub fn new(receiver: Box<dyn DataLinkReceiver>, capture: Arc<PacketCapture>) -> Self {
+        CaptureReceiver { receiver, capture }
+    }
+}
+
+impl DataLinkReceiver for CaptureReceiver {
+    fn next(&mut self) -> io::Result<&[u8]> {
+        let frame = self.receiver.next()?;
+        self.capture.write_frame(frame);
+        Ok(frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn should_write_pcap_records() {
+        let path = std::env::temp_dir().join("arp-scan-capture-test.pcap");
+        let capture = PacketCapture::create(path.to_str().unwrap()).unwrap();
+
+        capture.write_frame(&[0xff; 42]);
+        capture.write_frame(&[0x01, 0x02]);
+        capture.flush().unwrap();
+
+        let content = fs::read(&path).unwrap();
+        assert_eq!(content.len(), 24 + (16 + 42) + (16 + 2));
+        assert_eq!(&content[0..4], &[0xd4, 0xc3, 0xb2, 0xa1]);
+        assert_eq!(&content[20..24], &[1, 0, 0, 0]);
+        assert_eq!(&content[32..36], &[42, 0, 0, 0]);
+        assert_eq!(&content[36..40], &[42, 0, 0, 0]);
+        assert_eq!(&content[content.len() - 2..], &[0x01, 0x02]);
+
+        fs::remove_file(path).unwrap();
+    }
+}
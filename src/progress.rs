@@ -0,0 +1,235 @@
+use std::env;
+use std::io::{self, IsTerminal, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::time;
+
+const RENDER_INTERVAL: Duration = Duration::from_millis(200);
+const BAR_WIDTH: usize = 24;
+const DEFAULT_TERMINAL_WIDTH: usize = 80;
+
+/**
+ * A progress bar for the request phase of a scan, rendered on a single line
+ * of stderr (so that it never collides with results written on stdout). The
+ * bar is redrawn at a time-based cadence, and cleared once all requests have
+ * been sent. Nothing is rendered if the progress bar is disabled, or if
+ * stderr is not a terminal. With retries, the current pass is rendered too.
+ * Targets skipped by the sender threads are counted as done, so that the bar
+ * still reaches its total when hosts are not probed again.
+ */
+pub struct ProgressBar {
+    total_count: u128,
+    interval_ms: u64,
+    timeout_ms: u64,
+    pass_count: usize,
+    pass_index: AtomicUsize,
+    skipped_count: AtomicUsize,
+    last_render: Mutex<Option<Instant>>,
+    is_enabled: bool,
+}
+
+impl ProgressBar {
+    pub fn new(
+        total_count: u128,
+        interval_ms: u64,
+        timeout_ms: u64,
+        pass_count: usize,
+        is_enabled: bool,
+    ) -> Self {
+        ProgressBar {
+            total_count,
+            interval_ms,
+            timeout_ms,
+            pass_count,
+            pass_index: AtomicUsize::new(0),
+            skipped_count: AtomicUsize::new(0),
+            last_render: Mutex::new(None),
+            is_enabled: is_enabled && io::stderr().is_terminal(),
+        }
+    }
+
+    /**
+     * Record the retry pass being sent (starting from 0), rendered on the
+     * next update of the progress bar.
+     */
+    pub fn start_pass(&self, pass_index: usize) {
+        self.pass_index.store(pass_index, Ordering::Relaxed);
+    }
+
+    /**
+     * Count a target that will not be sent any request during the current
+     * pass (e.g. a host that already responded), as if it had been sent.
+     */
+    pub fn skip_target(&self) {
+        self.skipped_count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /**
+     * The number of targets done for the given sent count, including the
+     * skipped targets.
+     */
+    fn compute_done_count(&self, sent_count: usize) -> u128 {
+        sent_count as u128 + self.skipped_count.load(Ordering::Relaxed) as u128
+    }
+
+    /**
+     * Render the progress bar for the given sent count, unless it has already
+     * been rendered recently. Sender threads never wait for each other here,
+     * a busy render lock simply skips the update.
+     */
+    pub fn update(&self, sent_count: usize) {
+        if !self.is_enabled {
+            return;
+        }
+
+        let mut last_render = match self.last_render.try_lock() {
+            Ok(last_render) => last_render,
+            Err(_) => return,
+        };
+        if last_render.is_some_and(|render_time| render_time.elapsed() < RENDER_INTERVAL) {
+            return;
+        }
+        *last_render = Some(Instant::now());
+
+        let done_count = self.compute_done_count(sent_count);
+        let remaining_count = self.total_count.saturating_sub(done_count);
+        let eta_ms = remaining_count * self.interval_ms as u128 + self.timeout_ms as u128;
+        let pass = (self.pass_count > 1)
+            .then(|| (self.pass_index.load(Ordering::Relaxed) + 1, self.pass_count));
+        let line = render_line(
+            done_count,
+            self.total_count,
+            pass,
+            eta_ms,
+            find_terminal_width(),
+        );
+
+        let mut stderr = io::stderr().lock();
+        let _ = write!(stderr, "\r\x1B[2K{}", line);
+        let _ = stderr.flush();
+    }
+
+    /**
+     * Clear the progress bar line, so that the following outputs (scan phase
+     * details, results table, ...) start on a clean line.
+     */
+    pub fn finish(&self) {
+        if !self.is_enabled {
+            return;
+        }
+
+        let mut stderr = io::stderr().lock();
+        let _ = write!(stderr, "\r\x1B[2K");
+        let _ = stderr.flush();
+    }
+}
+
+/**
+ * Build a progress line such as '[#####-----] 120/256 46.9% · ETA 3s', which
+ * starts with the pass number (e.g. 'Pass 2/3') when several passes are sent.
+ * The line is truncated to the terminal width, to avoid wrapping (which would
+ * break the carriage-return redraw on narrow terminals).
+ */
+fn render_line(
+    sent_count: u128,
+    total_count: u128,
+    pass: Option<(usize, usize)>,
+    eta_ms: u128,
+    terminal_width: usize,
+) -> String {
+    let progress_ratio = match total_count {
+        0 => 1.0,
+        _ => (sent_count.min(total_count) as f64) / (total_count as f64),
+    };
+    let filled_width = (progress_ratio * BAR_WIDTH as f64).round() as usize;
+
+    let pass_text = match pass {
+        Some((pass_number, pass_count)) => format!("Pass {}/{} ", pass_number, pass_count),
+        None => String::new(),
+    };
+
+    let line = format!(
+        "{}[{}{}] {}/{} {:.1}% · ETA {}",
+        pass_text,
+        "#".repeat(filled_width),
+        "-".repeat(BAR_WIDTH - filled_width),
+        sent_count,
+        total_count,
+        progress_ratio * 100.0,
+        time::format_milliseconds(eta_ms)
+    );
+
+    line.chars()
+        .take(terminal_width.saturating_sub(1))
+        .collect()
+}
+
+/**
+ * The terminal width is read from the COLUMNS variable (exported by most
+ * shells), with a conservative default.
+ */
+fn find_terminal_width() -> usize {
+    env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.parse::<usize>().ok())
+        .filter(|columns| *columns > 0)
+        .unwrap_or(DEFAULT_TERMINAL_WIDTH)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn should_render_progress_line() {
+        assert_eq!(
+            render_line(128, 256, None, 3500, 80),
+            "[############------------] 128/256 50.0% · ETA 3s"
+        );
+    }
+
+    #[test]
+    fn should_render_progress_line_with_pass() {
+        assert_eq!(
+            render_line(300, 768, Some((2, 3)), 3500, 80),
+            "Pass 2/3 [#########---------------] 300/768 39.1% · ETA 3s"
+        );
+    }
+
+    #[test]
+    fn should_render_complete_progress_line() {
+        assert_eq!(
+            render_line(0, 0, None, 500, 80),
+            "[########################] 0/0 100.0% · ETA 500ms"
+        );
+    }
+
+    #[test]
+    fn should_truncate_progress_line() {
+        let line = render_line(10, 256, None, 60_000, 20);
+
+        assert_eq!(line.chars().count(), 19);
+        assert!(line.starts_with("[#-----"));
+    }
+
+    #[test]
+    fn should_skip_disabled_progress_bar() {
+        let progress_bar = ProgressBar::new(256, 10, 2000, 1, false);
+        progress_bar.update(10);
+        progress_bar.finish();
+
+        assert!(progress_bar.last_render.lock().unwrap().is_none());
+    }
+
+    #[test]
+    fn should_count_skipped_targets() {
+        let progress_bar = ProgressBar::new(512, 10, 2000, 2, false);
+        progress_bar.skip_target();
+        progress_bar.skip_target();
+
+        assert_eq!(progress_bar.compute_done_count(300), 302);
+    }
+}
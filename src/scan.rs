@@ -0,0 +1,1422 @@
+use std::any::Any;
+use std::collections::VecDeque;
+use std::io::Write;
+use std::iter::Peekable;
+use std::net::{IpAddr, Ipv4Addr};
+use std::panic;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use ansi_term::Style;
+use ipnetwork::IpNetwork;
+use pnet::packet::ethernet::EtherType;
+use pnet_datalink::{DataLinkReceiver, DataLinkSender, MacAddr, NetworkInterface};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::args::ScanOptions;
+use crate::error::ScanError;
+#[cfg(target_os = "linux")]
+use crate::filter;
+use crate::ndp;
+use crate::network::{self, NetworkIterator, ResponseSummary, ScanTracker, TargetDetails};
+use crate::pcap::{CaptureReceiver, CaptureSender, PacketCapture};
+use crate::progress::ProgressBar;
+use crate::utils;
+use crate::vendor::Vendor;
+
+const SEND_ERROR_WARNING_PERCENT: usize = 1;
+
+// Maximum number of addresses kept as decoy sources, and of probed addresses
+// waiting for the timeout before becoming decoy sources (see '--decoys').
+const DECOY_POOL_MAX: usize = 256;
+
+type DatalinkChannel = (Box<dyn DataLinkSender>, Box<dyn DataLinkReceiver>);
+type DatalinkChannels = (Vec<Box<dyn DataLinkSender>>, Box<dyn DataLinkReceiver>);
+type TargetIterator<'p> = Box<dyn Iterator<Item = IpAddr> + Send + 'p>;
+
+/**
+ * An interface to scan, with the networks to scan on this interface.
+ */
+pub type ScanTarget<'a> = (&'a NetworkInterface, Vec<&'a IpNetwork>);
+
+/**
+ * Perform a single ARP scan on the network, without writing anything on the
+ * terminal. The interfaces & networks are selected with the scan options (as
+ * done by the CLI), and the scan summary is returned with all discovered
+ * targets. This is the main entry point for embedding ARP scans, warnings
+ * about the requested scan are given by 'describe_scan_warnings'.
+ */
+pub fn run_scan(
+    scan_options: Arc<ScanOptions>,
+) -> Result<(ResponseSummary, Vec<TargetDetails>), ScanError> {
+    let interfaces = pnet_datalink::interfaces();
+    let scan_targets = compute_scan_targets(&interfaces, &scan_options)?;
+    network::check_host_count(
+        compute_scan_size(&scan_targets, &scan_options),
+        &scan_options,
+    )?;
+
+    let has_reached_timeout = Arc::new(AtomicBool::new(false));
+    let mut scan_session = ScanSession::open(
+        scan_targets,
+        Arc::clone(&scan_options),
+        has_reached_timeout,
+        false,
+    )?;
+    scan_session.scan_round()
+}
+
+/**
+ * Select the interfaces & networks to scan: all eligible interfaces with the
+ * '--all-interfaces' flag, or a single interface otherwise (given by the user
+ * or the default one).
+ */
+pub fn compute_scan_targets<'a>(
+    interfaces: &'a [NetworkInterface],
+    scan_options: &'a Arc<ScanOptions>,
+) -> Result<Vec<ScanTarget<'a>>, ScanError> {
+    match scan_options.all_interfaces {
+        true => network::compute_all_interfaces_configuration(interfaces, scan_options),
+        false => Ok(vec![network::compute_network_configuration(
+            interfaces,
+            scan_options,
+        )?]),
+    }
+}
+
+/**
+ * Count the hosts of all scan targets, after sampling.
+ */
+pub fn compute_scan_size(scan_targets: &[ScanTarget], scan_options: &ScanOptions) -> u128 {
+    scan_targets
+        .iter()
+        .map(|(_, ip_networks)| {
+            utils::compute_network_size(ip_networks, scan_options.sample_percent)
+        })
+        .sum()
+}
+
+/**
+ * Describe the risks of the requested scan, which should be reported before
+ * the scan starts (the CLI prints them even in quiet mode). A forced source
+ * IPv4 outside of the interface subnets may be ignored by hosts, and decoy
+ * requests spoof other addresses of the scanned networks.
+ */
+pub fn describe_scan_warnings(
+    scan_targets: &[ScanTarget],
+    scan_options: &Arc<ScanOptions>,
+) -> Vec<String> {
+    let mut warnings = vec![];
+    let is_arp_scan = !scan_options.passive && !scan_options.ipv6;
+
+    // A forced source IPv4 is never refused, as it may target a secondary
+    // subnet on the same L2 segment.
+    if let (Some(source_ipv4), true) = (scan_options.source_ipv4, is_arp_scan) {
+        for (selected_interface, _) in scan_targets.iter() {
+            if !network::is_interface_subnet_ip(selected_interface, source_ipv4) {
+                warnings.push(format!(
+                    "Source IPv4 {} is outside of the subnets of {}, some hosts may not reply",
+                    source_ipv4, selected_interface.name
+                ));
+            }
+        }
+    }
+
+    // Decoy sources are only addresses confirmed silent after the timeout, the
+    // decoys expected to be sent are reported (none for small ranges).
+    if scan_options.decoy_count > 0 && !scan_options.dry_run {
+        let network_size = compute_scan_size(scan_targets, scan_options);
+        let estimation = network::compute_scan_estimation(network_size, scan_options);
+        match estimation.decoy_count {
+            0 => warnings.push(format!(
+                "No decoy request will be sent, all {} targets are requested before the timeout confirms silent addresses",
+                network_size
+            )),
+            decoy_count => warnings.push(format!(
+                "Sending up to {} decoy requests per target (~{} in total) with spoofed source IPv4 addresses, targets may cache them with the interface MAC",
+                scan_options.decoy_count, decoy_count
+            )),
+        }
+    }
+
+    warnings
+}
+
+/**
+ * Datalink channels & scan context of a single interface. Several interfaces
+ * are scanned one after another with the '--all-interfaces' flag.
+ */
+struct InterfaceScan<'a> {
+    context: ScanContext<'a>,
+    gateway_ipv4: Option<Ipv4Addr>,
+    tx_list: Vec<Box<dyn DataLinkSender>>,
+    rx: Box<dyn DataLinkReceiver>,
+}
+
+/**
+ * A scan of one or several interfaces, whose datalink channels stay open
+ * between the scan rounds (e.g. in watch mode). Wake-on-LAN packets are sent
+ * when the session is opened, and the maximum duration of the whole session
+ * is enforced by a watchdog. Progress messages are only written on stdout for
+ * verbose sessions (the CLI).
+ */
+pub struct ScanSession<'a> {
+    interface_scans: Vec<InterfaceScan<'a>>,
+    scan_options: Arc<ScanOptions>,
+    packet_capture: Option<Arc<PacketCapture>>,
+    vendor_list: Vendor,
+    has_reached_timeout: Arc<AtomicBool>,
+}
+
+impl<'a> ScanSession<'a> {
+    pub fn open(
+        scan_targets: Vec<ScanTarget<'a>>,
+        scan_options: Arc<ScanOptions>,
+        has_reached_timeout: Arc<AtomicBool>,
+        is_verbose: bool,
+    ) -> Result<ScanSession<'a>, ScanError> {
+        let is_progress_output = is_verbose && scan_options.is_progress_output();
+        let packet_capture = open_packet_capture(&scan_options)?;
+
+        let mut interface_scans = vec![];
+        for (selected_interface, ip_networks) in scan_targets {
+            // The default gateway is only looked up for IPv4 (ARP) scans.
+            let gateway_ipv4 = match scan_options.ipv6 {
+                true => None,
+                false => network::find_default_gateway(&selected_interface.name),
+            };
+
+            if is_progress_output {
+                utils::display_prescan_details(
+                    &ip_networks,
+                    selected_interface,
+                    gateway_ipv4,
+                    Arc::clone(&scan_options),
+                );
+            }
+
+            let (mut tx_list, rx) = open_datalink_channels(
+                selected_interface,
+                scan_options.tx_threads,
+                scan_options.rcv_timeout_ms,
+                network::find_response_ethertype(&scan_options),
+                packet_capture.as_ref(),
+            )?;
+
+            // Wake-on-LAN magic packets are sent before the scan, giving a
+            // chance to the woken hosts to respond to the ARP requests.
+            for target_mac in &scan_options.wake_targets {
+                let source_mac = network::find_source_mac(selected_interface, &scan_options)?;
+                network::send_wake_packet(
+                    &mut tx_list[0],
+                    selected_interface,
+                    source_mac,
+                    *target_mac,
+                )?;
+                if is_progress_output {
+                    let dimmed =
+                        utils::color_style(Style::new().dimmed(), scan_options.color_enabled);
+                    println!("{: <16} {}", dimmed.paint("Wake-on-LAN"), target_mac);
+                }
+            }
+
+            let context = ScanContext::new(
+                selected_interface,
+                ip_networks,
+                Arc::clone(&scan_options),
+                Arc::clone(&has_reached_timeout),
+                is_verbose,
+            )?;
+            interface_scans.push(InterfaceScan {
+                context,
+                gateway_ipv4,
+                tx_list,
+                rx,
+            });
+        }
+
+        let vendor_list = Vendor::new(&scan_options.oui_file);
+        if is_verbose && scan_options.is_host_logged() && vendor_list.has_vendor_db() {
+            eprintln!("[v] Loaded vendor database {}", scan_options.oui_file);
+        }
+
+        if let Some(max_duration_ms) = scan_options.max_duration_ms {
+            start_duration_watchdog(
+                max_duration_ms,
+                Arc::clone(&has_reached_timeout),
+                is_verbose,
+            );
+        }
+
+        Ok(ScanSession {
+            interface_scans,
+            scan_options,
+            packet_capture,
+            vendor_list,
+            has_reached_timeout,
+        })
+    }
+
+    /**
+     * Perform a scan round on all interfaces, one after another. An
+     * interruption stops the round without scanning the remaining interfaces.
+     * Hosts answered for by the gateway are flagged as proxy ARP responses,
+     * and known hosts are dropped with '--only-new-macs'.
+     */
+    pub fn scan_round(&mut self) -> Result<(ResponseSummary, Vec<TargetDetails>), ScanError> {
+        let mut interface_results = vec![];
+        for interface_scan in self.interface_scans.iter_mut() {
+            let (response_summary, mut target_details) = perform_scan_round(
+                &interface_scan.context,
+                &mut interface_scan.tx_list,
+                &mut interface_scan.rx,
+                &mut self.vendor_list,
+            )?;
+            network::flag_proxy_arp_targets(&mut target_details, interface_scan.gateway_ipv4);
+            if self.scan_options.only_new_macs {
+                target_details.retain(|detail| detail.known != Some(true));
+            }
+            interface_results.push((
+                interface_scan.context.interface.name.clone(),
+                response_summary,
+                target_details,
+            ));
+            if self.has_reached_timeout.load(Ordering::Relaxed) {
+                break;
+            }
+        }
+        if let Some(packet_capture) = &self.packet_capture {
+            packet_capture.flush()?;
+        }
+
+        let scan_results = match self.scan_options.all_interfaces {
+            true => network::merge_interface_results(interface_results),
+            false => interface_results
+                .into_iter()
+                .next()
+                .map(|(_, response_summary, target_details)| (response_summary, target_details)),
+        };
+        scan_results.ok_or_else(|| {
+            ScanError::InterfaceNotFound("No network interface has been scanned".to_string())
+        })
+    }
+
+    /**
+     * The names of the scanned interfaces, separated with commas.
+     */
+    pub fn interface_label(&self) -> String {
+        self.interface_scans
+            .iter()
+            .map(|interface_scan| interface_scan.context.interface.name.as_str())
+            .collect::<Vec<&str>>()
+            .join(",")
+    }
+
+    /**
+     * The default gateway of the first scanned interface that has one.
+     */
+    pub fn gateway_ipv4(&self) -> Option<Ipv4Addr> {
+        self.interface_scans
+            .iter()
+            .find_map(|interface_scan| interface_scan.gateway_ipv4)
+    }
+
+    /**
+     * All networks scanned by the session, on every interface.
+     */
+    pub fn scanned_networks(&self) -> Vec<IpNetwork> {
+        self.interface_scans
+            .iter()
+            .flat_map(|interface_scan| interface_scan.context.ip_networks.iter())
+            .map(|ip_network| **ip_network)
+            .collect()
+    }
+
+    /**
+     * The scan trackers of all interfaces, which give the live status of the
+     * current round from another thread.
+     */
+    pub fn trackers(&self) -> Vec<Arc<ScanTracker>> {
+        self.interface_scans
+            .iter()
+            .map(|interface_scan| interface_scan.context.tracker())
+            .collect()
+    }
+}
+
+/**
+ * Create the packet capture file if requested (see '--pcap'), all frames sent
+ * and received on the datalink channels will then be written in this file.
+ */
+fn open_packet_capture(
+    scan_options: &ScanOptions,
+) -> Result<Option<Arc<PacketCapture>>, ScanError> {
+    match &scan_options.pcap_file {
+        Some(pcap_file) => Ok(Some(Arc::new(PacketCapture::create(pcap_file)?))),
+        None => Ok(None),
+    }
+}
+
+/**
+ * Open an Ethernet datalink channel on the given network interface. The
+ * receiver is configured with a read timeout, so that the response thread
+ * can regularly check if the scan should be stopped: the end of the scan is
+ * delayed by at most this timeout.
+ */
+fn open_datalink_channel(
+    interface: &NetworkInterface,
+    rcv_timeout_ms: u64,
+) -> Result<DatalinkChannel, ScanError> {
+    // The interface is kept in promiscuous mode, since responses to a forced
+    // source MAC (--source-mac) are not addressed to the interface itself.
+    let channel_config = pnet_datalink::Config {
+        read_timeout: Some(Duration::from_millis(rcv_timeout_ms)),
+        promiscuous: true,
+        ..pnet_datalink::Config::default()
+    };
+
+    match pnet_datalink::channel(interface, channel_config) {
+        Ok(pnet_datalink::Channel::Ethernet(tx, rx)) => Ok((tx, rx)),
+        Ok(_) => Err(ScanError::Channel(
+            "expected an Ethernet datalink channel".to_string(),
+        )),
+        Err(error) => Err(ScanError::Channel(error.to_string())),
+    }
+}
+
+/**
+ * Open the receiver reading all responses of a scan. On Linux, a dedicated
+ * packet socket is opened with a kernel filter on the response EtherType, so
+ * that the traffic of other protocols never reaches the response thread.
+ */
+#[cfg(target_os = "linux")]
+fn open_response_receiver(
+    interface: &NetworkInterface,
+    _rx: Box<dyn DataLinkReceiver>,
+    ethertype: EtherType,
+    rcv_timeout_ms: u64,
+) -> Result<Box<dyn DataLinkReceiver>, ScanError> {
+    filter::open_filtered_receiver(interface, ethertype.0, rcv_timeout_ms)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn open_response_receiver(
+    _interface: &NetworkInterface,
+    rx: Box<dyn DataLinkReceiver>,
+    _ethertype: EtherType,
+    _rcv_timeout_ms: u64,
+) -> Result<Box<dyn DataLinkReceiver>, ScanError> {
+    Ok(rx)
+}
+
+/**
+ * Open the datalink channels required for a scan on the given interface. Each
+ * sender thread owns a dedicated datalink sender, the additional receivers are
+ * not used (all responses are read by the response receiver, filtered on the
+ * given EtherType). If a packet capture is given, all channels are wrapped to
+ * write their frames in the capture.
+ */
+fn open_datalink_channels(
+    interface: &NetworkInterface,
+    tx_threads: usize,
+    rcv_timeout_ms: u64,
+    ethertype: EtherType,
+    packet_capture: Option<&Arc<PacketCapture>>,
+) -> Result<DatalinkChannels, ScanError> {
+    let (tx, channel_rx) = open_datalink_channel(interface, rcv_timeout_ms)?;
+    let mut rx = open_response_receiver(interface, channel_rx, ethertype, rcv_timeout_ms)?;
+
+    let mut tx_list: Vec<Box<dyn DataLinkSender>> = vec![tx];
+    for _ in 1..tx_threads {
+        let (thread_tx, _) = open_datalink_channel(interface, rcv_timeout_ms)?;
+        tx_list.push(thread_tx);
+    }
+
+    if let Some(packet_capture) = packet_capture {
+        tx_list = tx_list
+            .into_iter()
+            .map(|tx| -> Box<dyn DataLinkSender> {
+                Box::new(CaptureSender::new(tx, Arc::clone(packet_capture)))
+            })
+            .collect();
+        rx = Box::new(CaptureReceiver::new(rx, Arc::clone(packet_capture)));
+    }
+
+    Ok((tx_list, rx))
+}
+
+/**
+ * Start a watchdog thread ending the whole scan once its wall-clock duration
+ * exceeds the given cap. The interruption flag is raised as done by CTRL+C,
+ * so that the current round stops sending & waiting and keeps its partial
+ * results. The watchdog exits early if the scan is interrupted otherwise.
+ */
+fn start_duration_watchdog(
+    max_duration_ms: u64,
+    has_reached_timeout: Arc<AtomicBool>,
+    is_verbose: bool,
+) {
+    let scan_start = Instant::now();
+    let max_duration = Duration::from_millis(max_duration_ms);
+    thread::spawn(move || {
+        while !has_reached_timeout.load(Ordering::Relaxed) {
+            let remaining = max_duration.saturating_sub(scan_start.elapsed());
+            if remaining.is_zero() {
+                if is_verbose {
+                    eprintln!(
+                        "[!] Maximum duration of {}s reached, ending scan with partial results...",
+                        max_duration_ms / 1000
+                    );
+                }
+                has_reached_timeout.store(true, Ordering::Relaxed);
+                break;
+            }
+            thread::sleep(remaining.min(Duration::from_millis(100)));
+        }
+    });
+}
+
+/**
+ * Groups together all details required to perform a scan round: the selected
+ * interface & networks, the scan timings and the interruption flag shared
+ * with the CTRL+C handler. Progress messages & streamed results are only
+ * written on stdout for verbose contexts (the CLI).
+ */
+struct ScanContext<'a> {
+    interface: &'a NetworkInterface,
+    ip_networks: Vec<&'a IpNetwork>,
+    scan_options: Arc<ScanOptions>,
+    source_mac: MacAddr,
+    source_ip: IpAddr,
+    network_size: u128,
+    interval_ms: u64,
+    target_interval_ms: u64,
+    target_seed: Option<u64>,
+    tracker: Arc<ScanTracker>,
+    decoy_pool: DecoyPool,
+    has_reached_timeout: Arc<AtomicBool>,
+    is_verbose: bool,
+}
+
+impl<'a> ScanContext<'a> {
+    fn new(
+        interface: &'a NetworkInterface,
+        ip_networks: Vec<&'a IpNetwork>,
+        scan_options: Arc<ScanOptions>,
+        has_reached_timeout: Arc<AtomicBool>,
+        is_verbose: bool,
+    ) -> Result<ScanContext<'a>, ScanError> {
+        let network_size = utils::compute_network_size(&ip_networks, scan_options.sample_percent);
+        let estimation = network::compute_scan_estimation(network_size, &scan_options);
+        let interval_ms = estimation.interval_ms;
+
+        // Decoys are paced as regular requests, each target then lasts for the
+        // interval of all its requests (including the decoys expected to be
+        // sent on average).
+        let target_interval_ms = match network_size {
+            0 => interval_ms,
+            _ => {
+                let packet_count = network_size + estimation.decoy_count;
+                (u128::from(interval_ms) * packet_count / network_size)
+                    .try_into()
+                    .unwrap_or(u64::MAX)
+            }
+        };
+
+        // A passive scan never sends ARP requests, the interface may therefore
+        // have no IPv4 address. IPv6 neighbor scans only require an IPv6
+        // address on the interface.
+        let (source_mac, source_ip) = match (scan_options.passive, scan_options.ipv6) {
+            (true, _) => (MacAddr::zero(), IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+            (false, false) => (
+                network::find_source_mac(interface, &scan_options)?,
+                IpAddr::V4(network::find_source_ip(
+                    interface,
+                    scan_options.source_ipv4,
+                )?),
+            ),
+            (false, true) => (
+                network::find_source_mac(interface, &scan_options)?,
+                IpAddr::V6(network::find_source_ipv6(interface, &ip_networks)?),
+            ),
+        };
+
+        // A sample should be the same for all retry passes, the target seed
+        // is therefore drawn once if sampling without any requested seed.
+        let target_seed = match scan_options.sample_percent {
+            Some(_) => Some(scan_options.seed.unwrap_or_else(rand::random)),
+            None => scan_options.seed,
+        };
+
+        Ok(ScanContext {
+            interface,
+            ip_networks,
+            scan_options,
+            source_mac,
+            source_ip,
+            network_size,
+            interval_ms,
+            target_interval_ms,
+            target_seed,
+            tracker: Arc::new(ScanTracker::new()),
+            decoy_pool: DecoyPool::new(),
+            has_reached_timeout,
+            is_verbose,
+        })
+    }
+
+    /**
+     * The scan tracker is shared by all rounds of the scan, which allows
+     * reading the live status of the current round from another thread.
+     */
+    fn tracker(&self) -> Arc<ScanTracker> {
+        Arc::clone(&self.tracker)
+    }
+
+    fn is_progress_output(&self) -> bool {
+        self.is_verbose && self.scan_options.is_progress_output()
+    }
+}
+
+/**
+ * Perform a single ARP scan round on the datalink channels. ARP requests are
+ * sent for all targets (with retries) by one thread per datalink sender, while
+ * a scoped thread collects the responses, until the timeout is reached or an
+ * interruption is received.
+ */
+fn perform_scan_round(
+    context: &ScanContext,
+    tx_list: &mut [Box<dyn DataLinkSender>],
+    rx: &mut Box<dyn DataLinkReceiver>,
+    vendor_list: &mut Vendor,
+) -> Result<(ResponseSummary, Vec<TargetDetails>), ScanError> {
+    let scan_options = &context.scan_options;
+    let has_reached_timeout = &context.has_reached_timeout;
+
+    // The 'timed_out' mutex is shared accross the main thread (which performs
+    // ARP packet sending) and the response thread (which receives and stores
+    // all ARP responses).
+    let timed_out = Arc::new(AtomicBool::new(false));
+    let cloned_timed_out = Arc::clone(&timed_out);
+
+    // Streamed outputs (such as NDJSON) are written by the response thread
+    // itself, as soon as a new target is discovered on the network.
+    let stream_writer: Option<Box<dyn Write + Send>> =
+        match context.is_verbose && scan_options.is_stream_output() {
+            true => Some(Box::new(std::io::stdout())),
+            false => None,
+        };
+
+    let pass_count = match scan_options.passive {
+        true => 0,
+        false => scan_options.retry_count,
+    };
+    let tracker: &ScanTracker = &context.tracker;
+    tracker.start_round();
+    context.decoy_pool.clear();
+    if !scan_options.passive {
+        tracker.record_source_mac(context.source_mac);
+        for pool_mac in &scan_options.mac_pool {
+            tracker.record_source_mac(*pool_mac);
+        }
+    }
+
+    let progress_bar = ProgressBar::new(
+        scan_options.compute_request_count(context.network_size),
+        context.target_interval_ms,
+        scan_options.timeout_ms,
+        pass_count,
+        context.is_progress_output(),
+    );
+
+    // The vendor list is lent to the response thread for the round only, it
+    // may still be needed afterwards to enhance the ARP cache entries.
+    let round_vendor_list: &mut Vendor = vendor_list;
+    let round_start_time = SystemTime::now();
+    let (mut response_summary, mut target_details, is_partial) = thread::scope(|scope| {
+        let cloned_options = Arc::clone(scan_options);
+        let cloned_reached_timeout = Arc::clone(has_reached_timeout);
+        let cloned_tracker = tracker;
+        let arp_responses = scope.spawn(move || {
+            network::receive_arp_responses(
+                rx,
+                cloned_options,
+                cloned_timed_out,
+                cloned_reached_timeout,
+                round_vendor_list,
+                stream_writer,
+                cloned_tracker,
+            )
+        });
+
+        // Each retry pass shares a single target iterator between all sender
+        // threads. Hosts that already responded are skipped on the following
+        // retry passes, unless all targets should be retried.
+        let total_sent = AtomicUsize::new(0);
+        for pass_index in 0..pass_count {
+            let has_reached_limit = scan_options
+                .request_limit
+                .is_some_and(|request_limit| total_sent.load(Ordering::Relaxed) >= request_limit);
+            if has_reached_timeout.load(Ordering::Relaxed) || has_reached_limit {
+                break;
+            }
+
+            // With '--retry-on-silence', retry passes only iterate over the
+            // addresses left unanswered by the previous passes. There is no
+            // need for another pass once all targets responded.
+            let is_silence_pass = pass_index > 0 && scan_options.retry_on_silence;
+            let pass_targets: TargetIterator = match is_silence_pass {
+                true => Box::new(iterate_silent_targets(
+                    &context.ip_networks,
+                    scan_options,
+                    context.target_seed,
+                    tracker,
+                    &progress_bar,
+                )),
+                false => Box::new(NetworkIterator::new(
+                    &context.ip_networks,
+                    &scan_options.excluded_networks,
+                    scan_options.target_order,
+                    context.target_seed,
+                    scan_options.sample_percent,
+                )),
+            };
+            let mut pass_targets = pass_targets.peekable();
+            if is_silence_pass && pass_targets.peek().is_none() {
+                break;
+            }
+
+            progress_bar.start_pass(pass_index);
+            let send_pass = SendPass {
+                ip_addresses: Mutex::new(pass_targets),
+                total_sent: &total_sent,
+                interval_ms: scan_options.compute_pass_interval(context.interval_ms, pass_index),
+                tracker,
+                progress_bar: &progress_bar,
+                skip_responded: pass_index > 0 && !scan_options.retry_all,
+            };
+
+            let send_result = thread::scope(|send_scope| {
+                let send_threads: Vec<_> = tx_list
+                    .iter_mut()
+                    .enumerate()
+                    .map(|(tx_index, tx)| {
+                        let send_pass = &send_pass;
+                        let rng_stream = (pass_index * scan_options.tx_threads + tx_index) as u64;
+                        let rng = utils::build_rng(scan_options.seed, rng_stream);
+                        send_scope.spawn(move || send_arp_requests(context, tx, send_pass, rng))
+                    })
+                    .collect();
+                send_threads
+                    .into_iter()
+                    .map(|send_thread| {
+                        send_thread
+                            .join()
+                            .unwrap_or_else(|panic_payload| panic::resume_unwind(panic_payload))
+                    })
+                    .collect::<Result<Vec<()>, ScanError>>()
+            });
+
+            // The response thread is stopped before failing, otherwise the
+            // thread scope would wait for it forever.
+            if let Err(err) = send_result {
+                timed_out.store(true, Ordering::Relaxed);
+                return Err(err);
+            }
+        }
+
+        let total_sent = total_sent.into_inner();
+        progress_bar.finish();
+        let missing_decoys = context.decoy_pool.missing_count();
+        if context.is_verbose && missing_decoys > 0 {
+            eprintln!(
+                "[warn] {} of {} decoy requests were not sent, no silent address was known yet (see --decoys)",
+                missing_decoys,
+                total_sent * scan_options.decoy_count
+            );
+        }
+        if context.is_progress_output() && scan_options.passive {
+            println!(
+                "Listening for ARP traffic (timeout: {}ms)",
+                scan_options.timeout_ms
+            );
+        }
+
+        if context.is_progress_output() && total_sent > 0 {
+            println!(
+                "Sent: {} packets · Waiting for responses (timeout: {}ms)",
+                total_sent, scan_options.timeout_ms
+            );
+        }
+
+        // Once the ARP packets are sent, the main thread will sleep for T seconds
+        // (where T is the timeout option). After the sleep phase, the response
+        // thread will receive a stop request through the 'timed_out' mutex.
+        let effective_timeout_ms = match scan_options.adaptive_timeout {
+            true => wait_adaptive_timeout(context, tracker),
+            false => {
+                let mut sleep_ms_mount: u64 = 0;
+                while !has_reached_timeout.load(Ordering::Relaxed)
+                    && sleep_ms_mount < scan_options.timeout_ms
+                {
+                    thread::sleep(Duration::from_millis(100));
+                    sleep_ms_mount += 100;
+                }
+                None
+            }
+        };
+        timed_out.store(true, Ordering::Relaxed);
+
+        // A panic of the response thread (e.g. on an unexpected frame) keeps
+        // the targets published so far, instead of losing the whole scan.
+        let (mut response_summary, target_details, is_partial) = match arp_responses.join() {
+            Ok(responses) => {
+                let (response_summary, target_details) = responses?;
+                (response_summary, target_details, false)
+            }
+            Err(panic_payload) => {
+                let target_details = tracker.take_discovered_targets();
+                eprintln!(
+                    "[warn] Receive thread failed ({}), keeping the {} hosts discovered so far",
+                    describe_panic(panic_payload.as_ref()),
+                    target_details.len()
+                );
+                (
+                    build_partial_summary(round_start_time),
+                    target_details,
+                    true,
+                )
+            }
+        };
+        response_summary.effective_timeout_ms = effective_timeout_ms;
+        response_summary.send_errors = tracker.send_error_count();
+
+        // Requests that never left the interface make the scan estimations
+        // optimistic, the user is warned when this becomes significant.
+        if context.is_verbose
+            && has_significant_send_errors(response_summary.send_errors, total_sent)
+        {
+            eprintln!(
+                "[warn] {} of {} requests could not be sent, consider increasing the interval (-I)",
+                response_summary.send_errors, total_sent
+            );
+        }
+
+        Ok((response_summary, target_details, is_partial))
+    })?;
+
+    // Partial targets were not enhanced by the response thread, the vendor
+    // list is available again once the thread scope is closed.
+    if is_partial {
+        response_summary.dns_timeout_count +=
+            network::enhance_target_details(&mut target_details, scan_options, vendor_list)?;
+    }
+
+    if scan_options.include_cache {
+        let cache_entries = network::read_arp_cache(&context.interface.name);
+        response_summary.dns_timeout_count += network::merge_arp_cache(
+            &mut target_details,
+            &cache_entries,
+            &context.ip_networks,
+            scan_options,
+            vendor_list,
+        )?;
+    }
+
+    Ok((response_summary, target_details))
+}
+
+/**
+ * Build the summary of a round whose response thread failed. Packet counts
+ * are lost with the thread, only the round timings are known.
+ */
+fn build_partial_summary(start_time: SystemTime) -> ResponseSummary {
+    let end_time = SystemTime::now();
+    ResponseSummary {
+        packet_count: 0,
+        arp_count: 0,
+        duration_ms: end_time
+            .duration_since(start_time)
+            .unwrap_or_default()
+            .as_millis(),
+        duplicates: vec![],
+        effective_timeout_ms: None,
+        dns_timeout_count: 0,
+        gratuitous_count: 0,
+        malformed_count: 0,
+        filtered_count: 0,
+        seen_count: 0,
+        unknown_vendor_count: 0,
+        send_errors: 0,
+        start_time,
+        end_time,
+    }
+}
+
+/**
+ * Extract the message of a thread panic, which is usually a string.
+ */
+fn describe_panic(panic_payload: &(dyn Any + Send)) -> String {
+    match panic_payload.downcast_ref::<&str>() {
+        Some(message) => message.to_string(),
+        None => match panic_payload.downcast_ref::<String>() {
+            Some(message) => message.clone(),
+            None => "unknown panic".to_string(),
+        },
+    }
+}
+
+/**
+ * Send errors are significant when at least 1% of the requests could not be
+ * sent, a few isolated errors are not worth a warning.
+ */
+fn has_significant_send_errors(send_errors: usize, total_sent: usize) -> bool {
+    send_errors > 0 && send_errors * 100 >= total_sent * SEND_ERROR_WARNING_PERCENT
+}
+
+/**
+ * Wait for ARP responses with an adaptive timeout. The wait phase ends when no
+ * response has been received during the adaptive window (computed from the
+ * observed RTTs), or when the adaptive limit is reached. The effective wait
+ * duration is returned, if the wait phase was not interrupted.
+ */
+fn wait_adaptive_timeout(context: &ScanContext, tracker: &ScanTracker) -> Option<u128> {
+    let timeout_ms = context.scan_options.timeout_ms;
+    let wait_start = Instant::now();
+
+    while !context.has_reached_timeout.load(Ordering::Relaxed) {
+        let waited_ms = wait_start.elapsed().as_millis();
+        let quiet_start = match tracker.last_response() {
+            Some(last_response) if last_response > wait_start => last_response,
+            _ => wait_start,
+        };
+
+        let has_quiet_window =
+            quiet_start.elapsed().as_millis() >= tracker.compute_adaptive_window(timeout_ms);
+        if has_quiet_window || waited_ms >= ScanTracker::compute_adaptive_limit(timeout_ms) {
+            return Some(waited_ms);
+        }
+
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    None
+}
+
+/**
+ * Iterate over the targets that did not respond yet, so that a retry pass only
+ * sends requests to them. The targets are enumerated lazily in the requested
+ * order (with exclusions & sampling), the silent addresses are never listed
+ * in memory even on wide networks. Targets that responded are counted as
+ * skipped on the progress bar.
+ */
+fn iterate_silent_targets<'p>(
+    ip_networks: &[&IpNetwork],
+    scan_options: &ScanOptions,
+    target_seed: Option<u64>,
+    tracker: &'p ScanTracker,
+    progress_bar: &'p ProgressBar,
+) -> impl Iterator<Item = IpAddr> + Send + 'p {
+    NetworkIterator::new(
+        ip_networks,
+        &scan_options.excluded_networks,
+        scan_options.target_order,
+        target_seed,
+        scan_options.sample_percent,
+    )
+    .filter(move |ip_address| {
+        let has_responded = tracker.has_responded(*ip_address);
+        if has_responded {
+            progress_bar.skip_target();
+        }
+        !has_responded
+    })
+}
+
+/**
+ * Groups together the state shared by all sender threads during a single
+ * request pass: the target iterator, the sent request counter, the request
+ * interval of the pass, the response tracker and the progress bar.
+ */
+struct SendPass<'p> {
+    ip_addresses: Mutex<Peekable<TargetIterator<'p>>>,
+    total_sent: &'p AtomicUsize,
+    interval_ms: u64,
+    tracker: &'p ScanTracker,
+    progress_bar: &'p ProgressBar,
+    skip_responded: bool,
+}
+
+/**
+ * Send ARP requests from a single sender thread, until all targets of the
+ * shared iterator have been consumed. With multiple sender threads, each
+ * thread waits N times the interval so that the global scan rate is kept.
+ * Hosts that already responded may be skipped (for retry passes).
+ */
+fn send_arp_requests(
+    context: &ScanContext,
+    tx: &mut Box<dyn DataLinkSender>,
+    send_pass: &SendPass,
+    mut rng: StdRng,
+) -> Result<(), ScanError> {
+    let scan_options = &context.scan_options;
+    let SendPass {
+        ip_addresses,
+        total_sent,
+        interval_ms,
+        tracker,
+        progress_bar,
+        skip_responded,
+    } = send_pass;
+    let request_limit = scan_options.request_limit.unwrap_or(usize::MAX);
+    let thread_interval_ms = interval_ms * scan_options.tx_threads as u64;
+
+    loop {
+        if context.has_reached_timeout.load(Ordering::Relaxed) {
+            break;
+        }
+
+        // A poisoned lock only means that another sender thread panicked, the
+        // remaining targets can still be consumed.
+        let next_address = ip_addresses
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .next();
+
+        let ip_address = match next_address {
+            Some(ip_address) => ip_address,
+            None => break,
+        };
+
+        // Targets are checked against the scan mode, so that only IPv4
+        // addresses are sent ARP requests and only IPv6 addresses are sent
+        // neighbor solicitations.
+        let is_same_family = ip_address.is_ipv4() == context.source_ip.is_ipv4();
+        if !is_same_family || (*skip_responded && tracker.has_responded(ip_address)) {
+            progress_bar.skip_target();
+            progress_bar.update(total_sent.load(Ordering::Relaxed));
+            continue;
+        }
+
+        // Each request slot is reserved before sending, so that the request
+        // limit is never exceeded with multiple sender threads.
+        let reserved_count =
+            total_sent.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |sent_count| {
+                (sent_count < request_limit).then_some(sent_count + 1)
+            });
+        let sent_count = match reserved_count {
+            Ok(previous_count) => previous_count + 1,
+            Err(_) => break,
+        };
+
+        // With '--decoys', the real request is sent at a random position among
+        // the decoy requests, so that it cannot be told apart by its order.
+        let source_mac = select_source_mac(context, sent_count, &mut rng);
+        let decoys_before = match scan_options.decoy_count {
+            0 => 0,
+            decoy_count => rng.gen_range(0..=decoy_count),
+        };
+        let decoys_sent_before = send_decoy_requests(
+            context,
+            tx,
+            source_mac,
+            ip_address,
+            decoys_before,
+            thread_interval_ms,
+            &mut rng,
+        )?;
+
+        if scan_options.adaptive_timeout || scan_options.show_latency {
+            tracker.record_request(ip_address);
+        }
+        tracker.record_sent();
+        let is_sent = match (ip_address, context.source_ip) {
+            (IpAddr::V4(target_ipv4), IpAddr::V4(source_ipv4)) => network::send_arp_request(
+                tx,
+                context.interface,
+                source_mac,
+                source_ipv4,
+                target_ipv4,
+                Arc::clone(scan_options),
+            )?,
+            (IpAddr::V6(target_ipv6), IpAddr::V6(source_ipv6)) => ndp::send_neighbor_solicitation(
+                tx,
+                context.interface,
+                source_mac,
+                source_ipv6,
+                target_ipv6,
+            )?,
+            _ => true,
+        };
+        if !is_sent {
+            tracker.record_send_error();
+        }
+        if let (true, IpAddr::V4(target_ipv4)) = (scan_options.decoy_count > 0, ip_address) {
+            context.decoy_pool.record_probe(target_ipv4);
+        }
+        progress_bar.update(sent_count);
+        thread::sleep(compute_jittered_interval(
+            thread_interval_ms,
+            scan_options.jitter_percent,
+            &mut rng,
+        ));
+
+        let decoys_after = scan_options.decoy_count - decoys_before;
+        let decoys_sent_after = send_decoy_requests(
+            context,
+            tx,
+            source_mac,
+            ip_address,
+            decoys_after,
+            thread_interval_ms,
+            &mut rng,
+        )?;
+
+        let decoys_sent = decoys_sent_before + decoys_sent_after;
+        if decoys_sent < scan_options.decoy_count && ip_address.is_ipv4() {
+            context
+                .decoy_pool
+                .record_missing(scan_options.decoy_count - decoys_sent);
+            if scan_options.is_frame_logged() {
+                eprintln!(
+                    "[vv] Sent {} of {} decoy requests to {} (no silent address known yet)",
+                    decoys_sent, scan_options.decoy_count, ip_address
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/**
+ * Send decoy ARP requests to a target (see '--decoys'), each one from a
+ * spoofed source IPv4 drawn in the decoy pool. Decoys are neither counted as
+ * sent requests nor as send errors, and their replies are dropped by the
+ * response thread. Each decoy is followed by the request interval, so that
+ * the requested interval & bandwidth also cover the decoys. Returns the number
+ * of sent decoys, which is lower than requested while no silent address is
+ * known.
+ */
+fn send_decoy_requests(
+    context: &ScanContext,
+    tx: &mut Box<dyn DataLinkSender>,
+    source_mac: MacAddr,
+    target_ip: IpAddr,
+    decoy_count: usize,
+    thread_interval_ms: u64,
+    rng: &mut StdRng,
+) -> Result<usize, ScanError> {
+    let (target_ipv4, source_ipv4) = match (target_ip, context.source_ip) {
+        (IpAddr::V4(target_ipv4), IpAddr::V4(source_ipv4)) => (target_ipv4, source_ipv4),
+        _ => return Ok(0),
+    };
+
+    let timeout = Duration::from_millis(context.scan_options.timeout_ms);
+    let mut sent_count = 0;
+    for _ in 0..decoy_count {
+        let decoy_ipv4 =
+            context
+                .decoy_pool
+                .select(&context.tracker, timeout, &[source_ipv4, target_ipv4], rng);
+        if let Some(decoy_ipv4) = decoy_ipv4 {
+            context.tracker.record_decoy(decoy_ipv4);
+            network::send_arp_request(
+                tx,
+                context.interface,
+                source_mac,
+                decoy_ipv4,
+                target_ipv4,
+                Arc::clone(&context.scan_options),
+            )?;
+            thread::sleep(compute_jittered_interval(
+                thread_interval_ms,
+                context.scan_options.jitter_percent,
+                rng,
+            ));
+            sent_count += 1;
+        }
+    }
+    Ok(sent_count)
+}
+
+/**
+ * The spoofed sources of the decoy requests. Targets may cache a decoy source
+ * with the MAC of the scanning interface, only addresses confirmed silent are
+ * therefore used: addresses that were sent a request more than the timeout
+ * ago, and did not respond since. No decoy is sent until such addresses are
+ * known (e.g. on the first requests of a scan).
+ */
+struct DecoyPool {
+    probed_ips: Mutex<VecDeque<(Instant, Ipv4Addr)>>,
+    silent_ips: Mutex<Vec<Ipv4Addr>>,
+    missing_count: AtomicUsize,
+}
+
+impl DecoyPool {
+    fn new() -> Self {
+        DecoyPool {
+            probed_ips: Mutex::new(VecDeque::new()),
+            silent_ips: Mutex::new(vec![]),
+            missing_count: AtomicUsize::new(0),
+        }
+    }
+
+    /**
+     * Forget all addresses, hosts may have joined the network since the
+     * previous round.
+     */
+    fn clear(&self) {
+        lock_pool(&self.probed_ips).clear();
+        lock_pool(&self.silent_ips).clear();
+        self.missing_count.store(0, Ordering::Relaxed);
+    }
+
+    /**
+     * Count the decoy requests that could not be sent, since no silent
+     * address was known yet.
+     */
+    fn record_missing(&self, missing_count: usize) {
+        self.missing_count
+            .fetch_add(missing_count, Ordering::Relaxed);
+    }
+
+    fn missing_count(&self) -> usize {
+        self.missing_count.load(Ordering::Relaxed)
+    }
+
+    /**
+     * Record a probed address, which may become a decoy source once the
+     * timeout is reached without any response.
+     */
+    fn record_probe(&self, ipv4: Ipv4Addr) {
+        let mut probed_ips = lock_pool(&self.probed_ips);
+        if probed_ips.len() < DECOY_POOL_MAX {
+            probed_ips.push_back((Instant::now(), ipv4));
+        }
+    }
+
+    /**
+     * Draw a decoy source among the silent addresses, other than the given
+     * excluded addresses (the scan source & the target).
+     */
+    fn select(
+        &self,
+        tracker: &ScanTracker,
+        timeout: Duration,
+        excluded_ips: &[Ipv4Addr],
+        rng: &mut StdRng,
+    ) -> Option<Ipv4Addr> {
+        let mut probed_ips = lock_pool(&self.probed_ips);
+        let mut silent_ips = lock_pool(&self.silent_ips);
+
+        while let Some((probe_time, ipv4)) = probed_ips.front().copied() {
+            if probe_time.elapsed() < timeout {
+                break;
+            }
+            probed_ips.pop_front();
+            if silent_ips.len() < DECOY_POOL_MAX && !silent_ips.contains(&ipv4) {
+                silent_ips.push(ipv4);
+            }
+        }
+        silent_ips.retain(|ipv4| !tracker.has_responded(IpAddr::V4(*ipv4)));
+
+        let candidate_ips: Vec<Ipv4Addr> = silent_ips
+            .iter()
+            .filter(|ipv4| !excluded_ips.contains(ipv4))
+            .copied()
+            .collect();
+        candidate_ips.choose(rng).copied()
+    }
+}
+
+// A poisoned lock only means that another sender thread panicked, the pool
+// stays consistent since each update is a single insertion or removal.
+fn lock_pool<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/**
+ * Select the source MAC of a request. With a MAC pool, the addresses are
+ * rotated in order over all sender threads (using the global request count),
+ * or drawn from the seeded generator when targets are randomized.
+ */
+fn select_source_mac(context: &ScanContext, sent_count: usize, rng: &mut StdRng) -> MacAddr {
+    let mac_pool = &context.scan_options.mac_pool;
+    match (mac_pool.is_empty(), context.scan_options.has_random_order()) {
+        (true, _) => context.source_mac,
+        (false, false) => mac_pool[(sent_count - 1) % mac_pool.len()],
+        (false, true) => mac_pool[rng.gen_range(0..mac_pool.len())],
+    }
+}
+
+/**
+ * Compute the wait duration between two ARP requests. With a jitter, each
+ * interval is randomized within ±percent of the requested interval (with a
+ * uniform distribution), so that the average scan rate is left unchanged.
+ */
+fn compute_jittered_interval(
+    interval_ms: u64,
+    jitter_percent: Option<u8>,
+    rng: &mut StdRng,
+) -> Duration {
+    let interval = Duration::from_millis(interval_ms);
+    match jitter_percent {
+        Some(jitter_percent) if jitter_percent > 0 && interval_ms > 0 => {
+            let jitter_ratio = f64::from(jitter_percent) / 100.0;
+            interval.mul_f64(1.0 + rng.gen_range(-jitter_ratio..=jitter_ratio))
+        }
+        _ => interval,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::args::build_args;
+    use std::collections::HashSet;
+
+    #[test]
+    fn should_iterate_silent_targets() {
+        let ip_network: IpNetwork = "192.168.1.0/30".parse().unwrap();
+        let options = ScanOptions::new(&build_args().get_matches_from(vec![
+            "arp-scan",
+            "--retry-on-silence",
+            "-x",
+            "192.168.1.3",
+        ]))
+        .unwrap();
+        let tracker = ScanTracker::new();
+        tracker.record_response(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1)));
+        let progress_bar = ProgressBar::new(6, 10, 2000, 2, false);
+
+        let silent_targets: Vec<IpAddr> =
+            iterate_silent_targets(&[&ip_network], &options, None, &tracker, &progress_bar)
+                .collect();
+
+        assert_eq!(
+            silent_targets,
+            vec![
+                IpAddr::V4(Ipv4Addr::new(192, 168, 1, 0)),
+                IpAddr::V4(Ipv4Addr::new(192, 168, 1, 2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_only_select_silent_decoys() {
+        let decoy_pool = DecoyPool::new();
+        let tracker = ScanTracker::new();
+        let mut rng = utils::build_rng(Some(42), 0);
+        let source_ipv4 = Ipv4Addr::new(192, 168, 1, 2);
+        let silent_ipv4 = Ipv4Addr::new(192, 168, 1, 10);
+        let live_ipv4 = Ipv4Addr::new(192, 168, 1, 11);
+        let recent_ipv4 = Ipv4Addr::new(192, 168, 1, 12);
+
+        decoy_pool.record_probe(silent_ipv4);
+        decoy_pool.record_probe(live_ipv4);
+        tracker.record_response(IpAddr::V4(live_ipv4));
+        let early_decoy =
+            decoy_pool.select(&tracker, Duration::from_secs(60), &[source_ipv4], &mut rng);
+        thread::sleep(Duration::from_millis(20));
+        decoy_pool.record_probe(recent_ipv4);
+
+        let decoys: HashSet<Option<Ipv4Addr>> = (0..20)
+            .map(|_| {
+                decoy_pool.select(
+                    &tracker,
+                    Duration::from_millis(10),
+                    &[source_ipv4],
+                    &mut rng,
+                )
+            })
+            .collect();
+        let target_decoy = decoy_pool.select(
+            &tracker,
+            Duration::from_millis(10),
+            &[silent_ipv4],
+            &mut rng,
+        );
+
+        assert_eq!(early_decoy, None);
+        assert_eq!(decoys, HashSet::from([Some(silent_ipv4)]));
+        assert_eq!(target_decoy, None);
+
+        decoy_pool.clear();
+        assert_eq!(
+            decoy_pool.select(&tracker, Duration::ZERO, &[source_ipv4], &mut rng),
+            None
+        );
+    }
+
+    #[test]
+    fn should_raise_timeout_after_max_duration() {
+        let has_reached_timeout = Arc::new(AtomicBool::new(false));
+
+        start_duration_watchdog(50, Arc::clone(&has_reached_timeout), false);
+        assert!(!has_reached_timeout.load(Ordering::Relaxed));
+
+        let watchdog_start = Instant::now();
+        while !has_reached_timeout.load(Ordering::Relaxed)
+            && watchdog_start.elapsed() < Duration::from_secs(2)
+        {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(has_reached_timeout.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn should_keep_interval_without_jitter() {
+        let mut rng = utils::build_rng(Some(42), 0);
+
+        assert_eq!(
+            compute_jittered_interval(10, None, &mut rng),
+            Duration::from_millis(10)
+        );
+        assert_eq!(
+            compute_jittered_interval(10, Some(0), &mut rng),
+            Duration::from_millis(10)
+        );
+    }
+
+    #[test]
+    fn should_keep_average_interval_with_jitter() {
+        let mut rng = utils::build_rng(Some(42), 0);
+
+        let intervals: Vec<Duration> = (0..10_000)
+            .map(|_| compute_jittered_interval(10, Some(50), &mut rng))
+            .collect();
+        let average_ms = intervals.iter().sum::<Duration>().as_secs_f64() * 1000.0 / 10_000.0;
+
+        assert!(intervals
+            .iter()
+            .all(|interval| *interval >= Duration::from_millis(5)
+                && *interval <= Duration::from_millis(15)));
+        assert!((average_ms - 10.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn should_warn_on_significant_send_errors() {
+        assert!(!has_significant_send_errors(0, 1000));
+        assert!(!has_significant_send_errors(9, 1000));
+        assert!(has_significant_send_errors(10, 1000));
+        assert!(has_significant_send_errors(1, 1));
+    }
+
+    #[test]
+    fn should_reproduce_jitter_with_seed() {
+        let mut first_rng = utils::build_rng(Some(42), 3);
+        let mut second_rng = utils::build_rng(Some(42), 3);
+
+        for _ in 0..100 {
+            assert_eq!(
+                compute_jittered_interval(10, Some(30), &mut first_rng),
+                compute_jittered_interval(10, Some(30), &mut second_rng)
+            );
+        }
+    }
+}
@@ -0,0 +1,295 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
+use std::time::{Duration, Instant};
+
+const SNMP_PORT: u16 = 161;
+const SNMP_VERSION_2C: u8 = 1;
+const SNMP_MAX_MESSAGE_SIZE: usize = 1500;
+
+// BER encoding of the sysName.0 object identifier (1.3.6.1.2.1.1.5.0)
+const SYS_NAME_OID: [u8; 8] = [0x2b, 0x06, 0x01, 0x02, 0x01, 0x01, 0x05, 0x00];
+
+const BER_INTEGER: u8 = 0x02;
+const BER_OCTET_STRING: u8 = 0x04;
+const BER_NULL: u8 = 0x05;
+const BER_OID: u8 = 0x06;
+const BER_SEQUENCE: u8 = 0x30;
+const SNMP_GET_REQUEST: u8 = 0xa0;
+const SNMP_GET_RESPONSE: u8 = 0xa2;
+
+/**
+ * Query the system name (sysName.0) of a host with a single SNMPv2c GET
+ * request. Returns None if the host does not answer before the timeout, if
+ * the community is rejected or if the object is not available.
+ */
+pub fn query_sys_name(ip: IpAddr, community: &str, timeout_ms: u64) -> Option<String> {
+    let bind_address: IpAddr = match ip {
+        IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+        IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+    };
+    let socket = UdpSocket::bind(SocketAddr::new(bind_address, 0)).ok()?;
+    let target_address = SocketAddr::new(ip, SNMP_PORT);
+
+    let request_id: u32 = rand::random::<u32>() & 0x7fff_ffff;
+    let request = build_get_request(community, request_id);
+    socket.send_to(&request, target_address).ok()?;
+
+    // Datagrams from other hosts (or late responses) are skipped, until the
+    // expected response is received or the timeout is reached.
+    let deadline = Instant::now() + Duration::from_millis(timeout_ms);
+    let mut response_buffer = [0u8; SNMP_MAX_MESSAGE_SIZE];
+    loop {
+        let remaining = deadline.checked_duration_since(Instant::now())?;
+        socket
+            .set_read_timeout(Some(remaining.max(Duration::from_millis(1))))
+            .ok()?;
+        let (response_len, response_address) = socket.recv_from(&mut response_buffer).ok()?;
+        if response_address != target_address {
+            continue;
+        }
+        if let Some(sys_name) = parse_sys_name(&response_buffer[..response_len], request_id) {
+            return Some(sys_name);
+        }
+    }
+}
+
+/**
+ * Build a SNMPv2c GET request for the sysName.0 object.
+ */
+fn build_get_request(community: &str, request_id: u32) -> Vec<u8> {
+    let variable_binding = encode_tlv(
+        BER_SEQUENCE,
+        &[
+            encode_tlv(BER_OID, &SYS_NAME_OID),
+            encode_tlv(BER_NULL, &[]),
+        ]
+        .concat(),
+    );
+    let pdu = encode_tlv(
+        SNMP_GET_REQUEST,
+        &[
+            encode_integer(request_id),
+            encode_tlv(BER_INTEGER, &[0]),
+            encode_tlv(BER_INTEGER, &[0]),
+            encode_tlv(BER_SEQUENCE, &variable_binding),
+        ]
+        .concat(),
+    );
+
+    encode_tlv(
+        BER_SEQUENCE,
+        &[
+            encode_tlv(BER_INTEGER, &[SNMP_VERSION_2C]),
+            encode_tlv(BER_OCTET_STRING, community.as_bytes()),
+            pdu,
+        ]
+        .concat(),
+    )
+}
+
+/**
+ * Encode a positive BER integer on the minimal number of bytes, a leading
+ * zero byte being kept when the high bit would make the value negative.
+ */
+fn encode_integer(value: u32) -> Vec<u8> {
+    let value_bytes = value.to_be_bytes();
+    let first_index = value_bytes
+        .windows(2)
+        .take_while(|pair| pair[0] == 0 && pair[1] & 0x80 == 0)
+        .count();
+    encode_tlv(BER_INTEGER, &value_bytes[first_index..])
+}
+
+/**
+ * Encode a BER type-length-value, with the short length form for contents
+ * below 128 bytes and the long form otherwise.
+ */
+fn encode_tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut tlv = vec![tag];
+    match content.len() {
+        length if length < 0x80 => tlv.push(length as u8),
+        length => {
+            let length_bytes: Vec<u8> = (length as u32)
+                .to_be_bytes()
+                .into_iter()
+                .skip_while(|byte| *byte == 0)
+                .collect();
+            tlv.push(0x80 | length_bytes.len() as u8);
+            tlv.extend(length_bytes);
+        }
+    }
+    tlv.extend_from_slice(content);
+    tlv
+}
+
+/**
+ * Read the BER type-length-values of a buffer one after another. Every read
+ * is bounds-checked, truncated or malformed messages give None.
+ */
+struct BerReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> BerReader<'a> {
+    fn read_tlv(&mut self) -> Option<(u8, &'a [u8])> {
+        let (&tag, rest) = self.data.split_first()?;
+        let (&first_length, rest) = rest.split_first()?;
+
+        let (length, rest) = match first_length {
+            length if length < 0x80 => (length as usize, rest),
+            length => {
+                let length_size = (length & 0x7f) as usize;
+                if length_size == 0 || length_size > 4 || rest.len() < length_size {
+                    return None;
+                }
+                let (length_bytes, rest) = rest.split_at(length_size);
+                let length = length_bytes
+                    .iter()
+                    .fold(0usize, |length, byte| (length << 8) | *byte as usize);
+                (length, rest)
+            }
+        };
+
+        if rest.len() < length {
+            return None;
+        }
+        let (content, rest) = rest.split_at(length);
+        self.data = rest;
+        Some((tag, content))
+    }
+
+    fn read_expected(&mut self, expected_tag: u8) -> Option<&'a [u8]> {
+        match self.read_tlv()? {
+            (tag, content) if tag == expected_tag => Some(content),
+            _ => None,
+        }
+    }
+}
+
+fn decode_integer(content: &[u8]) -> Option<i64> {
+    if content.is_empty() || content.len() > 8 {
+        return None;
+    }
+    let initial: i64 = match content[0] & 0x80 {
+        0 => 0,
+        _ => -1,
+    };
+    Some(
+        content
+            .iter()
+            .fold(initial, |value, byte| (value << 8) | *byte as i64),
+    )
+}
+
+/**
+ * Parse a SNMP GET response, and extract the sysName.0 value if the response
+ * matches the request and carries no error. Exceptions (noSuchObject, ...)
+ * and empty names give None.
+ */
+fn parse_sys_name(response: &[u8], request_id: u32) -> Option<String> {
+    let mut message = BerReader {
+        data: BerReader { data: response }.read_expected(BER_SEQUENCE)?,
+    };
+    message.read_expected(BER_INTEGER)?;
+    message.read_expected(BER_OCTET_STRING)?;
+
+    let mut pdu = BerReader {
+        data: message.read_expected(SNMP_GET_RESPONSE)?,
+    };
+    if decode_integer(pdu.read_expected(BER_INTEGER)?)? != i64::from(request_id) {
+        return None;
+    }
+    if decode_integer(pdu.read_expected(BER_INTEGER)?)? != 0 {
+        return None;
+    }
+    pdu.read_expected(BER_INTEGER)?;
+
+    let mut variable_bindings = BerReader {
+        data: pdu.read_expected(BER_SEQUENCE)?,
+    };
+    let mut variable_binding = BerReader {
+        data: variable_bindings.read_expected(BER_SEQUENCE)?,
+    };
+    if variable_binding.read_expected(BER_OID)? != SYS_NAME_OID {
+        return None;
+    }
+
+    let sys_name = variable_binding.read_expected(BER_OCTET_STRING)?;
+    let sys_name = String::from_utf8_lossy(sys_name).trim().to_string();
+    match sys_name.is_empty() {
+        true => None,
+        false => Some(sys_name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn build_get_response(request_id: u32, error_status: u8, value: Vec<u8>) -> Vec<u8> {
+        let variable_binding = encode_tlv(
+            BER_SEQUENCE,
+            &[encode_tlv(BER_OID, &SYS_NAME_OID), value].concat(),
+        );
+        let pdu = encode_tlv(
+            SNMP_GET_RESPONSE,
+            &[
+                encode_integer(request_id),
+                encode_tlv(BER_INTEGER, &[error_status]),
+                encode_tlv(BER_INTEGER, &[0]),
+                encode_tlv(BER_SEQUENCE, &variable_binding),
+            ]
+            .concat(),
+        );
+        encode_tlv(
+            BER_SEQUENCE,
+            &[
+                encode_tlv(BER_INTEGER, &[SNMP_VERSION_2C]),
+                encode_tlv(BER_OCTET_STRING, b"public"),
+                pdu,
+            ]
+            .concat(),
+        )
+    }
+
+    #[test]
+    fn should_build_get_request() {
+        let request = build_get_request("public", 0x1234);
+
+        assert_eq!(
+            request,
+            vec![
+                0x30, 0x27, 0x02, 0x01, 0x01, 0x04, 0x06, b'p', b'u', b'b', b'l', b'i', b'c', 0xa0,
+                0x1a, 0x02, 0x02, 0x12, 0x34, 0x02, 0x01, 0x00, 0x02, 0x01, 0x00, 0x30, 0x0e, 0x30,
+                0x0c, 0x06, 0x08, 0x2b, 0x06, 0x01, 0x02, 0x01, 0x01, 0x05, 0x00, 0x05, 0x00
+            ]
+        );
+        assert_eq!(encode_integer(0), vec![0x02, 0x01, 0x00]);
+        assert_eq!(encode_integer(0x80), vec![0x02, 0x02, 0x00, 0x80]);
+        assert_eq!(
+            &encode_tlv(BER_OCTET_STRING, &[0; 200])[..3],
+            &[0x04, 0x81, 200]
+        );
+    }
+
+    #[test]
+    fn should_parse_sys_name() {
+        let name_value = encode_tlv(BER_OCTET_STRING, b"core-switch-01");
+        let response = build_get_response(42, 0, name_value.clone());
+
+        assert_eq!(
+            parse_sys_name(&response, 42),
+            Some("core-switch-01".to_string())
+        );
+        assert_eq!(parse_sys_name(&response, 43), None);
+        assert_eq!(parse_sys_name(&response[..response.len() - 4], 42), None);
+        assert_eq!(
+            parse_sys_name(&build_get_response(42, 2, name_value), 42),
+            None
+        );
+        assert_eq!(
+            parse_sys_name(&build_get_response(42, 0, vec![0x80, 0x00]), 42),
+            None
+        );
+    }
+}
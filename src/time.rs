@@ -1,3 +1,5 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
 /**
  * Parse a given time string into milliseconds. This can be used to convert a
  * string such as '20ms', '10s' or '1h' into adequate milliseconds. Without
@@ -67,10 +69,55 @@ pub fn format_milliseconds(milliseconds: u128) -> String {
     format!("{}h", hours)
 }
 
+/**
+ * Format a system time as an RFC3339 string in UTC, with a second precision
+ * (e.g. '2024-03-01T14:05:09Z'). Times before the UNIX epoch are formatted
+ * as the epoch itself.
+ */
+pub fn format_rfc3339(time: SystemTime) -> String {
+    let epoch_seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+
+    let days = epoch_seconds / 86_400;
+    let day_seconds = epoch_seconds % 86_400;
+
+    // Convert the number of days since the epoch to a civil date, based on
+    // the proleptic Gregorian calendar (eras of 400 years).
+    let shifted_days = days + 719_468;
+    let era = shifted_days / 146_097;
+    let day_of_era = shifted_days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = match shifted_month < 10 {
+        true => shifted_month + 3,
+        false => shifted_month - 9,
+    };
+    let year = match month <= 2 {
+        true => year_of_era + era * 400 + 1,
+        false => year_of_era + era * 400,
+    };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        day_seconds / 3600,
+        day_seconds % 3600 / 60,
+        day_seconds % 60
+    )
+}
+
 #[cfg(test)]
 mod tests {
 
     use super::*;
+    use std::time::Duration;
 
     #[test]
     fn should_parse_milliseconds() {
@@ -128,4 +175,23 @@ mod tests {
     fn should_display_hours() {
         assert_eq!(format_milliseconds(4_200_000), "1h".to_string());
     }
+
+    // ---
+
+    #[test]
+    fn should_format_epoch_as_rfc3339() {
+        assert_eq!(format_rfc3339(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+    }
+
+    #[test]
+    fn should_format_leap_day_as_rfc3339() {
+        let time = UNIX_EPOCH + Duration::from_secs(1_709_215_509);
+        assert_eq!(format_rfc3339(time), "2024-02-29T14:05:09Z");
+    }
+
+    #[test]
+    fn should_format_year_end_as_rfc3339() {
+        let time = UNIX_EPOCH + Duration::from_secs(978_307_199);
+        assert_eq!(format_rfc3339(time), "2000-12-31T23:59:59Z");
+    }
 }
@@ -1,15 +1,34 @@
+use std::cmp::Ordering;
 use std::env;
-use std::process;
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::net::Ipv4Addr;
+use std::sync::atomic::{self, AtomicUsize};
 use std::sync::Arc;
+use std::thread;
 
 use ansi_term::Color::{Blue, Green, Red, Yellow};
 use ansi_term::Style;
 use ipnetwork::{IpNetwork, NetworkSize};
 use pnet_datalink::NetworkInterface;
-use serde::Serialize;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::ser::SerializeMap;
+use serde::{Serialize, Serializer};
+use sha2::{Digest, Sha256};
 
-use crate::args::ScanOptions;
-use crate::network::{ResponseSummary, TargetDetails};
+use crate::args::{ScanOptions, SortField};
+use crate::compare::ScanComparison;
+use crate::error::ScanError;
+use crate::network::{
+    self, DuplicateDetails, InterfaceLink, ResponseSummary, TargetDetails, TargetSource, WatchRound,
+};
+use crate::time;
+use crate::vendor::UNKNOWN_VENDOR;
+
+const VENDOR_BAR_MAX_LEN: usize = 30;
+const TERMINAL_WIDTH_DEFAULT: usize = 80;
+const COMPACT_COLUMN_MIN_LEN: usize = 8;
 
 /**
  * Based on the current UNIX environment, find if the process is run as root
@@ -19,63 +38,115 @@ pub fn is_root_user() -> bool {
     env::var("USER").unwrap_or_else(|_| String::from("")) == *"root"
 }
 
+/**
+ * Find if the terminal output should be colored. Colors are disabled with the
+ * '--no-color' flag, the NO_COLOR environment variable (see no-color.org) or
+ * when stdout is not a terminal (redirected to a file, piped, ...).
+ */
+pub fn is_color_enabled(no_color_flag: bool) -> bool {
+    let has_no_color_env = env::var("NO_COLOR").is_ok_and(|value| !value.is_empty());
+
+    !no_color_flag && !has_no_color_env && io::stdout().is_terminal()
+}
+
+/**
+ * Find the width of the terminal, as given by the COLUMNS environment
+ * variable (set by most shells). The width defaults to 80 columns otherwise,
+ * which is also the usual width of CI logs.
+ */
+pub fn find_terminal_width() -> usize {
+    env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.trim().parse::<usize>().ok())
+        .filter(|columns| *columns > 0)
+        .unwrap_or(TERMINAL_WIDTH_DEFAULT)
+}
+
+/**
+ * Returns the given terminal style if colors are enabled, or a plain style
+ * otherwise. A plain style will not print any escape code around the text.
+ */
+pub fn color_style(style: Style, color_enabled: bool) -> Style {
+    match color_enabled {
+        true => style,
+        false => Style::new(),
+    }
+}
+
 /**
  * Prints on stdout a list of all available network interfaces with some
  * technical details. The goal is to present the most useful technical details
- * to pick the right network interface for scans.
+ * to pick the right network interface for scans. Only the interfaces eligible
+ * for scans can be displayed (see '--ready-only'), the totals still count all
+ * interfaces.
  */
-pub fn show_interfaces(interfaces: &[NetworkInterface]) {
+pub fn show_interfaces(interfaces: &[NetworkInterface], ready_only: bool, color_enabled: bool) {
+    let bold = color_style(Style::new().bold(), color_enabled);
+    let dimmed = color_style(Style::new().dimmed(), color_enabled);
+    let green = color_style(Green.normal(), color_enabled);
+    let blue = color_style(Blue.normal(), color_enabled);
+    let yellow = color_style(Yellow.dimmed(), color_enabled);
+
     let mut interface_count = 0;
     let mut ready_count = 0;
 
     println!();
-    println!("{}", Style::new().bold().paint("NETWORK INTERFACES"));
+    println!("{}", bold.paint("NETWORK INTERFACES"));
     println!();
     println!(
-        "{: <6} {: <18} {: <10} {: <20} {}",
-        Style::new().dimmed().paint("Index"),
-        Style::new().dimmed().paint("Interface"),
-        Style::new().dimmed().paint("Status"),
-        Style::new().dimmed().paint("MAC Address"),
-        Style::new().dimmed().paint("IP Address")
+        "{: <6} {: <18} {: <10} {: <20} {: <8} {: <11} {}",
+        dimmed.paint("Index"),
+        dimmed.paint("Interface"),
+        dimmed.paint("Status"),
+        dimmed.paint("MAC Address"),
+        dimmed.paint("MTU"),
+        dimmed.paint("Speed"),
+        dimmed.paint("IP Address")
     );
-    println!("{}", Style::new().dimmed().paint("─".repeat(78)));
+    println!("{}", dimmed.paint("─".repeat(98)));
 
     for interface in interfaces.iter() {
+        interface_count += 1;
+        let is_ready = is_eligible_interface(interface);
+        if is_ready {
+            ready_count += 1;
+        }
+        if ready_only && !is_ready {
+            continue;
+        }
+
         let up_text = match interface.is_up() {
-            true => Green.paint("UP"),
-            false => Style::new().dimmed().paint("DOWN"),
+            true => green.paint("UP"),
+            false => dimmed.paint("DOWN"),
         };
         let mac_text = match interface.mac {
             Some(mac_address) => format!("{}", mac_address),
-            None => Style::new().dimmed().paint("-").to_string(),
+            None => dimmed.paint("-").to_string(),
         };
         let first_ip = match interface.ips.first() {
             Some(ip_address) => format!("{}", ip_address),
-            None => Style::new().dimmed().paint("-").to_string(),
+            None => dimmed.paint("-").to_string(),
         };
+        let interface_link = network::read_interface_link(&interface.name);
 
         println!(
-            "{: <6} {: <18} {: <10} {: <20} {}",
-            Style::new().dimmed().paint(format!("{}", interface.index)),
+            "{: <6} {: <18} {: <10} {: <20} {: <8} {: <11} {}",
+            dimmed.paint(format!("{}", interface.index)),
             interface.name,
             up_text,
-            Yellow.dimmed().paint(&mac_text),
-            Blue.paint(&first_ip)
+            yellow.paint(&mac_text),
+            format_link_mtu(interface_link.mtu),
+            format_link_speed(interface_link.speed_mbps),
+            blue.paint(&first_ip)
         );
-
-        interface_count += 1;
-        if interface.is_up() && !interface.is_loopback() && !interface.ips.is_empty() {
-            ready_count += 1;
-        }
     }
 
-    println!("{}", Style::new().dimmed().paint("─".repeat(78)));
+    println!("{}", dimmed.paint("─".repeat(98)));
     println!(
         "{} total · {} ready · default: {}",
         interface_count,
-        Green.paint(ready_count.to_string()),
-        Blue.paint(
+        green.paint(ready_count.to_string()),
+        blue.paint(
             select_default_interface(interfaces)
                 .map(|i| i.name.clone())
                 .unwrap_or_else(|| "none".to_string())
@@ -84,6 +155,94 @@ pub fn show_interfaces(interfaces: &[NetworkInterface]) {
     println!();
 }
 
+#[derive(Serialize)]
+struct SerializableInterface {
+    name: String,
+    index: u32,
+    mac: Option<String>,
+    ips: Vec<String>,
+    is_up: bool,
+    is_loopback: bool,
+    mtu: Option<u32>,
+    speed_mbps: Option<u32>,
+}
+
+/**
+ * Export the network interfaces as a JSON array, so that tooling can select
+ * an interface without parsing the interface table. Interfaces without MAC
+ * address are exported with a null MAC. The link details are given by
+ * 'read_link' (usually 'network::read_interface_link').
+ */
+pub fn export_interfaces_to_json(
+    interfaces: &[NetworkInterface],
+    is_pretty: bool,
+    read_link: impl Fn(&str) -> InterfaceLink,
+) -> Result<String, ScanError> {
+    let exportable_interfaces: Vec<SerializableInterface> = interfaces
+        .iter()
+        .map(|interface| {
+            let interface_link = read_link(&interface.name);
+            SerializableInterface {
+                name: interface.name.clone(),
+                index: interface.index,
+                mac: interface.mac.map(|mac_address| mac_address.to_string()),
+                ips: interface
+                    .ips
+                    .iter()
+                    .map(|ip_network| ip_network.to_string())
+                    .collect(),
+                is_up: interface.is_up(),
+                is_loopback: interface.is_loopback(),
+                mtu: interface_link.mtu,
+                speed_mbps: interface_link.speed_mbps,
+            }
+        })
+        .collect();
+
+    let json_result = match is_pretty {
+        true => serde_json::to_string_pretty(&exportable_interfaces),
+        false => serde_json::to_string(&exportable_interfaces),
+    };
+    json_result.map_err(|err| ScanError::Serialization(format!("JSON, {}", err)))
+}
+
+fn format_link_mtu(mtu: Option<u32>) -> String {
+    match mtu {
+        Some(mtu) => mtu.to_string(),
+        None => "unknown".to_string(),
+    }
+}
+
+/**
+ * Format a link speed (given in Mb/s by the kernel), switching to Gb/s for
+ * round values such as 1000 or 25000.
+ */
+fn format_link_speed(speed_mbps: Option<u32>) -> String {
+    match speed_mbps {
+        Some(speed_mbps) if speed_mbps % 1000 == 0 => format!("{} Gb/s", speed_mbps / 1000),
+        Some(speed_mbps) => format!("{} Mb/s", speed_mbps),
+        None => "unknown".to_string(),
+    }
+}
+
+/**
+ * Format bytes as an hexadecimal dump, with 16 bytes per line prefixed by
+ * their offset (as displayed by packet analyzers).
+ */
+pub fn format_hex_dump(bytes: &[u8]) -> String {
+    bytes
+        .chunks(16)
+        .enumerate()
+        .map(|(line_index, line_bytes)| {
+            let hex_bytes: Vec<String> = line_bytes
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect();
+            format!("{:04x}  {}\n", line_index * 16, hex_bytes.join(" "))
+        })
+        .collect()
+}
+
 pub fn print_ascii_packet() {
     println!();
     println!(" 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 ");
@@ -109,30 +268,49 @@ pub fn print_ascii_packet() {
 }
 
 /**
- * Find a default network interface for scans, based on the operating system
- * priority and some interface technical details.
+ * Check if a network interface can be scanned: the interface should be up, not
+ * loopback, and have both a MAC address and an IPv4 address.
  */
-pub fn select_default_interface(interfaces: &[NetworkInterface]) -> Option<NetworkInterface> {
-    let default_interface = interfaces.iter().find(|interface| {
-        if interface.mac.is_none() {
-            return false;
-        }
+fn is_eligible_interface(interface: &NetworkInterface) -> bool {
+    if interface.mac.is_none() {
+        return false;
+    }
 
-        if interface.ips.is_empty() || !interface.is_up() || interface.is_loopback() {
-            return false;
-        }
+    if interface.ips.is_empty() || !interface.is_up() || interface.is_loopback() {
+        return false;
+    }
 
-        let potential_ipv4 = interface.ips.iter().find(|ip| ip.is_ipv4());
-        if potential_ipv4.is_none() {
-            return false;
-        }
+    let potential_ipv4 = interface.ips.iter().find(|ip| ip.is_ipv4());
+    if potential_ipv4.is_none() {
+        return false;
+    }
 
-        true
-    });
+    true
+}
+
+/**
+ * Find a default network interface for scans, based on the operating system
+ * priority and some interface technical details.
+ */
+pub fn select_default_interface(interfaces: &[NetworkInterface]) -> Option<NetworkInterface> {
+    let default_interface = interfaces
+        .iter()
+        .find(|interface| is_eligible_interface(interface));
 
     default_interface.cloned()
 }
 
+/**
+ * Find all network interfaces that could be selected as default interface,
+ * in the operating system order (see '--all-interfaces').
+ */
+pub fn select_eligible_interfaces(interfaces: &[NetworkInterface]) -> Vec<&NetworkInterface> {
+    interfaces
+        .iter()
+        .filter(|interface| is_eligible_interface(interface))
+        .collect()
+}
+
 /**
  * Display scan settings before launching an ARP scan. This includes network
  * details (IP range, interface, ...) and timing informations.
@@ -140,8 +318,14 @@ pub fn select_default_interface(interfaces: &[NetworkInterface]) -> Option<Netwo
 pub fn display_prescan_details(
     ip_networks: &[&IpNetwork],
     selected_interface: &NetworkInterface,
+    gateway_ipv4: Option<Ipv4Addr>,
     scan_options: Arc<ScanOptions>,
 ) {
+    let color_enabled = scan_options.color_enabled;
+    let bold = color_style(Style::new().bold(), color_enabled);
+    let dimmed = color_style(Style::new().dimmed(), color_enabled);
+    let blue = color_style(Blue.normal(), color_enabled);
+
     let mut network_list = ip_networks
         .iter()
         .take(5)
@@ -154,131 +338,345 @@ pub fn display_prescan_details(
     }
 
     println!();
-    println!("{}", Style::new().bold().paint("SCAN CONFIGURATION"));
+    println!("{}", bold.paint("SCAN CONFIGURATION"));
     println!();
     println!(
         "{: <16} {}",
-        Style::new().dimmed().paint("Interface"),
-        Blue.paint(&selected_interface.name)
+        dimmed.paint("Interface"),
+        blue.paint(&selected_interface.name)
+    );
+    let interface_link = network::read_interface_link(&selected_interface.name);
+    println!(
+        "{: <16} {}",
+        dimmed.paint("MTU"),
+        format_link_mtu(interface_link.mtu)
     );
     println!(
         "{: <16} {}",
-        Style::new().dimmed().paint("Target"),
-        network_list
+        dimmed.paint("Link speed"),
+        format_link_speed(interface_link.speed_mbps)
     );
+    println!("{: <16} {}", dimmed.paint("Target"), network_list);
+    match gateway_ipv4 {
+        Some(gateway_ipv4) => println!("{: <16} {}", dimmed.paint("Gateway"), gateway_ipv4),
+        None => println!(
+            "{: <16} {}",
+            dimmed.paint("Gateway"),
+            dimmed.paint("unknown")
+        ),
+    }
     if let Some(forced_source_ipv4) = scan_options.source_ipv4 {
         println!(
             "{: <16} {} {}",
-            Style::new().dimmed().paint("Source IP"),
+            dimmed.paint("Source IP"),
             forced_source_ipv4,
-            Style::new().dimmed().paint("(forced)")
+            dimmed.paint("(forced)")
         );
     }
     if let Some(forced_destination_mac) = scan_options.destination_mac {
         println!(
             "{: <16} {} {}",
-            Style::new().dimmed().paint("Dest MAC"),
+            dimmed.paint("Dest MAC"),
             forced_destination_mac,
-            Style::new().dimmed().paint("(forced)")
+            dimmed.paint("(forced)")
         );
     }
     println!();
 }
 
 /**
- * Computes multiple IPv4 networks total size, IPv6 network are not being
- * supported by this function.
+ * Computes multiple networks total size, IPv6 networks being only accepted in
+ * the IPv6 neighbor scan mode (with a limited size). When only a sample of the
+ * networks is scanned, the expected sampled size is returned.
  */
-pub fn compute_network_size(ip_networks: &[&IpNetwork]) -> u128 {
-    ip_networks.iter().fold(0u128, |total_size, ip_network| {
+pub fn compute_network_size(ip_networks: &[&IpNetwork], sample_percent: Option<f64>) -> u128 {
+    let network_size = ip_networks.iter().fold(0u128, |total_size, ip_network| {
         let network_size: u128 = match ip_network.size() {
             NetworkSize::V4(ipv4_network_size) => ipv4_network_size.into(),
-            NetworkSize::V6(_) => {
-                eprintln!("IPv6 networks are not supported by the ARP protocol");
-                process::exit(1);
-            }
+            NetworkSize::V6(ipv6_network_size) => ipv6_network_size,
         };
         total_size + network_size
-    })
+    });
+
+    match sample_percent {
+        Some(sample_percent) => (network_size as f64 * sample_percent / 100.0).round() as u128,
+        None => network_size,
+    }
+}
+
+/**
+ * Sort the scan results on the requested field. Hosts sharing the same value
+ * (e.g. the same vendor) are ordered by IP address, and hosts without any
+ * vendor or latency are listed last.
+ */
+pub fn sort_target_details(target_details: &mut [TargetDetails], sort_field: SortField) {
+    match sort_field {
+        SortField::Ipv4 => target_details.sort_by_key(|detail| detail.ip_address()),
+        SortField::Mac => {
+            target_details.sort_by_key(|detail| (detail.mac.octets(), detail.ip_address()))
+        }
+        SortField::Vendor => target_details.sort_by_cached_key(|detail| {
+            (
+                detail.vendor.is_none(),
+                detail.vendor.as_ref().map(|vendor| vendor.to_lowercase()),
+                detail.ip_address(),
+            )
+        }),
+        SortField::Latency => target_details.sort_by(|first, second| {
+            let latency_order = match (first.response_ms, second.response_ms) {
+                (Some(first_ms), Some(second_ms)) => first_ms.total_cmp(&second_ms),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            };
+            latency_order.then_with(|| first.ip_address().cmp(&second.ip_address()))
+        }),
+    }
+}
+
+/**
+ * Compute the widths of the IP & MAC address columns from the longest values
+ * (IPv6 addresses are much longer than IPv4 ones), with a 2-character gap.
+ * The widths never go below the header & IPv4 layout.
+ */
+fn compute_address_widths(target_details: &[TargetDetails]) -> (usize, usize) {
+    let ip_len = target_details
+        .iter()
+        .map(|detail| detail.ip_address().to_string().len() + 2)
+        .fold(17, usize::max);
+    let mac_len = target_details
+        .iter()
+        .map(|detail| detail.mac.to_string().len() + 2)
+        .fold(19, usize::max);
+    (ip_len, mac_len)
 }
 
 /**
  * Display the scan results on stdout with a table. The 'final_result' vector
- * contains all items that will be displayed.
+ * contains all items that will be displayed. In watch mode, the terminal is
+ * cleared before each new round so that the table is redrawn in place.
  */
 pub fn display_scan_results(
     response_summary: ResponseSummary,
     mut target_details: Vec<TargetDetails>,
+    watch_round: Option<&WatchRound>,
+    gateway_ipv4: Option<Ipv4Addr>,
+    scanned_networks: &[IpNetwork],
     options: &ScanOptions,
 ) {
-    target_details.sort_by_key(|item| item.ipv4);
+    let color_enabled = options.color_enabled;
+    let bold = color_style(Style::new().bold(), color_enabled);
+    let dimmed = color_style(Style::new().dimmed(), color_enabled);
+    let green = color_style(Green.normal(), color_enabled);
+    let red = color_style(Red.normal(), color_enabled);
+    let blue = color_style(Blue.normal(), color_enabled);
+    let yellow = color_style(Yellow.dimmed(), color_enabled);
 
+    if watch_round.is_some_and(|round| round.index > 0) && io::stdout().is_terminal() {
+        print!("\x1B[2J\x1B[1;1H");
+    }
+
+    sort_target_details(&mut target_details, options.sort_field);
+
+    let (ip_len, mac_len) = compute_address_widths(&target_details);
     let mut hostname_len = 15;
     let mut vendor_len = 15;
+    let vendors: Vec<String> = target_details
+        .iter()
+        .map(|detail| format_vendor(detail, options.show_oui))
+        .collect();
     for detail in target_details.iter() {
         if let Some(hostname) = &detail.hostname {
             if hostname.len() > hostname_len {
                 hostname_len = hostname.len();
             }
         }
-
-        if let Some(vendor) = &detail.vendor {
-            if vendor.len() > vendor_len {
-                vendor_len = vendor.len();
-            }
+    }
+    for vendor in vendors.iter() {
+        if vendor.chars().count() > vendor_len {
+            vendor_len = vendor.chars().count();
         }
     }
 
+    // The latency column is opt-in, to avoid widening the default table.
+    let latency_len = match options.show_latency {
+        true => 10,
+        false => 0,
+    };
+    let reply_len = match options.show_reply_count {
+        true => 8,
+        false => 0,
+    };
+    let source_len = match options.include_cache {
+        true => 8,
+        false => 0,
+    };
+    let ports_len = match options.probe_ports.is_empty() {
+        true => 0,
+        false => target_details
+            .iter()
+            .map(|detail| format_open_ports(&detail.open_ports).len() + 1)
+            .fold(11, usize::max),
+    };
+    let sys_name_len = match options.snmp_community.is_some() {
+        true => target_details
+            .iter()
+            .filter_map(|detail| detail.sys_name.as_ref())
+            .map(|sys_name| sys_name.chars().count() + 1)
+            .fold(11, usize::max),
+        false => 0,
+    };
+    // In compact mode, hostnames and vendors are truncated so that the whole
+    // table fits in the requested width.
+    if let Some(compact_width) = options.compact_width {
+        let fixed_len =
+            ip_len + mac_len + 3 + latency_len + reply_len + source_len + ports_len + sys_name_len;
+        (hostname_len, vendor_len) = fit_column_widths(
+            hostname_len,
+            vendor_len,
+            compact_width.saturating_sub(fixed_len),
+        );
+    }
+    let separator = match options.compact_width {
+        Some(_) => "-",
+        None => "─",
+    };
+
+    let table_len = ip_len
+        + mac_len
+        + hostname_len
+        + vendor_len
+        + 3
+        + latency_len
+        + reply_len
+        + source_len
+        + ports_len
+        + sys_name_len;
+
     if !target_details.is_empty() {
         println!();
-        println!("{}", Style::new().bold().paint("RESULTS"));
+        println!("{}", bold.paint("RESULTS"));
         println!();
         println!(
-            "{: <17} {: <19} {: <h_max$} {: <v_max$}",
-            Style::new().dimmed().paint("IP Address"),
-            Style::new().dimmed().paint("MAC Address"),
-            Style::new().dimmed().paint("Hostname"),
-            Style::new().dimmed().paint("Vendor"),
+            "{: <i_max$} {: <m_max$} {: <h_max$} {: <v_max$}{}{}{}{}{}",
+            dimmed.paint("IP Address"),
+            dimmed.paint("MAC Address"),
+            dimmed.paint("Hostname"),
+            dimmed.paint("Vendor"),
+            match options.show_latency {
+                true => format!(" {: <9}", dimmed.paint("Latency")),
+                false => String::new(),
+            },
+            match options.show_reply_count {
+                true => format!(" {}", dimmed.paint("Replies")),
+                false => String::new(),
+            },
+            match options.include_cache {
+                true => format!(" {: <7}", dimmed.paint("Source")),
+                false => String::new(),
+            },
+            match options.probe_ports.is_empty() {
+                true => String::new(),
+                false => format!(
+                    " {: <p_max$}",
+                    dimmed.paint("Open ports"),
+                    p_max = ports_len - 1
+                ),
+            },
+            match options.snmp_community.is_some() {
+                true => format!(
+                    " {: <s_max$}",
+                    dimmed.paint("SNMP name"),
+                    s_max = sys_name_len - 1
+                ),
+                false => String::new(),
+            },
+            i_max = ip_len,
+            m_max = mac_len,
             h_max = hostname_len,
             v_max = vendor_len
         );
 
-        println!(
-            "{}",
-            Style::new()
-                .dimmed()
-                .paint("─".repeat(17 + 19 + hostname_len + vendor_len + 3))
-        );
+        println!("{}", dimmed.paint(separator.repeat(table_len)));
     }
 
-    for detail in target_details.iter() {
+    for (detail, vendor) in target_details.iter().zip(vendors.iter()) {
         let hostname: &str = match &detail.hostname {
             Some(hostname) => hostname,
             None if !options.resolve_hostname => "-",
             None => "",
         };
-        let vendor: &str = match &detail.vendor {
-            Some(vendor) => vendor,
-            None => "-",
+        let hostname = truncate_text(hostname, hostname_len);
+        let vendor = truncate_text(vendor, vendor_len);
+        let latency = match options.show_latency {
+            true => format!(" {: <9}", format_latency(detail.response_ms)),
+            false => String::new(),
+        };
+        let reply_count = match options.show_reply_count {
+            true => format!(" {: <7}", detail.reply_count),
+            false => String::new(),
+        };
+        let source = match (options.include_cache, detail.source) {
+            (true, Some(TargetSource::Cache)) if detail.stale => red.paint(" stale  ").to_string(),
+            (true, Some(TargetSource::Cache)) => " cache  ".to_string(),
+            (true, _) => " active ".to_string(),
+            (false, _) => String::new(),
+        };
+        // Stale cache entries (cached hosts that did not answer) and proxy ARP
+        // responses (answered by the gateway) are dimmed, while hosts missing
+        // from the known MAC list are highlighted.
+        let (ip_style, mac_style) = match (detail.stale || detail.proxy_arp, detail.known) {
+            (_, Some(false)) => (red, red),
+            (true, _) => (dimmed, dimmed),
+            (false, _) => (blue, yellow),
+        };
+        let open_ports = match options.probe_ports.is_empty() {
+            true => String::new(),
+            false => format!(
+                " {: <p_max$}",
+                format_open_ports(&detail.open_ports),
+                p_max = ports_len - 1
+            ),
+        };
+        let sys_name = match options.snmp_community.is_some() {
+            true => format!(
+                " {: <s_max$}",
+                detail.sys_name.as_deref().unwrap_or("-"),
+                s_max = sys_name_len - 1
+            ),
+            false => String::new(),
+        };
+        let gateway_tag = match (gateway_ipv4 == Some(detail.ipv4), detail.proxy_arp) {
+            (true, _) => green.paint(" ◆ gateway").to_string(),
+            (false, true) => dimmed.paint(" ◇ proxy ARP").to_string(),
+            (false, false) => String::new(),
+        };
+        let unexpected_tag = match detail.known {
+            Some(false) => red.paint(" ⚠ unexpected").to_string(),
+            _ => String::new(),
         };
         println!(
-            "{: <17} {: <19} {: <h_max$} {: <v_max$}",
-            Blue.paint(format!("{}", detail.ipv4)),
-            Yellow.dimmed().paint(format!("{}", detail.mac)),
+            "{: <i_max$} {: <m_max$} {: <h_max$} {: <v_max$}{}{}{}{}{}{}{}",
+            ip_style.paint(format!("{}", detail.ip_address())),
+            mac_style.paint(format!("{}", detail.mac)),
             hostname,
-            Style::new().dimmed().paint(vendor),
+            dimmed.paint(vendor.as_str()),
+            latency,
+            reply_count,
+            source,
+            open_ports,
+            sys_name,
+            gateway_tag,
+            unexpected_tag,
+            i_max = ip_len,
+            m_max = mac_len,
             h_max = hostname_len,
             v_max = vendor_len
         );
     }
 
     if !target_details.is_empty() {
-        println!(
-            "{}",
-            Style::new()
-                .dimmed()
-                .paint("─".repeat(17 + 19 + hostname_len + vendor_len + 3))
-        );
+        println!("{}", dimmed.paint(separator.repeat(table_len)));
     }
 
     println!();
@@ -286,160 +684,2163 @@ pub fn display_scan_results(
     let target_count = target_details.len();
 
     println!();
-    println!("{}", Style::new().bold().paint("SUMMARY"));
+    println!("{}", bold.paint("SUMMARY"));
     println!();
 
     println!(
         "{: <16} {}",
-        Style::new().dimmed().paint("Hosts found"),
+        dimmed.paint("Hosts found"),
         match target_count {
-            0 => Red.paint(format!("{}", target_count)),
-            _ => Green.paint(format!("{}", target_count)),
+            0 => red.paint(format!("{}", target_count)),
+            _ => green.paint(format!("{}", target_count)),
         }
     );
 
-    println!(
-        "{: <16} {:.3}s",
-        Style::new().dimmed().paint("Duration"),
-        seconds_duration
-    );
+    println!("{: <16} {:.3}s", dimmed.paint("Duration"), seconds_duration);
 
     println!(
         "{: <16} {}",
-        Style::new().dimmed().paint("Packets recv"),
+        dimmed.paint("Packets recv"),
         response_summary.packet_count
     );
 
     println!(
         "{: <16} {}",
-        Style::new().dimmed().paint("ARP filtered"),
+        dimmed.paint("ARP filtered"),
         response_summary.arp_count
     );
 
-    println!();
-}
+    if let Some(gateway_ipv4) = gateway_ipv4 {
+        let has_gateway_response = target_details
+            .iter()
+            .any(|detail| detail.ipv4 == gateway_ipv4);
+        println!(
+            "{: <16} {} {}",
+            dimmed.paint("Gateway"),
+            gateway_ipv4,
+            match has_gateway_response {
+                true => green.paint("(responded)"),
+                false => red.paint("(no response)"),
+            }
+        );
+    }
 
-#[derive(Serialize)]
-struct SerializableResultItem {
-    ipv4: String,
-    mac: String,
-    hostname: String,
-    vendor: String,
-}
+    if options.known_macs.is_some() {
+        let unexpected_count = target_details
+            .iter()
+            .filter(|detail| detail.known == Some(false))
+            .count();
+        println!(
+            "{: <16} {}",
+            dimmed.paint("Unexpected"),
+            match unexpected_count {
+                0 => green.paint(unexpected_count.to_string()),
+                _ => red.paint(unexpected_count.to_string()),
+            }
+        );
+    }
 
-#[derive(Serialize)]
-struct SerializableGlobalResult {
-    packet_count: usize,
-    arp_count: usize,
-    duration_ms: u128,
-    results: Vec<SerializableResultItem>,
-}
+    if response_summary.send_errors > 0 {
+        println!(
+            "{: <16} {}",
+            dimmed.paint("Send errors"),
+            red.paint(response_summary.send_errors.to_string())
+        );
+    }
 
-/**
- * Transforms an ARP scan result (including KPI and target details) to a structure
- * that can be serialized for export (JSON, YAML, CSV, ...)
- */
-fn get_serializable_result(
-    response_summary: ResponseSummary,
-    target_details: Vec<TargetDetails>,
-) -> SerializableGlobalResult {
-    let exportable_results: Vec<SerializableResultItem> = target_details
-        .into_iter()
-        .map(|detail| {
-            let hostname = match &detail.hostname {
-                Some(hostname) => hostname.clone(),
-                None => String::from(""),
-            };
+    if let Some(effective_timeout_ms) = response_summary.effective_timeout_ms {
+        println!(
+            "{: <16} {}ms {}",
+            dimmed.paint("Timeout"),
+            effective_timeout_ms,
+            dimmed.paint("(adaptive)")
+        );
+    }
 
-            let vendor = match &detail.vendor {
-                Some(vendor) => vendor.clone(),
-                None => String::from(""),
-            };
+    if !options.mac_prefixes.is_empty() {
+        println!(
+            "{: <16} {}",
+            dimmed.paint("Hosts seen"),
+            response_summary.seen_count
+        );
+    }
 
-            SerializableResultItem {
-                ipv4: format!("{}", detail.ipv4),
-                mac: format!("{}", detail.mac),
-                hostname,
-                vendor,
-            }
-        })
-        .collect();
+    if response_summary.unknown_vendor_count > 0 {
+        println!(
+            "{: <16} {}",
+            dimmed.paint("Unknown vendors"),
+            yellow.paint(response_summary.unknown_vendor_count.to_string())
+        );
+    }
 
-    SerializableGlobalResult {
-        packet_count: response_summary.packet_count,
-        arp_count: response_summary.arp_count,
-        duration_ms: response_summary.duration_ms,
-        results: exportable_results,
+    if response_summary.gratuitous_count > 0 {
+        println!(
+            "{: <16} {}",
+            dimmed.paint("Gratuitous ARP"),
+            response_summary.gratuitous_count
+        );
     }
-}
 
-/**
- * Export the scan results as a JSON string with response details (timings, ...)
- * and ARP results from the local network.
- */
-pub fn export_to_json(
-    response_summary: ResponseSummary,
-    mut target_details: Vec<TargetDetails>,
-) -> String {
-    target_details.sort_by_key(|item| item.ipv4);
+    if response_summary.malformed_count > 0 {
+        println!(
+            "{: <16} {}",
+            dimmed.paint("Malformed frames"),
+            yellow.paint(response_summary.malformed_count.to_string())
+        );
+    }
+
+    if response_summary.filtered_count > 0 {
+        println!(
+            "{: <16} {}",
+            dimmed.paint("Filtered frames"),
+            response_summary.filtered_count
+        );
+    }
 
-    let global_result = get_serializable_result(response_summary, target_details);
+    if response_summary.dns_timeout_count > 0 {
+        println!(
+            "{: <16} {}",
+            dimmed.paint("DNS timeouts"),
+            yellow.paint(response_summary.dns_timeout_count.to_string())
+        );
+    }
 
-    serde_json::to_string(&global_result).unwrap_or_else(|err| {
-        eprintln!("Could not export JSON results ({})", err);
-        process::exit(1);
-    })
-}
+    if !response_summary.duplicates.is_empty() {
+        println!(
+            "{: <16} {}",
+            dimmed.paint("Duplicates"),
+            red.paint(response_summary.duplicates.len().to_string())
+        );
+    }
 
-/**
- * Export the scan results as a YAML string with response details (timings, ...)
- * and ARP results from the local network.
- */
-pub fn export_to_yaml(
-    response_summary: ResponseSummary,
-    mut target_details: Vec<TargetDetails>,
-) -> String {
-    target_details.sort_by_key(|item| item.ipv4);
+    if let Some(round) = watch_round {
+        println!("{: <16} {}", dimmed.paint("Watch round"), round.index + 1);
+        println!(
+            "{: <16} {}",
+            dimmed.paint("Appeared"),
+            green.paint(format_ipv4_list(&round.appeared))
+        );
+        println!(
+            "{: <16} {}",
+            dimmed.paint("Disappeared"),
+            red.paint(format_ipv4_list(&round.disappeared))
+        );
+    }
 
-    let global_result = get_serializable_result(response_summary, target_details);
+    if options.vendor_summary && !target_details.is_empty() {
+        display_vendor_breakdown(&target_details, color_enabled);
+    }
 
-    serde_yaml::to_string(&global_result).unwrap_or_else(|err| {
-        eprintln!("Could not export YAML results ({})", err);
-        process::exit(1);
-    })
+    if options.per_network_summary {
+        display_network_breakdown(&target_details, scanned_networks, color_enabled);
+    }
+
+    if !response_summary.duplicates.is_empty() {
+        let warning = color_style(Yellow.bold(), color_enabled);
+
+        println!();
+        for duplicate in response_summary.duplicates.iter() {
+            let duplicate_text = match duplicate.is_shared_mac() {
+                true => format!(
+                    "⚠ duplicate MAC {} used by {} and {}",
+                    duplicate.mac, duplicate.ipv4, duplicate.conflicting_ipv4
+                ),
+                false => format!(
+                    "⚠ duplicate MAC {} and {} used by {}",
+                    duplicate.mac, duplicate.conflicting_mac, duplicate.ipv4
+                ),
+            };
+            println!("{}", warning.paint(duplicate_text));
+        }
+    }
+
+    println!();
 }
 
 /**
- * Export the scan results as a CSV string with response details (timings, ...)
- * and ARP results from the local network.
+ * Format a list of IPv4 addresses on a single line, a dash is returned if the
+ * list is empty.
  */
-pub fn export_to_csv(
-    response_summary: ResponseSummary,
-    mut target_details: Vec<TargetDetails>,
-) -> String {
-    target_details.sort_by_key(|item| item.ipv4);
+fn format_ipv4_list(ipv4_list: &[Ipv4Addr]) -> String {
+    match ipv4_list.is_empty() {
+        true => "-".to_string(),
+        false => ipv4_list
+            .iter()
+            .map(|ipv4| ipv4.to_string())
+            .collect::<Vec<String>>()
+            .join(", "),
+    }
+}
 
-    let global_result = get_serializable_result(response_summary, target_details);
+/**
+ * Display the differences with a previous scan on stdout, each host being
+ * prefixed with a marker: '+' for added hosts, '-' for removed hosts and '~'
+ * for hosts whose MAC address or vendor changed.
+ */
+pub fn display_scan_comparison(
+    comparison: &ScanComparison,
+    compare_file: &str,
+    color_enabled: bool,
+) {
+    let bold = color_style(Style::new().bold(), color_enabled);
+    let dimmed = color_style(Style::new().dimmed(), color_enabled);
+    let green = color_style(Green.normal(), color_enabled);
+    let red = color_style(Red.normal(), color_enabled);
+    let yellow = color_style(Yellow.normal(), color_enabled);
 
-    let mut wtr = csv::Writer::from_writer(vec![]);
+    println!(
+        "{} {}",
+        bold.paint("COMPARISON"),
+        dimmed.paint(format!("(with {})", compare_file))
+    );
+    println!();
 
-    for result in global_result.results {
-        wtr.serialize(result).unwrap_or_else(|err| {
-            eprintln!("Could not serialize result to CSV ({})", err);
-            process::exit(1);
-        });
+    if comparison.is_empty() {
+        println!("{}", dimmed.paint("No change since the previous scan"));
+        println!();
+        return;
     }
-    wtr.flush().unwrap_or_else(|err| {
-        eprintln!("Could not flush CSV writer buffer ({})", err);
-        process::exit(1);
-    });
 
-    let convert_writer = wtr.into_inner().unwrap_or_else(|err| {
-        eprintln!("Could not convert final CSV result ({})", err);
-        process::exit(1);
-    });
-    String::from_utf8(convert_writer).unwrap_or_else(|err| {
-        eprintln!("Could not convert final CSV result to text ({})", err);
-        process::exit(1);
-    })
+    for host in comparison.added.iter() {
+        println!(
+            "{} {: <17} {: <19} {}",
+            green.paint("+"),
+            host.ip_address,
+            host.mac,
+            host.vendor
+        );
+    }
+    for host in comparison.removed.iter() {
+        println!(
+            "{} {: <17} {: <19} {}",
+            red.paint("-"),
+            host.ip_address,
+            host.mac,
+            host.vendor
+        );
+    }
+    for host in comparison.changed.iter() {
+        let mac_text = match host.previous_mac.eq_ignore_ascii_case(&host.mac) {
+            true => host.mac.clone(),
+            false => format!("{} → {}", host.previous_mac, host.mac),
+        };
+        let vendor_text =
+            match host.previous_vendor.is_empty() || host.previous_vendor == host.vendor {
+                true => host.vendor.clone(),
+                false => format!("{} → {}", host.previous_vendor, host.vendor),
+            };
+        println!(
+            "{} {: <17} {} {}",
+            yellow.paint("~"),
+            host.ip_address,
+            mac_text,
+            vendor_text
+        );
+    }
+    println!();
+}
+
+/**
+ * Export the differences with a previous scan as a JSON object, with the
+ * 'added', 'removed' and 'changed' host arrays.
+ */
+pub fn export_comparison_to_json(
+    comparison: &ScanComparison,
+    is_pretty: bool,
+) -> Result<String, ScanError> {
+    let json_result = match is_pretty {
+        true => serde_json::to_string_pretty(comparison),
+        false => serde_json::to_string(comparison),
+    };
+    json_result.map_err(|err| ScanError::Serialization(format!("JSON comparison, {}", err)))
+}
+
+#[derive(Serialize)]
+struct SerializableResultItem {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ipv4: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ipv6: Option<String>,
+    mac: String,
+    hostname: String,
+    vendor: String,
+    last_seen: String,
+    response_ms: Option<f64>,
+    reply_count: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    interface: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    open_ports: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sys_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stale: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    proxy_arp: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    known: Option<bool>,
+}
+
+impl SerializableResultItem {
+    fn ip_address(&self) -> &str {
+        self.ipv6
+            .as_deref()
+            .or(self.ipv4.as_deref())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Serialize)]
+struct SerializableDuplicateItem {
+    ipv4: String,
+    mac: String,
+    conflicting_ipv4: String,
+    conflicting_mac: String,
+}
+
+#[derive(Serialize)]
+struct SerializableSummary {
+    packet_count: usize,
+    arp_count: usize,
+    duration_ms: u128,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    effective_timeout_ms: Option<u128>,
+    dns_timeout_count: usize,
+    gratuitous_count: usize,
+    malformed_count: usize,
+    filtered_count: usize,
+    seen_count: usize,
+    unknown_vendor_count: usize,
+    send_errors: usize,
+    start_time: String,
+    end_time: String,
+    duplicates: Vec<SerializableDuplicateItem>,
+}
+
+#[derive(Serialize)]
+struct SerializableKpis {
+    packet_count: usize,
+    arp_count: usize,
+    duration_ms: u128,
+    host_count: usize,
+}
+
+#[derive(Serialize)]
+struct SerializableWatchRound {
+    index: usize,
+    timestamp: u64,
+    appeared: Vec<String>,
+    disappeared: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct SerializableGlobalResult {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    round: Option<SerializableWatchRound>,
+    packet_count: usize,
+    arp_count: usize,
+    duration_ms: u128,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    effective_timeout_ms: Option<u128>,
+    dns_timeout_count: usize,
+    gratuitous_count: usize,
+    malformed_count: usize,
+    filtered_count: usize,
+    seen_count: usize,
+    unknown_vendor_count: usize,
+    send_errors: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unexpected_count: Option<usize>,
+    start_time: String,
+    end_time: String,
+    duplicates: Vec<SerializableDuplicateItem>,
+    vendor_breakdown: SerializableVendorBreakdown,
+    results: Vec<SerializableResultItem>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sha256: Option<String>,
+}
+
+/**
+ * Host count of each vendor, serialized as a map that keeps the breakdown
+ * order (most frequent vendors first).
+ */
+struct SerializableVendorBreakdown(Vec<(String, usize)>);
+
+impl Serialize for SerializableVendorBreakdown {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut vendor_map = serializer.serialize_map(Some(self.0.len()))?;
+        for (vendor, host_count) in self.0.iter() {
+            vendor_map.serialize_entry(vendor, host_count)?;
+        }
+        vendor_map.end()
+    }
+}
+
+/**
+ * Display the vendor breakdown of the results as an histogram, each bar being
+ * scaled on the most frequent vendor.
+ */
+fn display_vendor_breakdown(target_details: &[TargetDetails], color_enabled: bool) {
+    let bold = color_style(Style::new().bold(), color_enabled);
+    let blue = color_style(Blue.normal(), color_enabled);
+
+    let vendor_breakdown = compute_vendor_breakdown(target_details);
+    let vendor_len = vendor_breakdown
+        .iter()
+        .map(|(vendor, _)| vendor.chars().count())
+        .fold(15, usize::max);
+    let max_count = vendor_breakdown
+        .first()
+        .map(|(_, host_count)| *host_count)
+        .unwrap_or(1);
+
+    println!();
+    println!("{}", bold.paint("VENDORS"));
+    println!();
+    for (vendor, host_count) in vendor_breakdown.iter() {
+        let bar_len = (host_count * VENDOR_BAR_MAX_LEN).div_ceil(max_count);
+        println!(
+            "{: <v_max$} {: >5} {}",
+            vendor,
+            host_count,
+            blue.paint("█".repeat(bar_len)),
+            v_max = vendor_len
+        );
+    }
+}
+
+/**
+ * Display the number of discovered hosts in each scanned network, followed by
+ * the number of hosts found outside of these networks (if any).
+ */
+fn display_network_breakdown(
+    target_details: &[TargetDetails],
+    scanned_networks: &[IpNetwork],
+    color_enabled: bool,
+) {
+    let bold = color_style(Style::new().bold(), color_enabled);
+    let dimmed = color_style(Style::new().dimmed(), color_enabled);
+    let green = color_style(Green.normal(), color_enabled);
+
+    let (network_breakdown, outside_count) =
+        compute_network_breakdown(target_details, scanned_networks);
+    let network_len = network_breakdown
+        .iter()
+        .map(|(network, _)| network.to_string().len())
+        .fold(16, usize::max);
+
+    println!();
+    println!("{}", bold.paint("NETWORKS"));
+    println!();
+    for (network, host_count) in network_breakdown.iter() {
+        let host_count_text = match host_count {
+            0 => dimmed.paint(host_count.to_string()),
+            _ => green.paint(host_count.to_string()),
+        };
+        println!(
+            "{: <n_max$} {}",
+            network.to_string(),
+            host_count_text,
+            n_max = network_len
+        );
+    }
+    if outside_count > 0 {
+        println!(
+            "{: <n_max$} {}",
+            dimmed.paint("Other"),
+            outside_count,
+            n_max = network_len
+        );
+    }
+}
+
+/**
+ * Count the discovered hosts in each scanned network, a host being counted in
+ * the first network containing its address. Networks are given in their
+ * canonical form (e.g. '192.168.1.0/24' for an interface address such as
+ * '192.168.1.21/24') and listed once. Hosts outside of all scanned networks
+ * (e.g. seen in passive mode) are counted separately.
+ */
+pub fn compute_network_breakdown(
+    target_details: &[TargetDetails],
+    scanned_networks: &[IpNetwork],
+) -> (Vec<(IpNetwork, usize)>, usize) {
+    let mut network_breakdown: Vec<(IpNetwork, usize)> = vec![];
+    for scanned_network in scanned_networks.iter() {
+        let canonical_network = IpNetwork::new(scanned_network.network(), scanned_network.prefix())
+            .unwrap_or(*scanned_network);
+        if !network_breakdown
+            .iter()
+            .any(|(network, _)| *network == canonical_network)
+        {
+            network_breakdown.push((canonical_network, 0));
+        }
+    }
+
+    let mut outside_count = 0;
+    for detail in target_details.iter() {
+        let host_network = network_breakdown
+            .iter_mut()
+            .find(|(network, _)| network.contains(detail.ip_address()));
+        match host_network {
+            Some((_, host_count)) => *host_count += 1,
+            None => outside_count += 1,
+        }
+    }
+
+    (network_breakdown, outside_count)
+}
+
+/**
+ * Group the targets by vendor and count the hosts of each vendor, the most
+ * frequent vendors coming first (ties are sorted by name). Targets without a
+ * resolved vendor are grouped in a single unknown bucket.
+ */
+pub fn compute_vendor_breakdown(target_details: &[TargetDetails]) -> Vec<(String, usize)> {
+    let mut vendor_breakdown: Vec<(String, usize)> = vec![];
+    for detail in target_details.iter() {
+        let vendor = detail.vendor.as_deref().unwrap_or(UNKNOWN_VENDOR);
+        match vendor_breakdown
+            .iter_mut()
+            .find(|(known_vendor, _)| known_vendor == vendor)
+        {
+            Some((_, host_count)) => *host_count += 1,
+            None => vendor_breakdown.push((vendor.to_string(), 1)),
+        }
+    }
+
+    vendor_breakdown.sort_by(|(vendor_a, count_a), (vendor_b, count_b)| {
+        count_b.cmp(count_a).then_with(|| vendor_a.cmp(vendor_b))
+    });
+    vendor_breakdown
+}
+
+/**
+ * Compute the widths of the hostname and vendor columns, so that both fit in
+ * the available width. A column shorter than half of the available width is
+ * kept intact, the other one being shrunk. Columns never go below a minimal
+ * width, even if the table overflows.
+ */
+fn fit_column_widths(
+    hostname_len: usize,
+    vendor_len: usize,
+    available_len: usize,
+) -> (usize, usize) {
+    if hostname_len + vendor_len <= available_len {
+        return (hostname_len, vendor_len);
+    }
+
+    let half_len = (available_len / 2).max(COMPACT_COLUMN_MIN_LEN);
+    let remaining_len = |used_len: usize| {
+        available_len
+            .saturating_sub(used_len)
+            .max(COMPACT_COLUMN_MIN_LEN)
+    };
+    match (hostname_len <= half_len, vendor_len <= half_len) {
+        (true, _) => (hostname_len, remaining_len(hostname_len)),
+        (_, true) => (remaining_len(vendor_len), vendor_len),
+        _ => (half_len, remaining_len(half_len)),
+    }
+}
+
+/**
+ * Truncate a text to the given number of characters, the last character
+ * being replaced by an ellipsis when the text is too long.
+ */
+fn truncate_text(text: &str, max_len: usize) -> String {
+    match text.chars().count() > max_len {
+        true => {
+            let truncated_text: String = text.chars().take(max_len.saturating_sub(1)).collect();
+            format!("{}…", truncated_text)
+        }
+        false => text.to_string(),
+    }
+}
+
+/**
+ * Formats the response latency of a target, which is unknown if the host
+ * responded without any tracked request (e.g. in passive mode).
+ */
+fn format_latency(response_ms: Option<f64>) -> String {
+    match response_ms {
+        Some(response_ms) => format!("{:.2}ms", response_ms),
+        None => "-".to_string(),
+    }
+}
+
+/**
+ * Formats the open TCP ports of a target as a comma-separated list, or as a
+ * dash when no probed port is open.
+ */
+fn format_open_ports(open_ports: &[u16]) -> String {
+    match open_ports.is_empty() {
+        true => "-".to_string(),
+        false => open_ports
+            .iter()
+            .map(|port| port.to_string())
+            .collect::<Vec<String>>()
+            .join(","),
+    }
+}
+
+/**
+ * Formats the vendor of a target, unresolved vendors being displayed as
+ * unknown. The OUI prefix can be appended to unknown vendors, so that they
+ * can be looked up manually.
+ */
+fn format_vendor(detail: &TargetDetails, show_oui: bool) -> String {
+    match (&detail.vendor, show_oui) {
+        (Some(vendor), _) => vendor.clone(),
+        (None, false) => UNKNOWN_VENDOR.to_string(),
+        (None, true) => format!(
+            "{} {:02X}:{:02X}:{:02X}",
+            UNKNOWN_VENDOR, detail.mac.0, detail.mac.1, detail.mac.2
+        ),
+    }
+}
+
+/**
+ * Transforms a single target detail to a structure that can be serialized for
+ * export, missing hostnames are exported as empty strings and unresolved
+ * vendors as unknown.
+ */
+fn get_serializable_item(detail: &TargetDetails) -> SerializableResultItem {
+    let hostname = match &detail.hostname {
+        Some(hostname) => hostname.clone(),
+        None => String::from(""),
+    };
+
+    let vendor = format_vendor(detail, false);
+
+    // Hosts found by an IPv6 neighbor scan are only exported with their IPv6
+    // address, the unspecified IPv4 address is left out.
+    let (ipv4, ipv6) = match detail.ipv6 {
+        Some(ipv6) => (None, Some(ipv6.to_string())),
+        None => (Some(detail.ipv4.to_string()), None),
+    };
+
+    SerializableResultItem {
+        ipv4,
+        ipv6,
+        mac: format!("{}", detail.mac),
+        hostname,
+        vendor,
+        last_seen: time::format_rfc3339(detail.last_seen),
+        response_ms: detail.response_ms,
+        reply_count: detail.reply_count,
+        interface: detail.interface.clone(),
+        source: detail.source.map(|source| source.to_string()),
+        open_ports: match detail.open_ports.is_empty() {
+            true => None,
+            false => Some(format_open_ports(&detail.open_ports)),
+        },
+        sys_name: detail.sys_name.clone(),
+        stale: detail.source.map(|_| detail.stale),
+        proxy_arp: detail.proxy_arp.then_some(true),
+        known: detail.known,
+    }
+}
+
+// Fields available in '--format' templates, named after the exported fields.
+const TEMPLATE_FIELDS: [&str; 17] = [
+    "ip",
+    "ipv4",
+    "ipv6",
+    "mac",
+    "hostname",
+    "vendor",
+    "last_seen",
+    "response_ms",
+    "latency",
+    "reply_count",
+    "interface",
+    "source",
+    "open_ports",
+    "sys_name",
+    "stale",
+    "proxy_arp",
+    "known",
+];
+
+/**
+ * A part of a '--format' template, either copied as is or replaced by a field
+ * of each host.
+ */
+#[derive(Debug, PartialEq)]
+pub enum TemplateToken {
+    Text(String),
+    Field(String),
+}
+
+/**
+ * Parse a '--format' template such as '{ipv4}\t{mac}\t{vendor}'. Placeholders
+ * are checked against the known fields, braces are escaped by doubling them
+ * ('{{' & '}}') and the '\t' & '\n' sequences are expanded, since shells give
+ * them as is.
+ */
+pub fn parse_output_template(template_text: &str) -> Result<Vec<TemplateToken>, String> {
+    let mut tokens: Vec<TemplateToken> = vec![];
+    let mut text = String::new();
+    let mut characters = template_text.chars().peekable();
+
+    while let Some(character) = characters.next() {
+        match (character, characters.peek()) {
+            ('{', Some('{')) | ('}', Some('}')) => {
+                text.push(character);
+                characters.next();
+            }
+            ('\\', Some('t')) => {
+                text.push('\t');
+                characters.next();
+            }
+            ('\\', Some('n')) => {
+                text.push('\n');
+                characters.next();
+            }
+            ('{', _) => {
+                let field_name: String = characters.by_ref().take_while(|c| *c != '}').collect();
+                if !TEMPLATE_FIELDS.contains(&field_name.as_str()) {
+                    return Err(format!(
+                        "unknown placeholder {{{}}}, expected one of {}",
+                        field_name,
+                        TEMPLATE_FIELDS.join(", ")
+                    ));
+                }
+                if !text.is_empty() {
+                    tokens.push(TemplateToken::Text(std::mem::take(&mut text)));
+                }
+                tokens.push(TemplateToken::Field(field_name));
+            }
+            ('}', _) => return Err("unexpected '}', use '}}' for a literal brace".to_string()),
+            _ => text.push(character),
+        }
+    }
+    if !text.is_empty() {
+        tokens.push(TemplateToken::Text(text));
+    }
+
+    Ok(tokens)
+}
+
+/**
+ * Expand a parsed template for a single host, with the same values as the
+ * exports. Missing values (e.g. no hostname) are replaced by an empty string.
+ */
+pub fn format_template_line(template: &[TemplateToken], detail: &TargetDetails) -> String {
+    let item = get_serializable_item(detail);
+
+    template
+        .iter()
+        .map(|token| match token {
+            TemplateToken::Text(text) => text.clone(),
+            TemplateToken::Field(field_name) => match field_name.as_str() {
+                "ip" => item.ip_address().to_string(),
+                "ipv4" => item.ipv4.clone().unwrap_or_default(),
+                "ipv6" => item.ipv6.clone().unwrap_or_default(),
+                "mac" => item.mac.clone(),
+                "hostname" => item.hostname.clone(),
+                "vendor" => item.vendor.clone(),
+                "last_seen" => item.last_seen.clone(),
+                "response_ms" | "latency" => item
+                    .response_ms
+                    .map(|response_ms| response_ms.to_string())
+                    .unwrap_or_default(),
+                "reply_count" => item.reply_count.to_string(),
+                "interface" => item.interface.clone().unwrap_or_default(),
+                "source" => item.source.clone().unwrap_or_default(),
+                "open_ports" => item.open_ports.clone().unwrap_or_default(),
+                "sys_name" => item.sys_name.clone().unwrap_or_default(),
+                "stale" => item
+                    .stale
+                    .map(|stale| stale.to_string())
+                    .unwrap_or_default(),
+                "proxy_arp" => item
+                    .proxy_arp
+                    .map(|proxy_arp| proxy_arp.to_string())
+                    .unwrap_or_default(),
+                "known" => item
+                    .known
+                    .map(|known| known.to_string())
+                    .unwrap_or_default(),
+                _ => String::new(),
+            },
+        })
+        .collect()
+}
+
+/**
+ * Transforms all duplicates found during the scan to structures that can be
+ * serialized for export.
+ */
+fn get_serializable_duplicates(duplicates: &[DuplicateDetails]) -> Vec<SerializableDuplicateItem> {
+    duplicates
+        .iter()
+        .map(|duplicate| SerializableDuplicateItem {
+            ipv4: format!("{}", duplicate.ipv4),
+            mac: format!("{}", duplicate.mac),
+            conflicting_ipv4: format!("{}", duplicate.conflicting_ipv4),
+            conflicting_mac: format!("{}", duplicate.conflicting_mac),
+        })
+        .collect()
+}
+
+/**
+ * Transforms an ARP scan result (including KPI and target details) to a structure
+ * that can be serialized for export (JSON, YAML, CSV, ...)
+ */
+fn get_serializable_result(
+    response_summary: ResponseSummary,
+    target_details: Vec<TargetDetails>,
+    watch_round: Option<&WatchRound>,
+) -> SerializableGlobalResult {
+    let exportable_results: Vec<SerializableResultItem> =
+        target_details.iter().map(get_serializable_item).collect();
+    let vendor_breakdown = SerializableVendorBreakdown(compute_vendor_breakdown(&target_details));
+
+    // Hosts are only compared with known MAC addresses with '--known-macs',
+    // the unexpected hosts are then counted as in the plain summary.
+    let unexpected_count = target_details
+        .iter()
+        .any(|detail| detail.known.is_some())
+        .then(|| {
+            target_details
+                .iter()
+                .filter(|detail| detail.known == Some(false))
+                .count()
+        });
+
+    let exportable_round = watch_round.map(|round| SerializableWatchRound {
+        index: round.index,
+        timestamp: round.timestamp,
+        appeared: round.appeared.iter().map(|ipv4| ipv4.to_string()).collect(),
+        disappeared: round
+            .disappeared
+            .iter()
+            .map(|ipv4| ipv4.to_string())
+            .collect(),
+    });
+
+    SerializableGlobalResult {
+        round: exportable_round,
+        packet_count: response_summary.packet_count,
+        arp_count: response_summary.arp_count,
+        duration_ms: response_summary.duration_ms,
+        effective_timeout_ms: response_summary.effective_timeout_ms,
+        dns_timeout_count: response_summary.dns_timeout_count,
+        gratuitous_count: response_summary.gratuitous_count,
+        malformed_count: response_summary.malformed_count,
+        filtered_count: response_summary.filtered_count,
+        seen_count: response_summary.seen_count,
+        unknown_vendor_count: response_summary.unknown_vendor_count,
+        send_errors: response_summary.send_errors,
+        unexpected_count,
+        start_time: time::format_rfc3339(response_summary.start_time),
+        end_time: time::format_rfc3339(response_summary.end_time),
+        duplicates: get_serializable_duplicates(&response_summary.duplicates),
+        vendor_breakdown,
+        results: exportable_results,
+        sha256: None,
+    }
+}
+
+/**
+ * Add the SHA-256 hash of a global result (see '--sign'), computed over its
+ * canonical form: the compact JSON serialization, without the hash field.
+ * The same hash is written in JSON & YAML outputs, consumers can recompute it
+ * after removing the field and serializing the result as compact JSON.
+ */
+fn sign_global_result(global_result: &mut SerializableGlobalResult) -> Result<(), ScanError> {
+    global_result.sha256 = None;
+    let canonical_json = serde_json::to_string(&global_result)
+        .map_err(|err| ScanError::Serialization(format!("JSON signature, {}", err)))?;
+    global_result.sha256 = Some(sha256_hex(canonical_json.as_bytes()));
+    Ok(())
+}
+
+/**
+ * Format the SHA-256 hash of some data as a lowercase hexadecimal string (as
+ * printed by 'sha256sum').
+ */
+fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/**
+ * Export the scan results as a JSON string with response details (timings, ...)
+ * and ARP results from the local network. The compact form is kept by default
+ * for machine consumption, the pretty form is indented with two spaces.
+ */
+pub fn export_to_json(
+    response_summary: ResponseSummary,
+    mut target_details: Vec<TargetDetails>,
+    sort_field: SortField,
+    watch_round: Option<&WatchRound>,
+    is_pretty: bool,
+    is_signed: bool,
+) -> Result<String, ScanError> {
+    sort_target_details(&mut target_details, sort_field);
+
+    let mut global_result = get_serializable_result(response_summary, target_details, watch_round);
+    if is_signed {
+        sign_global_result(&mut global_result)?;
+    }
+
+    let json_result = match is_pretty {
+        true => serde_json::to_string_pretty(&global_result),
+        false => serde_json::to_string(&global_result),
+    };
+    json_result.map_err(|err| ScanError::Serialization(format!("JSON, {}", err)))
+}
+
+/**
+ * Export the scan results as a YAML string with response details (timings, ...)
+ * and ARP results from the local network.
+ */
+pub fn export_to_yaml(
+    response_summary: ResponseSummary,
+    mut target_details: Vec<TargetDetails>,
+    sort_field: SortField,
+    watch_round: Option<&WatchRound>,
+    is_signed: bool,
+) -> Result<String, ScanError> {
+    sort_target_details(&mut target_details, sort_field);
+
+    let mut global_result = get_serializable_result(response_summary, target_details, watch_round);
+    if is_signed {
+        sign_global_result(&mut global_result)?;
+    }
+
+    serde_yaml::to_string(&global_result)
+        .map_err(|err| ScanError::Serialization(format!("YAML, {}", err)))
+}
+
+/**
+ * Export the scan results as a CSV string with response details (timings, ...)
+ * and ARP results from the local network.
+ */
+pub fn export_to_csv(
+    response_summary: ResponseSummary,
+    mut target_details: Vec<TargetDetails>,
+    sort_field: SortField,
+) -> Result<String, ScanError> {
+    sort_target_details(&mut target_details, sort_field);
+
+    let global_result = get_serializable_result(response_summary, target_details, None);
+
+    let mut wtr = csv::Writer::from_writer(vec![]);
+
+    for result in global_result.results {
+        wtr.serialize(result)
+            .map_err(|err| ScanError::Serialization(format!("CSV, {}", err)))?;
+    }
+    wtr.flush()
+        .map_err(|err| ScanError::Serialization(format!("CSV buffer, {}", err)))?;
+
+    let convert_writer = wtr
+        .into_inner()
+        .map_err(|err| ScanError::Serialization(format!("CSV buffer, {}", err)))?;
+    String::from_utf8(convert_writer)
+        .map_err(|err| ScanError::Serialization(format!("CSV text, {}", err)))
+}
+
+/**
+ * Export the scan results as a self-contained HTML report, with a summary
+ * header (counts, duration, ...) and a table of all discovered hosts. The
+ * report can be opened in any browser without external resources.
+ */
+pub fn export_to_html(
+    response_summary: ResponseSummary,
+    mut target_details: Vec<TargetDetails>,
+    sort_field: SortField,
+    watch_round: Option<&WatchRound>,
+) -> String {
+    sort_target_details(&mut target_details, sort_field);
+
+    let global_result = get_serializable_result(response_summary, target_details, watch_round);
+
+    let result_rows: String = global_result
+        .results
+        .iter()
+        .map(|result| {
+            format!(
+                "      <tr><td>{}</td><td class=\"mac\">{}</td><td>{}</td><td>{}</td></tr>\n",
+                escape_html(result.ip_address()),
+                escape_html(&result.mac),
+                escape_html(&result.hostname),
+                escape_html(&result.vendor)
+            )
+        })
+        .collect();
+
+    let duplicate_items: String = global_result
+        .duplicates
+        .iter()
+        .map(|duplicate| {
+            format!(
+                "    <li>{} ({}) conflicts with {} ({})</li>\n",
+                escape_html(&duplicate.ipv4),
+                escape_html(&duplicate.mac),
+                escape_html(&duplicate.conflicting_ipv4),
+                escape_html(&duplicate.conflicting_mac)
+            )
+        })
+        .collect();
+    let duplicate_section = match duplicate_items.is_empty() {
+        true => String::new(),
+        false => format!(
+            "  <h2>Duplicate MAC addresses</h2>\n  <ul class=\"warning\">\n{}  </ul>\n",
+            duplicate_items
+        ),
+    };
+
+    let round_text = match &global_result.round {
+        Some(round) => format!(" · watch round {}", round.index + 1),
+        None => String::new(),
+    };
+
+    format!(
+        "<!DOCTYPE html>
+<html lang=\"en\">
+<head>
+  <meta charset=\"utf-8\">
+  <title>ARP scan report</title>
+  <style>
+    body {{ font-family: sans-serif; margin: 2em; color: #222; }}
+    h1 {{ color: #0a7e8c; }}
+    .summary span {{ display: inline-block; margin-right: 2em; }}
+    table {{ border-collapse: collapse; margin-top: 1em; }}
+    th, td {{ border-bottom: 1px solid #ddd; padding: 0.4em 1em; text-align: left; }}
+    th {{ background: #f4f4f4; }}
+    .mac {{ font-family: monospace; }}
+    .warning {{ color: #b35900; }}
+  </style>
+</head>
+<body>
+  <h1>ARP scan report</h1>
+  <p class=\"summary\">
+    <span><b>{}</b> hosts found</span>
+    <span><b>{}</b> packets received</span>
+    <span><b>{}</b> ARP packets filtered</span>
+    <span><b>{:.3}s</b> duration{}</span>
+  </p>
+{}  <table>
+    <thead>
+      <tr><th>IP Address</th><th>MAC Address</th><th>Hostname</th><th>Vendor</th></tr>
+    </thead>
+    <tbody>
+{}    </tbody>
+  </table>
+</body>
+</html>
+",
+        global_result.results.len(),
+        global_result.packet_count,
+        global_result.arp_count,
+        global_result.duration_ms as f32 / 1000_f32,
+        round_text,
+        duplicate_section,
+        result_rows
+    )
+}
+
+/**
+ * Export the scan results as an XML document, with a '<scan>' root element
+ * holding the summary attributes and a '<host>' element per discovered host.
+ * The same fields as the JSON & YAML exports are written, missing optional
+ * values are left out. This format is mainly intended for legacy tooling that
+ * only ingests XML.
+ */
+pub fn export_to_xml(
+    response_summary: ResponseSummary,
+    mut target_details: Vec<TargetDetails>,
+    sort_field: SortField,
+    watch_round: Option<&WatchRound>,
+) -> String {
+    sort_target_details(&mut target_details, sort_field);
+
+    let global_result = get_serializable_result(response_summary, target_details, watch_round);
+
+    let scan_attributes = format_xml_attributes(&[
+        ("packet_count", Some(global_result.packet_count.to_string())),
+        ("arp_count", Some(global_result.arp_count.to_string())),
+        ("duration_ms", Some(global_result.duration_ms.to_string())),
+        (
+            "effective_timeout_ms",
+            global_result
+                .effective_timeout_ms
+                .map(|timeout_ms| timeout_ms.to_string()),
+        ),
+        (
+            "dns_timeout_count",
+            Some(global_result.dns_timeout_count.to_string()),
+        ),
+        (
+            "gratuitous_count",
+            Some(global_result.gratuitous_count.to_string()),
+        ),
+        (
+            "malformed_count",
+            Some(global_result.malformed_count.to_string()),
+        ),
+        (
+            "filtered_count",
+            Some(global_result.filtered_count.to_string()),
+        ),
+        ("seen_count", Some(global_result.seen_count.to_string())),
+        (
+            "unknown_vendor_count",
+            Some(global_result.unknown_vendor_count.to_string()),
+        ),
+        ("send_errors", Some(global_result.send_errors.to_string())),
+        (
+            "unexpected_count",
+            global_result
+                .unexpected_count
+                .map(|unexpected_count| unexpected_count.to_string()),
+        ),
+        ("start_time", Some(global_result.start_time.clone())),
+        ("end_time", Some(global_result.end_time.clone())),
+    ]);
+
+    let round_element =
+        match &global_result.round {
+            Some(round) => {
+                let host_changes: String =
+                    round
+                        .appeared
+                        .iter()
+                        .map(|ipv4| format!("    <appeared ipv4=\"{}\"/>\n", escape_html(ipv4)))
+                        .chain(round.disappeared.iter().map(|ipv4| {
+                            format!("    <disappeared ipv4=\"{}\"/>\n", escape_html(ipv4))
+                        }))
+                        .collect();
+                format!(
+                    "  <round index=\"{}\" timestamp=\"{}\">\n{}  </round>\n",
+                    round.index, round.timestamp, host_changes
+                )
+            }
+            None => String::new(),
+        };
+
+    let duplicate_elements: String = global_result
+        .duplicates
+        .iter()
+        .map(|duplicate| {
+            format!(
+                "  <duplicate ipv4=\"{}\" mac=\"{}\" conflicting_ipv4=\"{}\" conflicting_mac=\"{}\"/>\n",
+                escape_html(&duplicate.ipv4),
+                escape_html(&duplicate.mac),
+                escape_html(&duplicate.conflicting_ipv4),
+                escape_html(&duplicate.conflicting_mac)
+            )
+        })
+        .collect();
+
+    let vendor_elements: String = global_result
+        .vendor_breakdown
+        .0
+        .iter()
+        .map(|(vendor, host_count)| {
+            format!(
+                "  <vendor name=\"{}\" host_count=\"{}\"/>\n",
+                escape_html(vendor),
+                host_count
+            )
+        })
+        .collect();
+
+    let host_elements: String = global_result
+        .results
+        .iter()
+        .map(|result| {
+            let host_attributes = format_xml_attributes(&[
+                ("ipv4", result.ipv4.clone()),
+                ("ipv6", result.ipv6.clone()),
+                ("mac", Some(result.mac.clone())),
+                ("hostname", Some(result.hostname.clone())),
+                ("vendor", Some(result.vendor.clone())),
+                ("last_seen", Some(result.last_seen.clone())),
+                (
+                    "response_ms",
+                    result
+                        .response_ms
+                        .map(|response_ms| response_ms.to_string()),
+                ),
+                ("reply_count", Some(result.reply_count.to_string())),
+                ("interface", result.interface.clone()),
+                ("source", result.source.clone()),
+                ("open_ports", result.open_ports.clone()),
+                ("sys_name", result.sys_name.clone()),
+                ("stale", result.stale.map(|stale| stale.to_string())),
+                (
+                    "proxy_arp",
+                    result.proxy_arp.map(|proxy_arp| proxy_arp.to_string()),
+                ),
+                ("known", result.known.map(|known| known.to_string())),
+            ]);
+            format!("  <host{}/>\n", host_attributes)
+        })
+        .collect();
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<scan{}>\n{}{}{}{}</scan>\n",
+        scan_attributes, round_element, duplicate_elements, vendor_elements, host_elements
+    )
+}
+
+/**
+ * Format the attributes of an XML element, with escaped values. Attributes
+ * without any value are left out, as the optional fields of JSON exports.
+ */
+fn format_xml_attributes(attributes: &[(&str, Option<String>)]) -> String {
+    attributes
+        .iter()
+        .filter_map(|(name, value)| {
+            value
+                .as_ref()
+                .map(|value| format!(" {}=\"{}\"", name, escape_html(value)))
+        })
+        .collect()
+}
+
+/**
+ * Escape the HTML special characters of a text, so that values found on the
+ * network (hostnames, ...) can be safely inserted in an HTML report. The same
+ * entities are valid in XML attributes.
+ */
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/**
+ * Export the scan results as a GitHub-flavored Markdown table, preceded by a
+ * summary blockquote (counts & duration). The output can be pasted as is in
+ * tickets or wiki pages.
+ */
+pub fn export_to_markdown(
+    response_summary: ResponseSummary,
+    mut target_details: Vec<TargetDetails>,
+    sort_field: SortField,
+    watch_round: Option<&WatchRound>,
+) -> String {
+    sort_target_details(&mut target_details, sort_field);
+
+    let global_result = get_serializable_result(response_summary, target_details, watch_round);
+
+    let round_text = match &global_result.round {
+        Some(round) => format!(" · watch round {}", round.index + 1),
+        None => String::new(),
+    };
+
+    let result_rows: String = global_result
+        .results
+        .iter()
+        .map(|result| {
+            format!(
+                "| {} | {} | {} | {} |\n",
+                escape_markdown(result.ip_address()),
+                escape_markdown(&result.mac),
+                escape_markdown(&result.hostname),
+                escape_markdown(&result.vendor)
+            )
+        })
+        .collect();
+
+    format!(
+        "> **{}** hosts found · **{}** packets received · **{}** ARP packets filtered · **{:.3}s** duration{}
+
+| IP Address | MAC Address | Hostname | Vendor |
+| --- | --- | --- | --- |
+{}",
+        global_result.results.len(),
+        global_result.packet_count,
+        global_result.arp_count,
+        global_result.duration_ms as f32 / 1000_f32,
+        round_text,
+        result_rows
+    )
+}
+
+/**
+ * Escape the characters breaking a Markdown table cell: pipes would split the
+ * cell, and line breaks would end the table row.
+ */
+fn escape_markdown(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace('\n', " ")
+}
+
+/**
+ * Export the scan KPIs in the Prometheus text exposition format. Combined with
+ * an output file, the result can be dropped in the textfile collector directory
+ * of a node_exporter. All metrics are labelled with the scanned interface.
+ */
+pub fn export_to_prometheus(
+    response_summary: &ResponseSummary,
+    target_details: &[TargetDetails],
+    interface_name: &str,
+) -> String {
+    let labels = format!(
+        "{{interface=\"{}\"}}",
+        escape_prometheus_label(interface_name)
+    );
+
+    let metrics: [(&str, &str, String); 4] = [
+        (
+            "arp_scan_hosts_total",
+            "Number of hosts discovered during the scan",
+            target_details.len().to_string(),
+        ),
+        (
+            "arp_scan_packets_received",
+            "Number of packets received during the scan",
+            response_summary.packet_count.to_string(),
+        ),
+        (
+            "arp_scan_arp_packets",
+            "Number of ARP packets filtered during the scan",
+            response_summary.arp_count.to_string(),
+        ),
+        (
+            "arp_scan_duration_seconds",
+            "Duration of the scan in seconds",
+            format!("{:.3}", response_summary.duration_ms as f64 / 1000_f64),
+        ),
+    ];
+
+    metrics
+        .iter()
+        .map(|(name, help, value)| {
+            format!(
+                "# HELP {} {}\n# TYPE {} gauge\n{}{} {}\n",
+                name, help, name, name, labels, value
+            )
+        })
+        .collect()
+}
+
+/**
+ * Escape a Prometheus label value (backslash, double-quote & line feed).
+ */
+fn escape_prometheus_label(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/**
+ * Export a single discovered target as a one-line JSON object. This is used by
+ * the NDJSON output, where each host is streamed as soon as it responds.
+ */
+pub fn export_target_to_ndjson(target_detail: &TargetDetails) -> Result<String, ScanError> {
+    let exportable_item = get_serializable_item(target_detail);
+
+    serde_json::to_string(&exportable_item)
+        .map_err(|err| ScanError::Serialization(format!("NDJSON, {}", err)))
+}
+
+/**
+ * Export the scan response details (timings, ...) as a one-line JSON object,
+ * closing the NDJSON stream once all targets have been emitted.
+ */
+pub fn export_summary_to_ndjson(response_summary: &ResponseSummary) -> Result<String, ScanError> {
+    let exportable_summary = SerializableSummary {
+        packet_count: response_summary.packet_count,
+        arp_count: response_summary.arp_count,
+        duration_ms: response_summary.duration_ms,
+        effective_timeout_ms: response_summary.effective_timeout_ms,
+        dns_timeout_count: response_summary.dns_timeout_count,
+        gratuitous_count: response_summary.gratuitous_count,
+        malformed_count: response_summary.malformed_count,
+        filtered_count: response_summary.filtered_count,
+        seen_count: response_summary.seen_count,
+        unknown_vendor_count: response_summary.unknown_vendor_count,
+        send_errors: response_summary.send_errors,
+        start_time: time::format_rfc3339(response_summary.start_time),
+        end_time: time::format_rfc3339(response_summary.end_time),
+        duplicates: get_serializable_duplicates(&response_summary.duplicates),
+    };
+
+    serde_json::to_string(&exportable_summary)
+        .map_err(|err| ScanError::Serialization(format!("NDJSON summary, {}", err)))
+}
+
+/**
+ * Export only the scan KPIs (counts & duration) as a compact JSON object,
+ * without the host list, for dashboards polling the scan metrics.
+ */
+pub fn export_summary_to_json(
+    response_summary: &ResponseSummary,
+    target_details: &[TargetDetails],
+) -> Result<String, ScanError> {
+    let exportable_kpis = SerializableKpis {
+        packet_count: response_summary.packet_count,
+        arp_count: response_summary.arp_count,
+        duration_ms: response_summary.duration_ms,
+        host_count: target_details.len(),
+    };
+
+    serde_json::to_string(&exportable_kpis)
+        .map_err(|err| ScanError::Serialization(format!("JSON summary, {}", err)))
+}
+
+/**
+ * Append a line to a time series file (see '--append-jsonl'), creating the
+ * file if needed. The line is flushed right away, so that the file stays
+ * usable by other tools between two watch rounds.
+ */
+pub fn append_result_line(file_path: &str, result_line: &str) -> Result<(), ScanError> {
+    let append_error = |err: io::Error| {
+        ScanError::Io(format!(
+            "Could not append results to {} ({})",
+            file_path, err
+        ))
+    };
+
+    let mut append_file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(file_path)
+        .map_err(append_error)?;
+    writeln!(append_file, "{}", result_line).map_err(append_error)?;
+    append_file.flush().map_err(append_error)
+}
+
+/**
+ * Format the single-line summary of '--plain-summary'. The line has a stable
+ * 'key=value' format (new keys are only appended), so that scripts can parse
+ * it without depending on the decorated summary of the plain output.
+ */
+pub fn format_plain_summary(
+    response_summary: &ResponseSummary,
+    target_details: &[TargetDetails],
+) -> String {
+    format!(
+        "SUMMARY hosts={} packets={} arp={} duration_ms={}",
+        target_details.len(),
+        response_summary.packet_count,
+        response_summary.arp_count,
+        response_summary.duration_ms
+    )
+}
+
+/**
+ * Build a random number generator, seeded from the given seed (for
+ * reproducible scans) or from the system entropy. The stream number gives
+ * distinct sequences for a single seed (e.g. one per sender thread).
+ */
+pub fn build_rng(seed: Option<u64>, stream: u64) -> StdRng {
+    match seed {
+        Some(seed) => StdRng::seed_from_u64(seed ^ stream.wrapping_mul(0x9e37_79b9_7f4a_7c15)),
+        None => StdRng::from_entropy(),
+    }
+}
+
+/**
+ * Map all items with a bounded number of worker threads, each worker taking
+ * the next item until the list is exhausted. Results are returned in the
+ * order of the items, and a panic of any worker fails the whole map.
+ */
+pub fn parallel_map<T, R, F>(
+    items: &[T],
+    thread_count: usize,
+    map_item: F,
+) -> Result<Vec<R>, ScanError>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let next_index = &AtomicUsize::new(0);
+    let map_item = &map_item;
+
+    let worker_results = thread::scope(|scope| {
+        let workers: Vec<_> = (0..thread_count.max(1).min(items.len()))
+            .map(|_| {
+                scope.spawn(move || {
+                    let mut mapped_items = vec![];
+                    loop {
+                        let index = next_index.fetch_add(1, atomic::Ordering::Relaxed);
+                        match items.get(index) {
+                            Some(item) => mapped_items.push((index, map_item(item))),
+                            None => break mapped_items,
+                        }
+                    }
+                })
+            })
+            .collect();
+        // All workers are joined before checking for a panic, otherwise the
+        // scope would panic on the remaining ones.
+        let worker_results: Vec<_> = workers.into_iter().map(|worker| worker.join()).collect();
+        worker_results
+            .into_iter()
+            .collect::<Result<Vec<Vec<(usize, R)>>, _>>()
+    })
+    .map_err(|_| ScanError::Io("A worker thread failed while enhancing targets".to_string()))?;
+
+    let mut mapped_items: Vec<(usize, R)> = worker_results.into_iter().flatten().collect();
+    mapped_items.sort_unstable_by_key(|(index, _)| *index);
+    Ok(mapped_items.into_iter().map(|(_, mapped)| mapped).collect())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use pnet_datalink::MacAddr;
+    use std::time::UNIX_EPOCH;
+
+    #[test]
+    fn should_keep_colors_when_enabled() {
+        let painted = color_style(Blue.normal(), true).paint("192.168.1.1");
+
+        assert_eq!(painted.to_string(), "\x1b[34m192.168.1.1\x1b[0m");
+    }
+
+    #[test]
+    fn should_remove_colors_when_disabled() {
+        let painted = color_style(Yellow.dimmed(), false).paint("192.168.1.1");
+
+        assert_eq!(painted.to_string(), "192.168.1.1");
+    }
+
+    #[test]
+    fn should_sort_target_details() {
+        let build_detail =
+            |last_octet: u8, mac_octet: u8, vendor: Option<&str>, latency| TargetDetails {
+                vendor: vendor.map(|vendor| vendor.to_string()),
+                response_ms: latency,
+                ..TargetDetails::test_default(
+                    Ipv4Addr::new(192, 168, 1, last_octet),
+                    MacAddr::new(0x40, 0x55, 0x82, 0xc3, 0xe5, mac_octet),
+                )
+            };
+        let mut target_details = vec![
+            build_detail(30, 0x01, None, Some(4.5)),
+            build_detail(20, 0x03, Some("nokia"), None),
+            build_detail(10, 0x02, Some("Apple"), Some(12.0)),
+            build_detail(40, 0x04, Some("Nokia"), Some(4.5)),
+        ];
+        let sorted_octets = |target_details: &[TargetDetails]| -> Vec<u8> {
+            target_details
+                .iter()
+                .map(|detail| detail.ipv4.octets()[3])
+                .collect()
+        };
+
+        sort_target_details(&mut target_details, SortField::Ipv4);
+        assert_eq!(sorted_octets(&target_details), vec![10, 20, 30, 40]);
+
+        sort_target_details(&mut target_details, SortField::Mac);
+        assert_eq!(sorted_octets(&target_details), vec![30, 10, 20, 40]);
+
+        sort_target_details(&mut target_details, SortField::Vendor);
+        assert_eq!(sorted_octets(&target_details), vec![10, 20, 40, 30]);
+
+        sort_target_details(&mut target_details, SortField::Latency);
+        assert_eq!(sorted_octets(&target_details), vec![30, 40, 10, 20]);
+    }
+
+    #[test]
+    fn should_fit_compact_columns() {
+        assert_eq!(fit_column_widths(15, 15, 41), (15, 15));
+        assert_eq!(fit_column_widths(15, 40, 41), (15, 26));
+        assert_eq!(fit_column_widths(40, 40, 41), (20, 21));
+        assert_eq!(fit_column_widths(40, 40, 4), (8, 8));
+
+        assert_eq!(
+            truncate_text("Raspberry Pi Trading Ltd", 12),
+            "Raspberry P…"
+        );
+        assert_eq!(truncate_text("Nokia", 12), "Nokia");
+    }
+
+    #[test]
+    fn should_compute_network_breakdown() {
+        let build_detail = |ipv4: Ipv4Addr| TargetDetails::test_default(ipv4, MacAddr::zero());
+        let scanned_networks: Vec<IpNetwork> = vec![
+            "192.168.1.21/24".parse().unwrap(),
+            "10.0.0.0/28".parse().unwrap(),
+            "192.168.1.0/24".parse().unwrap(),
+        ];
+        let target_details = vec![
+            build_detail(Ipv4Addr::new(192, 168, 1, 1)),
+            build_detail(Ipv4Addr::new(192, 168, 1, 254)),
+            build_detail(Ipv4Addr::new(172, 16, 0, 1)),
+        ];
+
+        let (network_breakdown, outside_count) =
+            compute_network_breakdown(&target_details, &scanned_networks);
+
+        assert_eq!(
+            network_breakdown,
+            vec![
+                ("192.168.1.0/24".parse().unwrap(), 2),
+                ("10.0.0.0/28".parse().unwrap(), 0),
+            ]
+        );
+        assert_eq!(outside_count, 1);
+    }
+
+    #[test]
+    fn should_compute_vendor_breakdown() {
+        let build_detail = |last_octet: u8, vendor: Option<&str>| TargetDetails {
+            vendor: vendor.map(|vendor| vendor.to_string()),
+            ..TargetDetails::test_default(Ipv4Addr::new(192, 168, 1, last_octet), MacAddr::zero())
+        };
+        let target_details = vec![
+            build_detail(10, Some("Nokia")),
+            build_detail(11, None),
+            build_detail(12, Some("Apple")),
+            build_detail(13, Some("Nokia")),
+            build_detail(14, Some("Cisco")),
+        ];
+
+        assert_eq!(
+            compute_vendor_breakdown(&target_details),
+            vec![
+                ("Nokia".to_string(), 2),
+                (UNKNOWN_VENDOR.to_string(), 1),
+                ("Apple".to_string(), 1),
+                ("Cisco".to_string(), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_export_markdown_table() {
+        let response_summary = ResponseSummary {
+            packet_count: 12,
+            arp_count: 3,
+            duration_ms: 1500,
+            duplicates: vec![],
+            effective_timeout_ms: None,
+            dns_timeout_count: 0,
+            gratuitous_count: 0,
+            malformed_count: 0,
+            filtered_count: 0,
+            seen_count: 0,
+            unknown_vendor_count: 0,
+            send_errors: 0,
+            start_time: UNIX_EPOCH,
+            end_time: UNIX_EPOCH,
+        };
+        let target_details = vec![TargetDetails {
+            hostname: Some("nas|backup".to_string()),
+            vendor: Some("Nokia".to_string()),
+            ..TargetDetails::test_default(
+                Ipv4Addr::new(192, 168, 1, 20),
+                MacAddr::new(0x40, 0x55, 0x82, 0xc3, 0xe5, 0x5b),
+            )
+        }];
+
+        let markdown_table =
+            export_to_markdown(response_summary, target_details, SortField::Ipv4, None);
+
+        assert_eq!(
+            markdown_table,
+            "> **1** hosts found · **12** packets received · **3** ARP packets filtered · **1.500s** duration\n\
+            \n\
+            | IP Address | MAC Address | Hostname | Vendor |\n\
+            | --- | --- | --- | --- |\n\
+            | 192.168.1.20 | 40:55:82:c3:e5:5b | nas\\|backup | Nokia |\n"
+        );
+    }
+
+    #[test]
+    fn should_escape_html_characters() {
+        assert_eq!(
+            escape_html("<host & \"co\">"),
+            "&lt;host &amp; &quot;co&quot;&gt;".to_string()
+        );
+    }
+
+    #[test]
+    fn should_export_html_report() {
+        let response_summary = ResponseSummary {
+            packet_count: 12,
+            arp_count: 3,
+            duration_ms: 1500,
+            duplicates: vec![],
+            effective_timeout_ms: None,
+            dns_timeout_count: 0,
+            gratuitous_count: 0,
+            malformed_count: 0,
+            filtered_count: 0,
+            seen_count: 0,
+            unknown_vendor_count: 0,
+            send_errors: 0,
+            start_time: UNIX_EPOCH,
+            end_time: UNIX_EPOCH,
+        };
+        let target_details = vec![TargetDetails {
+            hostname: Some("<script>".to_string()),
+            vendor: Some("Nokia".to_string()),
+            ..TargetDetails::test_default(
+                Ipv4Addr::new(192, 168, 1, 20),
+                MacAddr::new(0x40, 0x55, 0x82, 0xc3, 0xe5, 0x5b),
+            )
+        }];
+
+        let html_report = export_to_html(response_summary, target_details, SortField::Ipv4, None);
+
+        assert!(html_report.starts_with("<!DOCTYPE html>"));
+        assert!(html_report.contains("<b>1</b> hosts found"));
+        assert!(html_report.contains("<b>1.500s</b> duration"));
+        assert!(html_report.contains("<td>192.168.1.20</td>"));
+        assert!(html_report.contains("<td>&lt;script&gt;</td>"));
+        assert!(!html_report.contains("Duplicate MAC addresses"));
+    }
+
+    #[test]
+    fn should_export_xml_document() {
+        let response_summary = ResponseSummary {
+            packet_count: 12,
+            arp_count: 3,
+            duration_ms: 1500,
+            duplicates: vec![],
+            effective_timeout_ms: Some(800),
+            dns_timeout_count: 0,
+            gratuitous_count: 0,
+            malformed_count: 2,
+            filtered_count: 5,
+            seen_count: 2,
+            unknown_vendor_count: 1,
+            send_errors: 1,
+            start_time: UNIX_EPOCH,
+            end_time: UNIX_EPOCH,
+        };
+        let target_details = vec![
+            TargetDetails::test_default(
+                Ipv4Addr::new(192, 168, 1, 30),
+                MacAddr::new(0x00, 0x11, 0x22, 0x33, 0x44, 0x55),
+            ),
+            TargetDetails {
+                hostname: Some("<a & \"b\">".to_string()),
+                vendor: Some("Nokia".to_string()),
+                response_ms: Some(1.5),
+                reply_count: 2,
+                interface: Some("eth0".to_string()),
+                known: Some(true),
+                ..TargetDetails::test_default(
+                    Ipv4Addr::new(192, 168, 1, 20),
+                    MacAddr::new(0x40, 0x55, 0x82, 0xc3, 0xe5, 0x5b),
+                )
+            },
+        ];
+
+        let xml_document = export_to_xml(response_summary, target_details, SortField::Ipv4, None);
+
+        assert!(xml_document.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<scan packet_count=\"12\" arp_count=\"3\" duration_ms=\"1500\" effective_timeout_ms=\"800\""));
+        assert!(xml_document.contains(" malformed_count=\"2\" filtered_count=\"5\" seen_count=\"2\" unknown_vendor_count=\"1\" send_errors=\"1\" unexpected_count=\"0\" start_time=\"1970-01-01T00:00:00Z\" end_time=\"1970-01-01T00:00:00Z\">\n"));
+        assert!(xml_document.contains("<vendor name=\"Nokia\" host_count=\"1\"/>"));
+        assert!(xml_document.contains("<host ipv4=\"192.168.1.20\" mac=\"40:55:82:c3:e5:5b\" hostname=\"&lt;a &amp; &quot;b&quot;&gt;\" vendor=\"Nokia\" last_seen=\"1970-01-01T00:00:00Z\" response_ms=\"1.5\" reply_count=\"2\" interface=\"eth0\" known=\"true\"/>\n  <host ipv4=\"192.168.1.30\""));
+        assert!(xml_document.ends_with("</scan>\n"));
+
+        // Every element should be closed in order, and attribute values should
+        // not contain any unescaped markup.
+        let mut open_elements: Vec<&str> = vec![];
+        for element in xml_document.split('<').skip(2) {
+            let (tag, _) = element.split_once('>').unwrap();
+            assert_eq!(tag.matches('"').count() % 2, 0);
+            match (tag.strip_prefix('/'), tag.ends_with('/')) {
+                (Some(closing_name), _) => assert_eq!(open_elements.pop(), Some(closing_name)),
+                (None, true) => {}
+                (None, false) => open_elements.push(tag.split(' ').next().unwrap()),
+            }
+        }
+        assert!(open_elements.is_empty());
+    }
+
+    #[test]
+    fn should_export_interfaces_to_json() {
+        let interfaces = vec![NetworkInterface {
+            name: "eth0".to_string(),
+            description: String::new(),
+            index: 2,
+            mac: Some(MacAddr::new(0x02, 0, 0, 0, 0, 0x01)),
+            ips: vec!["192.168.1.2/24".parse().unwrap()],
+            flags: 1,
+        }];
+
+        assert_eq!(
+            export_interfaces_to_json(&interfaces, false, |_| InterfaceLink {
+                mtu: Some(1500),
+                speed_mbps: None,
+            })
+            .unwrap(),
+            "[{\"name\":\"eth0\",\"index\":2,\"mac\":\"02:00:00:00:00:01\",\"ips\":[\"192.168.1.2/24\"],\"is_up\":true,\"is_loopback\":false,\"mtu\":1500,\"speed_mbps\":null}]"
+        );
+    }
+
+    #[test]
+    fn should_export_single_ndjson_line() {
+        let target_detail = TargetDetails {
+            hostname: Some("host-a.home".to_string()),
+            ..TargetDetails::test_default(
+                Ipv4Addr::new(192, 168, 1, 20),
+                MacAddr::new(0x40, 0x55, 0x82, 0xc3, 0xe5, 0x5b),
+            )
+        };
+
+        assert_eq!(
+            export_target_to_ndjson(&target_detail).unwrap(),
+            "{\"ipv4\":\"192.168.1.20\",\"mac\":\"40:55:82:c3:e5:5b\",\"hostname\":\"host-a.home\",\"vendor\":\"(unknown)\",\"last_seen\":\"1970-01-01T00:00:00Z\",\"response_ms\":null,\"reply_count\":1}"
+        );
+    }
+
+    #[test]
+    fn should_export_ipv6_ndjson_line() {
+        let target_detail = TargetDetails {
+            ipv6: Some("fd00::20".parse().unwrap()),
+            response_ms: Some(1.5),
+            reply_count: 3,
+            ..TargetDetails::test_default(
+                Ipv4Addr::UNSPECIFIED,
+                MacAddr::new(0x40, 0x55, 0x82, 0xc3, 0xe5, 0x5b),
+            )
+        };
+
+        assert_eq!(
+            export_target_to_ndjson(&target_detail).unwrap(),
+            "{\"ipv6\":\"fd00::20\",\"mac\":\"40:55:82:c3:e5:5b\",\"hostname\":\"\",\"vendor\":\"(unknown)\",\"last_seen\":\"1970-01-01T00:00:00Z\",\"response_ms\":1.5,\"reply_count\":3}"
+        );
+    }
+
+    #[test]
+    fn should_compute_address_widths() {
+        let ipv4_detail = TargetDetails::test_default(
+            Ipv4Addr::new(192, 168, 1, 20),
+            MacAddr::new(0x40, 0x55, 0x82, 0xc3, 0xe5, 0x5b),
+        );
+        let mut ipv6_detail = ipv4_detail.clone();
+        ipv6_detail.ipv6 = Some("fd00:1234:5678::abcd:20".parse().unwrap());
+
+        assert_eq!(compute_address_widths(&[]), (17, 19));
+        assert_eq!(
+            compute_address_widths(std::slice::from_ref(&ipv4_detail)),
+            (17, 19)
+        );
+        assert_eq!(
+            compute_address_widths(&[ipv4_detail, ipv6_detail]),
+            (25, 19)
+        );
+    }
+
+    #[test]
+    fn should_compute_sampled_network_size() {
+        let network_a = IpNetwork::V4("10.0.0.0/16".parse().unwrap());
+        let network_b = IpNetwork::V4("192.168.1.0/24".parse().unwrap());
+        let ip_networks = vec![&network_a, &network_b];
+
+        assert_eq!(compute_network_size(&ip_networks, None), 65_792);
+        assert_eq!(compute_network_size(&ip_networks, Some(10.0)), 6579);
+    }
+
+    #[test]
+    fn should_format_latency() {
+        assert_eq!(format_latency(Some(1.23456)), "1.23ms");
+        assert_eq!(format_latency(Some(0.0)), "0.00ms");
+        assert_eq!(format_latency(None), "-");
+    }
+
+    #[test]
+    fn should_format_link_speed() {
+        assert_eq!(format_link_speed(Some(1000)), "1 Gb/s");
+        assert_eq!(format_link_speed(Some(2500)), "2500 Mb/s");
+        assert_eq!(format_link_speed(Some(100)), "100 Mb/s");
+        assert_eq!(format_link_speed(None), "unknown");
+    }
+
+    #[test]
+    fn should_format_unknown_vendor() {
+        let build_detail = |vendor: Option<&str>| TargetDetails {
+            vendor: vendor.map(|vendor| vendor.to_string()),
+            ..TargetDetails::test_default(
+                Ipv4Addr::new(192, 168, 1, 20),
+                MacAddr::new(0xb8, 0x27, 0xeb, 0x33, 0x44, 0x55),
+            )
+        };
+
+        assert_eq!(format_vendor(&build_detail(Some("Nokia")), true), "Nokia");
+        assert_eq!(format_vendor(&build_detail(None), false), "(unknown)");
+        assert_eq!(
+            format_vendor(&build_detail(None), true),
+            "(unknown) B8:27:EB"
+        );
+    }
+
+    #[test]
+    fn should_export_prometheus_metrics() {
+        let response_summary = ResponseSummary {
+            packet_count: 42,
+            arp_count: 12,
+            duration_ms: 1500,
+            duplicates: vec![],
+            effective_timeout_ms: None,
+            dns_timeout_count: 0,
+            gratuitous_count: 0,
+            malformed_count: 0,
+            filtered_count: 0,
+            seen_count: 0,
+            unknown_vendor_count: 0,
+            send_errors: 0,
+            start_time: UNIX_EPOCH,
+            end_time: UNIX_EPOCH,
+        };
+        let target_details = vec![TargetDetails::test_default(
+            Ipv4Addr::new(192, 168, 1, 20),
+            MacAddr::new(0x00, 0x11, 0x22, 0x33, 0x44, 0x55),
+        )];
+
+        let metrics = export_to_prometheus(&response_summary, &target_details, "eth0");
+
+        assert!(metrics.contains("# TYPE arp_scan_hosts_total gauge\n"));
+        assert!(metrics.contains("arp_scan_hosts_total{interface=\"eth0\"} 1\n"));
+        assert!(metrics.contains("arp_scan_packets_received{interface=\"eth0\"} 42\n"));
+        assert!(metrics.contains("arp_scan_arp_packets{interface=\"eth0\"} 12\n"));
+        assert!(metrics.contains("arp_scan_duration_seconds{interface=\"eth0\"} 1.500\n"));
+    }
+
+    #[test]
+    fn should_export_summary_json() {
+        let response_summary = ResponseSummary {
+            packet_count: 42,
+            arp_count: 12,
+            duration_ms: 1500,
+            duplicates: vec![],
+            effective_timeout_ms: Some(800),
+            dns_timeout_count: 0,
+            gratuitous_count: 0,
+            malformed_count: 0,
+            filtered_count: 0,
+            seen_count: 0,
+            unknown_vendor_count: 0,
+            send_errors: 0,
+            start_time: UNIX_EPOCH,
+            end_time: UNIX_EPOCH,
+        };
+
+        let summary_json = export_summary_to_json(&response_summary, &[]).unwrap();
+
+        assert_eq!(
+            summary_json,
+            "{\"packet_count\":42,\"arp_count\":12,\"duration_ms\":1500,\"host_count\":0}"
+        );
+    }
+
+    #[test]
+    fn should_format_template_line() {
+        let template = parse_output_template("{ipv4}\\t{mac}\\t{{{vendor}}} {hostname}.").unwrap();
+        let detail = TargetDetails {
+            vendor: Some("Nokia".to_string()),
+            response_ms: Some(1.5),
+            ..TargetDetails::test_default(
+                Ipv4Addr::new(192, 168, 1, 20),
+                MacAddr::new(0x00, 0x11, 0x22, 0x33, 0x44, 0x55),
+            )
+        };
+
+        assert_eq!(
+            format_template_line(&template, &detail),
+            "192.168.1.20\t00:11:22:33:44:55\t{Nokia} ."
+        );
+        assert!(parse_output_template("{ipv4} {mtu}")
+            .unwrap_err()
+            .starts_with("unknown placeholder {mtu}"));
+        assert!(parse_output_template("{ipv4}}").is_err());
+    }
+
+    #[test]
+    fn should_format_hex_dump() {
+        let bytes: Vec<u8> = (0..18).collect();
+
+        assert_eq!(
+            format_hex_dump(&bytes),
+            "0000  00 01 02 03 04 05 06 07 08 09 0a 0b 0c 0d 0e 0f\n0010  10 11\n"
+        );
+    }
+
+    #[test]
+    fn should_append_result_lines() {
+        let path = std::env::temp_dir().join("arp-scan-append-test.jsonl");
+        fs::write(&path, "{\"previous\":true}\n").unwrap();
+
+        for _ in 0..2 {
+            let result_line =
+                export_to_json(build_summary(), vec![], SortField::Ipv4, None, false, false)
+                    .unwrap();
+            append_result_line(path.to_str().unwrap(), &result_line).unwrap();
+        }
+
+        let content = fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], "{\"previous\":true}");
+        for line in &lines[1..] {
+            assert!(serde_json::from_str::<serde_json::Value>(line).is_ok());
+        }
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn should_format_plain_summary() {
+        let response_summary = ResponseSummary {
+            packet_count: 340,
+            arp_count: 15,
+            duration_ms: 2034,
+            duplicates: vec![],
+            effective_timeout_ms: None,
+            dns_timeout_count: 0,
+            gratuitous_count: 0,
+            malformed_count: 0,
+            filtered_count: 0,
+            seen_count: 0,
+            unknown_vendor_count: 0,
+            send_errors: 0,
+            start_time: UNIX_EPOCH,
+            end_time: UNIX_EPOCH,
+        };
+
+        assert_eq!(
+            format_plain_summary(&response_summary, &[]),
+            "SUMMARY hosts=0 packets=340 arp=15 duration_ms=2034"
+        );
+    }
+
+    #[test]
+    fn should_escape_prometheus_label() {
+        assert_eq!(
+            escape_prometheus_label("my \"eth\"\\0"),
+            "my \\\"eth\\\"\\\\0"
+        );
+    }
+
+    fn build_summary() -> ResponseSummary {
+        ResponseSummary {
+            packet_count: 2,
+            arp_count: 1,
+            duration_ms: 100,
+            duplicates: vec![],
+            effective_timeout_ms: None,
+            dns_timeout_count: 0,
+            gratuitous_count: 0,
+            malformed_count: 0,
+            filtered_count: 0,
+            seen_count: 0,
+            unknown_vendor_count: 0,
+            send_errors: 0,
+            start_time: UNIX_EPOCH,
+            end_time: UNIX_EPOCH,
+        }
+    }
+
+    #[test]
+    fn should_export_pretty_json() {
+        let compact_json =
+            export_to_json(build_summary(), vec![], SortField::Ipv4, None, false, false).unwrap();
+        let pretty_json =
+            export_to_json(build_summary(), vec![], SortField::Ipv4, None, true, false).unwrap();
+
+        assert!(compact_json.starts_with("{\"packet_count\":2,"));
+        assert!(!compact_json.contains('\n'));
+        assert!(compact_json.contains(
+            "\"start_time\":\"1970-01-01T00:00:00Z\",\"end_time\":\"1970-01-01T00:00:00Z\""
+        ));
+        assert!(pretty_json.starts_with("{\n  \"packet_count\": 2,\n"));
+    }
+
+    #[test]
+    fn should_export_unexpected_count() {
+        let target_details = vec![
+            TargetDetails {
+                known: Some(true),
+                ..TargetDetails::test_default(
+                    Ipv4Addr::new(192, 168, 1, 20),
+                    MacAddr::new(0x40, 0x55, 0x82, 0xc3, 0xe5, 0x5b),
+                )
+            },
+            TargetDetails {
+                known: Some(false),
+                ..TargetDetails::test_default(
+                    Ipv4Addr::new(192, 168, 1, 30),
+                    MacAddr::new(0x00, 0x11, 0x22, 0x33, 0x44, 0x55),
+                )
+            },
+        ];
+
+        let json_result = export_to_json(
+            build_summary(),
+            target_details.clone(),
+            SortField::Ipv4,
+            None,
+            false,
+            false,
+        )
+        .unwrap();
+        let yaml_result = export_to_yaml(
+            build_summary(),
+            target_details.clone(),
+            SortField::Ipv4,
+            None,
+            false,
+        )
+        .unwrap();
+        let xml_document = export_to_xml(build_summary(), target_details, SortField::Ipv4, None);
+        let unchecked_json =
+            export_to_json(build_summary(), vec![], SortField::Ipv4, None, false, false).unwrap();
+
+        assert!(json_result.contains("\"send_errors\":0,\"unexpected_count\":1,"));
+        assert!(yaml_result.contains("\nunexpected_count: 1\n"));
+        assert!(xml_document.contains(" send_errors=\"0\" unexpected_count=\"1\" "));
+        assert!(!unchecked_json.contains("unexpected_count"));
+    }
+
+    #[test]
+    fn should_sign_exported_results() {
+        let signed_json =
+            export_to_json(build_summary(), vec![], SortField::Ipv4, None, false, true).unwrap();
+        let unsigned_json =
+            export_to_json(build_summary(), vec![], SortField::Ipv4, None, false, false).unwrap();
+        let signed_yaml =
+            export_to_yaml(build_summary(), vec![], SortField::Ipv4, None, true).unwrap();
+
+        let (canonical_json, signature) = signed_json.split_once(",\"sha256\":\"").unwrap();
+        let expected_signature = sha256_hex(unsigned_json.as_bytes());
+        assert_eq!(format!("{}}}", canonical_json), unsigned_json);
+        assert_eq!(signature.trim_end_matches("\"}"), expected_signature);
+        assert!(signed_yaml.ends_with(&format!("sha256: {}\n", expected_signature)));
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn should_map_items_in_parallel() {
+        let items: Vec<u64> = (0..50).collect();
+
+        let squares = parallel_map(&items, 4, |item| item * item).unwrap();
+        let single_squares = parallel_map(&items, 0, |item| item * item).unwrap();
+        let empty_squares = parallel_map(&[] as &[u64], 4, |item| item * item).unwrap();
+
+        assert_eq!(
+            squares,
+            items.iter().map(|item| item * item).collect::<Vec<u64>>()
+        );
+        assert_eq!(single_squares, squares);
+        assert!(empty_squares.is_empty());
+        assert!(parallel_map(&items, 4, |item| assert!(*item < 40)).is_err());
+    }
 }
@@ -1,40 +1,138 @@
-use std::fs::File;
-use std::process;
+use std::collections::HashMap;
+use std::env;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::Command;
 
 use csv::{Position, Reader};
 use pnet_datalink::MacAddr;
 
+use crate::error::ScanError;
+
+pub const UNKNOWN_VENDOR: &str = "(unknown)";
+
+const IEEE_OUI_URL: &str = "https://standards-oui.ieee.org/oui/oui.csv";
+
+const CACHE_DIRECTORY: &str = "arp-scan-rs";
+const CACHE_DATABASE_FILE: &str = "ieee-oui.csv";
+
+// Vendor databases installed by common Linux distributions (ieee-data and
+// Wireshark packages), searched when no OUI file is given.
+const SYSTEM_DATABASE_PATHS: [&str; 3] = [
+    "/usr/share/ieee-data/oui.txt",
+    "/var/lib/ieee-data/oui.txt",
+    "/usr/share/wireshark/manuf",
+];
+
 // The Vendor structure performs search operations on a vendor database to find
 // which MAC address belongs to a specific vendor. All network vendors have a
 // dedicated MAC address range that is registered by the IEEE and maintained in
 // the OUI database. An OUI is a 24-bit globally unique assigned number
-// referenced by various standards.
+// referenced by various standards. Resolved OUI prefixes are cached, so that
+// each prefix is searched at most once in the database. Wireshark 'manuf'
+// files and IEEE 'oui.txt' files are supported as well, and fully loaded in
+// memory.
 pub struct Vendor {
     reader: Option<Reader<File>>,
+    prefix_entries: Option<Vec<PrefixEntry>>,
+    cache: HashMap<[u8; 3], Option<String>>,
+}
+
+// A vendor prefix of an in-memory database. Prefixes are usually 24-bit OUIs,
+// but smaller assignments of 'manuf' files use longer masks (e.g. /28 or /36),
+// the MAC address and its prefix are therefore compared as 48-bit numbers.
+#[derive(Debug, PartialEq)]
+struct PrefixEntry {
+    prefix: u64,
+    mask_len: u8,
+    vendor: String,
+}
+
+impl PrefixEntry {
+    fn matches(&self, mac_number: u64) -> bool {
+        let shift = 48 - u32::from(self.mask_len);
+        (mac_number >> shift) == (self.prefix >> shift)
+    }
+}
+
+// Text formats loaded in memory, the IEEE CSV format is used otherwise.
+#[derive(Debug, PartialEq)]
+enum TextFormat {
+    Manuf,
+    OuiText,
 }
 
 impl Vendor {
     // Create a new MAC vendor search instance based on the given datebase path
     // (absolute or relative). A failure will not throw an error, but leave the
     // vendor search instance without database reader.
+    // The database format is detected from the first entry: Wireshark 'manuf'
+    // files are used if the entry starts with a MAC prefix, IEEE 'oui.txt'
+    // files if it is the registry header, and the IEEE CSV format otherwise.
     pub fn new(path: &str) -> Self {
+        let prefix_entries = detect_text_format(path)
+            .and_then(|text_format| {
+                let database_text = fs::read_to_string(path).ok()?;
+                Some(match text_format {
+                    TextFormat::Manuf => parse_manuf(&database_text),
+                    TextFormat::OuiText => parse_oui_text(&database_text),
+                })
+            })
+            .filter(|prefix_entries| !prefix_entries.is_empty());
+        if prefix_entries.is_some() {
+            return Vendor {
+                reader: None,
+                prefix_entries,
+                cache: HashMap::new(),
+            };
+        }
+
         let file_result = File::open(path);
 
         match file_result {
             Ok(file) => Vendor {
                 reader: Some(Reader::from_reader(file)),
+                prefix_entries: None,
+                cache: HashMap::new(),
+            },
+            Err(_) => Vendor {
+                reader: None,
+                prefix_entries: None,
+                cache: HashMap::new(),
             },
-            Err(_) => Vendor { reader: None },
         }
     }
 
     pub fn has_vendor_db(&self) -> bool {
-        self.reader.is_some()
+        self.reader.is_some() || self.prefix_entries.is_some()
+    }
+
+    // Find a vendor name based on a given MAC address, using the cached result
+    // if the same OUI prefix has already been searched (even without match).
+    // Manuf prefixes may be longer than an OUI, they are searched directly.
+    pub fn lookup(&mut self, mac_address: &MacAddr) -> Option<String> {
+        if self.prefix_entries.is_some() {
+            return self.search_by_mac(mac_address);
+        }
+
+        let prefix = [mac_address.0, mac_address.1, mac_address.2];
+        if let Some(vendor) = self.cache.get(&prefix) {
+            return vendor.clone();
+        }
+
+        let vendor = self.search_by_mac(mac_address);
+        self.cache.insert(prefix, vendor.clone());
+        vendor
     }
 
     // Find a vendor name based on a given MAC address. A vendor search
     // operation will perform a whole read on the database for now.
     pub fn search_by_mac(&mut self, mac_address: &MacAddr) -> Option<String> {
+        if let Some(prefix_entries) = &self.prefix_entries {
+            return search_prefixes(prefix_entries, mac_address);
+        }
+
         match &mut self.reader {
             Some(reader) => {
                 // The {:02X} syntax forces to pad all numbers with zero values.
@@ -46,17 +144,12 @@ impl Vendor {
                 );
 
                 // Since we share a common instance of the CSV reader, it must be reset
-                // before each read (internal buffers will be cleared).
-                reader.seek(Position::new()).unwrap_or_else(|err| {
-                    eprintln!("Could not reset the CSV reader ({})", err);
-                    process::exit(1);
-                });
+                // before each read (internal buffers will be cleared). An unreadable
+                // database only leaves the vendor unknown, without stopping the scan.
+                reader.seek(Position::new()).ok()?;
 
                 for vendor_result in reader.records() {
-                    let record = vendor_result.unwrap_or_else(|err| {
-                        eprintln!("Could not read CSV record ({})", err);
-                        process::exit(1);
-                    });
+                    let record = vendor_result.ok()?;
                     let potential_oui = record.get(1).unwrap_or("");
 
                     if vendor_oui.eq(potential_oui) {
@@ -71,7 +164,233 @@ impl Vendor {
     }
 }
 
+// Find the vendor database to use when no OUI file is given: the default
+// path if it exists, the registry downloaded in the user cache directory (see
+// '--update-oui'), or the first database installed on the system.
+pub fn find_system_database(default_path: &str) -> Option<String> {
+    std::iter::once(default_path)
+        .chain(find_cache_database_path().as_deref())
+        .chain(SYSTEM_DATABASE_PATHS)
+        .find(|path| Path::new(path).is_file())
+        .map(str::to_string)
+}
+
+// Find where the downloaded IEEE registry is cached, in the XDG cache directory
+// (or '~/.cache' when the XDG variable is not set), so that '--update-oui'
+// never needs to write in a system directory.
+pub fn find_cache_database_path() -> Option<String> {
+    let cache_directory = match env::var("XDG_CACHE_HOME") {
+        Ok(xdg_directory) if !xdg_directory.is_empty() => PathBuf::from(xdg_directory),
+        _ => PathBuf::from(env::var("HOME").ok()?).join(".cache"),
+    };
+
+    let cache_path = cache_directory
+        .join(CACHE_DIRECTORY)
+        .join(CACHE_DATABASE_FILE);
+    cache_path.to_str().map(str::to_string)
+}
+
+// Detect a text database from its first entry (comments and empty lines are
+// skipped). A Wireshark 'manuf' entry starts with a MAC prefix such as
+// '00:00:0C', an IEEE 'oui.txt' file starts with the 'OUI/MA-L' header.
+fn detect_text_format(path: &str) -> Option<TextFormat> {
+    let file = File::open(path).ok()?;
+    let first_line = BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .find(|line| !line.trim().is_empty() && !line.starts_with('#'))?;
+
+    if parse_manuf_line(&first_line).is_some() {
+        return Some(TextFormat::Manuf);
+    }
+    match first_line.starts_with("OUI/") || parse_oui_text_line(&first_line).is_some() {
+        true => Some(TextFormat::OuiText),
+        false => None,
+    }
+}
+
+// Parse all vendors of an IEEE 'oui.txt' file. Each vendor is listed twice
+// (with the '(hex)' and '(base 16)' notations), only the first one is read.
+fn parse_oui_text(oui_text: &str) -> Vec<PrefixEntry> {
+    oui_text.lines().filter_map(parse_oui_text_line).collect()
+}
+
+// Parse a single 'oui.txt' line such as '00-22-72   (hex)\t\tAmerican Micro-Fuel
+// Device Corp.', other lines (addresses, headers, ...) are skipped.
+fn parse_oui_text_line(line: &str) -> Option<PrefixEntry> {
+    let (prefix_text, vendor) = line.split_once("(hex)")?;
+    let vendor = vendor.trim();
+    if vendor.is_empty() {
+        return None;
+    }
+
+    let (prefix, mask_len) = parse_prefix(prefix_text.trim())?;
+    Some(PrefixEntry {
+        prefix,
+        mask_len,
+        vendor: vendor.to_string(),
+    })
+}
+
+// Parse all entries of a Wireshark 'manuf' file. Malformed lines are skipped,
+// since the file is maintained by hand.
+fn parse_manuf(manuf_text: &str) -> Vec<PrefixEntry> {
+    manuf_text
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(parse_manuf_line)
+        .collect()
+}
+
+// Parse a single 'manuf' line: a MAC prefix (with an optional mask length), a
+// short vendor name and an optional long vendor name, separated by tabs. The
+// long name is preferred, since it matches the IEEE organization names.
+fn parse_manuf_line(line: &str) -> Option<PrefixEntry> {
+    let mut fields = line.split('\t').map(str::trim);
+    let prefix_text = fields.next()?;
+    let short_name = fields.next().filter(|name| !name.is_empty())?;
+    let long_name = fields.next().filter(|name| !name.is_empty());
+
+    let (prefix, mask_len) = parse_prefix(prefix_text)?;
+    Some(PrefixEntry {
+        prefix,
+        mask_len,
+        vendor: long_name.unwrap_or(short_name).to_string(),
+    })
+}
+
+// Parse a MAC prefix of 3 to 6 bytes (separated by colons, dashes or dots),
+// with an optional mask length. The prefix is returned as a 48-bit number.
+fn parse_prefix(prefix_text: &str) -> Option<(u64, u8)> {
+    let (address_text, mask_text) = match prefix_text.split_once('/') {
+        Some((address_text, mask_text)) => (address_text, Some(mask_text)),
+        None => (prefix_text, None),
+    };
+    let prefix_bytes = address_text
+        .split([':', '-', '.'])
+        .map(|byte_text| match byte_text.len() {
+            2 => u8::from_str_radix(byte_text, 16).ok(),
+            _ => None,
+        })
+        .collect::<Option<Vec<u8>>>()?;
+    if prefix_bytes.len() < 3 || prefix_bytes.len() > 6 {
+        return None;
+    }
+
+    let mask_len = match mask_text {
+        Some(mask_text) => mask_text.parse::<u8>().ok().filter(|mask| *mask <= 48)?,
+        None => (prefix_bytes.len() * 8) as u8,
+    };
+    let prefix = (0..6).fold(0u64, |prefix, index| {
+        (prefix << 8) | u64::from(prefix_bytes.get(index).copied().unwrap_or(0))
+    });
+
+    Some((prefix, mask_len))
+}
+
+// Find the vendor of a MAC address in prefix entries, the longest matching
+// prefix being selected (a /28 assignment inside an OUI wins over the OUI).
+fn search_prefixes(prefix_entries: &[PrefixEntry], mac_address: &MacAddr) -> Option<String> {
+    let mac_number = mac_address.octets().iter().fold(0u64, |mac_number, byte| {
+        (mac_number << 8) | u64::from(*byte)
+    });
+
+    prefix_entries
+        .iter()
+        .filter(|prefix_entry| prefix_entry.matches(mac_number))
+        .max_by_key(|prefix_entry| prefix_entry.mask_len)
+        .map(|prefix_entry| prefix_entry.vendor.clone())
+}
+
+// Download the official IEEE OUI registry to the given path, and return the
+// number of vendor prefixes it contains. The registry is first downloaded next
+// to the destination, which is only replaced once the download is complete
+// and valid (an existing database is never clobbered by a partial download).
+pub fn update_database(path: &str) -> Result<usize, ScanError> {
+    update_database_from(IEEE_OUI_URL, path)
+}
+
+fn update_database_from(url: &str, path: &str) -> Result<usize, ScanError> {
+    let download_path = format!("{}.download", path);
+
+    if let Some(parent_directory) = Path::new(path).parent() {
+        fs::create_dir_all(parent_directory).map_err(|err| {
+            ScanError::Io(format!(
+                "Could not create the OUI database directory for {} ({})",
+                path, err
+            ))
+        })?;
+    }
+
+    let prefix_count =
+        match download_registry(url, &download_path).and_then(|_| count_prefixes(&download_path)) {
+            Ok(prefix_count) => prefix_count,
+            Err(err) => {
+                let _ = fs::remove_file(&download_path);
+                return Err(err);
+            }
+        };
+
+    fs::rename(&download_path, path).map_err(|err| {
+        let _ = fs::remove_file(&download_path);
+        ScanError::Io(format!(
+            "Could not write the OUI database to {} ({})",
+            path, err
+        ))
+    })?;
+    Ok(prefix_count)
+}
+
+// The registry is fetched with the system 'curl' command, which handles HTTPS
+// and proxy settings without bundling a TLS stack in this binary.
+fn download_registry(url: &str, path: &str) -> Result<(), ScanError> {
+    let status = Command::new("curl")
+        .args(["--fail", "--silent", "--show-error", "--location"])
+        .args(["--output", path, url])
+        .status()
+        .map_err(|err| {
+            ScanError::Io(format!(
+                "Could not run curl to download the OUI registry ({})",
+                err
+            ))
+        })?;
+
+    match status.success() {
+        true => Ok(()),
+        false => Err(ScanError::Io(format!(
+            "Could not download the OUI registry from {} ({})",
+            url, status
+        ))),
+    }
+}
+
+// Count the vendor prefixes of an OUI database, which should follow the IEEE
+// CSV format (registry, assignment, organization name & address).
+fn count_prefixes(path: &str) -> Result<usize, ScanError> {
+    let mut reader = Reader::from_path(path)
+        .map_err(|err| ScanError::Io(format!("Could not read the OUI registry ({})", err)))?;
+
+    let mut prefix_count = 0;
+    for vendor_result in reader.records() {
+        let record = vendor_result
+            .map_err(|err| ScanError::Io(format!("Malformed OUI registry ({})", err)))?;
+        let assignment = record.get(1).unwrap_or("");
+
+        if assignment.len() == 6 && assignment.chars().all(|c| c.is_ascii_hexdigit()) {
+            prefix_count += 1;
+        }
+    }
+
+    match prefix_count {
+        0 => Err(ScanError::Io(
+            "Malformed OUI registry (no vendor prefix found)".to_string(),
+        )),
+        _ => Ok(prefix_count),
+    }
+}
+
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
 
     use super::*;
@@ -128,6 +447,118 @@ mod tests {
         assert_eq!(vendor.search_by_mac(&mac), None);
     }
 
+    #[test]
+    fn should_cache_repeated_lookups() {
+        let mut vendor = Vendor::new("./data/ieee-oui.csv");
+
+        for host_byte in 0..=255 {
+            let mac = MacAddr::new(0x40, 0x55, 0x82, 0x00, 0x00, host_byte);
+            assert_eq!(vendor.lookup(&mac), Some("Nokia".to_string()));
+        }
+        let unknown_mac = MacAddr::new(0xbb, 0xbb, 0xbb, 0xd2, 0xf5, 0xb6);
+        assert_eq!(vendor.lookup(&unknown_mac), None);
+        assert_eq!(vendor.cache.len(), 2);
+
+        // Without database, only the cached prefixes can still be resolved.
+        vendor.reader = None;
+        let mac = MacAddr::new(0x40, 0x55, 0x82, 0xc3, 0xe5, 0x5b);
+        assert_eq!(vendor.lookup(&mac), Some("Nokia".to_string()));
+        assert_eq!(vendor.lookup(&unknown_mac), None);
+    }
+
+    #[test]
+    fn should_count_registry_prefixes() {
+        assert!(count_prefixes("./data/ieee-oui.csv").unwrap() > 0);
+        assert!(count_prefixes("./data/ip-list.txt").is_err());
+        assert!(count_prefixes("./unknown.csv").is_err());
+    }
+
+    #[test]
+    fn should_keep_database_on_failed_update() {
+        let path = std::env::temp_dir().join("arp-scan-failed-update.csv");
+        let path = path.to_str().unwrap();
+        fs::write(path, "previous database").unwrap();
+
+        let update_result = update_database_from("file:///unknown/oui.csv", path);
+
+        assert!(update_result.is_err());
+        assert_eq!(fs::read_to_string(path).unwrap(), "previous database");
+        assert!(fs::metadata(format!("{}.download", path)).is_err());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn should_find_masked_manuf_vendor() {
+        let path = std::env::temp_dir().join("arp-scan-manuf-test");
+        fs::write(
+            &path,
+            "# Wireshark manuf file\n\
+            \n\
+            00:1B:C5\tIeeeRegi\tIEEE Registration Authority\n\
+            00:1B:C5:00:00:00/36\tConverge\tConverging Systems Inc.\n\
+            70:B3:D5:10:00:00/28\tSmallCo\n\
+            not a prefix\tIgnored\n",
+        )
+        .unwrap();
+        let mut vendor = Vendor::new(path.to_str().unwrap());
+
+        assert!(vendor.has_vendor_db());
+        assert_eq!(
+            vendor.lookup(&MacAddr::new(0x00, 0x1b, 0xc5, 0x00, 0x00, 0x2a)),
+            Some("Converging Systems Inc.".to_string())
+        );
+        assert_eq!(
+            vendor.lookup(&MacAddr::new(0x00, 0x1b, 0xc5, 0x00, 0x10, 0x2a)),
+            Some("IEEE Registration Authority".to_string())
+        );
+        assert_eq!(
+            vendor.lookup(&MacAddr::new(0x70, 0xb3, 0xd5, 0x1f, 0xff, 0xff)),
+            Some("SmallCo".to_string())
+        );
+        assert_eq!(
+            vendor.lookup(&MacAddr::new(0x70, 0xb3, 0xd5, 0x20, 0x00, 0x00)),
+            None
+        );
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn should_find_oui_text_vendor() {
+        let path = std::env::temp_dir().join("arp-scan-oui-text-test");
+        fs::write(
+            &path,
+            "OUI/MA-L\t\t\tOrganization\n\
+            company_id\t\t\tOrganization\n\
+            \t\t\t\tAddress\n\
+            \n\
+            00-22-72   (hex)\t\tAmerican Micro-Fuel Device Corp.\n\
+            002272     (base 16)\t\tAmerican Micro-Fuel Device Corp.\n\
+            \t\t\t\t2181 Buchanan Loop\n",
+        )
+        .unwrap();
+        let mut vendor = Vendor::new(path.to_str().unwrap());
+
+        assert_eq!(
+            detect_text_format(path.to_str().unwrap()),
+            Some(TextFormat::OuiText)
+        );
+        assert_eq!(
+            vendor.lookup(&MacAddr::new(0x00, 0x22, 0x72, 0x01, 0x02, 0x03)),
+            Some("American Micro-Fuel Device Corp.".to_string())
+        );
+        assert_eq!(
+            vendor.lookup(&MacAddr::new(0x00, 0x22, 0x73, 0x01, 0x02, 0x03)),
+            None
+        );
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn should_detect_ieee_csv_database() {
+        assert_eq!(detect_text_format("./data/ieee-oui.csv"), None);
+        assert_eq!(parse_manuf_line("MA-L,002272,American Micro-Fuel"), None);
+    }
+
     #[test]
     fn should_pad_correctly_with_zeroes() {
         let mut vendor = Vendor::new("./data/ieee-oui.csv");
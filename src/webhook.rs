@@ -0,0 +1,168 @@
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::Duration;
+
+const WEBHOOK_ATTEMPTS: usize = 3;
+const WEBHOOK_TIMEOUT_S: u64 = 2;
+const WEBHOOK_RETRY_DELAY_MS: u64 = 500;
+
+// Pending notifications are given a last chance to be delivered at the end of
+// a scan round, without waiting for a slow endpoint more than this delay.
+const WEBHOOK_DRAIN_MS: u64 = 5000;
+
+/**
+ * Notify an HTTP endpoint of each discovered host, with a POST request whose
+ * body is the JSON object of the host (as streamed in NDJSON). Requests are
+ * sent by a background thread with the system 'curl' command, so that a slow
+ * endpoint never blocks the reception of ARP responses.
+ */
+pub struct WebhookNotifier {
+    sender: Option<Sender<(String, String)>>,
+    done_receiver: Receiver<()>,
+}
+
+impl WebhookNotifier {
+    pub fn new(url: &str) -> Self {
+        let (sender, receiver) = mpsc::channel::<(String, String)>();
+        let (done_sender, done_receiver) = mpsc::channel::<()>();
+        let url = url.to_string();
+
+        thread::spawn(move || {
+            for (ip_address, payload) in receiver {
+                if let Err(err) = post_payload(&url, &payload) {
+                    eprintln!(
+                        "[warn] Could not notify webhook of {} ({})",
+                        ip_address, err
+                    );
+                }
+            }
+            let _ = done_sender.send(());
+        });
+
+        WebhookNotifier {
+            sender: Some(sender),
+            done_receiver,
+        }
+    }
+
+    /**
+     * Queue the notification of a discovered host, the IP address is only
+     * used in the failure logs.
+     */
+    pub fn notify(&self, ip_address: String, payload: String) {
+        if let Some(sender) = &self.sender {
+            let _ = sender.send((ip_address, payload));
+        }
+    }
+
+    /**
+     * Stop accepting notifications, and wait (for a bounded delay) until the
+     * queued ones are delivered.
+     */
+    pub fn finish(mut self) {
+        self.sender = None;
+        let drain_timeout = Duration::from_millis(WEBHOOK_DRAIN_MS);
+        if let Err(RecvTimeoutError::Timeout) = self.done_receiver.recv_timeout(drain_timeout) {
+            eprintln!("[warn] Webhook notifications are still pending, not waiting for them");
+        }
+    }
+}
+
+/**
+ * Post a single payload, with a short timeout and a bounded number of
+ * attempts. HTTP errors (4xx & 5xx) are considered as failed attempts.
+ */
+fn post_payload(url: &str, payload: &str) -> Result<(), String> {
+    let mut last_error = String::new();
+    for attempt in 0..WEBHOOK_ATTEMPTS {
+        if attempt > 0 {
+            thread::sleep(Duration::from_millis(WEBHOOK_RETRY_DELAY_MS));
+        }
+
+        let output = Command::new("curl")
+            .args(build_post_args(url, payload))
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .output()
+            .map_err(|err| format!("could not run curl, {}", err))?;
+        if output.status.success() {
+            return Ok(());
+        }
+        last_error = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    }
+
+    Err(format!(
+        "{} attempts failed, {}",
+        WEBHOOK_ATTEMPTS, last_error
+    ))
+}
+
+/**
+ * Check that a webhook URL uses the HTTP or HTTPS scheme. Other schemes
+ * (e.g. 'file://') would let curl read or write local files as root.
+ */
+pub fn validate_webhook_url(url: &str) -> Result<(), String> {
+    match url.starts_with("http://") || url.starts_with("https://") {
+        true => Ok(()),
+        false => Err(format!("Expected an HTTP or HTTPS webhook URL ({})", url)),
+    }
+}
+
+/**
+ * Build the curl arguments of a POST request. The URL is given with '--url'
+ * (and restricted to HTTP & HTTPS with '--proto'), so that a value starting
+ * with a dash is never parsed as a curl option.
+ */
+fn build_post_args(url: &str, payload: &str) -> Vec<String> {
+    vec![
+        "--fail".to_string(),
+        "--silent".to_string(),
+        "--show-error".to_string(),
+        "--max-time".to_string(),
+        WEBHOOK_TIMEOUT_S.to_string(),
+        "--header".to_string(),
+        "Content-Type: application/json".to_string(),
+        "--data-binary".to_string(),
+        payload.to_string(),
+        "--proto".to_string(),
+        "=http,https".to_string(),
+        "--url".to_string(),
+        url.to_string(),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn should_build_post_args() {
+        let post_args = build_post_args(
+            "https://siem.local/hooks",
+            "{\"mac\":\"00:11:22:33:44:55\"}",
+        );
+
+        assert_eq!(post_args[4], "2");
+        assert_eq!(
+            &post_args[7..],
+            &[
+                "--data-binary".to_string(),
+                "{\"mac\":\"00:11:22:33:44:55\"}".to_string(),
+                "--proto".to_string(),
+                "=http,https".to_string(),
+                "--url".to_string(),
+                "https://siem.local/hooks".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn should_validate_webhook_url() {
+        assert!(validate_webhook_url("https://siem.local/hooks").is_ok());
+        assert!(validate_webhook_url("http://10.0.0.5:8080/arp").is_ok());
+        assert!(validate_webhook_url("--output=/etc/passwd").is_err());
+        assert!(validate_webhook_url("file:///etc/shadow").is_err());
+    }
+}